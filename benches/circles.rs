@@ -0,0 +1,46 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use image::{Rgba, RgbaImage};
+use rat::draw::{draw_circle, CircleAlgorithm};
+
+// one US Letter page at 72 PPI, filled with dots the way a typical run would
+fn circles(c: &mut Criterion) {
+    let mut group = c.benchmark_group("circles");
+
+    for &square_size in &[4.0f32, 18.0, 48.0] {
+        for &algorithm in CircleAlgorithm::algorithms() {
+            group.bench_with_input(
+                BenchmarkId::new(algorithm.to_string(), square_size),
+                &square_size,
+                |b, &square_size| {
+                    let mut page = RgbaImage::new(612, 792);
+                    let max_radius = (square_size.powi(2) * 2.0).sqrt() / 2.0;
+
+                    b.iter(|| {
+                        let mut y = 0.0;
+                        while y < 792.0 {
+                            let mut x = 0.0;
+                            while x < 612.0 {
+                                // vary the radius across the page like brightness would
+                                let radius = max_radius * (x / 612.0);
+                                draw_circle(
+                                    &mut page,
+                                    (x + square_size / 2.0, y + square_size / 2.0),
+                                    radius,
+                                    Rgba([0, 0, 0, 255]),
+                                    algorithm,
+                                );
+                                x += square_size;
+                            }
+                            y += square_size;
+                        }
+                    })
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, circles);
+criterion_main!(benches);
@@ -0,0 +1,180 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use image::{Rgba, RgbaImage};
+use rat::color::{Gradient, Inks, Palette};
+use rat::draw::{self, CircleAlgorithm};
+use rat::rasterize::{
+    self, Border, ChannelInvert, ColorDepth, Dither, DotShape, Edges, Framing, Jitter, Layout,
+    Luma, MaskBlend, MinDotSize, ParameterMatrix, Pattern, RadiusCurve, RasterizeArgs, Resampling,
+    RowOrder, StepAndRepeat, SvgOptimization, Tone, Transparency,
+};
+
+// US Letter at 72 PPI
+const PAPER_WIDTH_PIXELS: f32 = 612.0;
+const PAPER_HEIGHT_PIXELS: f32 = 792.0;
+
+// a phone photo sized image and a thumbnail, with enough going on that
+// every square samples something different
+fn source(width: u32, height: u32) -> RgbaImage {
+    RgbaImage::from_fn(width, height, |x, y| {
+        let r = (x * 255 / width) as u8;
+        let g = (y * 255 / height) as u8;
+        let b = ((x ^ y) & 0xff) as u8;
+        Rgba([r, g, b, 255])
+    })
+}
+
+fn args(image: &RgbaImage, pages: u32, square_size: f32) -> RasterizeArgs<'_> {
+    RasterizeArgs {
+        image,
+        mask: None,
+        paper_width_pixels: PAPER_WIDTH_PIXELS,
+        paper_height_pixels: PAPER_HEIGHT_PIXELS,
+        pages_width: pages,
+        pages_height: pages,
+        square_size,
+        min_radius_percentage: 0.0,
+        max_radius_percentage: 1.0,
+        color_depth: ColorDepth::Grayscale,
+        layout: Layout::Fit,
+        step_and_repeat: StepAndRepeat::default(),
+        border: Border::default(),
+        matrix: ParameterMatrix::default(),
+        gradient: Gradient::default(),
+        output_pixels_per_inch: rasterize::PIXELS_PER_INCH,
+        draft: false,
+        circle_algorithm: CircleAlgorithm::Scanline,
+        dither: Dither::None,
+        pattern: Pattern::Grid,
+        mask_blend: MaskBlend::Multiply,
+        line_angle: 45.0,
+        invert: ChannelInvert::default(),
+        inks: Inks::default(),
+        ink_layer: None,
+        row_order: RowOrder::RowMajor,
+        palette: Palette::default(),
+        transparency: Transparency::default(),
+        tone: Tone::default(),
+        dot_metadata: false,
+        radius_curve: RadiusCurve::default(),
+        luma: Luma::Rec601,
+        jitter: Jitter::default(),
+        dot_shape: DotShape::Circle,
+        follow_contours: false,
+        edges: Edges::default(),
+        framing: Framing::default(),
+        resampling: Resampling::Triangle,
+        svg_optimization: SvgOptimization::default(),
+        min_dot_size: MinDotSize::default(),
+        supersampling: 1,
+    }
+}
+
+// the whole sampling and drawing loop, from a source image to finished pages
+fn rasterize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rasterize");
+    // the big runs take seconds each
+    group.sample_size(10);
+
+    for &(size_name, width, height) in &[("small", 320, 240), ("large", 3024, 4032)] {
+        let image = source(width, height);
+
+        for &pages in &[1, 5] {
+            for &square_size in &[4.0f32, 18.0] {
+                group.bench_with_input(
+                    BenchmarkId::new(
+                        format!("{} {}x{} pages", size_name, pages, pages),
+                        square_size,
+                    ),
+                    &square_size,
+                    |b, &square_size| {
+                        b.iter(|| {
+                            rasterize::rasterize_image(args(&image, pages, square_size))
+                                .collect::<Vec<_>>()
+                        })
+                    },
+                );
+            }
+        }
+    }
+
+    group.finish();
+}
+
+// many pages drawn one after another, and all at once on a thread pool
+#[cfg(feature = "parallel")]
+fn parallel(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parallel");
+    group.sample_size(10);
+    let image = source(3024, 4032);
+
+    group.bench_function("serial 5x5 pages", |b| {
+        b.iter(|| rasterize::rasterize_image(args(&image, 5, 4.0)).collect::<Vec<_>>())
+    });
+    group.bench_function("parallel 5x5 pages", |b| {
+        b.iter(|| rasterize::rasterize_image_parallel(args(&image, 5, 4.0)))
+    });
+
+    group.finish();
+}
+
+// each pattern over the same page, since they sample and draw differently
+fn patterns(c: &mut Criterion) {
+    let mut group = c.benchmark_group("patterns");
+    let image = source(320, 240);
+
+    for &pattern in Pattern::patterns() {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(pattern),
+            &pattern,
+            |b, &pattern| {
+                b.iter(|| {
+                    let args = RasterizeArgs {
+                        pattern,
+                        ..args(&image, 1, 18.0)
+                    };
+                    rasterize::rasterize_image(args).collect::<Vec<_>>()
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+// a page of dots of every size a grid draws, with each way of filling circles
+fn circles(c: &mut Criterion) {
+    let mut group = c.benchmark_group("circles");
+    let mut page = RgbaImage::new(PAPER_WIDTH_PIXELS as u32, PAPER_HEIGHT_PIXELS as u32);
+    let color = Rgba([20, 40, 60, 255]);
+
+    for &algorithm in CircleAlgorithm::algorithms() {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(algorithm),
+            &algorithm,
+            |b, &algorithm| {
+                b.iter(|| {
+                    for y in (0..PAPER_HEIGHT_PIXELS as u32).step_by(6) {
+                        for x in (0..PAPER_WIDTH_PIXELS as u32).step_by(6) {
+                            let radius = ((x + y) % 9) as f32 / 2.0;
+                            draw::draw_circle(
+                                &mut page,
+                                (x as f32 + 3.0, y as f32 + 3.0),
+                                radius,
+                                color,
+                                algorithm,
+                            );
+                        }
+                    }
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+#[cfg(not(feature = "parallel"))]
+criterion_group!(benches, rasterize, patterns, circles);
+#[cfg(feature = "parallel")]
+criterion_group!(benches, rasterize, patterns, circles, parallel);
+criterion_main!(benches);
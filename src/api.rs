@@ -0,0 +1,93 @@
+// rat as a library, for other web apps and node scripts that want its outputs
+// without its ui. options are `Settings` as a plain object, so the settings.json
+// from a zip of the app's outputs can be passed in as it is. anything left out
+// is the app's default
+use crate::rasterize;
+use crate::settings::Settings;
+use image::RgbaImage;
+use wasm_bindgen::prelude::*;
+
+// settings as they come from js, where `undefined` and `null` mean all defaults
+pub fn settings_from_js(settings: &JsValue) -> Result<Settings, JsValue> {
+    if settings.is_undefined() || settings.is_null() {
+        return Ok(Settings::default());
+    }
+
+    let json: String = js_sys::JSON::stringify(settings)?.into();
+    Settings::from_json(json.as_bytes()).map_err(|e| JsValue::from_str(&e))
+}
+
+// a source image in any format rat reads, or why it can't be rasterized
+fn decode_image(bytes: &[u8]) -> Result<RgbaImage, String> {
+    let image = image::load_from_memory(bytes).map_err(|e| e.to_string())?;
+    rasterize::check_image(&image).map_err(|e| e.to_string())?;
+
+    Ok(image.to_rgba8())
+}
+
+// each page as png bytes
+pub fn pngs(bytes: &[u8], settings: &Settings) -> Result<Vec<Vec<u8>>, String> {
+    let image = decode_image(bytes)?;
+    let args = settings.rasterize_args(&image, None);
+
+    // natively, with the `parallel` feature, every page is drawn at once on a thread pool.
+    // in the browser pages are drawn one at a time, so only one is held unencoded
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    let pages = rasterize::rasterize_image_parallel(args).into_iter();
+    #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+    let pages = rasterize::rasterize_image(args);
+
+    pages
+        .map(|page| {
+            let mut png = vec![];
+            image::png::PngEncoder::new(&mut png)
+                .encode(
+                    page.as_raw(),
+                    page.width(),
+                    page.height(),
+                    image::ColorType::Rgba8,
+                )
+                .map_err(|e| e.to_string())?;
+            Ok(settings.tag_png(settings.color_profile.tag_png(png)))
+        })
+        .collect()
+}
+
+// each page as an svg document
+pub fn svgs(bytes: &[u8], settings: &Settings) -> Result<Vec<String>, String> {
+    let image = decode_image(bytes)?;
+
+    Ok(
+        rasterize::rasterize_svg(settings.rasterize_args(&image, None))
+            .map(|document| {
+                settings
+                    .tag_svg(settings.color_profile.tag_svg(document))
+                    .to_string()
+            })
+            .collect(),
+    )
+}
+
+// rasterizes `bytes`, an encoded image, into an array of one Uint8Array of png bytes per page
+#[wasm_bindgen]
+pub fn rasterize_to_pngs(bytes: &[u8], options: JsValue) -> Result<js_sys::Array, JsValue> {
+    let settings = settings_from_js(&options)?;
+
+    Ok(pngs(bytes, &settings)
+        .map_err(|e| JsValue::from_str(&e))?
+        .iter()
+        .map(|png| js_sys::Uint8Array::from(png.as_slice()))
+        .collect())
+}
+
+// rasterizes `bytes`, an encoded image, into an array of one svg string per page
+#[wasm_bindgen]
+pub fn rasterize_to_svgs(bytes: &[u8], options: JsValue) -> Result<js_sys::Array, JsValue> {
+    let settings = settings_from_js(&options)?;
+
+    Ok(svgs(bytes, &settings)
+        .map_err(|e| JsValue::from_str(&e))?
+        .into_iter()
+        .map(JsValue::from)
+        .collect())
+}
@@ -0,0 +1,301 @@
+// the "download all" zip and project bundles. the zip crate can only deflate at its one
+// default level, and its own deflate pulls in flate2, so entries are written here instead,
+// deflated with the same encoder the png encoder uses, at the level asked for. they are
+// read back here too, inflated with the png decoder's inflate, when a project is exported
+// or imported
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::slice::Iter;
+
+// how much work goes into shrinking each file. svg pages shrink to a
+// tenth or less of their size, pngs and jpegs hardly at all
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ZipCompression {
+    Stored,
+    Fast,
+    Default,
+    Best,
+}
+
+impl ZipCompression {
+    pub fn from_string(s: &str) -> Option<ZipCompression> {
+        match s {
+            "None" => Some(ZipCompression::Stored),
+            "Fast" => Some(ZipCompression::Fast),
+            "Default" => Some(ZipCompression::Default),
+            "Best" => Some(ZipCompression::Best),
+            _ => None,
+        }
+    }
+
+    pub fn zip_compressions() -> Iter<'static, Self> {
+        const ZIP_COMPRESSIONS: [ZipCompression; 4] = [
+            ZipCompression::Stored,
+            ZipCompression::Fast,
+            ZipCompression::Default,
+            ZipCompression::Best,
+        ];
+        ZIP_COMPRESSIONS.iter()
+    }
+
+    fn deflate_level(&self) -> Option<deflate::Compression> {
+        match self {
+            ZipCompression::Stored => None,
+            ZipCompression::Fast => Some(deflate::Compression::Fast),
+            ZipCompression::Default => Some(deflate::Compression::Default),
+            ZipCompression::Best => Some(deflate::Compression::Best),
+        }
+    }
+}
+
+impl fmt::Display for ZipCompression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ZipCompression::Stored => "None",
+            ZipCompression::Fast => "Fast",
+            ZipCompression::Default => "Default",
+            ZipCompression::Best => "Best",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_DIRECTORY_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+// 2.0, the first version with deflate
+const ZIP_VERSION: u16 = 20;
+// filenames are utf-8
+const UTF8_FLAG: u16 = 1 << 11;
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+// 1980-01-01 00:00, the earliest time a zip can hold. outputs are
+// made in the browser, which has no clock that zips can use anyway
+const DOS_TIME: u16 = 0;
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+// zip64, which lifts the limits of a plain zip, isn't written,
+// so what goes past them is an error instead
+#[derive(Clone, Debug, PartialEq)]
+pub enum ZipError {
+    // past 65535 files
+    TooManyFiles,
+    // a file, or the zip itself, past 4 GiB
+    TooBig(String),
+    // past 65535 bytes
+    NameTooLong(String),
+    // not a zip `ZipWriter` wrote, or cut short
+    Unreadable,
+}
+
+impl fmt::Display for ZipError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ZipError::TooManyFiles => write!(f, "a zip can hold at most {} files", u16::MAX),
+            ZipError::TooBig(filename) => {
+                write!(
+                    f,
+                    "{} is too big for a zip, which is limited to 4 GiB",
+                    filename
+                )
+            }
+            ZipError::NameTooLong(filename) => {
+                write!(f, "the name {} is too long for a zip", filename)
+            }
+            ZipError::Unreadable => write!(f, "the zip could not be read"),
+        }
+    }
+}
+
+// a zip, built up in memory one file at a time
+pub struct ZipWriter {
+    zip: Vec<u8>,
+    central_directory: Vec<u8>,
+    entries: u16,
+    compression: ZipCompression,
+}
+
+impl ZipWriter {
+    pub fn new(compression: ZipCompression) -> Self {
+        ZipWriter {
+            zip: vec![],
+            central_directory: vec![],
+            entries: 0,
+            compression,
+        }
+    }
+
+    // files that deflating doesn't make smaller, like pngs, are stored as they are.
+    // a file that doesn't fit leaves the zip as it was
+    pub fn add(&mut self, filename: &str, bytes: &[u8]) -> Result<(), ZipError> {
+        if self.entries == u16::MAX {
+            return Err(ZipError::TooManyFiles);
+        }
+        let too_big = || ZipError::TooBig(filename.to_string());
+        let size = u32::try_from(bytes.len()).map_err(|_| too_big())?;
+        let offset = u32::try_from(self.zip.len()).map_err(|_| too_big())?;
+        let name = filename.as_bytes();
+        let name_length =
+            u16::try_from(name.len()).map_err(|_| ZipError::NameTooLong(filename.to_string()))?;
+
+        let mut crc = crc32fast::Hasher::new();
+        crc.update(bytes);
+        let crc = crc.finalize();
+
+        let deflated = self
+            .compression
+            .deflate_level()
+            .map(|level| deflate::deflate_bytes_conf(bytes, level))
+            .filter(|deflated| deflated.len() < bytes.len());
+        let (method, data) = match &deflated {
+            Some(deflated) => (METHOD_DEFLATED, deflated.as_slice()),
+            None => (METHOD_STORED, bytes),
+        };
+        // never more than `size`, which fits
+        let compressed_size = data.len() as u32;
+
+        // the parts of the local header and the central directory header that are the same
+        let mut common = vec![];
+        put_u16(&mut common, ZIP_VERSION);
+        put_u16(&mut common, UTF8_FLAG);
+        put_u16(&mut common, method);
+        put_u16(&mut common, DOS_TIME);
+        put_u16(&mut common, DOS_DATE);
+        put_u32(&mut common, crc);
+        put_u32(&mut common, compressed_size);
+        put_u32(&mut common, size);
+        put_u16(&mut common, name_length);
+        // no extra field
+        put_u16(&mut common, 0);
+
+        put_u32(&mut self.zip, LOCAL_FILE_HEADER_SIGNATURE);
+        self.zip.extend_from_slice(&common);
+        self.zip.extend_from_slice(name);
+        self.zip.extend_from_slice(data);
+
+        put_u32(
+            &mut self.central_directory,
+            CENTRAL_DIRECTORY_HEADER_SIGNATURE,
+        );
+        put_u16(&mut self.central_directory, ZIP_VERSION);
+        self.central_directory.extend_from_slice(&common);
+        // no comment, starting on the only disk, with no file attributes
+        put_u16(&mut self.central_directory, 0);
+        put_u16(&mut self.central_directory, 0);
+        put_u16(&mut self.central_directory, 0);
+        put_u32(&mut self.central_directory, 0);
+        put_u32(&mut self.central_directory, offset);
+        self.central_directory.extend_from_slice(name);
+
+        self.entries += 1;
+        Ok(())
+    }
+
+    // the zip's bytes, with the central directory that lists every file at the end
+    pub fn finish(mut self) -> Result<Vec<u8>, ZipError> {
+        let too_big = || ZipError::TooBig("the zip".to_string());
+        let offset = u32::try_from(self.zip.len()).map_err(|_| too_big())?;
+        let central_directory_size =
+            u32::try_from(self.central_directory.len()).map_err(|_| too_big())?;
+        self.zip.extend_from_slice(&self.central_directory);
+
+        put_u32(&mut self.zip, END_OF_CENTRAL_DIRECTORY_SIGNATURE);
+        // this disk, and the disk the central directory starts on
+        put_u16(&mut self.zip, 0);
+        put_u16(&mut self.zip, 0);
+        put_u16(&mut self.zip, self.entries);
+        put_u16(&mut self.zip, self.entries);
+        put_u32(&mut self.zip, central_directory_size);
+        put_u32(&mut self.zip, offset);
+        // no comment
+        put_u16(&mut self.zip, 0);
+
+        Ok(self.zip)
+    }
+}
+
+// every file of a zip `ZipWriter` wrote, as (filename, bytes), in the order they were added
+pub fn read_zip(zip: &[u8]) -> Result<Vec<(String, Vec<u8>)>, ZipError> {
+    // offsets come from the zip itself, so every sum of them is checked
+    let sum = |a: usize, b: usize| a.checked_add(b).ok_or(ZipError::Unreadable);
+    let field = |at: usize| get_u32(zip, at).ok_or(ZipError::Unreadable);
+    let short_field = |at: usize| get_u16(zip, at).ok_or(ZipError::Unreadable);
+    let bytes =
+        |start: usize, length: usize| get_bytes(zip, start, length).ok_or(ZipError::Unreadable);
+
+    // with no comment, the end of central directory record is the last 22 bytes
+    let end = zip
+        .len()
+        .checked_sub(22)
+        .filter(|&end| get_u32(zip, end) == Some(END_OF_CENTRAL_DIRECTORY_SIGNATURE))
+        .ok_or(ZipError::Unreadable)?;
+    let entries = short_field(sum(end, 10)?)?;
+    let mut header = field(sum(end, 16)?)? as usize;
+
+    let mut files = Vec::with_capacity(entries as usize);
+    for _ in 0..entries {
+        let header_field = |at: usize| field(sum(header, at)?);
+        let header_short_field = |at: usize| short_field(sum(header, at)?);
+
+        if header_field(0)? != CENTRAL_DIRECTORY_HEADER_SIGNATURE {
+            return Err(ZipError::Unreadable);
+        }
+        let method = header_short_field(10)?;
+        let crc = header_field(16)?;
+        let compressed_size = header_field(20)? as usize;
+        let name_length = header_short_field(28)? as usize;
+        let extra_length = header_short_field(30)? as usize;
+        let comment_length = header_short_field(32)? as usize;
+        let offset = header_field(42)? as usize;
+        let name = bytes(sum(header, 46)?, name_length)?;
+        let filename = String::from_utf8(name.to_vec()).map_err(|_| ZipError::Unreadable)?;
+
+        // the file starts after its local header, which has a name and extra field of its own
+        let local_name_length = short_field(sum(offset, 26)?)? as usize;
+        let local_extra_length = short_field(sum(offset, 28)?)? as usize;
+        let start = sum(
+            sum(offset, 30)?,
+            sum(local_name_length, local_extra_length)?,
+        )?;
+        let data = bytes(start, compressed_size)?;
+
+        let contents = match method {
+            METHOD_STORED => data.to_vec(),
+            METHOD_DEFLATED => {
+                miniz_oxide::inflate::decompress_to_vec(data).map_err(|_| ZipError::Unreadable)?
+            }
+            _ => return Err(ZipError::Unreadable),
+        };
+        if crc32fast::hash(&contents) != crc {
+            return Err(ZipError::Unreadable);
+        }
+        files.push((filename, contents));
+
+        let header_length = sum(sum(46, name_length)?, sum(extra_length, comment_length)?)?;
+        header = sum(header, header_length)?;
+    }
+
+    Ok(files)
+}
+
+fn get_bytes(bytes: &[u8], start: usize, length: usize) -> Option<&[u8]> {
+    bytes.get(start..start.checked_add(length)?)
+}
+
+fn get_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    let field = get_bytes(bytes, at, 2)?;
+    Some(u16::from_le_bytes([field[0], field[1]]))
+}
+
+fn get_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    let field = get_bytes(bytes, at, 4)?;
+    Some(u32::from_le_bytes([field[0], field[1], field[2], field[3]]))
+}
+
+fn put_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
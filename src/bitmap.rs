@@ -0,0 +1,87 @@
+use image::{DynamicImage, ImageBuffer, RgbaImage};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Blob, CanvasRenderingContext2d, HtmlCanvasElement, ImageBitmap};
+
+// photos from phones are often heic or avif, which the image crate can't decode.
+// a browser that can show them decodes them for us, and we read the pixels back
+// off a canvas, the same as a camera frame
+pub async fn decode(bytes: &[u8]) -> Result<DynamicImage, JsValue> {
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("no window"))?;
+
+    let blob =
+        Blob::new_with_u8_array_sequence(&js_sys::Array::of1(&js_sys::Uint8Array::from(bytes)))?;
+    let bitmap = JsFuture::from(window.create_image_bitmap_with_blob(&blob)?)
+        .await?
+        .dyn_into::<ImageBitmap>()?;
+
+    let pixels = bitmap_pixels(&window, &bitmap);
+    // a bitmap holds its pixels until it is closed, not until it is collected
+    bitmap.close();
+
+    pixels
+}
+
+fn bitmap_pixels(window: &web_sys::Window, bitmap: &ImageBitmap) -> Result<DynamicImage, JsValue> {
+    let (width, height) = (bitmap.width(), bitmap.height());
+
+    let canvas = new_canvas(window)?;
+    canvas.set_width(width);
+    canvas.set_height(height);
+
+    let context = canvas
+        .get_context("2d")?
+        .ok_or_else(|| JsValue::from_str("no 2d canvas context"))?
+        .dyn_into::<CanvasRenderingContext2d>()?;
+
+    context.draw_image_with_image_bitmap(bitmap, 0.0, 0.0)?;
+
+    let image_data = context.get_image_data(0.0, 0.0, width as f64, height as f64)?;
+
+    // canvas image data is always non-premultiplied 8-bit RGBA
+    let buffer = ImageBuffer::from_raw(width, height, image_data.data().0)
+        .ok_or_else(|| JsValue::from_str("decoded image had an unexpected size"))?;
+
+    Ok(DynamicImage::ImageRgba8(buffer))
+}
+
+thread_local! {
+    // a canvas asked for a type it can't encode gives a png instead
+    static CAN_ENCODE_WEBP: bool = web_sys::window()
+        .and_then(|window| new_canvas(&window).ok())
+        .and_then(|canvas| canvas.to_data_url_with_type("image/webp").ok())
+        .is_some_and(|url| url.starts_with(WEBP_DATA_URL_PREFIX));
+}
+
+const WEBP_DATA_URL_PREFIX: &str = "data:image/webp;base64,";
+
+pub fn can_encode_webp() -> bool {
+    CAN_ENCODE_WEBP.with(|can| *can)
+}
+
+// the image crate can't encode webp, but most browsers can, off a canvas. read back
+// as a data url, not a blob, since pages are encoded as they're drawn, without waiting
+pub fn encode_webp(image: &RgbaImage, quality: u8) -> Result<Vec<u8>, JsValue> {
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("no window"))?;
+    let canvas = new_canvas(&window)?;
+    crate::draw_on_canvas(&canvas, image)?;
+
+    let url = canvas.to_data_url_with_type_and_encoder_options(
+        "image/webp",
+        &JsValue::from_f64(quality as f64 / 100.0),
+    )?;
+    let encoded = url
+        .strip_prefix(WEBP_DATA_URL_PREFIX)
+        .ok_or_else(|| JsValue::from_str("this browser can't encode webp"))?;
+
+    base64::decode(encoded).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+fn new_canvas(window: &web_sys::Window) -> Result<HtmlCanvasElement, JsValue> {
+    window
+        .document()
+        .ok_or_else(|| JsValue::from_str("no document"))?
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()
+        .map_err(JsValue::from)
+}
@@ -0,0 +1,69 @@
+use image::{DynamicImage, ImageBuffer};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    CanvasRenderingContext2d, HtmlCanvasElement, HtmlVideoElement, MediaStream,
+    MediaStreamConstraints, MediaStreamTrack,
+};
+
+// ask the browser for a video-only stream from the user's camera.
+// this is where the permission prompt appears.
+pub async fn request_stream() -> Result<MediaStream, JsValue> {
+    let media_devices = web_sys::window()
+        .ok_or_else(|| JsValue::from_str("no window"))?
+        .navigator()
+        .media_devices()?;
+
+    let constraints = MediaStreamConstraints::new();
+    constraints.set_video(&JsValue::TRUE);
+
+    let promise = media_devices.get_user_media_with_constraints(&constraints)?;
+    let stream = JsFuture::from(promise).await?;
+
+    stream.dyn_into::<MediaStream>()
+}
+
+// grab whatever frame the video element is currently showing,
+// by drawing it to an offscreen canvas and reading the pixels back
+pub fn capture_frame(video: &HtmlVideoElement) -> Result<DynamicImage, JsValue> {
+    let width = video.video_width();
+    let height = video.video_height();
+
+    if width == 0 || height == 0 {
+        return Err(JsValue::from_str("camera is not ready yet"));
+    }
+
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| JsValue::from_str("no document"))?;
+
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(width);
+    canvas.set_height(height);
+
+    let context = canvas
+        .get_context("2d")?
+        .ok_or_else(|| JsValue::from_str("no 2d canvas context"))?
+        .dyn_into::<CanvasRenderingContext2d>()?;
+
+    context.draw_image_with_html_video_element(video, 0.0, 0.0)?;
+
+    let image_data = context.get_image_data(0.0, 0.0, width as f64, height as f64)?;
+
+    // canvas image data is always non-premultiplied 8-bit RGBA
+    let buffer = ImageBuffer::from_raw(width, height, image_data.data().0)
+        .ok_or_else(|| JsValue::from_str("camera frame had an unexpected size"))?;
+
+    Ok(DynamicImage::ImageRgba8(buffer))
+}
+
+// turn the camera off, which also clears the browser's "recording" indicator
+pub fn stop_stream(stream: &MediaStream) {
+    for track in stream.get_tracks().iter() {
+        if let Ok(track) = track.dyn_into::<MediaStreamTrack>() {
+            track.stop();
+        }
+    }
+}
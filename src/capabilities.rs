@@ -0,0 +1,110 @@
+// what this build of rat can do, so the about panel and bug reports can say exactly.
+// everything here is settled when rat is compiled. what the browser running it
+// supports is found out by the app itself
+use crate::rasterize::Pattern;
+use image::{ImageError, ImageFormat};
+use std::fmt;
+
+// every format the image crate knows, whether or not its decoder was compiled in
+const IMAGE_FORMATS: [ImageFormat; 13] = [
+    ImageFormat::Png,
+    ImageFormat::Jpeg,
+    ImageFormat::Gif,
+    ImageFormat::WebP,
+    ImageFormat::Pnm,
+    ImageFormat::Tiff,
+    ImageFormat::Tga,
+    ImageFormat::Bmp,
+    ImageFormat::Ico,
+    ImageFormat::Hdr,
+    ImageFormat::Farbfeld,
+    ImageFormat::Avif,
+    ImageFormat::Dds,
+];
+
+// rat's own cargo features
+const FEATURES: [(&str, bool); 3] = [
+    ("corpus-tests", cfg!(feature = "corpus-tests")),
+    ("golden-tests", cfg!(feature = "golden-tests")),
+    ("parallel", cfg!(feature = "parallel")),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Capabilities {
+    // the git revision it was built from
+    pub version: &'static str,
+    // the formats source images and masks can be read from
+    pub input_formats: Vec<&'static str>,
+    pub output_formats: Vec<&'static str>,
+    pub patterns: Vec<Pattern>,
+    pub features: Vec<&'static str>,
+    // whether wasm simd and threads were compiled in
+    pub simd: bool,
+    pub threads: bool,
+}
+
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        version: env!("RAT_VERSION").trim(),
+        input_formats: IMAGE_FORMATS
+            .iter()
+            .filter(|format| can_decode(**format))
+            .filter_map(|format| format.extensions_str().first().copied())
+            .collect(),
+        output_formats: vec![
+            "png", "jpg", "svg", "gcode", "eps", "dxf", "stl", "csv", "html", "txt", "zip",
+        ],
+        patterns: Pattern::patterns().copied().collect(),
+        features: FEATURES
+            .iter()
+            .filter(|(_, enabled)| *enabled)
+            .map(|(feature, _)| *feature)
+            .collect(),
+        simd: cfg!(target_feature = "simd128"),
+        threads: cfg!(target_feature = "atomics"),
+    }
+}
+
+// the image crate knows about every format, but only decodes those whose features are on.
+// with nothing to decode, the others fail as unsupported rather than as broken
+fn can_decode(format: ImageFormat) -> bool {
+    !matches!(
+        image::load_from_memory_with_format(&[], format),
+        Err(ImageError::Unsupported(_))
+    )
+}
+
+// whether rat can decode `bytes` itself, going by the format they look to be in
+pub fn decodes(bytes: &[u8]) -> bool {
+    image::guess_format(bytes).is_ok_and(can_decode)
+}
+
+// one `name: values` line each, to paste into a bug report
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |items: &[&str]| {
+            if items.is_empty() {
+                "none".to_string()
+            } else {
+                items.join(", ")
+            }
+        };
+        let yes_no = |b: bool| if b { "yes" } else { "no" };
+
+        writeln!(f, "rat: {}", self.version)?;
+        writeln!(f, "input formats: {}", list(&self.input_formats))?;
+        writeln!(f, "output formats: {}", list(&self.output_formats))?;
+        writeln!(
+            f,
+            "patterns: {}",
+            self.patterns
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+        writeln!(f, "features: {}", list(&self.features))?;
+        writeln!(f, "compiled with simd: {}", yes_no(self.simd))?;
+        write!(f, "compiled with threads: {}", yes_no(self.threads))
+    }
+}
@@ -0,0 +1,446 @@
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// an opaque color picked by the user, as used by `<input type="color">`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color::new(0, 0, 0);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Color { r, g, b }
+    }
+
+    // parses "#rrggbb", which is the only format color inputs produce
+    pub fn from_hex(s: &str) -> Option<Color> {
+        let s = s.trim().strip_prefix('#')?;
+
+        if s.len() != 6 {
+            return None;
+        }
+
+        let r = u8::from_str_radix(s.get(0..2)?, 16).ok()?;
+        let g = u8::from_str_radix(s.get(2..4)?, 16).ok()?;
+        let b = u8::from_str_radix(s.get(4..6)?, 16).ok()?;
+
+        Some(Color::new(r, g, b))
+    }
+
+    pub fn from_rgba(pixel: Rgba<u8>) -> Self {
+        Color::new(pixel[0], pixel[1], pixel[2])
+    }
+
+    pub fn to_rgba(self) -> Rgba<u8> {
+        Rgba([self.r, self.g, self.b, 255])
+    }
+
+    fn luma(self) -> f32 {
+        0.299 * self.r as f32 + 0.587 * self.g as f32 + 0.114 * self.b as f32
+    }
+
+    // CIE L*a*b*, with a D65 white point
+    fn to_lab(self) -> [f32; 3] {
+        let (r, g, b) = (to_linear(self.r), to_linear(self.g), to_linear(self.b));
+        let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+        let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+        let f = |t: f32| {
+            if t > 0.008856 {
+                t.cbrt()
+            } else {
+                7.787 * t + 16.0 / 116.0
+            }
+        };
+
+        [
+            116.0 * f(y) - 16.0,
+            500.0 * (f(x) - f(y)),
+            200.0 * (f(y) - f(z)),
+        ]
+    }
+
+    // linear interpolation from `self` (t = 0) to `other` (t = 1)
+    pub fn mix(self, other: Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+
+        Color::new(
+            channel(self.r, other.r),
+            channel(self.g, other.g),
+            channel(self.b, other.b),
+        )
+    }
+}
+
+// a two-color ramp from the color used for the darkest parts of the image
+// to the color used for the lightest
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Gradient {
+    pub dark: Color,
+    pub light: Color,
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Gradient {
+            // navy
+            dark: Color::new(0x1b, 0x2a, 0x4a),
+            // cream
+            light: Color::new(0xf5, 0xec, 0xd7),
+        }
+    }
+}
+
+impl Gradient {
+    pub fn at(self, brightness: f32) -> Color {
+        self.dark.mix(self.light, brightness)
+    }
+}
+
+// the inks of a duotone or tritone print, from darkest to lightest.
+// duotones only use the first two
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Inks {
+    pub colors: [Color; 3],
+}
+
+impl Default for Inks {
+    fn default() -> Self {
+        Inks {
+            colors: [
+                Color::BLACK,
+                // teal
+                Color::new(0x1f, 0x7a, 0x8c),
+                // mustard
+                Color::new(0xe0, 0xa4, 0x1b),
+            ],
+        }
+    }
+}
+
+impl Inks {
+    // which of the first `count` inks prints the tones around `brightness`,
+    // each taking an equal share of the range from dark to light
+    pub fn index_at(self, count: usize, brightness: f32) -> usize {
+        ((brightness.clamp(0.0, 1.0) * count as f32) as usize).min(count - 1)
+    }
+}
+
+pub const MAX_PALETTE_COLORS: usize = 8;
+// k-means always settles within this many rounds on the handful of colors a palette has
+const KMEANS_ITERATIONS: usize = 16;
+
+// a limited set of inks that every shape is snapped to, for screen printing
+// or plotting with a few pens. only the first `len` colors are used.
+// when `automatic`, they are picked from the image with `kmeans` instead
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    pub colors: [Color; MAX_PALETTE_COLORS],
+    pub len: usize,
+    pub automatic: bool,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette {
+            colors: [
+                Color::BLACK,
+                // process cyan, magenta and yellow
+                Color::new(0x00, 0x9f, 0xe3),
+                Color::new(0xe5, 0x00, 0x7e),
+                Color::new(0xff, 0xed, 0x00),
+                Color::new(0xd7, 0x26, 0x1e),
+                Color::new(0x00, 0x96, 0x4b),
+                Color::new(0x1d, 0x3f, 0x9a),
+                Color::new(0xff, 0xff, 0xff),
+            ],
+            len: 4,
+            automatic: true,
+        }
+    }
+}
+
+impl Palette {
+    pub fn colors(&self) -> &[Color] {
+        &self.colors[..self.len.clamp(1, MAX_PALETTE_COLORS)]
+    }
+
+    pub fn nearest(&self, color: Color) -> Color {
+        let distance = |other: &Color| {
+            let (r, g, b) = (
+                color.r as i32 - other.r as i32,
+                color.g as i32 - other.g as i32,
+                color.b as i32 - other.b as i32,
+            );
+            r * r + g * g + b * b
+        };
+
+        self.colors()
+            .iter()
+            .copied()
+            .min_by_key(distance)
+            .unwrap_or(Color::BLACK)
+    }
+}
+
+// the `count` colors that best stand in for `pixels`, by k-means clustering, darkest first.
+// clusters start from colors spread evenly over the pixels sorted by brightness,
+// so the same pixels always give the same palette
+pub fn kmeans(pixels: &[Color], count: usize) -> Vec<Color> {
+    if pixels.is_empty() || count == 0 {
+        return vec![];
+    }
+
+    let as_f32 = |color: &Color| [color.r as f32, color.g as f32, color.b as f32];
+    let luma = |color: &[f32; 3]| 0.299 * color[0] + 0.587 * color[1] + 0.114 * color[2];
+
+    let mut sorted = pixels.iter().map(as_f32).collect::<Vec<_>>();
+    sorted.sort_by(|a, b| {
+        luma(a)
+            .partial_cmp(&luma(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut centers = (0..count)
+        .map(|i| sorted[(2 * i + 1) * sorted.len() / (2 * count)])
+        .collect::<Vec<_>>();
+
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut sums = vec![[0.0f32; 3]; count];
+        let mut counts = vec![0usize; count];
+
+        for pixel in sorted.iter() {
+            let nearest = (0..count)
+                .min_by(|a, b| {
+                    let distance = |center: &[f32; 3]| {
+                        (0..3).map(|c| (pixel[c] - center[c]).powi(2)).sum::<f32>()
+                    };
+                    distance(&centers[*a])
+                        .partial_cmp(&distance(&centers[*b]))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap_or(0);
+
+            for c in 0..3 {
+                sums[nearest][c] += pixel[c];
+            }
+            counts[nearest] += 1;
+        }
+
+        let mut moved = false;
+        for (center, (sum, n)) in centers.iter_mut().zip(sums.iter().zip(counts)) {
+            // an empty cluster keeps its old center
+            if n == 0 {
+                continue;
+            }
+
+            let mean = [sum[0] / n as f32, sum[1] / n as f32, sum[2] / n as f32];
+            moved |= mean != *center;
+            *center = mean;
+        }
+
+        if !moved {
+            break;
+        }
+    }
+
+    centers.sort_by(|a, b| {
+        luma(a)
+            .partial_cmp(&luma(b))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    centers
+        .iter()
+        .map(|[r, g, b]| Color::new(r.round() as u8, g.round() as u8, b.round() as u8))
+        .collect()
+}
+
+// colors closer than this (CIE76 delta E) are hard to tell apart as small dots
+const LOOK_ALIKE_DISTANCE: f32 = 20.0;
+
+// the Okabe-Ito colors, in the order that keeps each prefix of them the most
+// distinct under every kind of `ColorVision`. the first six never look alike
+const COLOR_BLIND_SAFE: [Color; 8] = [
+    Color::BLACK,
+    // yellow
+    Color::new(0xf0, 0xe4, 0x42),
+    // sky blue
+    Color::new(0x56, 0xb4, 0xe9),
+    // vermillion
+    Color::new(0xd5, 0x5e, 0x00),
+    // blue
+    Color::new(0x00, 0x72, 0xb2),
+    // reddish purple
+    Color::new(0xcc, 0x79, 0xa7),
+    // orange
+    Color::new(0xe6, 0x9f, 0x00),
+    // bluish green
+    Color::new(0x00, 0x9e, 0x73),
+];
+
+// `count` plate colors that stay distinct with color vision deficiencies, darkest first
+pub fn color_blind_safe(count: usize) -> Vec<Color> {
+    let mut colors = COLOR_BLIND_SAFE
+        .iter()
+        .copied()
+        .take(count)
+        .collect::<Vec<_>>();
+
+    colors.sort_by(|a, b| {
+        a.luma()
+            .partial_cmp(&b.luma())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    colors
+}
+
+// the first pair of `colors` (by index) that look alike, and to whom
+pub fn look_alike(colors: &[Color]) -> Option<(usize, usize, ColorVision)> {
+    for vision in ColorVision::visions() {
+        let seen = colors
+            .iter()
+            .map(|color| vision.simulate(*color).to_lab())
+            .collect::<Vec<_>>();
+
+        for i in 0..seen.len() {
+            for j in i + 1..seen.len() {
+                let distance = (0..3)
+                    .map(|c| (seen[i][c] - seen[j][c]).powi(2))
+                    .sum::<f32>()
+                    .sqrt();
+
+                if distance < LOOK_ALIKE_DISTANCE {
+                    return Some((i, j, *vision));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// the common color vision deficiencies, each simulated with the full-severity
+// matrices from Machado, Oliveira and Fernandes (2009), which act on linear RGB
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ColorVision {
+    Typical,
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+impl ColorVision {
+    pub fn from_string(s: &str) -> Option<ColorVision> {
+        match s {
+            "Typical" => Some(ColorVision::Typical),
+            "Protanopia" => Some(ColorVision::Protanopia),
+            "Deuteranopia" => Some(ColorVision::Deuteranopia),
+            "Tritanopia" => Some(ColorVision::Tritanopia),
+            _ => None,
+        }
+    }
+
+    pub fn visions() -> std::slice::Iter<'static, Self> {
+        const VISIONS: [ColorVision; 4] = [
+            ColorVision::Typical,
+            ColorVision::Protanopia,
+            ColorVision::Deuteranopia,
+            ColorVision::Tritanopia,
+        ];
+        VISIONS.iter()
+    }
+
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            ColorVision::Typical => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            ColorVision::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            ColorVision::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            ColorVision::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+
+    pub fn simulate(self, color: Color) -> Color {
+        if self == ColorVision::Typical {
+            return color;
+        }
+
+        let linear = [to_linear(color.r), to_linear(color.g), to_linear(color.b)];
+        let m = self.matrix();
+        let channel = |row: [f32; 3]| {
+            from_linear(row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2])
+        };
+
+        Color::new(channel(m[0]), channel(m[1]), channel(m[2]))
+    }
+
+    // in place, keeping each pixel's alpha
+    pub fn simulate_image(self, image: &mut RgbaImage) {
+        if self == ColorVision::Typical {
+            return;
+        }
+
+        for pixel in image.pixels_mut() {
+            let Color { r, g, b } = self.simulate(Color::from_rgba(*pixel));
+            *pixel = Rgba([r, g, b, pixel[3]]);
+        }
+    }
+}
+
+impl fmt::Display for ColorVision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ColorVision::Typical => "Typical",
+            ColorVision::Protanopia => "Protanopia",
+            ColorVision::Deuteranopia => "Deuteranopia",
+            ColorVision::Tritanopia => "Tritanopia",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// sRGB transfer function
+fn to_linear(channel: u8) -> f32 {
+    let c = channel as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(linear: f32) -> u8 {
+    let c = linear.clamp(0.0, 1.0);
+    let c = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (c * 255.0).round() as u8
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt;
+use yew::{
+    html, html::ChangeData, Callback, Component, ComponentLink, Html, InputData, Properties,
+    ShouldRender,
+};
+
+// the pages of a pinned run next to the same pages of the latest run, to judge small
+// differences in dot size or color depth. pages are paired up in the order they were made.
+// the pinned pages' object urls are kept by whoever pinned them, this only shows them
+pub struct Comparison {
+    link: ComponentLink<Self>,
+    props: ComparisonProps,
+    view: ComparisonView,
+    // how far across the pinned page shows in `ComparisonView::Split`, in percent
+    split: f32,
+}
+
+pub enum ComparisonMsg {
+    UpdateView(String),
+    UpdateSplit(String),
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct ComparisonProps {
+    // (filename, object url) of each page, as in the output gallery
+    pub pinned: Vec<(String, String)>,
+    pub current: Vec<(String, String)>,
+    pub onunpin: Callback<()>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ComparisonView {
+    SideBySide,
+    // the latest page over the pinned one, cut off where the slider is
+    Split,
+}
+
+impl ComparisonView {
+    fn from_string(s: &str) -> Option<ComparisonView> {
+        match s {
+            "side by side" => Some(ComparisonView::SideBySide),
+            "split" => Some(ComparisonView::Split),
+            _ => None,
+        }
+    }
+
+    fn views() -> std::slice::Iter<'static, Self> {
+        const VIEWS: [ComparisonView; 2] = [ComparisonView::SideBySide, ComparisonView::Split];
+        VIEWS.iter()
+    }
+}
+
+impl fmt::Display for ComparisonView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ComparisonView::SideBySide => "side by side",
+            ComparisonView::Split => "split",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl Comparison {
+    fn side_by_side(pinned: Option<&(String, String)>, current: Option<&(String, String)>) -> Html {
+        let page = |page: Option<&(String, String)>, caption: &str| match page {
+            Some((filename, url)) => html! {
+                <figure class="comparison-page">
+                    <img src={url.to_string()} alt={filename.to_string()} />
+                    <figcaption>{ format!("{}: {}", caption, filename) }</figcaption>
+                </figure>
+            },
+            None => html! {},
+        };
+
+        html! {
+            <div>
+                { page(pinned, "pinned") }
+                { page(current, "latest") }
+            </div>
+        }
+    }
+
+    fn split(&self, pinned: &(String, String), current: &(String, String)) -> Html {
+        let (pinned_filename, pinned_url) = pinned;
+        let (current_filename, current_url) = current;
+
+        html! {
+            <div class="comparison-split">
+                <img src={pinned_url.to_string()} alt={pinned_filename.to_string()} />
+                <img
+                class="comparison-current"
+                style={ format!("clip-path: inset(0 0 0 {}%);", self.split) }
+                src={current_url.to_string()}
+                alt={current_filename.to_string()} />
+            </div>
+        }
+    }
+}
+
+impl Component for Comparison {
+    type Message = ComparisonMsg;
+    type Properties = ComparisonProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Comparison {
+            link,
+            props,
+            view: ComparisonView::SideBySide,
+            split: 50.0,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            ComparisonMsg::UpdateView(s) => match ComparisonView::from_string(&s) {
+                Some(view) => {
+                    self.view = view;
+                    true
+                }
+                None => false,
+            },
+            ComparisonMsg::UpdateSplit(s) => match s.parse::<f32>() {
+                Ok(split) => {
+                    self.split = split.clamp(0.0, 100.0);
+                    true
+                }
+                Err(_) => false,
+            },
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.props != props {
+            self.props = props;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn view(&self) -> Html {
+        let pages = self.props.pinned.len().max(self.props.current.len());
+        let onunpin = self.props.onunpin.clone();
+
+        html! {
+            <div>
+                <div>
+                    { "compare: " }
+                    <select name="comparison-view" onchange=self.link.callback(|e: ChangeData| {
+                        match e {
+                            ChangeData::Select(s) => ComparisonMsg::UpdateView(s.value()),
+                            _ => unreachable!()
+                        }
+                    })>
+                    {
+                        for ComparisonView::views().map(|view| {
+                            html! {
+                                <option value={ view.to_string() } selected={ *view == self.view }> { view.to_string() } </option>
+                            }
+                        })
+                    }
+                    </select>
+                    {
+                        if self.view == ComparisonView::Split {
+                            html! {
+                                <input
+                                type="range"
+                                name="comparison-split"
+                                aria-label="where the pinned page ends"
+                                min="0"
+                                max="100"
+                                value={ self.split.to_string() }
+                                oninput=self.link.callback(|e: InputData| ComparisonMsg::UpdateSplit(e.value))/>
+                            }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    <button onclick=Callback::from(move |_| onunpin.emit(()))>
+                        { "unpin" }
+                    </button>
+                </div>
+
+                {
+                    for (0..pages).map(|i| {
+                        let pinned = self.props.pinned.get(i);
+                        let current = self.props.current.get(i);
+
+                        match (self.view, pinned, current) {
+                            (ComparisonView::Split, Some(pinned), Some(current)) => {
+                                self.split(pinned, current)
+                            }
+                            // a page only one of the runs has is shown on its own
+                            _ => Comparison::side_by_side(pinned, current),
+                        }
+                    })
+                }
+            </div>
+        }
+    }
+}
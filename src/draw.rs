@@ -0,0 +1,421 @@
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+const CHANNELS: usize = 4;
+
+// how dots are drawn onto raster pages.
+// centers are in pixel coordinates, where pixel (x, y) is centered on (x, y).
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CircleAlgorithm {
+    // `imageproc`'s midpoint circle routine.
+    // the radius is truncated to whole pixels and small circles come out lopsided
+    Imageproc,
+    // fills every pixel whose center is inside the circle, one row span at a time,
+    // writing each span straight into the page's buffer
+    Scanline,
+    // like `Scanline`, but edge pixels are blended by how much of them the circle covers.
+    // the other dot shapes, dashes and lines are antialiased the same way
+    Antialiased,
+}
+
+impl CircleAlgorithm {
+    pub fn from_string(s: &str) -> Option<CircleAlgorithm> {
+        match s {
+            "imageproc" => Some(CircleAlgorithm::Imageproc),
+            "Scanline" => Some(CircleAlgorithm::Scanline),
+            "Antialiased" => Some(CircleAlgorithm::Antialiased),
+            _ => None,
+        }
+    }
+
+    pub fn algorithms() -> std::slice::Iter<'static, Self> {
+        const ALGORITHMS: [CircleAlgorithm; 3] = [
+            CircleAlgorithm::Imageproc,
+            CircleAlgorithm::Scanline,
+            CircleAlgorithm::Antialiased,
+        ];
+        ALGORITHMS.iter()
+    }
+}
+
+impl fmt::Display for CircleAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            CircleAlgorithm::Imageproc => "imageproc",
+            CircleAlgorithm::Scanline => "Scanline",
+            CircleAlgorithm::Antialiased => "Antialiased",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+pub fn draw_circle(
+    image: &mut RgbaImage,
+    center: (f32, f32),
+    radius: f32,
+    color: Rgba<u8>,
+    algorithm: CircleAlgorithm,
+) {
+    match algorithm {
+        CircleAlgorithm::Imageproc => imageproc::drawing::draw_filled_circle_mut(
+            image,
+            (center.0.round() as i32, center.1.round() as i32),
+            radius as i32,
+            color,
+        ),
+        CircleAlgorithm::Scanline => fill_circle_scanline(image, center, radius, color),
+        CircleAlgorithm::Antialiased => fill_circle_antialiased(image, center, radius, color),
+    }
+}
+
+fn fill_circle_scanline(image: &mut RgbaImage, center: (f32, f32), radius: f32, color: Rgba<u8>) {
+    if radius < 0.0 {
+        return;
+    }
+
+    let (width, height) = image.dimensions();
+    let (center_x, center_y) = center;
+    let radius_squared = radius * radius;
+
+    let y_start = (center_y - radius).ceil().max(0.0) as u32;
+    let y_end = (center_y + radius).floor().min(height as f32 - 1.0);
+
+    if y_end < 0.0 {
+        return;
+    }
+
+    for y in y_start..=y_end as u32 {
+        let dy = y as f32 - center_y;
+        let half_span = (radius_squared - dy * dy).max(0.0).sqrt();
+
+        let x_start = (center_x - half_span).ceil().max(0.0) as u32;
+        let x_end = (center_x + half_span).floor().min(width as f32 - 1.0);
+
+        if x_end < 0.0 {
+            continue;
+        }
+
+        fill_span(image, y, x_start, x_end as u32, color);
+    }
+}
+
+// pixels `x_start` to `x_end` of row `y`, both included, set to `color`.
+// rows are contiguous in the buffer, so this skips `put_pixel`'s bounds checks on every pixel
+fn fill_span(image: &mut RgbaImage, y: u32, x_start: u32, x_end: u32, color: Rgba<u8>) {
+    if x_start > x_end {
+        return;
+    }
+
+    let width = image.width() as usize;
+    let row_start = y as usize * width;
+    let start = (row_start + x_start as usize) * CHANNELS;
+    let end = (row_start + x_end as usize + 1) * CHANNELS;
+
+    for pixel in image.as_mut()[start..end].chunks_exact_mut(CHANNELS) {
+        pixel.copy_from_slice(&color.0);
+    }
+}
+
+fn fill_circle_antialiased(
+    image: &mut RgbaImage,
+    center: (f32, f32),
+    radius: f32,
+    color: Rgba<u8>,
+) {
+    if radius <= 0.0 {
+        return;
+    }
+
+    let (width, height) = image.dimensions();
+    let (center_x, center_y) = center;
+
+    // pixels within half a pixel of the edge are partially covered
+    let outer = radius + 0.5;
+    let inner = (radius - 0.5).max(0.0);
+    let outer_squared = outer * outer;
+    let inner_squared = inner * inner;
+
+    let y_start = (center_y - outer).floor().max(0.0) as u32;
+    let y_end = (center_y + outer).ceil().min(height as f32 - 1.0);
+    let x_start = (center_x - outer).floor().max(0.0) as u32;
+    let x_end = (center_x + outer).ceil().min(width as f32 - 1.0);
+
+    if y_end < 0.0 || x_end < 0.0 {
+        return;
+    }
+
+    for y in y_start..=y_end as u32 {
+        let dy = y as f32 - center_y;
+
+        for x in x_start..=x_end as u32 {
+            let dx = x as f32 - center_x;
+            let distance_squared = dx * dx + dy * dy;
+
+            if distance_squared >= outer_squared {
+                continue;
+            }
+
+            if distance_squared <= inner_squared {
+                image.put_pixel(x, y, color);
+            } else {
+                // approximate coverage by the distance from the pixel center to the edge
+                let coverage = (outer - distance_squared.sqrt()).clamp(0.0, 1.0);
+                let existing = *image.get_pixel(x, y);
+                image.put_pixel(x, y, blend(existing, color, coverage));
+            }
+        }
+    }
+}
+
+// `source` composited over `destination` with its alpha scaled by `coverage`
+fn blend(destination: Rgba<u8>, source: Rgba<u8>, coverage: f32) -> Rgba<u8> {
+    let source_alpha = source[3] as f32 / 255.0 * coverage;
+    let destination_alpha = destination[3] as f32 / 255.0;
+    let out_alpha = source_alpha + destination_alpha * (1.0 - source_alpha);
+
+    if out_alpha <= 0.0 {
+        return Rgba([0, 0, 0, 0]);
+    }
+
+    let channel = |i: usize| {
+        let source_channel = source[i] as f32 * source_alpha;
+        let destination_channel = destination[i] as f32 * destination_alpha * (1.0 - source_alpha);
+        ((source_channel + destination_channel) / out_alpha).round() as u8
+    };
+
+    Rgba([
+        channel(0),
+        channel(1),
+        channel(2),
+        (out_alpha * 255.0).round() as u8,
+    ])
+}
+
+// `image` shrunk into `target`, `factor` times smaller each way, each pixel being the
+// average of a `factor` x `factor` block. colors are weighted by their alpha, so the
+// transparent page around a dot doesn't darken its edges
+pub fn shrink(image: &RgbaImage, factor: u32, target: &mut RgbaImage) {
+    let blocks = (factor * factor) as u64;
+
+    for (x, y, pixel) in target.enumerate_pixels_mut() {
+        let mut totals = [0u64; CHANNELS];
+        for block_y in y * factor..(y + 1) * factor {
+            for block_x in x * factor..(x + 1) * factor {
+                let source = image.get_pixel(block_x, block_y);
+                let alpha = source[3] as u64;
+                for (total, channel) in totals.iter_mut().zip(&source.0[..3]) {
+                    *total += *channel as u64 * alpha;
+                }
+                totals[3] += alpha;
+            }
+        }
+
+        *pixel = if totals[3] == 0 {
+            Rgba([0, 0, 0, 0])
+        } else {
+            let channel = |i: usize| ((totals[i] + totals[3] / 2) / totals[3]) as u8;
+            let alpha = ((totals[3] + blocks / 2) / blocks) as u8;
+            Rgba([channel(0), channel(1), channel(2), alpha])
+        };
+    }
+}
+
+// a straight stroke `width` pixels wide with round ends,
+// filling every pixel whose center is inside it.
+// consecutive segments of a line overlap at their ends, so they join without gaps
+pub fn draw_segment(
+    image: &mut RgbaImage,
+    from: (f32, f32),
+    to: (f32, f32),
+    width: f32,
+    color: Rgba<u8>,
+    algorithm: CircleAlgorithm,
+) {
+    if width <= 0.0 {
+        return;
+    }
+
+    let (image_width, image_height) = image.dimensions();
+    let half_width = width / 2.0;
+    let half_width_squared = half_width * half_width;
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length_squared = dx * dx + dy * dy;
+
+    // antialiased pixels within half a pixel of the edge are partially covered
+    let antialiased = algorithm == CircleAlgorithm::Antialiased;
+    let reach = if antialiased {
+        half_width + 0.5
+    } else {
+        half_width
+    };
+
+    let x_start = (from.0.min(to.0) - reach).ceil().max(0.0) as u32;
+    let x_end = (from.0.max(to.0) + reach)
+        .floor()
+        .min(image_width as f32 - 1.0);
+    let y_start = (from.1.min(to.1) - reach).ceil().max(0.0) as u32;
+    let y_end = (from.1.max(to.1) + reach)
+        .floor()
+        .min(image_height as f32 - 1.0);
+
+    if x_end < 0.0 || y_end < 0.0 {
+        return;
+    }
+
+    for y in y_start..=y_end as u32 {
+        for x in x_start..=x_end as u32 {
+            let (px, py) = (x as f32 - from.0, y as f32 - from.1);
+
+            // the closest point on the segment, as a fraction of the way along it
+            let t = if length_squared > 0.0 {
+                ((px * dx + py * dy) / length_squared).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+
+            let distance_squared = (px - t * dx).powi(2) + (py - t * dy).powi(2);
+            if antialiased {
+                let coverage = (reach - distance_squared.sqrt()).clamp(0.0, 1.0);
+                cover(image, x, y, color, coverage);
+            } else if distance_squared <= half_width_squared {
+                image.put_pixel(x, y, color);
+            }
+        }
+    }
+}
+
+// `color` over pixel (`x`, `y`), as much as `coverage` of the pixel is covered
+fn cover(image: &mut RgbaImage, x: u32, y: u32, color: Rgba<u8>, coverage: f32) {
+    if coverage >= 1.0 {
+        image.put_pixel(x, y, color);
+    } else if coverage > 0.0 {
+        let existing = *image.get_pixel(x, y);
+        image.put_pixel(x, y, blend(existing, color, coverage));
+    }
+}
+
+// a closed outline, filling every pixel whose center is inside it.
+// each row is filled between pairs of the places the outline crosses it
+pub fn draw_polygon(
+    image: &mut RgbaImage,
+    points: &[(f32, f32)],
+    color: Rgba<u8>,
+    algorithm: CircleAlgorithm,
+) {
+    if points.len() < 3 {
+        return;
+    }
+
+    if algorithm == CircleAlgorithm::Antialiased {
+        fill_polygon_antialiased(image, points, color);
+        return;
+    }
+
+    let (image_width, image_height) = image.dimensions();
+
+    let (y_min, y_max) = points
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), point| {
+            (min.min(point.1), max.max(point.1))
+        });
+    let y_start = y_min.ceil().max(0.0) as u32;
+    let y_end = y_max.floor().min(image_height as f32 - 1.0);
+
+    if y_end < 0.0 {
+        return;
+    }
+
+    let mut crossings = Vec::with_capacity(points.len());
+
+    for y in y_start..=y_end as u32 {
+        let row = y as f32;
+
+        crossings.clear();
+        for (i, from) in points.iter().enumerate() {
+            let to = points[(i + 1) % points.len()];
+            if (from.1 <= row) != (to.1 <= row) {
+                crossings.push(from.0 + (row - from.1) / (to.1 - from.1) * (to.0 - from.0));
+            }
+        }
+        crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        for span in crossings.chunks_exact(2) {
+            let x_start = span[0].ceil().max(0.0);
+            let x_end = span[1].floor().min(image_width as f32 - 1.0);
+
+            if x_end < x_start {
+                continue;
+            }
+
+            fill_span(image, y, x_start as u32, x_end as u32, color);
+        }
+    }
+}
+
+// every pixel within half a pixel of the outline, covered by how far inside of it
+// its center is, and everything further inside filled in
+fn fill_polygon_antialiased(image: &mut RgbaImage, points: &[(f32, f32)], color: Rgba<u8>) {
+    let (image_width, image_height) = image.dimensions();
+
+    let (x_min, y_min, x_max, y_max) = points.iter().fold(
+        (
+            f32::INFINITY,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::NEG_INFINITY,
+        ),
+        |(x_min, y_min, x_max, y_max), point| {
+            (
+                x_min.min(point.0),
+                y_min.min(point.1),
+                x_max.max(point.0),
+                y_max.max(point.1),
+            )
+        },
+    );
+    let x_start = (x_min - 0.5).floor().max(0.0) as u32;
+    let x_end = (x_max + 0.5).ceil().min(image_width as f32 - 1.0);
+    let y_start = (y_min - 0.5).floor().max(0.0) as u32;
+    let y_end = (y_max + 0.5).ceil().min(image_height as f32 - 1.0);
+
+    if x_end < 0.0 || y_end < 0.0 {
+        return;
+    }
+
+    for y in y_start..=y_end as u32 {
+        for x in x_start..=x_end as u32 {
+            let (px, py) = (x as f32, y as f32);
+            let mut inside = false;
+            let mut distance_squared = f32::INFINITY;
+
+            for (i, from) in points.iter().enumerate() {
+                let to = points[(i + 1) % points.len()];
+
+                if (from.1 <= py) != (to.1 <= py)
+                    && px < from.0 + (py - from.1) / (to.1 - from.1) * (to.0 - from.0)
+                {
+                    inside = !inside;
+                }
+
+                let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+                let length_squared = dx * dx + dy * dy;
+                let t = if length_squared > 0.0 {
+                    (((px - from.0) * dx + (py - from.1) * dy) / length_squared).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                distance_squared = distance_squared
+                    .min((px - from.0 - t * dx).powi(2) + (py - from.1 - t * dy).powi(2));
+            }
+
+            let distance = distance_squared.sqrt();
+            let coverage = if inside {
+                0.5 + distance
+            } else {
+                0.5 - distance
+            };
+            cover(image, x, y, color, coverage.clamp(0.0, 1.0));
+        }
+    }
+}
@@ -0,0 +1,162 @@
+// finds the outlines in an image, for strokes drawn over the halftone where
+// squares too big to show fine detail would otherwise lose it.
+// brightness is smoothed, sobel gives each pixel's gradient, and pixels are only kept
+// where the gradient is steeper than `threshold` and steeper than both of their neighbors
+// across the edge (as in canny edge detection), which thins edges to a pixel wide.
+// neighboring edge pixels are then joined up into strokes.
+
+// shorter strokes are mostly noise
+const MIN_STROKE_POINTS: usize = 3;
+
+// the horizontal and vertical gradients of a 3x3 neighborhood,
+// where `sample(dx, dy)` is the value at offsets of -1, 0 or 1
+pub fn sobel(sample: impl Fn(i32, i32) -> f32) -> (f32, f32) {
+    let x = sample(1, -1) + 2.0 * sample(1, 0) + sample(1, 1)
+        - sample(-1, -1)
+        - 2.0 * sample(-1, 0)
+        - sample(-1, 1);
+    let y = sample(-1, 1) + 2.0 * sample(0, 1) + sample(1, 1)
+        - sample(-1, -1)
+        - 2.0 * sample(0, -1)
+        - sample(1, -1);
+
+    (x, y)
+}
+
+// `brightness` is `width` * `height` values from 0 to 1, row by row.
+// each stroke is a list of pixel centers
+pub fn edge_strokes(
+    brightness: &[f32],
+    width: u32,
+    height: u32,
+    threshold: f32,
+) -> Vec<Vec<(f32, f32)>> {
+    let (width, height) = (width as usize, height as usize);
+    if width < 3 || height < 3 || brightness.len() != width * height {
+        return vec![];
+    }
+
+    let smoothed = smooth(brightness, width, height);
+
+    let mut gradients = vec![(0.0, 0.0); width * height];
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            gradients[y * width + x] = sobel(|dx, dy| {
+                smoothed[(y as i32 + dy) as usize * width + (x as i32 + dx) as usize]
+            });
+        }
+    }
+
+    let magnitude = |x: usize, y: usize| {
+        let (gradient_x, gradient_y) = gradients[y * width + x];
+        gradient_x.hypot(gradient_y)
+    };
+
+    let mut edges = vec![false; width * height];
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let here = magnitude(x, y);
+            if here < threshold {
+                continue;
+            }
+
+            // the neighbors on either side, across the edge
+            let (gradient_x, gradient_y) = gradients[y * width + x];
+            let (dx, dy) = across(gradient_x, gradient_y);
+            let before = magnitude((x as i32 - dx) as usize, (y as i32 - dy) as usize);
+            let after = magnitude((x as i32 + dx) as usize, (y as i32 + dy) as usize);
+
+            edges[y * width + x] = here >= before && here >= after;
+        }
+    }
+
+    join(&mut edges, width, height)
+}
+
+// a [1, 2, 1] blur each way, to keep sobel from picking up single pixel noise
+fn smooth(values: &[f32], width: usize, height: usize) -> Vec<f32> {
+    let blur = |values: &[f32], step: usize, length: usize, index: usize, position: usize| {
+        let before = if position > 0 { index - step } else { index };
+        let after = if position + 1 < length {
+            index + step
+        } else {
+            index
+        };
+        (values[before] + 2.0 * values[index] + values[after]) / 4.0
+    };
+
+    let horizontal = (0..width * height)
+        .map(|i| blur(values, 1, width, i, i % width))
+        .collect::<Vec<_>>();
+
+    (0..width * height)
+        .map(|i| blur(&horizontal, width, height, i, i / width))
+        .collect()
+}
+
+// the step to the next pixel along the gradient, to the nearest 45 degrees
+fn across(gradient_x: f32, gradient_y: f32) -> (i32, i32) {
+    let angle = gradient_y.atan2(gradient_x).to_degrees().rem_euclid(180.0);
+
+    if !(22.5..157.5).contains(&angle) {
+        (1, 0)
+    } else if angle < 67.5 {
+        (1, 1)
+    } else if angle < 112.5 {
+        (0, 1)
+    } else {
+        (-1, 1)
+    }
+}
+
+// follows each run of touching edge pixels, left-right top-bottom,
+// taking each pixel out of `edges` as it is used
+fn join(edges: &mut [bool], width: usize, height: usize) -> Vec<Vec<(f32, f32)>> {
+    let mut strokes = vec![];
+
+    for start in 0..edges.len() {
+        if !edges[start] {
+            continue;
+        }
+
+        edges[start] = false;
+        let (mut x, mut y) = (start % width, start / width);
+        let mut stroke = vec![(x as f32, y as f32)];
+
+        // straight neighbors first, so strokes don't cut corners
+        'walk: loop {
+            for (dx, dy) in [
+                (1, 0),
+                (0, 1),
+                (-1, 0),
+                (0, -1),
+                (1, 1),
+                (-1, 1),
+                (1, -1),
+                (-1, -1),
+            ] {
+                let (next_x, next_y) = (x as i32 + dx, y as i32 + dy);
+                if next_x < 0 || next_y < 0 || next_x >= width as i32 || next_y >= height as i32 {
+                    continue;
+                }
+
+                let next = next_y as usize * width + next_x as usize;
+                if edges[next] {
+                    edges[next] = false;
+                    x = next_x as usize;
+                    y = next_y as usize;
+                    stroke.push((x as f32, y as f32));
+                    continue 'walk;
+                }
+            }
+
+            break;
+        }
+
+        if stroke.len() >= MIN_STROKE_POINTS {
+            strokes.push(stroke);
+        }
+    }
+
+    strokes
+}
@@ -0,0 +1,13 @@
+pub mod api;
+pub mod archive;
+pub mod capabilities;
+pub mod color;
+pub mod draw;
+pub mod edges;
+pub mod mesh;
+pub mod rasterize;
+pub mod resample;
+pub mod settings;
+pub mod stipple;
+pub mod tsp;
+pub mod voronoi;
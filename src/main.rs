@@ -1,406 +1,1371 @@
-mod rasterize;
+mod bitmap;
+mod camera;
+mod compare;
+mod project;
+mod stepper;
+mod viewer;
 
 const RAT_VERSION: &str = env!("RAT_VERSION");
+const SETTINGS_STORAGE_KEY: &str = "rat.settings";
+const PRESETS_STORAGE_KEY: &str = "rat.presets";
+// sampling happens at `rasterize::PIXELS_PER_INCH`,
+// the image backend can draw its pages at any of these
+const OUTPUT_PIXELS_PER_INCH: [f32; 4] = [72.0, 150.0, 300.0, 600.0];
+// the radius curve editor is this many pixels square
+const RADIUS_CURVE_EDITOR_PIXELS: f32 = 160.0;
+// per page, in the svg backend's plot preview animation
+const PLOT_PREVIEW_FRAMES: u32 = 40;
+const PLOT_PREVIEW_FRAME_DELAY_MS: u32 = 100;
+// the most decimal places svg numbers are rounded to, past which rounding saves nothing
+const MAX_SVG_PRECISION: u8 = 4;
+// past 100%, dots overlap their neighbors ("halo mode")
+const MAX_RADIUS_PERCENTAGE_LIMIT: isize = 200;
+// of the image, from any one side
+const MAX_CROP_FRACTION: f32 = 0.95;
+// drags of the layout preview shorter than this, in pixels, are clicks rather than regions
+const MIN_REGION_DRAG: i32 = 4;
+// with auto rasterize on, how long after the last change a run starts,
+// so a run isn't started and thrown away for every step of a slider drag
+const AUTO_RASTERIZE_DELAY_MS: u64 = 500;
+// the most frames of an animated source that are rasterized
+const MAX_SOURCE_FRAMES: usize = 300;
+// of the image backend's build up animation, which holds on the finished poster
+const BUILD_UP_FRAMES: u32 = 24;
+const BUILD_UP_FRAME_DELAY_MS: u32 = 80;
+const BUILD_UP_FINISHED_DELAY_MS: u32 = 2000;
+// the smallest wasm module using a simd instruction, which browsers without simd
+// refuse to validate. the same check the wasm-feature-detect library does
+const SIMD_PROBE_MODULE: [u8; 31] = [
+    0, 97, 115, 109, 1, 0, 0, 0, 1, 5, 1, 96, 0, 1, 123, 3, 2, 1, 0, 10, 10, 1, 8, 0, 65, 0, 253,
+    15, 253, 98, 11,
+];
 
-use crate::rasterize::{ColorDepth, Orientation, PaperSize};
-use image::{ImageBuffer, Rgba};
+use crate::archive::{ZipCompression, ZipError, ZipWriter};
+use crate::color::{Color, ColorVision, MAX_PALETTE_COLORS};
+use crate::compare::Comparison;
+use crate::draw::CircleAlgorithm;
+use crate::project::ProjectManifest;
+use crate::rasterize::{
+    BuildUp, Channel, ColorDepth, ColorProfile, CropSide, Dither, DotShape, Framing, Layout, Luma,
+    MaskBlend, MatrixParameter, Orientation, PageFormat, PagePool, PageStream, PaperSize,
+    ParameterMatrix, Pattern, RadiusCurve, ReliefShape, Resampling, RowOrder, SourceImage,
+    RADIUS_CURVE_POINTS,
+};
+use crate::settings::{parse_number, Backend, Settings, SettingsHistory};
+use crate::stepper::Stepper;
+use crate::viewer::Gallery;
+use crate::voronoi::Stippler;
+use image::{AnimationDecoder, ImageBuffer, Rgba, RgbaImage};
+use rat::{archive, capabilities, color, draw, rasterize, settings, voronoi};
 use std::borrow::Borrow;
+use std::collections::BTreeMap;
 use std::fmt;
-use std::io::{Cursor, Seek, Write};
+use std::io::Cursor;
+use std::marker::PhantomData;
+use std::path::Path;
 use std::rc::Rc;
+use std::time::Duration;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{Clamped, JsCast};
 use web_sys::console;
-use web_sys::File;
+use web_sys::{File, MouseEvent};
+use yew::format::Json;
 use yew::services::reader::{FileData, ReaderTask};
-use yew::services::ReaderService;
+use yew::services::storage::Area;
+use yew::services::timeout::TimeoutTask;
+use yew::services::{ReaderService, StorageService, TimeoutService};
 use yew::{
-    html, html::ChangeData, Component, ComponentLink, Html, InputData, Properties, ShouldRender,
+    classes, html, html::ChangeData, Callback, Component, ComponentLink, Html, InputData, NodeRef,
+    Properties, ShouldRender,
 };
 
 enum MimeType {
+    Gif,
     Png,
+    Jpeg,
+    Webp,
     Svg,
+    // plain text and g-code
+    Text,
+    Eps,
+    Dxf,
+    Stl,
+    Csv,
+    Html,
     Zip,
 }
 
+impl MimeType {
+    // whether it can be shown as an `<img>`
+    fn is_image(&self) -> bool {
+        matches!(
+            self,
+            MimeType::Gif | MimeType::Png | MimeType::Jpeg | MimeType::Webp | MimeType::Svg
+        )
+    }
+}
+
 impl fmt::Display for MimeType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let s = match self {
+            MimeType::Gif => "image/gif",
             MimeType::Png => "image/png",
+            MimeType::Jpeg => "image/jpeg",
+            MimeType::Webp => "image/webp",
             MimeType::Svg => "image/svg+xml",
+            MimeType::Text => "text/plain",
+            MimeType::Eps => "application/postscript",
+            MimeType::Dxf => "image/vnd.dxf",
+            MimeType::Stl => "model/stl",
+            MimeType::Csv => "text/csv",
+            MimeType::Html => "text/html",
             MimeType::Zip => "application/zip",
         };
         write!(f, "{}", s)
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-enum Backend {
-    Image,
-    Svg,
-}
+// the files made from one page of a run, or from one of its outputs that isn't paged
+type OutputFiles = Vec<(String, Vec<u8>, MimeType)>;
 
-impl fmt::Display for Backend {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let s = match self {
-            Backend::Image => "Image",
-            Backend::Svg => "SVG",
-        };
-        write!(f, "{}", s)
+// what one kind of output turns a rasterization run into. each is shown by
+// `OutputBackendComponent`, so a new kind of output only has to say what files it makes
+trait OutputBackend: 'static {
+    // the kinds of file shown for outputs restored from a project
+    const PREVIEW_EXTENSIONS: &'static [&'static str];
+    // what the button that starts a run says
+    const ACTION: &'static str = "Rasterize";
+
+    // work a run does before it makes any files, a step at a time so the page can
+    // show its progress, like relaxing a voronoi stipple. most backends have none
+    type Preparation: Default;
+
+    fn preparation(_args: rasterize::RasterizeArgs, _settings: &Settings) -> Self::Preparation {
+        Self::Preparation::default()
     }
-}
 
-struct ImageBackend {
-    link: ComponentLink<Self>,
-    props: ImageBackendProps,
-    image_urls: Vec<String>,
-    zip_url: Option<String>,
-}
+    // takes one step of `preparation`, or returns false once it is done
+    fn prepare(_preparation: &mut Self::Preparation, _settings: &Settings) -> bool {
+        false
+    }
 
-pub enum ImageBackendMsg {
-    Rasterize,
-}
+    // how many of its steps `preparation` has taken, out of how many, if it takes any
+    fn progress(_preparation: &Self::Preparation, _settings: &Settings) -> Option<(u32, u32)> {
+        None
+    }
 
-#[derive(Clone, Properties)]
-struct ImageBackendProps {
-    pages_width: u32,
-    pages_height: u32,
-    image: Rc<Option<image::DynamicImage>>,
-    min_radius_percentage: f32,
-    max_radius_percentage: f32,
-    square_size: f32,
-    paper_size: PaperSize,
-    orientation: Orientation,
-    color_depth: ColorDepth,
+    // every file of a run, a page's worth at a time, in the order they are made.
+    // nothing is made until it is pulled from its stream, so a run can show its first
+    // page straight away and make the rest afterwards. image pages are drawn onto `pool`
+    fn outputs<'a>(
+        args: rasterize::RasterizeArgs<'a>,
+        settings: &'a Settings,
+        output_name: &'a str,
+        pool: &'a PagePool,
+        preparation: &'a Self::Preparation,
+    ) -> Vec<PageStream<'a, OutputFiles>>;
+
+    // how big a run would be, shown before it is started
+    fn estimate(args: rasterize::RasterizeArgs) -> Option<rasterize::Estimate> {
+        rasterize::estimate(args)
+    }
+
+    // how many dots a run left out for being too small to print
+    fn culled_dots(args: rasterize::RasterizeArgs) -> usize {
+        rasterize::culled_dot_count(args)
+    }
+
+    // the folders of the zip that each ink's layer goes in, for kinds of output that
+    // make a layer of each ink. the instructions in the zip point to them
+    fn plate_folders(_args: rasterize::RasterizeArgs, _settings: &Settings) -> Vec<String> {
+        vec![]
+    }
 }
 
-impl Component for ImageBackend {
-    type Message = ImageBackendMsg;
-    type Properties = ImageBackendProps;
+// where a run's files go as they are made. each is written straight into the
+// "download all" zip and given a preview, and the zip is the only place they are kept,
+// so that a big run doesn't hold every page as a raw image, then as a file, and then
+// again in the zip. exporting a project reads them back out of it
+struct OutputSink {
+    zip: ZipWriter,
+    // (filename, object url) for each file that can be shown
+    image_urls: Vec<(String, String)>,
+    // the first file that didn't fit in the zip. the run goes on without a zip
+    zip_error: Option<ZipError>,
+}
 
-    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        ImageBackend {
-            link,
-            props,
+impl OutputSink {
+    // the zip starts with the settings the outputs were made with, so they can be made again,
+    // and a map and instructions for putting the pages together
+    fn new(
+        args: rasterize::RasterizeArgs,
+        settings: &Settings,
+        output_name: &str,
+        plate_folders: &[String],
+    ) -> Self {
+        let mut sink = OutputSink {
+            zip: ZipWriter::new(settings.zip_compression),
             image_urls: vec![],
-            zip_url: None,
+            zip_error: None,
+        };
+        sink.keep(settings::SETTINGS_FILENAME.to_string(), settings.to_json());
+        if let Some(map) = rasterize::assembly_map(args) {
+            sink.keep(
+                rasterize::ASSEMBLY_MAP_FILENAME.to_string(),
+                map.to_string().into_bytes(),
+            );
         }
+        let instructions =
+            rasterize::assembly_instructions(args, output_name, &settings.summary(), plate_folders);
+        sink.keep(
+            rasterize::ASSEMBLY_INSTRUCTIONS_FILENAME.to_string(),
+            instructions.into_bytes(),
+        );
+        sink
     }
 
-    fn update(&mut self, msg: Self::Message) -> ShouldRender {
-        match msg {
-            Self::Message::Rasterize => {
-                if let Some(image) = self.props.image.borrow() {
-                    console::log_1(&"Starting rasterization".into());
-                    let paper_width_pixels =
-                        self.props.paper_size.width_pixels(self.props.orientation);
-                    let paper_height_pixels =
-                        self.props.paper_size.height_pixels(self.props.orientation);
-
-                    let args = rasterize::RasterizeArgs {
-                        image,
-                        paper_width_pixels,
-                        paper_height_pixels,
-                        pages_width: self.props.pages_width,
-                        pages_height: self.props.pages_height,
-                        square_size: self.props.square_size,
-                        min_radius_percentage: self.props.min_radius_percentage,
-                        max_radius_percentage: self.props.max_radius_percentage,
-                        color_depth: self.props.color_depth,
-                    };
+    fn keep(&mut self, filename: String, bytes: Vec<u8>) {
+        if self.zip_error.is_none() {
+            self.zip_error = self.zip.add(&filename, &bytes).err();
+        }
+    }
 
-                    let window = web_sys::window().unwrap().window();
-                    let performance = window.performance().unwrap();
-                    let start = performance.now();
-                    let subimages = rasterize::rasterize_image(args);
-                    let end = performance.now();
-                    let runtime = end - start;
-                    console::log_1(&runtime.into());
+    fn add(&mut self, filename: String, bytes: Vec<u8>, mime_type: MimeType) {
+        // plain text and g-code have no preview, they are only in the zip
+        if mime_type.is_image() {
+            let url = bytes_to_object_url(&bytes, &mime_type.to_string()).unwrap();
+            self.image_urls.push((filename.clone(), url));
+        }
 
-                    let mut pngs = vec![];
-                    let mut image_urls = vec![];
-                    let mut zip_inputs = vec![];
+        self.keep(filename, bytes);
+    }
 
-                    // encode image buffers as pngs
-                    for image in subimages {
-                        let png = encode_image_as_png_bytes(image);
-                        pngs.push(png);
-                    }
+    // hands the zip on to `on_outputs`, and gives back
+    // the previews and the object url of the zip
+    fn finish(self, on_outputs: &Callback<Rc<Vec<u8>>>) -> (Vec<(String, String)>, Option<String>) {
+        let zip = match self.zip_error {
+            Some(e) => Err(e),
+            None => self.zip.finish(),
+        };
+        let zip = match zip {
+            Ok(zip) => zip,
+            Err(e) => {
+                console::log_1(&format!("could not zip outputs: {}", e).into());
+                on_outputs.emit(Rc::default());
+                return (self.image_urls, None);
+            }
+        };
 
-                    // get image urls for each png so we can display them
-                    // on the page
-                    for png in pngs.iter() {
-                        let blob_url_str =
-                            bytes_to_object_url(png, &MimeType::Png.to_string()).unwrap();
-                        image_urls.push(blob_url_str);
-                    }
+        let zip_url = bytes_to_object_url(&zip, &MimeType::Zip.to_string()).ok();
+        on_outputs.emit(Rc::new(zip));
 
-                    self.image_urls = image_urls;
+        (self.image_urls, zip_url)
+    }
+}
 
-                    // zip up all pngs so we can provide the
-                    // "download all" link
-                    for (i, png) in pngs.into_iter().enumerate() {
-                        let filename = format!("{}.png", i + 1);
-                        zip_inputs.push((filename, png));
-                    }
+// png or jpeg pages, with a whole poster and ink layers if they are asked for
+struct ImageBackend;
+
+impl OutputBackend for ImageBackend {
+    const PREVIEW_EXTENSIONS: &'static [&'static str] = &["png", "jpg", "webp", "gif"];
+    type Preparation = ();
 
-                    let mut zip_buf = Cursor::new(vec![]);
-                    let _zipped_result = zip(&mut zip_buf, zip_inputs);
-                    let zip_url =
-                        bytes_to_object_url(zip_buf.get_ref(), &MimeType::Zip.to_string()).unwrap();
+    fn outputs<'a>(
+        args: rasterize::RasterizeArgs<'a>,
+        settings: &'a Settings,
+        output_name: &'a str,
+        pool: &'a PagePool,
+        _preparation: &'a (),
+    ) -> Vec<PageStream<'a, OutputFiles>> {
+        // a contact sheet stands in for the pages, to pick settings from before making them
+        if settings.contact_sheet.enabled {
+            return vec![PageStream::single(move || {
+                let sheet = match rasterize::contact_sheet(args, settings.contact_sheet) {
+                    Some(sheet) => sheet,
+                    None => return vec![],
+                };
+
+                vec![
+                    (
+                        format!("{}_contact_sheet.png", output_name),
+                        output_png_bytes(&sheet, settings),
+                        MimeType::Png,
+                    ),
+                    (
+                        format!("{}_contact_sheet.txt", output_name),
+                        settings.contact_sheet.legend().into_bytes(),
+                        MimeType::Text,
+                    ),
+                ]
+            })];
+        }
 
-                    self.zip_url = Some(zip_url);
+        let extension = page_format(settings).extension();
+        let mut outputs = vec![image_pages(args, settings, pool, move |i| {
+            format!("{}_{}.{}", output_name, i + 1, extension)
+        })];
 
-                    true
+        // the whole dot field as one image, for large-format printing
+        if settings.whole_poster && settings.layout != Layout::Matrix {
+            outputs.push(image_pages(
+                args.whole_poster(),
+                settings,
+                pool,
+                move |_| format!("{}_poster.{}", output_name, extension),
+            ));
+        }
+
+        // each ink of a duotone, tritone or palette as its own layer, to print separately
+        for (ink, folder) in Self::plate_folders(args, settings).into_iter().enumerate() {
+            outputs.push(image_pages(args.ink_layer(ink), settings, pool, move |i| {
+                format!("{}/{}_{}.{}", folder, output_name, i + 1, extension)
+            }));
+        }
+
+        if settings.build_up != BuildUp::None {
+            outputs.push(PageStream::single(move || {
+                // frames are encoded as they are drawn, with one drawn ahead to tell the last
+                let mut frames =
+                    rasterize::build_up(args, settings.build_up, BUILD_UP_FRAMES).peekable();
+                if frames.peek().is_none() {
+                    vec![]
                 } else {
-                    console::log_1(&"No image supplied, not rasterizing anything".into());
-                    false
+                    let frames = std::iter::from_fn(move || {
+                        let frame = frames.next()?;
+                        let delay_ms = if frames.peek().is_none() {
+                            BUILD_UP_FINISHED_DELAY_MS
+                        } else {
+                            BUILD_UP_FRAME_DELAY_MS
+                        };
+                        Some((frame, delay_ms))
+                    });
+                    let filename = format!("{}_build_up.gif", output_name);
+                    vec![(filename, encode_frames_as_gif_bytes(frames), MimeType::Gif)]
                 }
-            }
+            }));
         }
+
+        outputs
     }
 
-    // TODO figure out what to do here. Right now we naively rerender on any new props.
-    // The reason for this is because `image: Rc<Option<image::DynamicImage>>`
-    // does not implement `PartialEq`, otherwise we could derive it for the whole props.
-    fn change(&mut self, props: Self::Properties) -> ShouldRender {
-        self.props = props;
-        true
+    fn plate_folders(args: rasterize::RasterizeArgs, settings: &Settings) -> Vec<String> {
+        // a contact sheet is made instead of the pages, and their layers
+        if settings.contact_sheet.enabled {
+            vec![]
+        } else {
+            args.plate_folders()
+        }
     }
+}
 
-    fn view(&self) -> Html {
-        html! {
-            <div>
-                <div>
-                    <button onclick=self.link.callback(|_| Self::Message::Rasterize)>
-                        { "Rasterize" }
-                     </button>
-                </div>
+// webp pages are encoded by the browser, so one that can't encode them,
+// given settings from one that can, makes pngs instead
+fn page_format(settings: &Settings) -> PageFormat {
+    match settings.page_encoding.format {
+        PageFormat::Webp if !bitmap::can_encode_webp() => PageFormat::Png,
+        format => format,
+    }
+}
 
-                <div>
-                {
-                    if let Some(zip_url) = &self.zip_url {
-                        html! {
-                            <a style="display: inline;" href={zip_url.to_string()} alt={"download all"}>{"download all"}</a>
-                        }
-                    } else {
-                        html! {}
+// each page of a run, named by `filename` from its index, encoded as soon as it is
+// rasterized. pngs are drawn and encoded a strip at a time, so a page never has to
+// fit in memory whole. jpegs and webps are drawn whole, and their buffers go back
+// to `pool` for the next page to be drawn on
+fn image_pages<'a>(
+    args: rasterize::RasterizeArgs<'a>,
+    settings: &'a Settings,
+    pool: &'a PagePool,
+    filename: impl Fn(usize) -> String + 'a,
+) -> PageStream<'a, OutputFiles> {
+    match page_format(settings) {
+        PageFormat::Png => rasterize::rasterize_png(args).map_pages(move |i, png| {
+            let png = settings.tag_png(settings.color_profile.tag_png(png));
+            vec![(filename(i), png, MimeType::Png)]
+        }),
+        PageFormat::Jpeg => {
+            rasterize::rasterize_image_pooled(args, pool.clone()).map_pages(move |i, image| {
+                let jpeg = rasterize::encode_jpeg(&image, settings.page_encoding.quality);
+                pool.recycle(image);
+                vec![(filename(i), jpeg, MimeType::Jpeg)]
+            })
+        }
+        PageFormat::Webp => {
+            rasterize::rasterize_image_pooled(args, pool.clone()).map_pages(move |i, image| {
+                let webp = bitmap::encode_webp(&image, settings.page_encoding.quality);
+                pool.recycle(image);
+                match webp {
+                    Ok(webp) => vec![(filename(i), webp, MimeType::Webp)],
+                    Err(e) => {
+                        console::log_1(
+                            &format!("could not encode page {} as webp: {:?}", i + 1, e).into(),
+                        );
+                        vec![]
                     }
                 }
-                </div>
+            })
+        }
+    }
+}
 
-                <div>
-                {
-                    for self.image_urls.iter().map(|image_url| {
-                        html! {
-                            <div style="display: inline;">
-                                <a style="display: inline;" href={image_url.to_string()} alt={"meh"}>{"download"}</a>
-                                <img style="display: inline;" src={image_url.to_string()} alt={"meh"} />
-                            </div>
-                        }
+// svg pages, with a whole poster, plot preview animations, g-code, eps, dxf,
+// laser cutter svg, an stl relief, a csv dot map and a printable html page
+// if they are asked for
+struct SVGBackend;
+
+impl OutputBackend for SVGBackend {
+    const PREVIEW_EXTENSIONS: &'static [&'static str] = &["svg"];
+    type Preparation = ();
+
+    fn outputs<'a>(
+        args: rasterize::RasterizeArgs<'a>,
+        settings: &'a Settings,
+        output_name: &'a str,
+        _pool: &'a PagePool,
+        _preparation: &'a (),
+    ) -> Vec<PageStream<'a, OutputFiles>> {
+        let mut outputs = vec![rasterize::rasterize_svg(args).map_pages(move |i, svg| {
+            let filename = format!("{}_{}.svg", output_name, i + 1);
+            vec![(filename, svg_bytes(svg, settings), MimeType::Svg)]
+        })];
+
+        // the whole dot field as one document, for large-format printing
+        if settings.whole_poster && settings.layout != Layout::Matrix {
+            outputs.push(
+                rasterize::rasterize_svg(args.whole_poster()).map_pages(move |_, svg| {
+                    let filename = format!("{}_poster.svg", output_name);
+                    vec![(filename, svg_bytes(svg, settings), MimeType::Svg)]
+                }),
+            );
+        }
+
+        // the order a plotter would draw the dots in, as an animation.
+        // every page's animation is made in one go
+        if settings.plot_preview {
+            outputs.push(PageStream::single(move || {
+                rasterize::rasterize_plot_preview(args, PLOT_PREVIEW_FRAMES)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, frames)| {
+                        let filename = format!("{}_{}_plot.gif", output_name, i + 1);
+                        let frames = frames
+                            .into_iter()
+                            .map(|frame| (frame, PLOT_PREVIEW_FRAME_DELAY_MS));
+                        (filename, encode_frames_as_gif_bytes(frames), MimeType::Gif)
                     })
-                }
-                </div>
-            </div>
+                    .collect()
+            }));
+        }
+
+        if settings.gcode {
+            outputs.push(rasterize::rasterize_gcode(args).map_pages(move |i, gcode| {
+                let filename = format!("{}_{}.gcode", output_name, i + 1);
+                vec![(filename, gcode.into_bytes(), MimeType::Text)]
+            }));
+        }
+
+        if settings.eps {
+            outputs.push(rasterize::rasterize_eps(args).map_pages(move |i, eps| {
+                let filename = format!("{}_{}.eps", output_name, i + 1);
+                vec![(filename, eps.into_bytes(), MimeType::Eps)]
+            }));
+        }
+
+        if settings.dxf {
+            outputs.push(rasterize::rasterize_dxf(args).map_pages(move |i, dxf| {
+                let filename = format!("{}_{}.dxf", output_name, i + 1);
+                vec![(filename, dxf.into_bytes(), MimeType::Dxf)]
+            }));
+        }
+
+        if settings.laser.enabled {
+            outputs.push(
+                rasterize::rasterize_laser_svg(args, settings.laser).map_pages(move |i, svg| {
+                    let filename = format!("{}_{}_laser.svg", output_name, i + 1);
+                    vec![(filename, svg_bytes(svg, settings), MimeType::Svg)]
+                }),
+            );
+        }
+
+        if settings.relief.enabled {
+            outputs.push(rasterize::rasterize_stl(args, settings.relief).map_pages(
+                move |i, stl| {
+                    let filename = format!("{}_{}.stl", output_name, i + 1);
+                    vec![(filename, stl, MimeType::Stl)]
+                },
+            ));
+        }
+
+        if settings.dot_map {
+            outputs.push(rasterize::rasterize_dot_map(args).map_pages(move |i, csv| {
+                let filename = format!("{}_{}_dots.csv", output_name, i + 1);
+                vec![(filename, csv.into_bytes(), MimeType::Csv)]
+            }));
+        }
+
+        // every page in one file, to print from a browser at the paper's size
+        if settings.print_html {
+            outputs.push(PageStream::single(move || {
+                let html = rasterize::rasterize_print_html(args, output_name);
+                let filename = format!("{}.html", output_name);
+                vec![(filename, html.into_bytes(), MimeType::Html)]
+            }));
+        }
+
+        outputs
+    }
+}
+
+// character halftones, as plain text and as svg
+struct TextBackend;
+
+impl OutputBackend for TextBackend {
+    const PREVIEW_EXTENSIONS: &'static [&'static str] = &["svg"];
+    type Preparation = ();
+
+    fn outputs<'a>(
+        args: rasterize::RasterizeArgs<'a>,
+        settings: &'a Settings,
+        output_name: &'a str,
+        _pool: &'a PagePool,
+        _preparation: &'a (),
+    ) -> Vec<PageStream<'a, OutputFiles>> {
+        vec![
+            rasterize::rasterize_text(args, &settings.character_ramp).map_pages(move |i, page| {
+                vec![
+                    (
+                        format!("{}_{}.txt", output_name, i + 1),
+                        page.to_text().into_bytes(),
+                        MimeType::Text,
+                    ),
+                    (
+                        format!("{}_{}.svg", output_name, i + 1),
+                        svg_bytes(page.to_svg(), settings),
+                        MimeType::Svg,
+                    ),
+                ]
+            }),
+        ]
+    }
+}
+
+// weighted Voronoi stippling, as png and svg pages, and optionally a path through each
+// page's points. relaxing the points is iterative and slow, so a run relaxes them a round
+// at a time, showing its progress in between, before it makes any files
+struct VoronoiBackend;
+
+impl OutputBackend for VoronoiBackend {
+    const PREVIEW_EXTENSIONS: &'static [&'static str] = &["png"];
+    const ACTION: &'static str = "Stipple";
+    type Preparation = Option<Stippler>;
+
+    fn preparation(args: rasterize::RasterizeArgs, settings: &Settings) -> Self::Preparation {
+        Some(rasterize::voronoi_stippler(args, settings.voronoi))
+    }
+
+    fn prepare(stippler: &mut Self::Preparation, settings: &Settings) -> bool {
+        match stippler {
+            Some(stippler) if stippler.iterations() < settings.voronoi.iterations => {
+                stippler.relax();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn progress(stippler: &Self::Preparation, settings: &Settings) -> Option<(u32, u32)> {
+        let stippler = stippler.as_ref()?;
+        Some((stippler.iterations(), settings.voronoi.iterations))
+    }
+
+    fn outputs<'a>(
+        args: rasterize::RasterizeArgs<'a>,
+        settings: &'a Settings,
+        output_name: &'a str,
+        _pool: &'a PagePool,
+        stippler: &'a Self::Preparation,
+    ) -> Vec<PageStream<'a, OutputFiles>> {
+        let points = match stippler {
+            Some(stippler) => stippler.points(),
+            None => return vec![],
+        };
+
+        let mut outputs = vec![
+            rasterize::rasterize_points_image(args, points).map_pages(move |i, image| {
+                let filename = format!("{}_{}.png", output_name, i + 1);
+                vec![(filename, output_png_bytes(&image, settings), MimeType::Png)]
+            }),
+            rasterize::rasterize_points_svg(args, points).map_pages(move |i, svg| {
+                let filename = format!("{}_{}.svg", output_name, i + 1);
+                vec![(filename, svg_bytes(svg, settings), MimeType::Svg)]
+            }),
+        ];
+
+        if settings.tsp_path {
+            outputs.push(rasterize::rasterize_points_tours(args, points).map_pages(
+                move |i, tour| {
+                    vec![
+                        (
+                            format!("{}_{}_path.svg", output_name, i + 1),
+                            svg_bytes(tour.to_svg(), settings),
+                            MimeType::Svg,
+                        ),
+                        (
+                            format!("{}_{}.gcode", output_name, i + 1),
+                            tour.to_gcode().into_bytes(),
+                            MimeType::Text,
+                        ),
+                    ]
+                },
+            ));
         }
+
+        outputs
+    }
+
+    // the estimate is of squares, and how long relaxing takes isn't known up front
+    fn estimate(_args: rasterize::RasterizeArgs) -> Option<rasterize::Estimate> {
+        None
+    }
+
+    // stipples have no minimum dot size
+    fn culled_dots(_args: rasterize::RasterizeArgs) -> usize {
+        0
     }
 }
 
-struct SVGBackend {
+// the rasterize button and the outputs of a backend. a run makes a page's worth of
+// files at a time, showing each as it is made, so the first page is up straight away
+struct OutputBackendComponent<B: OutputBackend> {
     link: ComponentLink<Self>,
-    props: SVGBackendProps,
-    image_urls: Vec<String>,
+    props: BackendProps,
+    // the run in progress, if there is one
+    run: Option<OutputRun<B::Preparation>>,
+    _step_task: Option<TimeoutTask>,
+    // the run auto rasterize will start, once changes have stopped for a moment
+    _auto_task: Option<TimeoutTask>,
+    // (filename, object url) for each output that can be shown
+    image_urls: Vec<(String, String)>,
     zip_url: Option<String>,
+    // the pages of an earlier run, to compare later runs with
+    pinned: Option<Vec<(String, String)>>,
+    // how many dots the last run left out for being too small to print
+    culled_dots: usize,
+    backend: PhantomData<B>,
+}
+
+// a run takes many updates, so it keeps everything it was started with.
+// changes made while it is running don't mix into it, they are used by the next run
+struct OutputRun<P> {
+    image: Rc<Option<SourceImage>>,
+    mask: Rc<Option<SourceImage>>,
+    frames: Rc<Vec<SourceFrame>>,
+    output_name: String,
+    settings: Settings,
+    on_outputs: Callback<Rc<Vec<u8>>>,
+    sink: OutputSink,
+    pool: PagePool,
+    // the frame of an animated source being made,
+    // the stream of `OutputBackend::outputs` being made, and how far into it the run is
+    frame: usize,
+    output: usize,
+    page: usize,
+    files: usize,
+    // the frames of `Settings::animated_gif` made so far, and how long each is shown for
+    animation: Vec<(RgbaImage, u32)>,
+    // the backend's `OutputBackend::Preparation` for the frame being made,
+    // once it has been started
+    preparation: Option<P>,
+    start: f64,
 }
 
-pub enum SVGBackendMsg {
+pub enum OutputBackendMsg {
     Rasterize,
+    Step,
+    Pin,
+    Unpin,
 }
 
+impl<B: OutputBackend> OutputBackendComponent<B> {
+    fn schedule_step(&mut self) {
+        self._step_task = Some(TimeoutService::spawn(
+            Duration::from_millis(0),
+            self.link.callback(|_| OutputBackendMsg::Step),
+        ));
+    }
+}
+
+// what every backend is given. everything about how to rasterize is in `settings`
 #[derive(Clone, Properties)]
-struct SVGBackendProps {
-    pages_width: u32,
-    pages_height: u32,
-    image: Rc<Option<image::DynamicImage>>,
-    min_radius_percentage: f32,
-    max_radius_percentage: f32,
-    square_size: f32,
-    paper_size: PaperSize,
-    orientation: Orientation,
-    color_depth: ColorDepth,
+struct BackendProps {
+    on_outputs: Callback<Rc<Vec<u8>>>,
+    // the zip of an imported project's outputs
+    restored_outputs: Rc<Vec<u8>>,
+    // outputs are named after the source image: `<output_name>_1.png` etc.
+    output_name: String,
+    image: Rc<Option<SourceImage>>,
+    mask: Rc<Option<SourceImage>>,
+    // every frame of an animated source, the first of which is `image`. empty for stills
+    frames: Rc<Vec<SourceFrame>>,
+    settings: Settings,
 }
 
-impl Component for SVGBackend {
-    type Message = SVGBackendMsg;
-    type Properties = SVGBackendProps;
+impl BackendProps {
+    // whether auto rasterize should start a run for going from these props to `next`.
+    // a run in progress is started over rather than finished with settings already stale
+    fn auto_rasterizes(&self, next: &BackendProps) -> bool {
+        let changed = self.settings != next.settings
+            || !Rc::ptr_eq(&self.image, &next.image)
+            || !Rc::ptr_eq(&self.mask, &next.mask);
+
+        next.settings.auto_rasterize && next.image.is_some() && changed
+    }
+}
+
+impl<B: OutputBackend> Component for OutputBackendComponent<B> {
+    type Message = OutputBackendMsg;
+    type Properties = BackendProps;
 
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
-        SVGBackend {
+        let (image_urls, zip_url) = restore_outputs(&props.restored_outputs, B::PREVIEW_EXTENSIONS);
+
+        OutputBackendComponent {
             link,
             props,
-            image_urls: vec![],
-            zip_url: None,
+            run: None,
+            _step_task: None,
+            _auto_task: None,
+            image_urls,
+            zip_url,
+            pinned: None,
+            culled_dots: 0,
+            backend: PhantomData,
         }
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
             Self::Message::Rasterize => {
+                self._auto_task = None;
+
                 if let Some(image) = self.props.image.borrow() {
                     console::log_1(&"Starting rasterization".into());
-                    let paper_width_pixels =
-                        self.props.paper_size.width_pixels(self.props.orientation);
-                    let paper_height_pixels =
-                        self.props.paper_size.height_pixels(self.props.orientation);
-
-                    let args = rasterize::RasterizeArgs {
-                        image,
-                        paper_width_pixels,
-                        paper_height_pixels,
-                        pages_width: self.props.pages_width,
-                        pages_height: self.props.pages_height,
-                        square_size: self.props.square_size,
-                        min_radius_percentage: self.props.min_radius_percentage,
-                        max_radius_percentage: self.props.max_radius_percentage,
-                        color_depth: self.props.color_depth,
-                    };
 
-                    let window = web_sys::window().unwrap().window();
-                    let performance = window.performance().unwrap();
-                    let start = performance.now();
-                    let svgs = rasterize::rasterize_svg(args);
-                    let end = performance.now();
-                    let runtime = end - start;
-                    console::log_1(&runtime.into());
+                    let args = self
+                        .props
+                        .settings
+                        .rasterize_args(image, self.props.mask.as_deref());
 
-                    let image_urls = svgs
-                        .iter()
-                        .map(|svg| {
-                            let mut svg_string = Vec::new();
-                            svg::write(&mut svg_string, svg).unwrap();
-                            let s = String::from_utf8(svg_string).unwrap();
-                            bytes_to_object_url(s.as_bytes(), &MimeType::Svg.to_string()).unwrap()
-                        })
-                        .collect::<Vec<String>>();
+                    self.run = Some(OutputRun {
+                        image: self.props.image.clone(),
+                        mask: self.props.mask.clone(),
+                        frames: self.props.frames.clone(),
+                        output_name: self.props.output_name.clone(),
+                        settings: self.props.settings.clone(),
+                        on_outputs: self.props.on_outputs.clone(),
+                        sink: OutputSink::new(
+                            args,
+                            &self.props.settings,
+                            &self.props.output_name,
+                            &B::plate_folders(args, &self.props.settings),
+                        ),
+                        pool: PagePool::default(),
+                        frame: 0,
+                        output: 0,
+                        page: 0,
+                        files: 0,
+                        animation: vec![],
+                        preparation: None,
+                        start: performance_now(),
+                    });
+                    revoke_outputs(&self.image_urls, &self.zip_url, self.pinned.as_deref());
+                    self.image_urls = vec![];
+                    self.zip_url = None;
+                    self.culled_dots = 0;
+                    self.schedule_step();
+
+                    true
+                } else {
+                    console::log_1(&"No image supplied, not rasterizing anything".into());
+                    false
+                }
+            }
+
+            Self::Message::Step => {
+                let run = match &mut self.run {
+                    Some(run) => run,
+                    None => return false,
+                };
+
+                let image = run.image.clone();
+                let frames = run.frames.clone();
+                // an animated source's frames are made one after another,
+                // each with its outputs named for it
+                let (image, output_name) = match (frames.get(run.frame), image.as_ref()) {
+                    (Some(frame), _) => (
+                        &frame.image,
+                        format!("{}_frame_{}", run.output_name, run.frame + 1),
+                    ),
+                    (None, Some(image)) => (image, run.output_name.clone()),
+                    (None, None) => return false,
+                };
+                let args = run.settings.rasterize_args(image, run.mask.as_deref());
+
+                let preparation = run
+                    .preparation
+                    .get_or_insert_with(|| B::preparation(args, &run.settings));
+                if B::prepare(preparation, &run.settings) {
+                    self.schedule_step();
+                    return true;
+                }
+
+                // the outputs are started over every step, skipping what is already made,
+                // which only costs what it takes to lay the image out again
+                let mut outputs =
+                    B::outputs(args, &run.settings, &output_name, &run.pool, preparation);
+                let files = loop {
+                    let stream = match outputs.get_mut(run.output) {
+                        Some(stream) => stream,
+                        None => break None,
+                    };
+
+                    match stream.nth(run.page) {
+                        Some(files) => {
+                            run.page += 1;
+                            break Some(files);
+                        }
+                        None => {
+                            run.output += 1;
+                            run.page = 0;
+                        }
+                    }
+                };
+                drop(outputs);
 
-                    self.image_urls = image_urls;
+                if let Some(files) = files {
+                    for (filename, bytes, mime_type) in files {
+                        run.sink.add(filename, bytes, mime_type);
+                        run.files += 1;
+                    }
+                    self.image_urls.append(&mut run.sink.image_urls);
+                    self.schedule_step();
+                    return true;
+                }
 
-                    let mut zip_inputs = vec![];
+                if let Some(frame) = frames.get(run.frame) {
+                    if run.settings.animated_gif {
+                        if let Some(poster) = rasterize::animation_frame(args) {
+                            run.animation.push((poster, frame.delay_ms));
+                        }
+                    }
 
-                    // zip up all svgs so we can provide the
-                    // "download all" link
-                    for (i, svg) in svgs.iter().enumerate() {
-                        let filename = format!("{}.svg", i + 1);
-                        let mut svg_string: Vec<u8> = Vec::new();
-                        svg::write(&mut svg_string, svg).unwrap();
-                        zip_inputs.push((filename, svg_string));
+                    if run.frame + 1 < frames.len() {
+                        run.frame += 1;
+                        run.output = 0;
+                        run.page = 0;
+                        run.preparation = None;
+                        self.schedule_step();
+                        return true;
                     }
+                }
 
-                    let mut zip_buf = Cursor::new(vec![]);
-                    let _zipped_result = zip(&mut zip_buf, zip_inputs);
-                    let zip_url =
-                        bytes_to_object_url(zip_buf.get_ref(), &MimeType::Zip.to_string()).unwrap();
+                self.culled_dots = B::culled_dots(args);
 
-                    self.zip_url = Some(zip_url);
+                let mut run = match self.run.take() {
+                    Some(run) => run,
+                    None => return false,
+                };
+                console::log_1(&(performance_now() - run.start).into());
 
-                    true
-                } else {
-                    console::log_1(&"No image supplied, not rasterizeing anything".into());
-                    false
+                if !run.animation.is_empty() {
+                    let filename = format!("{}_animated.gif", run.output_name);
+                    let gif = encode_frames_as_gif_bytes(std::mem::take(&mut run.animation));
+                    run.sink.add(filename, gif, MimeType::Gif);
+                }
+
+                let (mut image_urls, zip_url) = run.sink.finish(&run.on_outputs);
+                self.image_urls.append(&mut image_urls);
+                self.zip_url = zip_url;
+
+                true
+            }
+
+            Self::Message::Pin => {
+                if let Some(pinned) = &self.pinned {
+                    revoke_object_urls(pinned, Some(&self.image_urls));
+                }
+                self.pinned = Some(self.image_urls.clone());
+                true
+            }
+
+            Self::Message::Unpin => {
+                if let Some(pinned) = self.pinned.take() {
+                    revoke_object_urls(&pinned, Some(&self.image_urls));
                 }
+                true
             }
         }
     }
 
     // TODO figure out what to do here. Right now we naively rerender on any new props.
-    // The reason for this is because `image: Rc<Option<image::DynamicImage>>`
+    // The reason for this is because `image: Rc<Option<SourceImage>>`
     // does not implement `PartialEq`, otherwise we could derive it for the whole props.
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        // a project was imported, so show its outputs in place of whatever we had
+        if !Rc::ptr_eq(&self.props.restored_outputs, &props.restored_outputs) {
+            let (image_urls, zip_url) =
+                restore_outputs(&props.restored_outputs, B::PREVIEW_EXTENSIONS);
+            revoke_outputs(&self.image_urls, &self.zip_url, self.pinned.as_deref());
+            self.image_urls = image_urls;
+            self.zip_url = zip_url;
+            self.culled_dots = 0;
+        }
+
+        if !props.settings.auto_rasterize {
+            self._auto_task = None;
+        } else if self.props.auto_rasterizes(&props) {
+            self._auto_task = Some(TimeoutService::spawn(
+                Duration::from_millis(AUTO_RASTERIZE_DELAY_MS),
+                self.link.callback(|_| OutputBackendMsg::Rasterize),
+            ));
+        }
+
         self.props = props;
         true
     }
 
-    // See https://github.com/yewstack/yew/blob/master/examples/std_web/inner_html/src/lib.rs
-    // for reference as to why this is this way
+    fn destroy(&mut self) {
+        revoke_outputs(&self.image_urls, &self.zip_url, None);
+        if let Some(pinned) = &self.pinned {
+            revoke_object_urls(pinned, Some(&self.image_urls));
+        }
+    }
+
     fn view(&self) -> Html {
-        if !self.image_urls.is_empty() {
-            html! {
-                <div>
-                    <div>
-                        <button onclick=self.link.callback(|_| Self::Message::Rasterize)>
-                            { "Rasterize" }
-                        </button>
-                    </div>
+        let estimate = Option::as_ref(&self.props.image).and_then(|image| {
+            B::estimate(
+                self.props
+                    .settings
+                    .rasterize_args(image, self.props.mask.as_deref()),
+            )
+        });
 
-                    <div>
+        html! {
+            <div>
+                { estimate_view(estimate) }
+
+                <div>
                 {
-                    if let Some(zip_url) = &self.zip_url {
-                        html! {
+                    if let Some(run) = &self.run {
+                        let progress = run
+                            .preparation
+                            .as_ref()
+                            .and_then(|preparation| B::progress(preparation, &run.settings))
+                            .filter(|(done, total)| done < total);
 
-                            <a style="display: inline;" href={zip_url.to_string()} alt={"download all"}>{"download all"}</a>
+                        html! {
+                            <>
+                                {
+                                    if let Some((done, total)) = progress {
+                                        html! {
+                                            <progress max={total.to_string()} value={done.to_string()}>
+                                            </progress>
+                                        }
+                                    } else if run.frames.is_empty() {
+                                        html! {
+                                            { format!("{} files made so far", run.files) }
+                                        }
+                                    } else {
+                                        html! {
+                                            {
+                                                format!(
+                                                    "{} files made so far, on frame {} of {}",
+                                                    run.files,
+                                                    run.frame + 1,
+                                                    run.frames.len()
+                                                )
+                                            }
+                                        }
+                                    }
+                                }
+                                { " settings changed now are used by the next run" }
+                            </>
                         }
                     } else {
-                        html! {}
+                        html! {
+                            <button onclick=self.link.callback(|_| Self::Message::Rasterize)>
+                                { B::ACTION }
+                            </button>
+                        }
                     }
                 }
-                    </div>
-
+                </div>
 
-                    <div>
                 {
-                    for self.image_urls.iter().map(|image_url| {
+                    if self.culled_dots > 0 {
                         html! {
-                            <div style="display: inline;">
-                                <a style="display: inline;" href={image_url.to_string()} alt={"meh"}>{"download"}</a>
-                                <img style="display: inline;" src={image_url.to_string()} alt={"meh"} />
+                            <div>
+                                {
+                                    format!(
+                                        "{} dots smaller than {}mm were left out",
+                                        self.culled_dots,
+                                        self.props.settings.min_dot_size.radius
+                                    )
+                                }
                             </div>
                         }
-                    })
+                    } else {
+                        html! {}
+                    }
                 }
-                </div>
 
-                </div>
-            }
-        } else {
-            html! {
-                <div>
-                    <div>
-                        <button onclick=self.link.callback(|_| Self::Message::Rasterize)>
-                            { "Rasterize" }
-                        </button>
-                    </div>
-                </div>
-            }
+                {
+                    outputs_view(
+                        &self.props.output_name,
+                        &self.image_urls,
+                        &self.zip_url,
+                        self.pinned.as_deref(),
+                        self.link.callback(|_| Self::Message::Pin),
+                        self.link.callback(|_| Self::Message::Unpin),
+                    )
+                }
+            </div>
         }
     }
 }
 
-pub struct Model {
-    link: ComponentLink<Self>,
-    tasks: Vec<ReaderTask>,
-    pages_width: u32,
-    pages_height: u32,
-    image: Rc<Option<image::DynamicImage>>,
-    min_radius_percentage: f32,
-    max_radius_percentage: f32,
-    square_size: f32,
-    paper_size: PaperSize,
-    orientation: Orientation,
-    backend: Backend,
-    color_depth: ColorDepth,
+// what a run will take, and a warning when it is likely too big for the browser
+fn estimate_view(estimate: Option<rasterize::Estimate>) -> Html {
+    let estimate = match estimate {
+        Some(estimate) => estimate,
+        None => return html! {},
+    };
+
+    let megabytes = estimate.peak_bytes as f64 / (1024.0 * 1024.0);
+
+    html! {
+        <>
+            <div>
+                {
+                    format!(
+                        "about {} pages, {} dots, {:.0}MB of memory and {:.0} seconds",
+                        estimate.pages,
+                        estimate.dots,
+                        megabytes.ceil(),
+                        estimate.seconds.ceil()
+                    )
+                }
+            </div>
+            {
+                if estimate.is_too_big() {
+                    html! {
+                        <div>
+                            { "this will likely run the browser out of memory, try fewer pages or a lower output dpi" }
+                        </div>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+        </>
+    }
+}
+
+// milliseconds since the app was loaded, for timing runs
+fn performance_now() -> f64 {
+    web_sys::window().unwrap().performance().unwrap().now()
+}
+
+// the "download all" link, and a gallery of the outputs to inspect and download.
+// once a run is pinned, later runs are shown next to it instead.
+// html! checks each prop of the `Comparison` with a bare `props.field;`
+#[allow(clippy::unnecessary_operation)]
+fn outputs_view(
+    output_name: &str,
+    image_urls: &[(String, String)],
+    zip_url: &Option<String>,
+    pinned: Option<&[(String, String)]>,
+    onpin: Callback<()>,
+    onunpin: Callback<()>,
+) -> Html {
+    html! {
+        <>
+            <div>
+            {
+                if let Some(zip_url) = zip_url {
+                    html! {
+                        <a style="display: inline;" href={zip_url.to_string()} download={format!("{}_pages.zip", output_name)} alt={"download all"}>{"download all"}</a>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+            {
+                if pinned.is_none() && !image_urls.is_empty() {
+                    html! {
+                        <button onclick=Callback::from(move |_| onpin.emit(()))>
+                            { "pin to compare" }
+                        </button>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+            </div>
+
+            {
+                if let Some(pinned) = pinned {
+                    html! {
+                        <Comparison
+                        pinned={pinned.to_vec()}
+                        current={image_urls.to_vec()}
+                        onunpin={onunpin}/>
+                    }
+                } else {
+                    html! {
+                        <Gallery pages={image_urls.to_vec()}/>
+                    }
+                }
+            }
+        </>
+    }
+}
+
+// an event listener attached to the whole document,
+// which is removed again when dropped
+struct DocumentListener {
+    event_type: &'static str,
+    closure: Closure<dyn FnMut(web_sys::Event)>,
+}
+
+impl DocumentListener {
+    fn new<F>(event_type: &'static str, f: F) -> Self
+    where
+        F: FnMut(web_sys::Event) + 'static,
+    {
+        let closure = Closure::wrap(Box::new(f) as Box<dyn FnMut(web_sys::Event)>);
+
+        let document = web_sys::window().unwrap().document().unwrap();
+        document
+            .add_event_listener_with_callback(event_type, closure.as_ref().unchecked_ref())
+            .unwrap();
+
+        DocumentListener {
+            event_type,
+            closure,
+        }
+    }
+}
+
+impl Drop for DocumentListener {
+    fn drop(&mut self) {
+        if let Some(document) = web_sys::window().and_then(|window| window.document()) {
+            let _ = document.remove_event_listener_with_callback(
+                self.event_type,
+                self.closure.as_ref().unchecked_ref(),
+            );
+        }
+    }
+}
+
+pub struct Model {
+    link: ComponentLink<Self>,
+    tasks: Vec<ReaderTask>,
+    _listeners: Vec<DocumentListener>,
+    storage: Option<StorageService>,
+    history: SettingsHistory,
+    presets: BTreeMap<String, Settings>,
+    preset_name: String,
+    // everything the controls set, which is what is saved, shared and undone
+    settings: Settings,
+    image: Rc<Option<SourceImage>>,
+    // every frame of an animated source, see `decode_frames`
+    frames: Rc<Vec<SourceFrame>>,
+    source_file: Option<FileData>,
+    // a second image blended over `image` before sampling
+    mask: Rc<Option<SourceImage>>,
+    // counted up whenever a new source image or mask is picked, so a browser decode
+    // that finishes after a newer one was picked is dropped, see `decode_in_browser`
+    source_load: u32,
+    mask_load: u32,
+    // why the last image could not be used, if it couldn't
+    image_error: Option<String>,
+    // the zip of the last run's files, empty until there is one
+    outputs: Rc<Vec<u8>>,
+    // the settings `outputs` were made with, which may since have changed
+    outputs_settings: Option<Settings>,
+    restored_outputs: Rc<Vec<u8>>,
+    project_url: Option<String>,
+    // what this build and browser support, for the about panel
+    about: String,
+    camera_stream: Option<web_sys::MediaStream>,
+    camera_video: NodeRef,
+    // the image laid out on the page grid, with a square to drag out to `square_size`
+    layout_preview: NodeRef,
+    layout_preview_scale: f32,
+    // what `layout_preview` was last drawn from
+    layout_previewed: Option<LayoutPreviewed>,
+    square_drag: Option<SquareDrag>,
+    region_drag: Option<RegionDrag>,
+    curve_drag: Option<CurveDrag>,
+}
+
+// hashes of the image and mask, and the settings the layout preview was drawn with
+type LayoutPreviewed = (Option<u64>, Option<u64>, Settings);
+
+// a region being dragged out across the layout preview, in the preview's pixels.
+// `origin` is where the preview's top left corner is, in client coordinates
+struct RegionDrag {
+    origin: (i32, i32),
+    from: (i32, i32),
+    to: (i32, i32),
+}
+
+// which of the radius curve's points is being dragged, and from where
+struct CurveDrag {
+    point: usize,
+    client_y: i32,
+    size: f32,
+}
+
+// where a drag of the layout preview's square started
+struct SquareDrag {
+    client_x: i32,
+    client_y: i32,
+    square_size: f32,
 }
 
 pub enum Msg {
     FileSelection(Vec<File>),
     FileLoaded(FileData),
+    // a file rat couldn't decode, as the browser decoded it, for the source load it was
+    FileDecoded(u32, FileData, Result<image::DynamicImage, String>),
+    OutputsReady(Box<Settings>, Rc<Vec<u8>>),
+    ExportProject,
+    ProjectSelection(Vec<File>),
+    ProjectLoaded(FileData),
+    StartCamera,
+    CameraStarted(web_sys::MediaStream),
+    CameraFailed(String),
+    CaptureCameraFrame,
+    StopCamera,
     UpdatePageWidth(String),
     UpdatePageHeight(String),
     UpdateSquareSize(String),
     UpdateMinRadiusPercentage(String),
     UpdateMaxRadiusPercentage(String),
+    UpdateRadiusJitter(String),
+    UpdatePositionJitter(String),
+    ToggleEdges,
+    UpdateEdgeThreshold(String),
+    UpdateEdgeWidth(String),
+    UpdateJitterSeed(String),
     UpdatePaperSize(String),
     UpdateOrientation(String),
     UpdateBackend(String),
     UpdateColorDepth(String),
+    UpdateLuma(String),
+    UpdateResampling(String),
+    ToggleSvgOptimization,
+    UpdateSvgPrecision(String),
+    ToggleMinDotSize,
+    UpdateMinDotRadius(String),
+    UpdateLayout(String),
+    UpdateRepeatColumns(String),
+    UpdateRepeatRows(String),
+    UpdateRepeatScale(String),
+    UpdateRepeatSpacing(String),
+    UpdateBorderThickness(String),
+    UpdateBorderInset(String),
+    UpdateBorderColor(String),
+    UpdateMatrixParameter(String),
+    UpdateMatrixFrom(String),
+    UpdateMatrixTo(String),
+    UpdateGradientDark(String),
+    UpdateGradientLight(String),
+    UpdateInk(usize, String),
+    UpdatePaletteSize(String),
+    TogglePaletteAutomatic,
+    UpdatePaletteColor(usize, String),
+    UseColorBlindSafeInks,
+    UpdateColorVision(String),
+    UpdateOutputPixelsPerInch(String),
+    UpdateColorProfile(String),
+    UpdatePageFormat(String),
+    UpdatePageQuality(String),
+    UpdateZipCompression(String),
+    ToggleDraft,
+    ToggleWholePoster,
+    UpdateCircleAlgorithm(String),
+    UpdateSupersampling(String),
+    ToggleContactSheet,
+    ToggleAnimatedGif,
+    UpdateContactSheetSquareSizeFrom(String),
+    UpdateContactSheetSquareSizeTo(String),
+    UpdateContactSheetMaxRadiusFrom(String),
+    UpdateContactSheetMaxRadiusTo(String),
+    UpdateContactSheetSteps(String),
+    UpdateDither(String),
+    UpdatePattern(String),
+    ApplySuggestedPages(u32, u32),
+    MaskSelection(Vec<File>),
+    MaskLoaded(FileData),
+    // a mask rat couldn't decode, as the browser decoded it, for the mask load it was
+    MaskDecoded(u32, Result<image::DynamicImage, String>),
+    ClearMask,
+    UpdateMaskBlend(String),
+    ToggleInvert(Channel),
+    TurnImage(u32),
+    ToggleFlipHorizontal,
+    ToggleFlipVertical,
+    UpdateCrop(CropSide, String),
+    ResetFraming,
+    UpdateGamma(String),
+    UpdateBrightness(String),
+    UpdateContrast(String),
+    ToggleCompositeBackground,
+    UpdateBackground(String),
+    ToggleSkipTransparent,
+    UpdateVoronoiPoints(String),
+    UpdateVoronoiIterations(String),
+    UpdateLineAngle(String),
+    UpdateDotShape(String),
+    ToggleFollowContours,
+    TogglePlotPreview,
+    LayoutPreviewScaled(f32),
+    StartSquareDrag(i32, i32),
+    DragSquare(i32, i32),
+    EndSquareDrag,
+    StartRegionDrag(i32, i32, i32, i32),
+    DragRegion(i32, i32),
+    EndRegionDrag,
+    StartCurveDrag(usize, i32),
+    DragCurve(i32),
+    EndCurveDrag,
+    ResetRadiusCurve,
+    UpdateRowOrder(String),
+    UpdateBuildUp(String),
+    ToggleGcode,
+    ToggleEps,
+    ToggleDxf,
+    ToggleRelief,
+    ToggleDotMap,
+    TogglePrintHtml,
+    ToggleLaser,
+    UpdateLaserKerf(String),
+    UpdateReliefBase(String),
+    UpdateReliefHeight(String),
+    UpdateReliefShape(String),
+    ToggleDotMetadata,
+    ToggleHighContrast,
+    ToggleAutoRasterize,
+    Undo,
+    Redo,
+    ToggleTspPath,
+    UpdateCharacterRamp(String),
+    UpdatePresetName(String),
+    SavePreset,
+    LoadPreset(String),
+    DeletePreset(String),
 }
 
 impl Component for Model {
@@ -408,24 +1373,116 @@ impl Component for Model {
     type Properties = ();
 
     fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
-        Model {
+        // images can be dropped anywhere on the page or pasted from the clipboard,
+        // in addition to being picked with the file input
+        let drop_link = link.clone();
+        let paste_link = link.clone();
+        let key_link = link.clone();
+
+        let listeners = vec![
+            // the browser only fires `drop` if `dragover` is cancelled
+            DocumentListener::new("dragover", |e| e.prevent_default()),
+            DocumentListener::new("drop", move |e| {
+                e.prevent_default();
+
+                let files = e
+                    .dyn_into::<web_sys::DragEvent>()
+                    .ok()
+                    .and_then(|e| e.data_transfer())
+                    .and_then(|data_transfer| data_transfer.files());
+
+                drop_link.send_message(Msg::FileSelection(first_image_file(files)));
+            }),
+            DocumentListener::new("paste", move |e| {
+                let files = e
+                    .dyn_into::<web_sys::ClipboardEvent>()
+                    .ok()
+                    .and_then(|e| e.clipboard_data())
+                    .and_then(|data_transfer| data_transfer.files());
+
+                let files = first_image_file(files);
+
+                if !files.is_empty() {
+                    paste_link.send_message(Msg::FileSelection(files));
+                }
+            }),
+            // ctrl (or cmd) z undoes a settings change, with shift or ctrl y redoes it.
+            // text boxes keep the shortcuts for undoing typing
+            DocumentListener::new("keydown", move |e| {
+                let in_text_box = e
+                    .target()
+                    .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+                    .is_some_and(|element| {
+                        matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA")
+                    });
+                let e = match e.dyn_into::<web_sys::KeyboardEvent>() {
+                    Ok(e) if (e.ctrl_key() || e.meta_key()) && !in_text_box => e,
+                    _ => return,
+                };
+
+                let msg = match (e.key().to_lowercase().as_str(), e.shift_key()) {
+                    ("z", false) => Msg::Undo,
+                    ("z", true) | ("y", _) => Msg::Redo,
+                    _ => return,
+                };
+                e.prevent_default();
+                key_link.send_message(msg);
+            }),
+        ];
+
+        let storage = StorageService::new(Area::Local).ok();
+
+        let mut model = Model {
             link,
             tasks: vec![],
-            pages_width: 1,
-            pages_height: 1,
+            _listeners: listeners,
+            storage,
+            history: SettingsHistory::default(),
+            presets: BTreeMap::new(),
+            preset_name: String::new(),
+            settings: Settings::default(),
             image: Rc::new(None),
-            square_size: 18.0,
-            min_radius_percentage: 0.0,
-            max_radius_percentage: 1.0,
-            paper_size: PaperSize::USLetter,
-            orientation: Orientation::Portrait,
-            backend: Backend::Image,
-            color_depth: ColorDepth::Rgb,
+            source_file: None,
+            frames: Rc::new(vec![]),
+            mask: Rc::new(None),
+            source_load: 0,
+            mask_load: 0,
+            image_error: None,
+            outputs: Rc::default(),
+            outputs_settings: None,
+            restored_outputs: Rc::default(),
+            project_url: None,
+            about: about(),
+            camera_stream: None,
+            camera_video: NodeRef::default(),
+            layout_preview: NodeRef::default(),
+            layout_preview_scale: 1.0,
+            layout_previewed: None,
+            square_drag: None,
+            region_drag: None,
+            curve_drag: None,
+        };
+
+        model.presets = model.load_presets();
+
+        // a shared link takes precedence over whatever was used last time
+        if let Some(settings) = settings_from_url() {
+            console::log_1(&"restored settings from the url".into());
+            model.settings = settings;
+        } else if let Some(settings) = model.load_settings() {
+            console::log_1(&"restored settings from local storage".into());
+            model.settings = settings;
         }
+
+        model
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
-        match msg {
+        // undoing and redoing move through the history rather than adding to it
+        let recorded = !matches!(msg, Msg::Undo | Msg::Redo);
+        let before = self.settings.clone();
+
+        let should_render = match msg {
             Msg::FileSelection(files) => {
                 for file in files {
                     let callback = self.link.callback(Msg::FileLoaded);
@@ -437,73 +1494,435 @@ impl Component for Model {
             }
 
             Msg::FileLoaded(file) => {
-                console::log_2(&"finished loading image: {}".into(), &file.name.into());
+                console::log_2(
+                    &"finished loading image: {}".into(),
+                    &file.name.as_str().into(),
+                );
+                self.source_load = self.source_load.wrapping_add(1);
+
+                match decode_image(&file.content) {
+                    Err(_) if !capabilities::decodes(&file.content) => {
+                        let load = self.source_load;
+                        self.decode_in_browser(file, "image", move |file, decoded| {
+                            Msg::FileDecoded(load, file, decoded)
+                        });
+
+                        false
+                    }
+                    decoded => self.use_source_file(file, decoded),
+                }
+            }
+
+            Msg::FileDecoded(load, file, _) if load != self.source_load => {
+                console::log_2(&"dropping stale image".into(), &file.name.as_str().into());
+
+                false
+            }
+
+            Msg::FileDecoded(_, file, decoded) => match decoded {
+                Ok(i) => {
+                    // the image is kept as a png, so exported projects can be read back
+                    let png = FileData {
+                        name: Path::new(&file.name)
+                            .with_extension("png")
+                            .to_string_lossy()
+                            .to_string(),
+                        content: encode_image_as_png_bytes(&i.to_rgba8(), ColorProfile::Untagged),
+                    };
+                    self.use_source_file(png, Ok(i))
+                }
+                Err(e) => self.use_source_file(file, Err(e)),
+            },
+
+            Msg::ProjectSelection(files) => {
+                for file in files {
+                    let callback = self.link.callback(Msg::ProjectLoaded);
+                    let task = ReaderService::read_file(file, callback).unwrap();
+                    self.tasks.push(task);
+                }
+
+                true
+            }
+
+            Msg::ProjectLoaded(file) => {
+                console::log_2(
+                    &"finished loading project: {}".into(),
+                    &file.name.as_str().into(),
+                );
+
+                match project::import_project(&file.content) {
+                    Ok(project) => {
+                        let i = match decode_image(&project.image_bytes) {
+                            Ok(i) => i,
+                            Err(e) => {
+                                console::log_2(
+                                    &"could not use project image".into(),
+                                    &e.as_str().into(),
+                                );
+                                self.image_error = Some(e);
+                                return true;
+                            }
+                        };
+
+                        self.source_load = self.source_load.wrapping_add(1);
+                        self.image = Rc::new(Some(SourceImage::new(&i)));
+                        self.frames = Rc::new(decode_frames(&project.image_bytes));
+                        self.image_error = None;
+                        self.source_file = Some(FileData {
+                            name: project.manifest.image_filename,
+                            content: project.image_bytes,
+                        });
+                        let compression = project
+                            .manifest
+                            .outputs_settings
+                            .as_ref()
+                            .unwrap_or(&project.manifest.settings)
+                            .zip_compression;
+                        self.outputs = match zip(&project.outputs, compression) {
+                            Ok(outputs) => Rc::new(outputs),
+                            Err(e) => {
+                                console::log_1(
+                                    &format!("could not zip project outputs: {}", e).into(),
+                                );
+                                Rc::default()
+                            }
+                        };
+                        self.restored_outputs = self.outputs.clone();
+                        self.settings = project.manifest.settings;
+                        self.outputs_settings = project.manifest.outputs_settings;
+
+                        true
+                    }
+                    Err(e) => {
+                        console::log_1(&format!("could not import project: {}", e).into());
+                        false
+                    }
+                }
+            }
+
+            Msg::StartCamera => {
+                let link = self.link.clone();
+
+                wasm_bindgen_futures::spawn_local(async move {
+                    match camera::request_stream().await {
+                        Ok(stream) => link.send_message(Msg::CameraStarted(stream)),
+                        Err(e) => link.send_message(Msg::CameraFailed(format!("{:?}", e))),
+                    }
+                });
+
+                false
+            }
+
+            Msg::CameraStarted(stream) => {
+                console::log_1(&"camera started".into());
+
+                if let Some(old_stream) = self.camera_stream.replace(stream) {
+                    camera::stop_stream(&old_stream);
+                }
+
+                true
+            }
+
+            Msg::CameraFailed(e) => {
+                console::log_2(&"could not start camera".into(), &e.into());
+
+                false
+            }
+
+            Msg::CaptureCameraFrame => {
+                let video = match self.camera_video.cast::<web_sys::HtmlVideoElement>() {
+                    Some(video) => video,
+                    None => return false,
+                };
+
+                match camera::capture_frame(&video).and_then(|i| {
+                    rasterize::check_image(&i)
+                        .map(|_| i)
+                        .map_err(|e| e.to_string().into())
+                }) {
+                    Ok(i) => {
+                        console::log_1(&"captured camera frame".into());
+
+                        let png = encode_image_as_png_bytes(&i.to_rgba8(), ColorProfile::Untagged);
+
+                        self.source_load = self.source_load.wrapping_add(1);
+                        self.image = Rc::new(Some(SourceImage::new(&i)));
+                        self.frames = Rc::new(vec![]);
+                        self.image_error = None;
+                        self.source_file = Some(FileData {
+                            name: "camera.png".to_string(),
+                            content: png,
+                        });
+                        self.outputs = Rc::default();
+                        self.outputs_settings = None;
+
+                        if let Some(stream) = self.camera_stream.take() {
+                            camera::stop_stream(&stream);
+                        }
 
-                let i = image::load_from_memory(&file.content).unwrap();
+                        true
+                    }
+                    Err(e) => {
+                        console::log_2(&"could not capture camera frame".into(), &e);
+                        false
+                    }
+                }
+            }
 
-                self.image = Rc::new(Some(i));
+            Msg::StopCamera => {
+                if let Some(stream) = self.camera_stream.take() {
+                    camera::stop_stream(&stream);
+                }
 
                 true
             }
 
+            Msg::OutputsReady(settings, outputs) => {
+                self.outputs = outputs;
+                self.outputs_settings = Some(*settings);
+
+                false
+            }
+
+            Msg::ExportProject => {
+                if let Some(source_file) = &self.source_file {
+                    let manifest = ProjectManifest::new(
+                        RAT_VERSION,
+                        &source_file.name,
+                        self.settings.clone(),
+                        self.outputs_settings.clone(),
+                    );
+
+                    let outputs = if self.outputs.is_empty() {
+                        vec![]
+                    } else {
+                        match archive::read_zip(&self.outputs) {
+                            Ok(outputs) => outputs,
+                            Err(e) => {
+                                console::log_1(&format!("could not export project: {}", e).into());
+                                return false;
+                            }
+                        }
+                    };
+                    let files =
+                        project::project_files(&manifest, &source_file.content, &outputs).unwrap();
+
+                    let project = match zip(&files, self.settings.zip_compression) {
+                        Ok(project) => project,
+                        Err(e) => {
+                            console::log_1(&format!("could not export project: {}", e).into());
+                            return false;
+                        }
+                    };
+                    let project_url =
+                        bytes_to_object_url(&project, &MimeType::Zip.to_string()).unwrap();
+
+                    if let Some(project_url) = self.project_url.replace(project_url) {
+                        revoke_object_url(&project_url);
+                    }
+
+                    true
+                } else {
+                    console::log_1(&"No image supplied, not exporting a project".into());
+                    false
+                }
+            }
+
             Msg::UpdatePageWidth(s) => {
-                let as_u32 = s.parse::<u32>().unwrap();
-                self.pages_width = as_u32;
+                let as_u32 = match parse_number::<u32>(&s) {
+                    Some(as_u32) => as_u32,
+                    None => {
+                        console::log_2(&"could not parse page width".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.pages_width = as_u32.max(1);
 
-                console::log_2(&"page width set to".into(), &self.pages_width.into());
+                console::log_2(
+                    &"page width set to".into(),
+                    &self.settings.pages_width.into(),
+                );
 
                 true
             }
 
             Msg::UpdatePageHeight(s) => {
-                let as_u32 = s.parse::<u32>().unwrap();
-                self.pages_height = as_u32;
+                let as_u32 = match parse_number::<u32>(&s) {
+                    Some(as_u32) => as_u32,
+                    None => {
+                        console::log_2(&"could not parse page height".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.pages_height = as_u32.max(1);
 
-                console::log_2(&"page height set to".into(), &self.pages_height.into());
+                console::log_2(
+                    &"page height set to".into(),
+                    &self.settings.pages_height.into(),
+                );
 
                 true
             }
 
             Msg::UpdateSquareSize(s) => {
-                let as_f32 = s.parse::<f32>().unwrap();
-                self.square_size = as_f32;
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32,
+                    None => {
+                        console::log_2(&"could not parse square size".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.square_size = as_f32;
 
                 console::log_3(
                     &"square_size set to".into(),
-                    &self.square_size.into(),
+                    &self.settings.square_size.into(),
                     &"mm".into(),
                 );
 
                 true
             }
 
-            Msg::UpdateMinRadiusPercentage(s) => {
-                let as_isize = s.parse::<isize>().unwrap();
-                self.min_radius_percentage = if as_isize < 0 {
-                    0.0
-                } else if as_isize > 100 {
-                    1.0
-                } else {
-                    as_isize as f32 / 100.0
+            Msg::LayoutPreviewScaled(scale) => {
+                self.layout_preview_scale = scale;
+
+                true
+            }
+
+            Msg::StartSquareDrag(client_x, client_y) => {
+                self.square_drag = Some(SquareDrag {
+                    client_x,
+                    client_y,
+                    square_size: self.settings.square_size,
+                });
+
+                false
+            }
+
+            Msg::DragSquare(client_x, client_y) => {
+                let drag = match &self.square_drag {
+                    Some(drag) => drag,
+                    None => return false,
                 };
 
-                if self.min_radius_percentage > self.max_radius_percentage {
-                    console::log_1(&"min raster % > max raster %, setting to 0%".into());
-                    self.min_radius_percentage = 0.0;
+                // the square is dragged out from its corner, so follow whichever way moved further
+                let dragged = (client_x - drag.client_x).max(client_y - drag.client_y) as f32;
+                self.settings.square_size = (drag.square_size
+                    + dragged / self.layout_preview_scale)
+                    .round()
+                    .max(1.0);
+
+                true
+            }
+
+            Msg::EndSquareDrag => {
+                self.square_drag = None;
+
+                false
+            }
+
+            Msg::StartRegionDrag(client_x, client_y, offset_x, offset_y) => {
+                // other layouts repeat or tile the image, so a region of the preview
+                // isn't a region of the image
+                if self.settings.layout != Layout::Fit {
+                    return false;
                 }
 
-                console::log_3(
-                    &"set min raster percentage to ".into(),
-                    &(self.min_radius_percentage * 100.0).into(),
-                    &"%".into(),
+                self.region_drag = Some(RegionDrag {
+                    origin: (client_x - offset_x, client_y - offset_y),
+                    from: (offset_x, offset_y),
+                    to: (offset_x, offset_y),
+                });
+
+                true
+            }
+
+            Msg::DragRegion(client_x, client_y) => {
+                let drag = match &mut self.region_drag {
+                    Some(drag) => drag,
+                    None => return false,
+                };
+
+                drag.to = (client_x - drag.origin.0, client_y - drag.origin.1);
+
+                true
+            }
+
+            Msg::EndRegionDrag => {
+                let drag = match self.region_drag.take() {
+                    Some(drag) => drag,
+                    None => return false,
+                };
+
+                if (drag.to.0 - drag.from.0).abs() < MIN_REGION_DRAG
+                    || (drag.to.1 - drag.from.1).abs() < MIN_REGION_DRAG
+                {
+                    return true;
+                }
+
+                let canvas = match self.layout_preview.cast::<web_sys::HtmlCanvasElement>() {
+                    Some(canvas) => canvas,
+                    None => return true,
+                };
+                let (width, height) = (canvas.width() as f32, canvas.height() as f32);
+                let fraction = |(x, y): (i32, i32)| (x as f32 / width, y as f32 / height);
+
+                self.settings
+                    .framing
+                    .crop_to(fraction(drag.from), fraction(drag.to));
+                console::log_2(
+                    &"cropped to region".into(),
+                    &format!("{:?}", self.settings.framing).into(),
                 );
 
                 true
             }
 
-            Msg::UpdateMaxRadiusPercentage(s) => {
-                let as_isize = s.parse::<isize>().unwrap();
-                self.max_radius_percentage = if as_isize < 0 {
+            Msg::StartCurveDrag(point, client_y) => {
+                self.curve_drag = Some(CurveDrag {
+                    point,
+                    client_y,
+                    size: self.settings.radius_curve.sizes[point],
+                });
+
+                false
+            }
+
+            Msg::DragCurve(client_y) => {
+                let drag = match &self.curve_drag {
+                    Some(drag) => drag,
+                    None => return false,
+                };
+
+                // up is bigger
+                let dragged = (drag.client_y - client_y) as f32 / RADIUS_CURVE_EDITOR_PIXELS;
+                self.settings.radius_curve.sizes[drag.point] =
+                    (drag.size + dragged).clamp(0.0, 1.0);
+
+                true
+            }
+
+            Msg::EndCurveDrag => {
+                self.curve_drag = None;
+
+                false
+            }
+
+            Msg::ResetRadiusCurve => {
+                self.settings.radius_curve = RadiusCurve::default();
+
+                true
+            }
+
+            Msg::UpdateMinRadiusPercentage(s) => {
+                let as_isize = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32.round() as isize,
+                    None => {
+                        console::log_2(&"could not parse min raster percentage".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.min_radius_percentage = if as_isize < 0 {
                     0.0
                 } else if as_isize > 100 {
                     1.0
@@ -511,261 +1930,2711 @@ impl Component for Model {
                     as_isize as f32 / 100.0
                 };
 
-                if self.max_radius_percentage < self.min_radius_percentage {
-                    console::log_1(&"max raster % < min raster %, setting to 100%".into());
-                    self.max_radius_percentage = 1.0;
+                if self.settings.min_radius_percentage > self.settings.max_radius_percentage {
+                    console::log_1(&"min raster % > max raster %, setting to 0%".into());
+                    self.settings.min_radius_percentage = 0.0;
                 }
 
                 console::log_3(
-                    &"set max raster percentage to ".into(),
-                    &(self.max_radius_percentage * 100.0).into(),
+                    &"set min raster percentage to ".into(),
+                    &(self.settings.min_radius_percentage * 100.0).into(),
                     &"%".into(),
                 );
 
                 true
             }
 
-            Msg::UpdatePaperSize(s) => {
-                if let Some(ps) = &PaperSize::from_string(&s) {
-                    self.paper_size = *ps;
-                }
-                console::log_1(&s.into());
+            Msg::UpdateRadiusJitter(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32,
+                    None => {
+                        console::log_2(&"could not parse radius jitter".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.jitter.radius = (as_f32 / 100.0).clamp(0.0, 1.0);
 
                 true
             }
 
-            Msg::UpdateOrientation(s) => {
-                match s.as_ref() {
-                    "Portrait" => self.orientation = Orientation::Portrait,
-                    "Landscape" => self.orientation = Orientation::Landscape,
-                    _ => unreachable!(),
-                }
-                console::log_1(&s.into());
+            Msg::ToggleEdges => {
+                self.settings.edges.enabled = !self.settings.edges.enabled;
 
                 true
             }
 
-            Msg::UpdateBackend(s) => {
-                match s.as_ref() {
-                    "Image" => {
-                        console::log_1(&"Image backend selected".into());
-                        self.backend = Backend::Image
+            Msg::UpdateEdgeThreshold(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32,
+                    None => {
+                        console::log_2(&"could not parse edge threshold".into(), &s.into());
+                        return false;
                     }
-                    "SVG" => {
-                        console::log_1(&"SVG backend selected".into());
-                        self.backend = Backend::Svg
+                };
+                self.settings.edges.threshold = (as_f32 / 100.0).clamp(0.0, 4.0);
+
+                true
+            }
+
+            Msg::UpdateEdgeWidth(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32,
+                    None => {
+                        console::log_2(&"could not parse edge width".into(), &s.into());
+                        return false;
                     }
-                    _ => unreachable!(),
-                }
+                };
+                self.settings.edges.width = as_f32.max(0.0);
 
                 true
             }
 
-            Msg::UpdateColorDepth(s) => {
-                match s.as_ref() {
-                    "RGB" => {
-                        console::log_1(&"RGB color selected".into());
-                        self.color_depth = ColorDepth::Rgb;
+            Msg::UpdatePositionJitter(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32,
+                    None => {
+                        console::log_2(&"could not parse position jitter".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.jitter.position = (as_f32 / 100.0).clamp(0.0, 1.0);
+
+                true
+            }
+
+            Msg::UpdateJitterSeed(s) => {
+                let as_u32 = match parse_number::<u32>(&s) {
+                    Some(as_u32) => as_u32,
+                    None => {
+                        console::log_2(&"could not parse jitter seed".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.jitter.seed = as_u32;
+
+                true
+            }
+
+            Msg::UpdateMaxRadiusPercentage(s) => {
+                let as_isize = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32.round() as isize,
+                    None => {
+                        console::log_2(&"could not parse max raster percentage".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.max_radius_percentage = if as_isize < 0 {
+                    0.0
+                } else if as_isize > MAX_RADIUS_PERCENTAGE_LIMIT {
+                    MAX_RADIUS_PERCENTAGE_LIMIT as f32 / 100.0
+                } else {
+                    as_isize as f32 / 100.0
+                };
+
+                if self.settings.max_radius_percentage < self.settings.min_radius_percentage {
+                    console::log_1(&"max raster % < min raster %, setting to 100%".into());
+                    self.settings.max_radius_percentage = 1.0;
+                }
+
+                console::log_3(
+                    &"set max raster percentage to ".into(),
+                    &(self.settings.max_radius_percentage * 100.0).into(),
+                    &"%".into(),
+                );
+
+                true
+            }
+
+            Msg::UpdatePaperSize(s) => {
+                if let Some(ps) = &PaperSize::from_string(&s) {
+                    self.settings.paper_size = *ps;
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::UpdateOrientation(s) => {
+                match s.as_ref() {
+                    "Portrait" => self.settings.orientation = Orientation::Portrait,
+                    "Landscape" => self.settings.orientation = Orientation::Landscape,
+                    _ => unreachable!(),
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::UpdateBackend(s) => {
+                match s.as_ref() {
+                    "Image" => {
+                        console::log_1(&"Image backend selected".into());
+                        self.settings.backend = Backend::Image
+                    }
+                    "SVG" => {
+                        console::log_1(&"SVG backend selected".into());
+                        self.settings.backend = Backend::Svg
+                    }
+                    "Voronoi stipple" => {
+                        console::log_1(&"Voronoi stipple backend selected".into());
+                        self.settings.backend = Backend::Voronoi
+                    }
+                    "Text" => {
+                        console::log_1(&"Text backend selected".into());
+                        self.settings.backend = Backend::Text
+                    }
+                    _ => unreachable!(),
+                }
+
+                true
+            }
+
+            Msg::UpdateColorDepth(s) => {
+                match s.as_ref() {
+                    "RGB" => {
+                        console::log_1(&"RGB color selected".into());
+                        self.settings.color_depth = ColorDepth::Rgb;
                     }
                     "Grayscale" => {
                         console::log_1(&"Grayscale selected".into());
-                        self.color_depth = ColorDepth::Grayscale;
+                        self.settings.color_depth = ColorDepth::Grayscale;
+                    }
+                    "True grayscale" => {
+                        console::log_1(&"True grayscale selected".into());
+                        self.settings.color_depth = ColorDepth::TrueGrayscale;
+                    }
+                    "Gradient" => {
+                        console::log_1(&"Gradient selected".into());
+                        self.settings.color_depth = ColorDepth::Gradient;
+                    }
+                    "Duotone" => {
+                        console::log_1(&"Duotone selected".into());
+                        self.settings.color_depth = ColorDepth::Duotone;
+                    }
+                    "Tritone" => {
+                        console::log_1(&"Tritone selected".into());
+                        self.settings.color_depth = ColorDepth::Tritone;
+                    }
+                    "Palette" => {
+                        console::log_1(&"Palette selected".into());
+                        self.settings.color_depth = ColorDepth::Palette;
                     }
                     _ => unreachable!(),
                 }
 
                 true
             }
-        }
-    }
 
-    fn change(&mut self, _: Self::Properties) -> ShouldRender {
-        true
-    }
+            Msg::UpdateLayout(s) => {
+                if let Some(layout) = Layout::from_string(&s) {
+                    self.settings.layout = layout;
+                }
+                console::log_1(&s.into());
 
-    fn view(&self) -> Html {
-        html! {
-            <div class="container">
-                <div class="row">
-                    <div class="column">
-                        <a href="https://github.com/ckampfe/rat">{ format!("source code version {}", RAT_VERSION) }</a>
-                        <div>
-                            {
-                                format!("{}in x {}in",
-                                   self.paper_size.width_inches(self.orientation) * self.pages_width as f32,
-                                   self.paper_size.height_inches(self.orientation) * self.pages_height as f32
-                                )
-                            }
-                        </div>
+                true
+            }
 
-                        <div>
-                            { format!("{}w x {}h pages", self.pages_width, self.pages_height) }
-                        </div>
+            Msg::UpdateRepeatColumns(s) => {
+                let as_u32 = match parse_number::<u32>(&s) {
+                    Some(as_u32) => as_u32.max(1),
+                    None => {
+                        console::log_2(&"could not parse repeat columns".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.step_and_repeat.columns = as_u32;
 
-                        <div>
-                            { format!("square size: {}", self.square_size)}
-                        </div>
+                true
+            }
+
+            Msg::UpdateRepeatRows(s) => {
+                let as_u32 = match parse_number::<u32>(&s) {
+                    Some(as_u32) => as_u32.max(1),
+                    None => {
+                        console::log_2(&"could not parse repeat rows".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.step_and_repeat.rows = as_u32;
+
+                true
+            }
+
+            Msg::UpdateRepeatScale(s) => {
+                let as_isize = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32.round() as isize,
+                    None => {
+                        console::log_2(&"could not parse repeat scale".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.step_and_repeat.scale = as_isize.clamp(1, 100) as f32 / 100.0;
+
+                true
+            }
+
+            Msg::UpdateRepeatSpacing(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32.max(0.0),
+                    None => {
+                        console::log_2(&"could not parse repeat spacing".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.step_and_repeat.spacing = as_f32;
+
+                true
+            }
+
+            Msg::UpdateBorderThickness(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32.max(0.0),
+                    None => {
+                        console::log_2(&"could not parse border thickness".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.border.thickness = as_f32;
+
+                true
+            }
+
+            Msg::UpdateBorderInset(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32.max(0.0),
+                    None => {
+                        console::log_2(&"could not parse border inset".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.border.inset = as_f32;
+
+                true
+            }
+
+            Msg::UpdateBorderColor(s) => {
+                if let Some(color) = Color::from_hex(&s) {
+                    self.settings.border.color = color;
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::UpdateMatrixParameter(s) => {
+                if let Some(parameter) = MatrixParameter::from_string(&s) {
+                    // start from sensible bounds for the newly selected parameter
+                    self.settings.matrix = match parameter {
+                        MatrixParameter::SquareSize => ParameterMatrix::default(),
+                        MatrixParameter::MaxRadiusPercentage => ParameterMatrix {
+                            parameter,
+                            from: 25.0,
+                            to: 100.0,
+                        },
+                    };
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::UpdateMatrixFrom(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32.max(0.0),
+                    None => {
+                        console::log_2(&"could not parse matrix start value".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.matrix.from = as_f32;
+
+                true
+            }
+
+            Msg::UpdateMatrixTo(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32.max(0.0),
+                    None => {
+                        console::log_2(&"could not parse matrix end value".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.matrix.to = as_f32;
+
+                true
+            }
+
+            Msg::UpdateGradientDark(s) => {
+                if let Some(color) = Color::from_hex(&s) {
+                    self.settings.gradient.dark = color;
+                }
+
+                true
+            }
+
+            Msg::UpdateGradientLight(s) => {
+                if let Some(color) = Color::from_hex(&s) {
+                    self.settings.gradient.light = color;
+                }
+
+                true
+            }
+
+            Msg::UpdateInk(ink, s) => {
+                if let Some(color) = Color::from_hex(&s) {
+                    self.settings.inks.colors[ink] = color;
+                }
+
+                true
+            }
+
+            Msg::UpdatePaletteSize(s) => {
+                let len = match parse_number::<usize>(&s) {
+                    Some(len) => len.clamp(2, MAX_PALETTE_COLORS),
+                    None => {
+                        console::log_2(&"could not parse palette size".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.palette.len = len;
+
+                true
+            }
+
+            Msg::TogglePaletteAutomatic => {
+                self.settings.palette.automatic = !self.settings.palette.automatic;
+
+                true
+            }
+
+            Msg::UpdatePaletteColor(i, s) => {
+                if let Some(color) = Color::from_hex(&s) {
+                    self.settings.palette.colors[i] = color;
+                }
+
+                true
+            }
+
+            Msg::UseColorBlindSafeInks => {
+                let count = match self.settings.color_depth {
+                    ColorDepth::Palette => self.settings.palette.colors().len(),
+                    color_depth => color_depth.ink_count().unwrap_or(0),
+                };
+                let inks = match self.settings.color_depth {
+                    ColorDepth::Palette => &mut self.settings.palette.colors[..count],
+                    _ => &mut self.settings.inks.colors[..count],
+                };
+
+                for (ink, color) in inks.iter_mut().zip(color::color_blind_safe(count)) {
+                    *ink = color;
+                }
+
+                true
+            }
+
+            Msg::UpdateColorVision(s) => {
+                if let Some(color_vision) = ColorVision::from_string(&s) {
+                    self.settings.color_vision = color_vision;
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::UpdateOutputPixelsPerInch(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32.max(rasterize::PIXELS_PER_INCH),
+                    None => {
+                        console::log_2(&"could not parse output dpi".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.output_pixels_per_inch = as_f32;
+
+                console::log_2(&"output dpi set to".into(), &as_f32.into());
+
+                true
+            }
+
+            Msg::ToggleDraft => {
+                self.settings.draft = !self.settings.draft;
+
+                console::log_2(&"draft mode".into(), &self.settings.draft.into());
+
+                true
+            }
+
+            Msg::ToggleWholePoster => {
+                self.settings.whole_poster = !self.settings.whole_poster;
+
+                console::log_2(
+                    &"whole poster output".into(),
+                    &self.settings.whole_poster.into(),
+                );
+
+                true
+            }
+
+            Msg::UpdateCircleAlgorithm(s) => {
+                if let Some(algorithm) = CircleAlgorithm::from_string(&s) {
+                    self.settings.circle_algorithm = algorithm;
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::UpdateSupersampling(s) => {
+                let as_u32 = match parse_number::<u32>(&s) {
+                    Some(as_u32) => as_u32.max(1),
+                    None => {
+                        console::log_2(&"could not parse supersampling".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.supersampling = as_u32;
+
+                console::log_2(&"supersampling set to".into(), &as_u32.into());
+
+                true
+            }
+
+            Msg::ToggleAnimatedGif => {
+                self.settings.animated_gif = !self.settings.animated_gif;
+
+                console::log_2(&"animated gif".into(), &self.settings.animated_gif.into());
+
+                true
+            }
+
+            Msg::ToggleContactSheet => {
+                self.settings.contact_sheet.enabled = !self.settings.contact_sheet.enabled;
+
+                true
+            }
+
+            Msg::UpdateContactSheetSquareSizeFrom(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32.max(0.0),
+                    None => {
+                        console::log_2(
+                            &"could not parse smallest contact sheet square size".into(),
+                            &s.into(),
+                        );
+                        return false;
+                    }
+                };
+                self.settings.contact_sheet.square_size_from = as_f32;
+
+                true
+            }
+
+            Msg::UpdateContactSheetSquareSizeTo(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32.max(0.0),
+                    None => {
+                        console::log_2(
+                            &"could not parse largest contact sheet square size".into(),
+                            &s.into(),
+                        );
+                        return false;
+                    }
+                };
+                self.settings.contact_sheet.square_size_to = as_f32;
+
+                true
+            }
+
+            Msg::UpdateContactSheetMaxRadiusFrom(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32.max(0.0),
+                    None => {
+                        console::log_2(
+                            &"could not parse smallest contact sheet dot size".into(),
+                            &s.into(),
+                        );
+                        return false;
+                    }
+                };
+                self.settings.contact_sheet.max_radius_from = as_f32;
+
+                true
+            }
+
+            Msg::UpdateContactSheetMaxRadiusTo(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32.max(0.0),
+                    None => {
+                        console::log_2(
+                            &"could not parse largest contact sheet dot size".into(),
+                            &s.into(),
+                        );
+                        return false;
+                    }
+                };
+                self.settings.contact_sheet.max_radius_to = as_f32;
+
+                true
+            }
+
+            Msg::UpdateContactSheetSteps(s) => {
+                let as_u32 = match parse_number::<u32>(&s) {
+                    Some(as_u32) => as_u32.clamp(1, rasterize::MAX_CONTACT_SHEET_STEPS),
+                    None => {
+                        console::log_2(&"could not parse contact sheet steps".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.contact_sheet.steps = as_u32;
+
+                true
+            }
+
+            Msg::UpdateDither(s) => {
+                if let Some(dither) = Dither::from_string(&s) {
+                    self.settings.dither = dither;
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::UpdatePattern(s) => {
+                if let Some(pattern) = Pattern::from_string(&s) {
+                    self.settings.pattern = pattern;
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::ApplySuggestedPages(columns, rows) => {
+                self.settings.pages_width = columns;
+                self.settings.pages_height = rows;
+
+                console::log_3(&"pages set to".into(), &columns.into(), &rows.into());
+
+                true
+            }
+
+            Msg::MaskSelection(files) => {
+                for file in files {
+                    let callback = self.link.callback(Msg::MaskLoaded);
+                    let task = ReaderService::read_file(file, callback).unwrap();
+                    self.tasks.push(task);
+                }
+
+                true
+            }
+
+            Msg::MaskLoaded(file) => {
+                console::log_2(
+                    &"finished loading mask: {}".into(),
+                    &file.name.as_str().into(),
+                );
+                self.mask_load = self.mask_load.wrapping_add(1);
+
+                match decode_image(&file.content) {
+                    Err(_) if !capabilities::decodes(&file.content) => {
+                        let load = self.mask_load;
+                        self.decode_in_browser(file, "mask", move |_, decoded| {
+                            Msg::MaskDecoded(load, decoded)
+                        });
+
+                        false
+                    }
+                    decoded => self.use_mask(decoded),
+                }
+            }
+
+            Msg::MaskDecoded(load, _) if load != self.mask_load => {
+                console::log_1(&"dropping stale mask".into());
+
+                false
+            }
+
+            Msg::MaskDecoded(_, decoded) => self.use_mask(decoded),
+
+            Msg::ClearMask => {
+                self.mask_load = self.mask_load.wrapping_add(1);
+                self.mask = Rc::new(None);
+
+                true
+            }
+
+            Msg::UpdateMaskBlend(s) => {
+                if let Some(mask_blend) = MaskBlend::from_string(&s) {
+                    self.settings.mask_blend = mask_blend;
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::UpdateGamma(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32,
+                    None => {
+                        console::log_2(&"could not parse gamma".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.tone.gamma = (as_f32 / 100.0).clamp(0.1, 5.0);
+
+                true
+            }
+
+            Msg::UpdateBrightness(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32,
+                    None => {
+                        console::log_2(&"could not parse brightness".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.tone.brightness = (as_f32 / 100.0).clamp(-1.0, 1.0);
+
+                true
+            }
+
+            Msg::UpdateContrast(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32,
+                    None => {
+                        console::log_2(&"could not parse contrast".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.tone.contrast = (as_f32 / 100.0).clamp(-1.0, 1.0);
+
+                true
+            }
+
+            Msg::ToggleCompositeBackground => {
+                self.settings.transparency.composite = !self.settings.transparency.composite;
+
+                true
+            }
+
+            Msg::UpdateBackground(s) => {
+                if let Some(color) = Color::from_hex(&s) {
+                    self.settings.transparency.background = color;
+                }
+
+                true
+            }
+
+            Msg::ToggleSkipTransparent => {
+                self.settings.transparency.skip_transparent =
+                    !self.settings.transparency.skip_transparent;
+
+                true
+            }
+
+            Msg::TurnImage(quarter_turns) => {
+                self.settings.framing.quarter_turns =
+                    (self.settings.framing.quarter_turns + quarter_turns) % 4;
+
+                true
+            }
+
+            Msg::ToggleFlipHorizontal => {
+                self.settings.framing.flip_horizontal = !self.settings.framing.flip_horizontal;
+
+                true
+            }
+
+            Msg::ToggleFlipVertical => {
+                self.settings.framing.flip_vertical = !self.settings.framing.flip_vertical;
+
+                true
+            }
+
+            Msg::UpdateCrop(side, s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32,
+                    None => {
+                        console::log_2(&"could not parse crop".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings
+                    .framing
+                    .set_crop(side, (as_f32 / 100.0).clamp(0.0, MAX_CROP_FRACTION));
+
+                true
+            }
+
+            Msg::ResetFraming => {
+                self.settings.framing = Framing::default();
+
+                true
+            }
+
+            Msg::ToggleInvert(channel) => {
+                self.settings.invert.toggle(channel);
+
+                console::log_3(
+                    &"invert".into(),
+                    &channel.to_string().into(),
+                    &self.settings.invert.get(channel).into(),
+                );
+
+                true
+            }
+
+            Msg::UpdateVoronoiPoints(s) => {
+                let as_u32 = match parse_number::<u32>(&s) {
+                    Some(as_u32) => as_u32,
+                    None => {
+                        console::log_2(&"could not parse voronoi points".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.voronoi.points = as_u32;
+
+                console::log_2(
+                    &"voronoi points set to".into(),
+                    &self.settings.voronoi.points.into(),
+                );
+
+                true
+            }
+
+            Msg::UpdateVoronoiIterations(s) => {
+                let as_u32 = match parse_number::<u32>(&s) {
+                    Some(as_u32) => as_u32,
+                    None => {
+                        console::log_2(&"could not parse voronoi iterations".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.voronoi.iterations = as_u32;
+
+                console::log_2(
+                    &"voronoi iterations set to".into(),
+                    &self.settings.voronoi.iterations.into(),
+                );
+
+                true
+            }
+
+            Msg::UpdateLineAngle(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32,
+                    None => {
+                        console::log_2(&"could not parse line angle".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.line_angle = as_f32;
+
+                console::log_2(
+                    &"line angle set to".into(),
+                    &self.settings.line_angle.into(),
+                );
+
+                true
+            }
+
+            Msg::UpdateDotShape(s) => {
+                if let Some(dot_shape) = DotShape::from_string(&s) {
+                    self.settings.dot_shape = dot_shape;
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::ToggleFollowContours => {
+                self.settings.follow_contours = !self.settings.follow_contours;
+
+                true
+            }
+
+            Msg::UpdatePageFormat(s) => {
+                if let Some(format) = PageFormat::from_string(&s) {
+                    self.settings.page_encoding.format = format;
+                }
+
+                true
+            }
+
+            Msg::UpdatePageQuality(s) => {
+                let as_u8 = match parse_number::<u8>(&s) {
+                    Some(as_u8) => as_u8,
+                    None => {
+                        console::log_2(&"could not parse jpeg quality".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.page_encoding.quality = as_u8.clamp(1, 100);
+
+                true
+            }
+
+            Msg::UpdateZipCompression(s) => {
+                if let Some(zip_compression) = ZipCompression::from_string(&s) {
+                    self.settings.zip_compression = zip_compression;
+                }
+
+                true
+            }
+
+            Msg::UpdateColorProfile(s) => {
+                if let Some(color_profile) = ColorProfile::from_string(&s) {
+                    self.settings.color_profile = color_profile;
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::UpdateResampling(s) => {
+                if let Some(resampling) = Resampling::from_string(&s) {
+                    self.settings.resampling = resampling;
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::UpdateLuma(s) => {
+                if let Some(luma) = Luma::from_string(&s) {
+                    self.settings.luma = luma;
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::UpdateRowOrder(s) => {
+                if let Some(row_order) = RowOrder::from_string(&s) {
+                    self.settings.row_order = row_order;
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::UpdateBuildUp(s) => {
+                if let Some(build_up) = BuildUp::from_string(&s) {
+                    self.settings.build_up = build_up;
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::ToggleGcode => {
+                self.settings.gcode = !self.settings.gcode;
+
+                true
+            }
+
+            Msg::ToggleEps => {
+                self.settings.eps = !self.settings.eps;
+
+                true
+            }
+
+            Msg::ToggleDxf => {
+                self.settings.dxf = !self.settings.dxf;
+
+                true
+            }
+
+            Msg::ToggleLaser => {
+                self.settings.laser.enabled = !self.settings.laser.enabled;
+
+                true
+            }
+
+            Msg::UpdateLaserKerf(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32,
+                    None => {
+                        console::log_2(&"could not parse laser kerf".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.laser.kerf = as_f32.max(0.0);
+
+                true
+            }
+
+            Msg::ToggleSvgOptimization => {
+                self.settings.svg_optimization.enabled = !self.settings.svg_optimization.enabled;
+
+                true
+            }
+
+            Msg::UpdateSvgPrecision(s) => {
+                let as_u8 = match parse_number::<u8>(&s) {
+                    Some(as_u8) => as_u8,
+                    None => {
+                        console::log_2(&"could not parse svg precision".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.svg_optimization.precision = as_u8.min(MAX_SVG_PRECISION);
+
+                true
+            }
+
+            Msg::ToggleMinDotSize => {
+                self.settings.min_dot_size.enabled = !self.settings.min_dot_size.enabled;
+
+                true
+            }
+
+            Msg::UpdateMinDotRadius(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32,
+                    None => {
+                        console::log_2(&"could not parse minimum dot radius".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.min_dot_size.radius = as_f32.max(0.0);
+
+                true
+            }
+
+            Msg::ToggleDotMap => {
+                self.settings.dot_map = !self.settings.dot_map;
+
+                true
+            }
+
+            Msg::TogglePrintHtml => {
+                self.settings.print_html = !self.settings.print_html;
+
+                true
+            }
+
+            Msg::ToggleRelief => {
+                self.settings.relief.enabled = !self.settings.relief.enabled;
+
+                true
+            }
+
+            Msg::UpdateReliefBase(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32,
+                    None => {
+                        console::log_2(&"could not parse relief base".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.relief.base = as_f32.max(0.0);
+
+                true
+            }
+
+            Msg::UpdateReliefHeight(s) => {
+                let as_f32 = match parse_number::<f32>(&s) {
+                    Some(as_f32) => as_f32,
+                    None => {
+                        console::log_2(&"could not parse relief height".into(), &s.into());
+                        return false;
+                    }
+                };
+                self.settings.relief.height = as_f32.max(0.0);
+
+                true
+            }
+
+            Msg::UpdateReliefShape(s) => {
+                if let Some(shape) = ReliefShape::from_string(&s) {
+                    self.settings.relief.shape = shape;
+                }
+                console::log_1(&s.into());
+
+                true
+            }
+
+            Msg::ToggleDotMetadata => {
+                self.settings.dot_metadata = !self.settings.dot_metadata;
+
+                true
+            }
+
+            Msg::ToggleHighContrast => {
+                self.settings.high_contrast = !self.settings.high_contrast;
+
+                true
+            }
+
+            Msg::ToggleAutoRasterize => {
+                self.settings.auto_rasterize = !self.settings.auto_rasterize;
+
+                console::log_2(
+                    &"auto rasterize".into(),
+                    &self.settings.auto_rasterize.into(),
+                );
+
+                true
+            }
+
+            Msg::TogglePlotPreview => {
+                self.settings.plot_preview = !self.settings.plot_preview;
+
+                console::log_2(&"plot preview".into(), &self.settings.plot_preview.into());
+
+                true
+            }
+
+            Msg::ToggleTspPath => {
+                self.settings.tsp_path = !self.settings.tsp_path;
+
+                console::log_2(&"tsp path".into(), &self.settings.tsp_path.into());
+
+                true
+            }
+
+            Msg::UpdateCharacterRamp(s) => {
+                console::log_2(&"character ramp set to".into(), &s.clone().into());
+
+                self.settings.character_ramp = s;
+
+                true
+            }
+
+            Msg::UpdatePresetName(s) => {
+                self.preset_name = s;
+
+                false
+            }
+
+            Msg::SavePreset => {
+                let name = self.preset_name.trim().to_string();
+
+                if name.is_empty() {
+                    console::log_1(&"presets need a name, not saving".into());
+                    return false;
+                }
+
+                console::log_2(&"saving preset".into(), &name.as_str().into());
+
+                self.presets.insert(name, self.settings.clone());
+                self.preset_name.clear();
+                self.save_presets();
+
+                true
+            }
+
+            Msg::LoadPreset(name) => {
+                if let Some(settings) = self.presets.get(&name).cloned() {
+                    console::log_2(&"loading preset".into(), &name.into());
+                    self.settings = settings;
+                }
+
+                true
+            }
+
+            Msg::DeletePreset(name) => {
+                console::log_2(&"deleting preset".into(), &name.as_str().into());
+
+                self.presets.remove(&name);
+                self.save_presets();
+
+                true
+            }
+
+            Msg::Undo => match self.history.undo(before.clone()) {
+                Some(settings) => {
+                    self.settings = settings;
+                    true
+                }
+                None => false,
+            },
+
+            Msg::Redo => match self.history.redo(before.clone()) {
+                Some(settings) => {
+                    self.settings = settings;
+                    true
+                }
+                None => false,
+            },
+        };
+
+        // most messages, like a mousemove with nothing dragged, leave the settings as they were
+        if self.settings != before {
+            if recorded {
+                self.history.record(before, performance_now());
+            }
+
+            self.save_settings();
+        }
+
+        should_render
+    }
+
+    fn change(&mut self, _: Self::Properties) -> ShouldRender {
+        true
+    }
+
+    fn rendered(&mut self, _first_render: bool) {
+        // the video element only exists once the camera has started,
+        // so the stream can't be attached to it until after rendering
+        if let (Some(stream), Some(video)) = (
+            &self.camera_stream,
+            self.camera_video.cast::<web_sys::HtmlVideoElement>(),
+        ) {
+            if video.src_object().is_none() {
+                video.set_src_object(Some(stream));
+            }
+        }
+
+        self.draw_layout_preview();
+    }
+
+    fn destroy(&mut self) {
+        if let Some(stream) = self.camera_stream.take() {
+            camera::stop_stream(&stream);
+        }
+    }
+
+    // html! checks each prop of a component, like a `Stepper`'s, with a bare `props.field;`
+    #[allow(clippy::unnecessary_operation)]
+    fn view(&self) -> Html {
+        html! {
+            <div class=classes!("container", self.settings.high_contrast.then_some("high-contrast"))>
+                <div class="row">
+                    <div class="column">
+                        <a href="https://github.com/ckampfe/rat">{ format!("source code version {}", RAT_VERSION) }</a>
+                        <details>
+                            <summary>{ "about this build" }</summary>
+                            <pre>{ &self.about }</pre>
+                        </details>
+                        <div>
+                            <a href={self.settings.to_url_fragment()}>{ "link to these settings" }</a>
+                        </div>
+                        <div>
+                            <button
+                            title="ctrl+z"
+                            disabled={ !self.history.can_undo() }
+                            onclick=self.link.callback(|_| Msg::Undo)>
+                                { "undo" }
+                            </button>
+                            <button
+                            title="ctrl+shift+z"
+                            disabled={ !self.history.can_redo() }
+                            onclick=self.link.callback(|_| Msg::Redo)>
+                                { "redo" }
+                            </button>
+                        </div>
+                        <div>
+                            <label>
+                                <input
+                                type="checkbox"
+                                name="high-contrast"
+                                checked=self.settings.high_contrast
+                                onclick=self.link.callback(|_| Msg::ToggleHighContrast)/>
+                                { " large, high contrast controls" }
+                            </label>
+                        </div>
+
+                        <div>
+                            { "presets: " }
+                            {
+                                for self.presets.keys().map(|name| {
+                                    let load_name = name.clone();
+                                    let delete_name = name.clone();
+
+                                    html! {
+                                        <div>
+                                            <button onclick=self.link.callback(move |_| Msg::LoadPreset(load_name.clone()))>
+                                                { name }
+                                            </button>
+                                            <button onclick=self.link.callback(move |_| Msg::DeletePreset(delete_name.clone()))>
+                                                { "delete" }
+                                            </button>
+                                        </div>
+                                    }
+                                })
+                            }
+                            <input
+                            type="text"
+                            name="preset-name"
+                            placeholder="preset name"
+                            value={self.preset_name.clone()}
+                            oninput=self.link.callback(|e: InputData| Msg::UpdatePresetName(e.value))/>
+                            <button onclick=self.link.callback(|_| Msg::SavePreset)>
+                                { "Save preset" }
+                            </button>
+                        </div>
+                        <div>
+                            {
+                                format!("{}in x {}in",
+                                   self.settings.paper_size.width_inches(self.settings.orientation) * self.settings.pages_width as f32,
+                                   self.settings.paper_size.height_inches(self.settings.orientation) * self.settings.pages_height as f32
+                                )
+                            }
+                        </div>
+
+                        <div>
+                            { format!("{}w x {}h pages", self.settings.pages_width, self.settings.pages_height) }
+                        </div>
+
+                        <div>
+                            { format!("square size: {}", self.settings.square_size)}
+                        </div>
+
+                        <div>
+                            <div>
+                                { "paper size: " }
+                                <select name="paper_size" onchange=self.link.callback(|e: ChangeData| {
+                                    match e {
+                                        ChangeData::Select(s) => {
+                                            Msg::UpdatePaperSize(s.value())
+                                        },
+                                        _ => unreachable!()
+                                    }
+                                })>
+                                {
+                                    for PaperSize::sizes().map(|paper_size| {
+                                        html! {
+                                            <option value={ paper_size.to_string() } selected={ *paper_size == self.settings.paper_size }> { paper_size.to_string() } </option>
+                                        }
+                                    })
+                                }
+                                </select>
+                            </div>
+
+                            <div>
+                                { "orientation: "}
+                                <select name="orientation" onchange=self.link.callback(|e: ChangeData| {
+                                    match e {
+                                        ChangeData::Select(s) => {
+                                            Msg::UpdateOrientation(s.value())
+                                        },
+                                        _ => unreachable!()
+                                    }
+                                })>
+                                   <option value={ Orientation::Portrait.to_string() } selected={ self.settings.orientation == Orientation::Portrait }> { Orientation::Portrait.to_string() } </option>
+                                   <option value={ Orientation::Landscape.to_string() } selected={ self.settings.orientation == Orientation::Landscape }> { Orientation::Landscape.to_string() } </option>
+                                </select>
+                            </div>
+
+                            <div>
+                                { "backend: " }
+                                <select name="backend" onchange=self.link.callback(|e: ChangeData| {
+                                    match e {
+                                        ChangeData::Select(s) => {
+                                            Msg::UpdateBackend(s.value())
+                                        },
+                                        _ => unreachable!()
+                                    }
+                                })>
+                                    <option value={ Backend::Image.to_string() } selected={ self.settings.backend == Backend::Image }> { Backend::Image.to_string() } </option>
+                                    <option value={ Backend::Svg.to_string() } selected={ self.settings.backend == Backend::Svg }> { Backend::Svg.to_string() } </option>
+                                    <option value={ Backend::Voronoi.to_string() } selected={ self.settings.backend == Backend::Voronoi }> { Backend::Voronoi.to_string() } </option>
+                                    <option value={ Backend::Text.to_string() } selected={ self.settings.backend == Backend::Text }> { Backend::Text.to_string() } </option>
+                                </select>
+
+                                { " color profile: " }
+                                <select name="color-profile" onchange=self.link.callback(|e: ChangeData| {
+                                    match e {
+                                        ChangeData::Select(s) => {
+                                            Msg::UpdateColorProfile(s.value())
+                                        },
+                                        _ => unreachable!()
+                                    }
+                                })>
+                                {
+                                    for ColorProfile::color_profiles().map(|color_profile| {
+                                        html! {
+                                            <option value={ color_profile.to_string() } selected={ *color_profile == self.settings.color_profile }> { color_profile.to_string() } </option>
+                                        }
+                                    })
+                                }
+                                </select>
+
+                                { " zip compression: " }
+                                <select name="zip-compression" onchange=self.link.callback(|e: ChangeData| {
+                                    match e {
+                                        ChangeData::Select(s) => {
+                                            Msg::UpdateZipCompression(s.value())
+                                        },
+                                        _ => unreachable!()
+                                    }
+                                })>
+                                {
+                                    for ZipCompression::zip_compressions().map(|zip_compression| {
+                                        html! {
+                                            <option value={ zip_compression.to_string() } selected={ *zip_compression == self.settings.zip_compression }> { zip_compression.to_string() } </option>
+                                        }
+                                    })
+                                }
+                                </select>
+                            </div>
+
+                            {
+                                if self.settings.backend == Backend::Voronoi {
+                                    html! {
+                                        <div>
+                                            { "points: " }
+                                            <input
+                                              type="text"
+                                              inputmode="numeric"
+                                              name="voronoi-points"
+                                              value={self.settings.voronoi.points.to_string()}
+                                              oninput=self.link.callback(|e: InputData| Msg::UpdateVoronoiPoints(e.value))/>
+                                            { " iterations: " }
+                                            <input
+                                              type="text"
+                                              inputmode="numeric"
+                                              name="voronoi-iterations"
+                                              value={self.settings.voronoi.iterations.to_string()}
+                                              oninput=self.link.callback(|e: InputData| Msg::UpdateVoronoiIterations(e.value))/>
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name="tsp-path"
+                                                checked=self.settings.tsp_path
+                                                onclick=self.link.callback(|_| Msg::ToggleTspPath)/>
+                                                { "also connect each page's points into one path, as svg and g-code" }
+                                            </label>
+                                        </div>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+
+                            {
+                                if self.settings.backend == Backend::Text {
+                                    html! {
+                                        <div>
+                                            { "characters, lightest to darkest: " }
+                                            <input
+                                              type="text"
+                                              name="character-ramp"
+                                              style="font-family: monospace; white-space: pre;"
+                                              value={self.settings.character_ramp.clone()}
+                                              oninput=self.link.callback(|e: InputData| Msg::UpdateCharacterRamp(e.value))/>
+                                        </div>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+
+                            <div>
+                                { "color: " }
+                                <select name="color_depth" onchange=self.link.callback(|e: ChangeData| {
+                                    match e {
+                                        ChangeData::Select(s) => {
+                                            Msg::UpdateColorDepth(s.value())
+                                        },
+                                        _ => unreachable!()
+                                    }
+                                })>
+                                    <option value={ ColorDepth::Rgb.to_string() } selected={ self.settings.color_depth == ColorDepth::Rgb }> { ColorDepth::Rgb.to_string() } </option>
+                                    <option value={ ColorDepth::Grayscale.to_string() } selected={ self.settings.color_depth == ColorDepth::Grayscale }> { ColorDepth::Grayscale.to_string() } </option>
+                                    <option value={ ColorDepth::TrueGrayscale.to_string() } selected={ self.settings.color_depth == ColorDepth::TrueGrayscale }> { ColorDepth::TrueGrayscale.to_string() } </option>
+                                    <option value={ ColorDepth::Gradient.to_string() } selected={ self.settings.color_depth == ColorDepth::Gradient }> { ColorDepth::Gradient.to_string() } </option>
+                                    <option value={ ColorDepth::Duotone.to_string() } selected={ self.settings.color_depth == ColorDepth::Duotone }> { ColorDepth::Duotone.to_string() } </option>
+                                    <option value={ ColorDepth::Tritone.to_string() } selected={ self.settings.color_depth == ColorDepth::Tritone }> { ColorDepth::Tritone.to_string() } </option>
+                                    <option value={ ColorDepth::Palette.to_string() } selected={ self.settings.color_depth == ColorDepth::Palette }> { ColorDepth::Palette.to_string() } </option>
+                                </select>
+
+                                { " brightness from: " }
+                                <select name="luma" onchange=self.link.callback(|e: ChangeData| {
+                                    match e {
+                                        ChangeData::Select(s) => {
+                                            Msg::UpdateLuma(s.value())
+                                        },
+                                        _ => unreachable!()
+                                    }
+                                })>
+                                {
+                                    for Luma::lumas().map(|luma| {
+                                        html! {
+                                            <option value={ luma.to_string() } selected={ *luma == self.settings.luma }> { luma.to_string() } </option>
+                                        }
+                                    })
+                                }
+                                </select>
+
+                                { " resampling: " }
+                                <select name="resampling" onchange=self.link.callback(|e: ChangeData| {
+                                    match e {
+                                        ChangeData::Select(s) => {
+                                            Msg::UpdateResampling(s.value())
+                                        },
+                                        _ => unreachable!()
+                                    }
+                                })>
+                                {
+                                    for Resampling::resamplings().map(|resampling| {
+                                        html! {
+                                            <option value={ resampling.to_string() } selected={ *resampling == self.settings.resampling }> { resampling.to_string() } </option>
+                                        }
+                                    })
+                                }
+                                </select>
+
+                                { " pattern: " }
+                                <select name="pattern" onchange=self.link.callback(|e: ChangeData| {
+                                    match e {
+                                        ChangeData::Select(s) => {
+                                            Msg::UpdatePattern(s.value())
+                                        },
+                                        _ => unreachable!()
+                                    }
+                                })>
+                                {
+                                    for Pattern::patterns().map(|pattern| {
+                                        html! {
+                                            <option value={ pattern.to_string() } selected={ *pattern == self.settings.pattern }> { pattern.to_string() } </option>
+                                        }
+                                    })
+                                }
+                                </select>
+                                {
+                                    if self.settings.pattern == Pattern::Grid {
+                                        html! {
+                                            <>
+                                                { " dot shape: " }
+                                                <select name="dot-shape" onchange=self.link.callback(|e: ChangeData| {
+                                                    match e {
+                                                        ChangeData::Select(s) => {
+                                                            Msg::UpdateDotShape(s.value())
+                                                        },
+                                                        _ => unreachable!()
+                                                    }
+                                                })>
+                                                {
+                                                    for DotShape::dot_shapes().map(|dot_shape| {
+                                                        html! {
+                                                            <option value={ dot_shape.to_string() } selected={ *dot_shape == self.settings.dot_shape }> { dot_shape.to_string() } </option>
+                                                        }
+                                                    })
+                                                }
+                                                </select>
+                                            </>
+                                        }
+                                    } else {
+                                        html! {}
+                                    }
+                                }
+                                {
+                                    if self.settings.pattern == Pattern::Grid && self.settings.dot_shape != DotShape::Circle {
+                                        html! {
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name="follow-contours"
+                                                checked=self.settings.follow_contours
+                                                onclick=self.link.callback(|_| Msg::ToggleFollowContours)/>
+                                                { " follow the image's contours" }
+                                            </label>
+                                        }
+                                    } else {
+                                        html! {}
+                                    }
+                                }
+                                {
+                                    // non-circular dots that aren't following contours are turned to the line angle
+                                    if self.settings.pattern == Pattern::Lines
+                                        || (self.settings.pattern == Pattern::Grid && self.settings.dot_shape != DotShape::Circle)
+                                    {
+                                        html! {
+                                            <>
+                                                { " angle (degrees): " }
+                                                <input
+                                                  type="text"
+                                                  inputmode="decimal"
+                                                  name="line-angle"
+                                                  value={self.settings.line_angle.to_string()}
+                                                  oninput=self.link.callback(|e: InputData| Msg::UpdateLineAngle(e.value))/>
+                                            </>
+                                        }
+                                    } else {
+                                        html! {}
+                                    }
+                                }
+
+                                { " dither: " }
+                                <select name="dither" onchange=self.link.callback(|e: ChangeData| {
+                                    match e {
+                                        ChangeData::Select(s) => {
+                                            Msg::UpdateDither(s.value())
+                                        },
+                                        _ => unreachable!()
+                                    }
+                                })>
+                                {
+                                    for Dither::dithers().map(|dither| {
+                                        html! {
+                                            <option value={ dither.to_string() } selected={ *dither == self.settings.dither }> { dither.to_string() } </option>
+                                        }
+                                    })
+                                }
+                                </select>
+                            </div>
+
+                            {
+                                if self.settings.backend == Backend::Image {
+                                    html! {
+                                        <div>
+                                            { "output dpi: " }
+                                            <select name="output_dpi" onchange=self.link.callback(|e: ChangeData| {
+                                                match e {
+                                                    ChangeData::Select(s) => {
+                                                        Msg::UpdateOutputPixelsPerInch(s.value())
+                                                    },
+                                                    _ => unreachable!()
+                                                }
+                                            })>
+                                            {
+                                                for OUTPUT_PIXELS_PER_INCH.iter().map(|ppi| {
+                                                    html! {
+                                                        <option value={ ppi.to_string() } selected={ (*ppi - self.settings.output_pixels_per_inch).abs() < f32::EPSILON }> { ppi.to_string() } </option>
+                                                    }
+                                                })
+                                            }
+                                            </select>
+
+                                            { " circles: " }
+                                            <select name="circle_algorithm" onchange=self.link.callback(|e: ChangeData| {
+                                                match e {
+                                                    ChangeData::Select(s) => {
+                                                        Msg::UpdateCircleAlgorithm(s.value())
+                                                    },
+                                                    _ => unreachable!()
+                                                }
+                                            })>
+                                            {
+                                                for CircleAlgorithm::algorithms().map(|algorithm| {
+                                                    html! {
+                                                        <option value={ algorithm.to_string() } selected={ *algorithm == self.settings.circle_algorithm }> { algorithm.to_string() } </option>
+                                                    }
+                                                })
+                                            }
+                                            </select>
+
+                                            { " supersampling: " }
+                                            <select name="supersampling" onchange=self.link.callback(|e: ChangeData| {
+                                                match e {
+                                                    ChangeData::Select(s) => {
+                                                        Msg::UpdateSupersampling(s.value())
+                                                    },
+                                                    _ => unreachable!()
+                                                }
+                                            })>
+                                            {
+                                                for rasterize::SUPERSAMPLING_FACTORS.iter().map(|factor| {
+                                                    html! {
+                                                        <option value={ factor.to_string() } selected={ *factor == self.settings.supersampling }> { format!("{}x", factor) } </option>
+                                                    }
+                                                })
+                                            }
+                                            </select>
+
+                                            { " format: " }
+                                            <select name="page_format" onchange=self.link.callback(|e: ChangeData| {
+                                                match e {
+                                                    ChangeData::Select(s) => {
+                                                        Msg::UpdatePageFormat(s.value())
+                                                    },
+                                                    _ => unreachable!()
+                                                }
+                                            })>
+                                            {
+                                                for PageFormat::page_formats()
+                                                    .filter(|format| **format != PageFormat::Webp || bitmap::can_encode_webp())
+                                                    .map(|format| {
+                                                        html! {
+                                                            <option value={ format.to_string() } selected={ *format == self.settings.page_encoding.format }> { format.to_string() } </option>
+                                                        }
+                                                    })
+                                            }
+                                            </select>
+                                            {
+                                                if matches!(self.settings.page_encoding.format, PageFormat::Jpeg | PageFormat::Webp) {
+                                                    html! {
+                                                        <>
+                                                            <div>{ format!("{} quality", self.settings.page_encoding.format) }</div>
+                                                            <Stepper
+                                                            name="jpeg-quality"
+                                                            min=1.0
+                                                            max=100.0
+                                                            step=5.0
+                                                            value={self.settings.page_encoding.quality as f32}
+                                                            onchange=self.link.callback(Msg::UpdatePageQuality)/>
+                                                        </>
+                                                    }
+                                                } else {
+                                                    html! {}
+                                                }
+                                            }
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name="contact-sheet"
+                                                checked=self.settings.contact_sheet.enabled
+                                                onclick=self.link.callback(|_| Msg::ToggleContactSheet)/>
+                                                { " make a contact sheet of previews over a range of settings, instead of pages" }
+                                            </label>
+                                            {
+                                                if self.settings.contact_sheet.enabled {
+                                                    html! {
+                                                        <>
+                                                            <div>{"square sizes, in pixels, from"}</div>
+                                                            <input
+                                                            type="text"
+                                                            inputmode="decimal"
+                                                            name="contact-sheet-square-size-from"
+                                                            value={self.settings.contact_sheet.square_size_from.to_string()}
+                                                            oninput=self.link.callback(|e: InputData| Msg::UpdateContactSheetSquareSizeFrom(e.value))/>
+
+                                                            <div>{"to"}</div>
+                                                            <input
+                                                            type="text"
+                                                            inputmode="decimal"
+                                                            name="contact-sheet-square-size-to"
+                                                            value={self.settings.contact_sheet.square_size_to.to_string()}
+                                                            oninput=self.link.callback(|e: InputData| Msg::UpdateContactSheetSquareSizeTo(e.value))/>
+
+                                                            <div>{"largest dots, percent, from"}</div>
+                                                            <input
+                                                            type="text"
+                                                            inputmode="decimal"
+                                                            name="contact-sheet-max-radius-from"
+                                                            value={self.settings.contact_sheet.max_radius_from.to_string()}
+                                                            oninput=self.link.callback(|e: InputData| Msg::UpdateContactSheetMaxRadiusFrom(e.value))/>
+
+                                                            <div>{"to"}</div>
+                                                            <input
+                                                            type="text"
+                                                            inputmode="decimal"
+                                                            name="contact-sheet-max-radius-to"
+                                                            value={self.settings.contact_sheet.max_radius_to.to_string()}
+                                                            oninput=self.link.callback(|e: InputData| Msg::UpdateContactSheetMaxRadiusTo(e.value))/>
+
+                                                            <div>{"columns and rows"}</div>
+                                                            <Stepper
+                                                            name="contact-sheet-steps"
+                                                            min=1.0
+                                                            max={rasterize::MAX_CONTACT_SHEET_STEPS as f32}
+                                                            value={self.settings.contact_sheet.steps as f32}
+                                                            onchange=self.link.callback(Msg::UpdateContactSheetSteps)/>
+
+                                                            <div>{ self.settings.contact_sheet.legend() }</div>
+                                                        </>
+                                                    }
+                                                } else {
+                                                    html! {}
+                                                }
+                                            }
+                                        </div>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+
+                            {
+                                if self.settings.color_depth == ColorDepth::Gradient {
+                                    html! {
+                                        <div>
+                                            { "dark: " }
+                                            <input
+                                            type="color"
+                                            name="gradient-dark"
+                                            value={self.settings.gradient.dark.to_string()}
+                                            oninput=self.link.callback(|e: InputData| Msg::UpdateGradientDark(e.value))/>
+                                            { " light: " }
+                                            <input
+                                            type="color"
+                                            name="gradient-light"
+                                            value={self.settings.gradient.light.to_string()}
+                                            oninput=self.link.callback(|e: InputData| Msg::UpdateGradientLight(e.value))/>
+                                        </div>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+
+                            {
+                                if let Some(ink_count) = self.settings.color_depth.ink_count() {
+                                    html! {
+                                        <div>
+                                            { "inks, dark to light: " }
+                                            {
+                                                for (0..ink_count).map(|ink| {
+                                                    html! {
+                                                        <input
+                                                        type="color"
+                                                        name={ format!("ink-{}", ink + 1) }
+                                                        value={self.settings.inks.colors[ink].to_string()}
+                                                        oninput=self.link.callback(move |e: InputData| Msg::UpdateInk(ink, e.value))/>
+                                                    }
+                                                })
+                                            }
+                                            { self.view_plate_colors(&self.settings.inks.colors[..ink_count]) }
+                                        </div>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+
+                            {
+                                if self.settings.color_depth == ColorDepth::Palette {
+                                    html! {
+                                        <div>
+                                            { "colors: " }
+                                            <input
+                                            type="number"
+                                            name="palette-size"
+                                            min="2"
+                                            max={MAX_PALETTE_COLORS.to_string()}
+                                            value={self.settings.palette.len.to_string()}
+                                            oninput=self.link.callback(|e: InputData| Msg::UpdatePaletteSize(e.value))/>
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name="palette-automatic"
+                                                checked=self.settings.palette.automatic
+                                                onclick=self.link.callback(|_| Msg::TogglePaletteAutomatic)/>
+                                                { " pick them from the image" }
+                                            </label>
+                                            {
+                                                if self.settings.palette.automatic {
+                                                    html! {}
+                                                } else {
+                                                    html! {
+                                                        <div>
+                                                        {
+                                                            for self.settings.palette.colors().iter().enumerate().map(|(i, color)| {
+                                                                html! {
+                                                                    <input
+                                                                    type="color"
+                                                                    name={ format!("palette-{}", i + 1) }
+                                                                    value={color.to_string()}
+                                                                    oninput=self.link.callback(move |e: InputData| Msg::UpdatePaletteColor(i, e.value))/>
+                                                                }
+                                                            })
+                                                        }
+                                                        { self.view_plate_colors(self.settings.palette.colors()) }
+                                                        </div>
+                                                    }
+                                                }
+                                            }
+                                        </div>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+
+                            <div>
+                                { "layout: " }
+                                <select name="layout" onchange=self.link.callback(|e: ChangeData| {
+                                    match e {
+                                        ChangeData::Select(s) => {
+                                            Msg::UpdateLayout(s.value())
+                                        },
+                                        _ => unreachable!()
+                                    }
+                                })>
+                                    <option value={ Layout::Fit.to_string() } selected={ self.settings.layout == Layout::Fit }> { Layout::Fit.to_string() } </option>
+                                    <option value={ Layout::StepAndRepeat.to_string() } selected={ self.settings.layout == Layout::StepAndRepeat }> { Layout::StepAndRepeat.to_string() } </option>
+                                    <option value={ Layout::Matrix.to_string() } selected={ self.settings.layout == Layout::Matrix }> { Layout::Matrix.to_string() } </option>
+                                </select>
+                            </div>
+
+                            {
+                                match self.settings.layout {
+                                    Layout::StepAndRepeat => html! {
+                                        <div>
+                                            <div>{"repeat columns"}</div>
+                                            <input
+                                            type="number"
+                                            name="repeat-columns"
+                                            min="1"
+                                            value={self.settings.step_and_repeat.columns.to_string()}
+                                            oninput=self.link.callback(|e: InputData| Msg::UpdateRepeatColumns(e.value))/>
+
+                                            <div>{"repeat rows"}</div>
+                                            <input
+                                            type="number"
+                                            name="repeat-rows"
+                                            min="1"
+                                            value={self.settings.step_and_repeat.rows.to_string()}
+                                            oninput=self.link.callback(|e: InputData| Msg::UpdateRepeatRows(e.value))/>
+
+                                            <div>{"repeat scale, percent of cell"}</div>
+                                            <Stepper
+                                            name="repeat-scale"
+                                            min=1.0
+                                            max=100.0
+                                            value={(self.settings.step_and_repeat.scale * 100.0).round()}
+                                            onchange=self.link.callback(Msg::UpdateRepeatScale)/>
+
+                                            <div>{"repeat spacing, in pixels"}</div>
+                                            <input
+                                            type="text"
+                                            inputmode="decimal"
+                                            name="repeat-spacing"
+                                            value={self.settings.step_and_repeat.spacing.to_string()}
+                                            oninput=self.link.callback(|e: InputData| Msg::UpdateRepeatSpacing(e.value))/>
+                                        </div>
+                                    },
+                                    Layout::Matrix => html! {
+                                        <div>
+                                            <div>{"one tile per page, varying"}</div>
+                                            <select name="matrix-parameter" onchange=self.link.callback(|e: ChangeData| {
+                                                match e {
+                                                    ChangeData::Select(s) => {
+                                                        Msg::UpdateMatrixParameter(s.value())
+                                                    },
+                                                    _ => unreachable!()
+                                                }
+                                            })>
+                                                <option value={ MatrixParameter::SquareSize.to_string() } selected={ self.settings.matrix.parameter == MatrixParameter::SquareSize }> { MatrixParameter::SquareSize.to_string() } </option>
+                                                <option value={ MatrixParameter::MaxRadiusPercentage.to_string() } selected={ self.settings.matrix.parameter == MatrixParameter::MaxRadiusPercentage }> { MatrixParameter::MaxRadiusPercentage.to_string() } </option>
+                                            </select>
+
+                                            <div>{"from"}</div>
+                                            <input
+                                            type="text"
+                                            inputmode="decimal"
+                                            name="matrix-from"
+                                            value={self.settings.matrix.from.to_string()}
+                                            oninput=self.link.callback(|e: InputData| Msg::UpdateMatrixFrom(e.value))/>
+
+                                            <div>{"to"}</div>
+                                            <input
+                                            type="text"
+                                            inputmode="decimal"
+                                            name="matrix-to"
+                                            value={self.settings.matrix.to.to_string()}
+                                            oninput=self.link.callback(|e: InputData| Msg::UpdateMatrixTo(e.value))/>
+                                        </div>
+                                    },
+                                    Layout::Fit => html! {},
+                                }
+                            }
+
+
+                            <input type="file" id="input" accept="image/*,.heic,.heif,.avif" onchange=self.link.callback(move |v: ChangeData| {
+                                let mut res = vec![];
+
+                                if let ChangeData::Files(files) = v {
+                                    if let Some(file) = files.get(0) {
+                                        res.push(file);
+                                    }
+                                }
+
+                                Msg::FileSelection(res)
+                            }) />
+                            <div>{"or drop an image anywhere on the page, or paste one with Ctrl+V"}</div>
+
+                            <div>
+                                { "overlay image: " }
+                                <input type="file" onchange=self.link.callback(move |v: ChangeData| {
+                                    let mut res = vec![];
+
+                                    if let ChangeData::Files(files) = v {
+                                        if let Some(file) = files.get(0) {
+                                            res.push(file);
+                                        }
+                                    }
+
+                                    Msg::MaskSelection(res)
+                                }) />
+                                {
+                                    if self.mask.is_some() {
+                                        html! {
+                                            <>
+                                                <select name="mask_blend" onchange=self.link.callback(|e: ChangeData| {
+                                                    match e {
+                                                        ChangeData::Select(s) => {
+                                                            Msg::UpdateMaskBlend(s.value())
+                                                        },
+                                                        _ => unreachable!()
+                                                    }
+                                                })>
+                                                {
+                                                    for MaskBlend::blends().map(|blend| {
+                                                        html! {
+                                                            <option value={ blend.to_string() } selected={ *blend == self.settings.mask_blend }> { blend.to_string() } </option>
+                                                        }
+                                                    })
+                                                }
+                                                </select>
+                                                <button onclick=self.link.callback(|_| Msg::ClearMask)>
+                                                    { "remove overlay" }
+                                                </button>
+                                            </>
+                                        }
+                                    } else {
+                                        html! {}
+                                    }
+                                }
+                            </div>
+                            <div>
+                                { "frame: " }
+                                <button onclick=self.link.callback(|_| Msg::TurnImage(3))>
+                                    { "turn left" }
+                                </button>
+                                <button onclick=self.link.callback(|_| Msg::TurnImage(1))>
+                                    { "turn right" }
+                                </button>
+                                <label>
+                                    <input
+                                    type="checkbox"
+                                    name="flip-horizontal"
+                                    checked=self.settings.framing.flip_horizontal
+                                    onclick=self.link.callback(|_| Msg::ToggleFlipHorizontal)/>
+                                    { " flip horizontally" }
+                                </label>
+                                <label>
+                                    <input
+                                    type="checkbox"
+                                    name="flip-vertical"
+                                    checked=self.settings.framing.flip_vertical
+                                    onclick=self.link.callback(|_| Msg::ToggleFlipVertical)/>
+                                    { " flip vertically" }
+                                </label>
+                                <button onclick=self.link.callback(|_| Msg::ResetFraming)>
+                                    { "reset" }
+                                </button>
+                                {
+                                    for CropSide::sides().map(|side| {
+                                        let side = *side;
+                                        html! {
+                                            <>
+                                                <div>{ format!("crop {}, percent", side) }</div>
+                                                <Stepper
+                                                name={ format!("crop-{}", side) }
+                                                min=0.0
+                                                max={ MAX_CROP_FRACTION * 100.0 }
+                                                value={ (self.settings.framing.crop(side) * 100.0).round() }
+                                                onchange=self.link.callback(move |s| Msg::UpdateCrop(side, s))/>
+                                            </>
+                                        }
+                                    })
+                                }
+                            </div>
+                            <div>
+                                { "invert channels: " }
+                                {
+                                    for Channel::channels().map(|channel| {
+                                        let channel = *channel;
+                                        html! {
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name={ format!("invert-{}", channel) }
+                                                checked=self.settings.invert.get(channel)
+                                                onclick=self.link.callback(move |_| Msg::ToggleInvert(channel))/>
+                                                { channel.to_string() }
+                                            </label>
+                                        }
+                                    })
+                                }
+                            </div>
+                            <div>
+                                <div>{"gamma, percent"}</div>
+                                <Stepper
+                                name="gamma"
+                                min=10.0
+                                max=500.0
+                                step=10.0
+                                value={(self.settings.tone.gamma * 100.0).round()}
+                                onchange=self.link.callback(Msg::UpdateGamma)/>
+
+                                <div>{"brightness"}</div>
+                                <Stepper
+                                name="brightness"
+                                min=-100.0
+                                max=100.0
+                                step=5.0
+                                value={(self.settings.tone.brightness * 100.0).round()}
+                                onchange=self.link.callback(Msg::UpdateBrightness)/>
+
+                                <div>{"contrast"}</div>
+                                <Stepper
+                                name="contrast"
+                                min=-100.0
+                                max=100.0
+                                step=5.0
+                                value={(self.settings.tone.contrast * 100.0).round()}
+                                onchange=self.link.callback(Msg::UpdateContrast)/>
+                            </div>
+                            <div>
+                                <label>
+                                    <input
+                                    type="checkbox"
+                                    name="composite-background"
+                                    checked=self.settings.transparency.composite
+                                    onclick=self.link.callback(|_| Msg::ToggleCompositeBackground)/>
+                                    { "put transparent parts over " }
+                                </label>
+                                <input
+                                type="color"
+                                name="background"
+                                value={self.settings.transparency.background.to_string()}
+                                oninput=self.link.callback(|e: InputData| Msg::UpdateBackground(e.value))/>
+                                <label>
+                                    <input
+                                    type="checkbox"
+                                    name="skip-transparent"
+                                    checked=self.settings.transparency.skip_transparent
+                                    onclick=self.link.callback(|_| Msg::ToggleSkipTransparent)/>
+                                    { " no dots where it is fully transparent" }
+                                </label>
+                            </div>
+                            {
+                                if let Some(e) = &self.image_error {
+                                    html! { <div>{ format!("could not use that image: {}", e) }</div> }
+                                } else {
+                                    html! {}
+                                }
+                            }
+                            {
+                                // stippling takes too long to do for every frame,
+                                // it only uses the first
+                                if !self.frames.is_empty() && self.settings.backend != Backend::Voronoi {
+                                    html! {
+                                        <div>
+                                            {
+                                                format!(
+                                                    "animated, every one of its {} frames is rasterized with these settings. ",
+                                                    self.frames.len()
+                                                )
+                                            }
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name="animated-gif"
+                                                checked=self.settings.animated_gif
+                                                onclick=self.link.callback(|_| Msg::ToggleAnimatedGif)/>
+                                                { " and made into an animated gif" }
+                                            </label>
+                                        </div>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+
+                            <div>
+                            {
+                                if self.camera_stream.is_some() {
+                                    html! {
+                                        <div>
+                                            <video ref=self.camera_video.clone() autoplay="true" playsinline="true" style="max-width: 100%;" />
+                                            <div>
+                                                <button onclick=self.link.callback(|_| Msg::CaptureCameraFrame)>
+                                                    { "Capture" }
+                                                </button>
+                                                <button onclick=self.link.callback(|_| Msg::StopCamera)>
+                                                    { "Cancel" }
+                                                </button>
+                                            </div>
+                                        </div>
+                                    }
+                                } else {
+                                    html! {
+                                        <button onclick=self.link.callback(|_| Msg::StartCamera)>
+                                            { "Capture from camera" }
+                                        </button>
+                                    }
+                                }
+                            }
+                            </div>
 
-                        <div>
                             <div>
-                                { "paper size: " }
-                                <select name="paper_size" onchange=self.link.callback(|e: ChangeData| {
-                                    match e {
-                                        ChangeData::Select(s) => {
-                                            Msg::UpdatePaperSize(s.value())
-                                        },
-                                        _ => unreachable!()
-                                    }
-                                })>
+                                <button onclick=self.link.callback(|_| Msg::ExportProject)>
+                                    { "Export project" }
+                                </button>
                                 {
-                                    for PaperSize::sizes().map(|paper_size| {
+                                    if let Some(project_url) = &self.project_url {
                                         html! {
-                                            <option value={ paper_size.to_string() }> { paper_size.to_string() } </option>
+                                            <a style="display: inline;" href={project_url.to_string()} download={format!("{}_project.zip", self.output_name())}>{"download project"}</a>
                                         }
-                                    })
+                                    } else {
+                                        html! {}
+                                    }
                                 }
-                                </select>
                             </div>
 
                             <div>
-                                { "orientation: "}
-                                <select name="orientation" onchange=self.link.callback(|e: ChangeData| {
-                                    match e {
-                                        ChangeData::Select(s) => {
-                                            Msg::UpdateOrientation(s.value())
-                                        },
-                                        _ => unreachable!()
+                                { "import project: " }
+                                <input type="file" accept=".zip,application/zip" onchange=self.link.callback(move |v: ChangeData| {
+                                    let mut res = vec![];
+
+                                    if let ChangeData::Files(files) = v {
+                                        if let Some(file) = files.get(0) {
+                                            res.push(file);
+                                        }
                                     }
-                                })>
-                                   <option value={ Orientation::Portrait.to_string() }> { Orientation::Portrait.to_string() } </option>
-                                   <option value={ Orientation::Landscape.to_string() }> { Orientation::Landscape.to_string() } </option>
-                                </select>
+
+                                    Msg::ProjectSelection(res)
+                                }) />
                             </div>
 
                             <div>
-                                { "backend: " }
-                                <select name="backend" onchange=self.link.callback(|e: ChangeData| {
-                                    match e {
-                                        ChangeData::Select(s) => {
-                                            Msg::UpdateBackend(s.value())
-                                        },
-                                        _ => unreachable!()
-                                    }
-                                })>
-                                    <option value={ Backend::Image.to_string() }> { Backend::Image.to_string() } </option>
-                                    <option value={ Backend::Svg.to_string() }> { Backend::Svg.to_string() } </option>
-                                </select>
+                                <label>
+                                    <input
+                                    type="checkbox"
+                                    name="draft"
+                                    checked=self.settings.draft
+                                    onclick=self.link.callback(|_| Msg::ToggleDraft)/>
+                                    { "draft (first page only, lower quality, much faster)" }
+                                </label>
                             </div>
 
                             <div>
-                                { "color: " }
-                                <select name="color_depth" onchange=self.link.callback(|e: ChangeData| {
-                                    match e {
-                                        ChangeData::Select(s) => {
-                                            Msg::UpdateColorDepth(s.value())
-                                        },
-                                        _ => unreachable!()
-                                    }
-                                })>
-                                    <option value={ ColorDepth::Rgb.to_string() }> { ColorDepth::Rgb.to_string() } </option>
-                                    <option value={ ColorDepth::Grayscale.to_string() }> { ColorDepth::Grayscale.to_string() } </option>
-                                </select>
+                                <label>
+                                    <input
+                                    type="checkbox"
+                                    name="whole-poster"
+                                    checked=self.settings.whole_poster
+                                    onclick=self.link.callback(|_| Msg::ToggleWholePoster)/>
+                                    { "also output the whole poster as a single file" }
+                                </label>
                             </div>
 
+                            {
+                                if self.settings.backend == Backend::Image {
+                                    html! {
+                                        <div>
+                                            { "animation of the poster being made: " }
+                                            <select name="build-up" onchange=self.link.callback(|e: ChangeData| {
+                                                match e {
+                                                    ChangeData::Select(s) => {
+                                                        Msg::UpdateBuildUp(s.value())
+                                                    },
+                                                    _ => unreachable!()
+                                                }
+                                            })>
+                                            {
+                                                for BuildUp::build_ups().map(|build_up| {
+                                                    html! {
+                                                        <option value={ build_up.to_string() } selected={ *build_up == self.settings.build_up }> { build_up.to_string() } </option>
+                                                    }
+                                                })
+                                            }
+                                            </select>
+                                        </div>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+
+                            {
+                                if self.settings.backend == Backend::Svg {
+                                    html! {
+                                        <div>
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name="plot-preview"
+                                                checked=self.settings.plot_preview
+                                                onclick=self.link.callback(|_| Msg::TogglePlotPreview)/>
+                                                { "also output an animation of the plotting order" }
+                                            </label>
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name="gcode"
+                                                checked=self.settings.gcode
+                                                onclick=self.link.callback(|_| Msg::ToggleGcode)/>
+                                                { " also output g-code" }
+                                            </label>
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name="eps"
+                                                checked=self.settings.eps
+                                                onclick=self.link.callback(|_| Msg::ToggleEps)/>
+                                                { " also output eps" }
+                                            </label>
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name="dxf"
+                                                checked=self.settings.dxf
+                                                onclick=self.link.callback(|_| Msg::ToggleDxf)/>
+                                                { " also output dxf, for cnc and laser cutters" }
+                                            </label>
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name="laser"
+                                                checked=self.settings.laser.enabled
+                                                onclick=self.link.callback(|_| Msg::ToggleLaser)/>
+                                                { " also output svg for laser cutters and engravers" }
+                                            </label>
+                                            {
+                                                if self.settings.laser.enabled {
+                                                    html! {
+                                                        <>
+                                                            <div>{"laser kerf, in millimeters"}</div>
+                                                            <Stepper
+                                                            name="laser-kerf"
+                                                            min=0.0
+                                                            max=1.0
+                                                            step=0.05
+                                                            value={self.settings.laser.kerf}
+                                                            onchange=self.link.callback(Msg::UpdateLaserKerf)/>
+                                                        </>
+                                                    }
+                                                } else {
+                                                    html! {}
+                                                }
+                                            }
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name="dot-map"
+                                                checked=self.settings.dot_map
+                                                onclick=self.link.callback(|_| Msg::ToggleDotMap)/>
+                                                { " also output a csv of every dot's position, size and color" }
+                                            </label>
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name="print-html"
+                                                checked=self.settings.print_html
+                                                onclick=self.link.callback(|_| Msg::TogglePrintHtml)/>
+                                                { " also output an html page of every page, to print from a browser" }
+                                            </label>
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name="svg-optimization"
+                                                checked=self.settings.svg_optimization.enabled
+                                                onclick=self.link.callback(|_| Msg::ToggleSvgOptimization)/>
+                                                { " make svgs smaller, rounding numbers and grouping dots by color" }
+                                            </label>
+                                            {
+                                                if self.settings.svg_optimization.enabled {
+                                                    html! {
+                                                        <>
+                                                            <div>{"decimal places"}</div>
+                                                            <Stepper
+                                                            name="svg-precision"
+                                                            min=0.0
+                                                            max={MAX_SVG_PRECISION as f32}
+                                                            value={self.settings.svg_optimization.precision as f32}
+                                                            onchange=self.link.callback(Msg::UpdateSvgPrecision)/>
+                                                        </>
+                                                    }
+                                                } else {
+                                                    html! {}
+                                                }
+                                            }
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name="relief"
+                                                checked=self.settings.relief.enabled
+                                                onclick=self.link.callback(|_| Msg::ToggleRelief)/>
+                                                { " also output an stl relief, for carving or 3d printing" }
+                                            </label>
+                                            {
+                                                if self.settings.relief.enabled {
+                                                    html! {
+                                                        <>
+                                                            <div>{"relief base, in millimeters"}</div>
+                                                            <Stepper
+                                                            name="relief-base"
+                                                            min=0.0
+                                                            max=20.0
+                                                            step=0.5
+                                                            value={self.settings.relief.base}
+                                                            onchange=self.link.callback(Msg::UpdateReliefBase)/>
 
-                            <input type="file" id="input" onchange=self.link.callback(move |v: ChangeData| {
-                                let mut res = vec![];
+                                                            <div>{"relief height, in millimeters"}</div>
+                                                            <Stepper
+                                                            name="relief-height"
+                                                            min=0.5
+                                                            max=20.0
+                                                            step=0.5
+                                                            value={self.settings.relief.height}
+                                                            onchange=self.link.callback(Msg::UpdateReliefHeight)/>
 
-                                if let ChangeData::Files(files) = v {
-                                    if let Some(file) = files.get(0) {
-                                        res.push(file);
+                                                            { " relief shape: " }
+                                                            <select name="relief-shape" onchange=self.link.callback(|e: ChangeData| {
+                                                                match e {
+                                                                    ChangeData::Select(s) => {
+                                                                        Msg::UpdateReliefShape(s.value())
+                                                                    },
+                                                                    _ => unreachable!()
+                                                                }
+                                                            })>
+                                                            {
+                                                                for ReliefShape::relief_shapes().map(|shape| {
+                                                                    html! {
+                                                                        <option value={ shape.to_string() } selected={ *shape == self.settings.relief.shape }> { shape.to_string() } </option>
+                                                                    }
+                                                                })
+                                                            }
+                                                            </select>
+                                                        </>
+                                                    }
+                                                } else {
+                                                    html! {}
+                                                }
+                                            }
+                                            <label>
+                                                <input
+                                                type="checkbox"
+                                                name="dot-metadata"
+                                                checked=self.settings.dot_metadata
+                                                onclick=self.link.callback(|_| Msg::ToggleDotMetadata)/>
+                                                { " label dots with their brightness, square and page" }
+                                            </label>
+                                            { " drawing order: " }
+                                            <select name="row-order" onchange=self.link.callback(|e: ChangeData| {
+                                                match e {
+                                                    ChangeData::Select(s) => {
+                                                        Msg::UpdateRowOrder(s.value())
+                                                    },
+                                                    _ => unreachable!()
+                                                }
+                                            })>
+                                            {
+                                                for RowOrder::row_orders().map(|row_order| {
+                                                    html! {
+                                                        <option value={ row_order.to_string() } selected={ *row_order == self.settings.row_order }> { row_order.to_string() } </option>
+                                                    }
+                                                })
+                                            }
+                                            </select>
+                                        </div>
                                     }
+                                } else {
+                                    html! {}
                                 }
+                            }
 
-                                Msg::FileSelection(res)
-                            }) />
+                            {
+                                match Option::as_ref(&self.image)
+                                    .filter(|_| self.settings.layout == Layout::Fit)
+                                    .and_then(|image| {
+                                        rasterize::suggested_pages(image, self.settings.framing, self.settings.paper_size, self.settings.orientation, self.settings.pages_width, self.settings.pages_height)
+                                    }) {
+                                    Some((columns, rows)) => html! {
+                                        <div>
+                                            { format!("most of these pages would be blank for this image, {}w x {}h pages would fit it better ", columns, rows) }
+                                            <button onclick=self.link.callback(move |_| Msg::ApplySuggestedPages(columns, rows))>
+                                                { "use them" }
+                                            </button>
+                                        </div>
+                                    },
+                                    None => html! {},
+                                }
+                            }
 
                             <div>{"width (pages)"}</div>
-                            <input
-                              type="range"
-                              name="width"
-                              min="1"
-                              max="25"
-                              value={self.pages_width.to_string()}
-                              oninput=self.link.callback(|e: InputData| Msg::UpdatePageWidth(e.value))/>
+                            <Stepper
+                            name="width"
+                            min=1.0
+                            max=25.0
+                            value={self.settings.pages_width as f32}
+                            onchange=self.link.callback(Msg::UpdatePageWidth)/>
 
                             <div>{"height (pages)"}</div>
-                            <input
-                              type="range"
-                              name="height"
-                              min="1"
-                              max="25"
-                              value={self.pages_height.to_string()} oninput=self.link.callback(|e: InputData| Msg::UpdatePageHeight(e.value))/>
+                            <Stepper
+                            name="height"
+                            min=1.0
+                            max=25.0
+                            value={self.settings.pages_height as f32}
+                            onchange=self.link.callback(Msg::UpdatePageHeight)/>
 
                             <div>{"square size, in pixels"}</div>
                             <input
-                            type="number"
+                            type="text"
+                            inputmode="decimal"
                             name="square-size"
-                            value={self.square_size.to_string()}
+                            value={self.settings.square_size.to_string()}
                             oninput=self.link.callback(|e: InputData| Msg::UpdateSquareSize(e.value))/>
+                            {
+                                if self.image.is_some() {
+                                    let square = self.settings.square_size * self.layout_preview_scale;
+
+                                    html! {
+                                        <div>
+                                            <div>
+                                                {
+                                                    if self.settings.layout == Layout::Fit {
+                                                        "or drag the corner of the square, and drag across the image to rasterize only that region (reset framing to undo). preview as seen with "
+                                                    } else {
+                                                        "or drag the corner of the square. preview as seen with "
+                                                    }
+                                                }
+                                                <select name="color-vision" onchange=self.link.callback(|e: ChangeData| {
+                                                    match e {
+                                                        ChangeData::Select(s) => {
+                                                            Msg::UpdateColorVision(s.value())
+                                                        },
+                                                        _ => unreachable!()
+                                                    }
+                                                })>
+                                                {
+                                                    for ColorVision::visions().map(|color_vision| {
+                                                        html! {
+                                                            <option value={ color_vision.to_string() } selected={ *color_vision == self.settings.color_vision }> { color_vision.to_string() } </option>
+                                                        }
+                                                    })
+                                                }
+                                                </select>
+                                                { " color vision" }
+                                            </div>
+                                            <div
+                                            style="position: relative; display: inline-block; user-select: none;"
+                                            onmousemove=self.link.batch_callback(|e: MouseEvent| vec![
+                                                Msg::DragSquare(e.client_x(), e.client_y()),
+                                                Msg::DragRegion(e.client_x(), e.client_y()),
+                                            ])
+                                            onmouseup=self.link.batch_callback(|_| vec![Msg::EndSquareDrag, Msg::EndRegionDrag])
+                                            onmouseleave=self.link.batch_callback(|_| vec![Msg::EndSquareDrag, Msg::EndRegionDrag])>
+                                                <canvas
+                                                ref=self.layout_preview.clone()
+                                                style={ if self.settings.layout == Layout::Fit { "display: block; cursor: crosshair;" } else { "display: block;" } }
+                                                onmousedown=self.link.callback(|e: MouseEvent| Msg::StartRegionDrag(e.client_x(), e.client_y(), e.offset_x(), e.offset_y()))/>
+                                                {
+                                                    match &self.region_drag {
+                                                        Some(drag) => html! {
+                                                            <div style={format!(
+                                                                "position: absolute; left: {}px; top: {}px; width: {}px; height: {}px; border: 1px dashed blue; box-sizing: border-box; pointer-events: none;",
+                                                                drag.from.0.min(drag.to.0),
+                                                                drag.from.1.min(drag.to.1),
+                                                                (drag.to.0 - drag.from.0).abs(),
+                                                                (drag.to.1 - drag.from.1).abs(),
+                                                            )}/>
+                                                        },
+                                                        None => html! {},
+                                                    }
+                                                }
+                                                <div style={format!("position: absolute; left: 0; top: 0; width: {}px; height: {}px; border: 1px solid red; box-sizing: border-box; pointer-events: none;", square, square)}>
+                                                    <div
+                                                    style="position: absolute; right: -5px; bottom: -5px; width: 10px; height: 10px; background: red; cursor: nwse-resize; pointer-events: auto;"
+                                                    onmousedown=self.link.callback(|e: MouseEvent| Msg::StartSquareDrag(e.client_x(), e.client_y()))/>
+                                                </div>
+                                            </div>
+                                        </div>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+
 
+                            { self.view_radius_curve() }
 
                             <div>{"minimum raster percentage"}</div>
-                            <input
-                            type="range"
+                            <Stepper
                             name="min-raster-perc"
-                            min="0"
-                            max="100"
-                            value={((self.min_radius_percentage * 100.0).floor() as usize).to_string()}
-                            oninput=self.link.callback(|e: InputData| Msg::UpdateMinRadiusPercentage(e.value))/>
-                            <span>{(self.min_radius_percentage * 100.0).floor() as usize}</span>
+                            min=0.0
+                            max=100.0
+                            step=5.0
+                            value={(self.settings.min_radius_percentage * 100.0).floor()}
+                            onchange=self.link.callback(Msg::UpdateMinRadiusPercentage)/>
 
                             <div>{"maximum raster percentage"}</div>
-                            <input
-                            type="range"
-                            min="1"
-                            max="100"
+                            <Stepper
                             name="max-raster-perc"
-                            value={((self.max_radius_percentage * 100.0).floor() as usize).to_string()}
-                            oninput=self.link.callback(|e: InputData| Msg::UpdateMaxRadiusPercentage(e.value))/>
-                        <span>{(self.max_radius_percentage * 100.0).floor() as usize}</span>
+                            min=1.0
+                            max={MAX_RADIUS_PERCENTAGE_LIMIT as f32}
+                            step=5.0
+                            value={(self.settings.max_radius_percentage * 100.0).floor()}
+                            onchange=self.link.callback(Msg::UpdateMaxRadiusPercentage)/>
+
+                            <label>
+                                <input
+                                type="checkbox"
+                                name="min-dot-size"
+                                checked=self.settings.min_dot_size.enabled
+                                onclick=self.link.callback(|_| Msg::ToggleMinDotSize)/>
+                                { " leave out dots too small to print" }
+                            </label>
+                            {
+                                if self.settings.min_dot_size.enabled {
+                                    html! {
+                                        <>
+                                            <div>{"smallest dot radius, in millimeters"}</div>
+                                            <Stepper
+                                            name="min-dot-radius"
+                                            min=0.0
+                                            max=2.0
+                                            step=0.05
+                                            value={self.settings.min_dot_size.radius}
+                                            onchange=self.link.callback(Msg::UpdateMinDotRadius)/>
+                                        </>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+
+                            <div>{"dot size jitter, percent"}</div>
+                            <Stepper
+                            name="radius-jitter"
+                            min=0.0
+                            max=100.0
+                            step=5.0
+                            value={(self.settings.jitter.radius * 100.0).round()}
+                            onchange=self.link.callback(Msg::UpdateRadiusJitter)/>
+
+                            <div>{"dot position jitter, percent of half a square"}</div>
+                            <Stepper
+                            name="position-jitter"
+                            min=0.0
+                            max=100.0
+                            step=5.0
+                            value={(self.settings.jitter.position * 100.0).round()}
+                            onchange=self.link.callback(Msg::UpdatePositionJitter)/>
+
+                            <div>{"jitter seed"}</div>
+                            <input
+                            type="text"
+                            inputmode="numeric"
+                            name="jitter-seed"
+                            value={self.settings.jitter.seed.to_string()}
+                            oninput=self.link.callback(|e: InputData| Msg::UpdateJitterSeed(e.value))/>
+
+                            <label>
+                                <input
+                                type="checkbox"
+                                name="edges"
+                                checked=self.settings.edges.enabled
+                                onclick=self.link.callback(|_| Msg::ToggleEdges)/>
+                                { " draw the image's edges over the pattern" }
+                            </label>
+                            {
+                                if self.settings.edges.enabled {
+                                    html! {
+                                        <>
+                                            <div>{"edge threshold, in hundredths (lower finds more edges)"}</div>
+                                            <Stepper
+                                            name="edge-threshold"
+                                            min=5.0
+                                            max=400.0
+                                            step=5.0
+                                            value={(self.settings.edges.threshold * 100.0).round()}
+                                            onchange=self.link.callback(Msg::UpdateEdgeThreshold)/>
+
+                                            <div>{"edge width, in pixels"}</div>
+                                            <Stepper
+                                            name="edge-width"
+                                            min=0.5
+                                            max=8.0
+                                            step=0.5
+                                            value={self.settings.edges.width}
+                                            onchange=self.link.callback(Msg::UpdateEdgeWidth)/>
+                                        </>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+
+                            <div>{"border thickness, in pixels (0 for none)"}</div>
+                            <input
+                            type="text"
+                            inputmode="decimal"
+                            name="border-thickness"
+                            value={self.settings.border.thickness.to_string()}
+                            oninput=self.link.callback(|e: InputData| Msg::UpdateBorderThickness(e.value))/>
+
+                            <div>{"border inset, in pixels"}</div>
+                            <input
+                            type="text"
+                            inputmode="decimal"
+                            name="border-inset"
+                            value={self.settings.border.inset.to_string()}
+                            oninput=self.link.callback(|e: InputData| Msg::UpdateBorderInset(e.value))/>
+
+                            <div>{"border color"}</div>
+                            <input
+                            type="color"
+                            name="border-color"
+                            value={self.settings.border.color.to_string()}
+                            oninput=self.link.callback(|e: InputData| Msg::UpdateBorderColor(e.value))/>
 
                         </div>
                     </div>
                     <div class="column"></div>
                     <div class="column"></div>
                 </div>
+                <div>
+                    <label>
+                        <input
+                        type="checkbox"
+                        name="auto-rasterize"
+                        checked=self.settings.auto_rasterize
+                        onclick=self.link.callback(|_| Msg::ToggleAutoRasterize)/>
+                        { " rasterize again whenever something changes" }
+                    </label>
+                </div>
                 <div class="row">
 
                     {
-                        match self.backend {
+                        match self.settings.backend {
                             Backend::Image => {
                                 html! {
-                                    <ImageBackend
+                                    <OutputBackendComponent<ImageBackend>
+                                        on_outputs={self.outputs_callback()}
+                                        restored_outputs={self.restored_outputs.clone()}
+                                        output_name={self.output_name()}
                                         image={self.image.clone()}
-                                        orientation={self.orientation}
-                                        pages_height={self.pages_height}
-                                        pages_width={self.pages_width}
-                                        paper_size={self.paper_size}
-                                        min_radius_percentage={self.min_radius_percentage}
-                                        max_radius_percentage={self.max_radius_percentage}
-                                        square_size={self.square_size}
-                                        color_depth={self.color_depth}
+                                        mask={self.mask.clone()}
+                                        frames={self.frames.clone()}
+                                        settings={self.settings.clone()}
                                     />
                                 }
-                            },
+                            }
                             Backend::Svg => {
                                 html! {
-                                    <SVGBackend
+                                    <OutputBackendComponent<SVGBackend>
+                                        on_outputs={self.outputs_callback()}
+                                        restored_outputs={self.restored_outputs.clone()}
+                                        output_name={self.output_name()}
                                         image={self.image.clone()}
-                                        orientation={self.orientation}
-                                        pages_height={self.pages_height}
-                                        pages_width={self.pages_width}
-                                        paper_size={self.paper_size}
-                                        min_radius_percentage={self.min_radius_percentage}
-                                        max_radius_percentage={self.max_radius_percentage}
-                                        square_size={self.square_size}
-                                        color_depth={self.color_depth}
+                                        mask={self.mask.clone()}
+                                        frames={self.frames.clone()}
+                                        settings={self.settings.clone()}
+                                    />
+                                }
+                            }
+                            Backend::Voronoi => {
+                                html! {
+                                    // stippling every frame of an animation would take far too long
+                                    <OutputBackendComponent<VoronoiBackend>
+                                        on_outputs={self.outputs_callback()}
+                                        restored_outputs={self.restored_outputs.clone()}
+                                        output_name={self.output_name()}
+                                        image={self.image.clone()}
+                                        mask={self.mask.clone()}
+                                        frames={Rc::default()}
+                                        settings={self.settings.clone()}
+                                    />
+                                }
+                            }
+                            Backend::Text => {
+                                html! {
+                                    <OutputBackendComponent<TextBackend>
+                                        on_outputs={self.outputs_callback()}
+                                        restored_outputs={self.restored_outputs.clone()}
+                                        output_name={self.output_name()}
+                                        image={self.image.clone()}
+                                        mask={self.mask.clone()}
+                                        frames={self.frames.clone()}
+                                        settings={self.settings.clone()}
                                     />
                                 }
                             }
@@ -778,44 +4647,488 @@ impl Component for Model {
     }
 }
 
-fn zip<W: Write + Seek>(
-    writer: &mut W,
-    files: Vec<(String, Vec<u8>)>,
-) -> zip::result::ZipResult<()> {
-    let mut zip = zip::ZipWriter::new(writer);
+impl Model {
+    // the radius curve, with its points dragged up and down to change it.
+    // brightness goes left to right and dot size bottom to top
+    fn view_radius_curve(&self) -> Html {
+        let editor = RADIUS_CURVE_EDITOR_PIXELS;
+        let to_editor = |brightness: f32, size: f32| (brightness * editor, (1.0 - size) * editor);
+
+        let line = (0..=editor as usize)
+            .map(|x| {
+                let brightness = x as f32 / editor;
+                let (x, y) = to_editor(brightness, self.settings.radius_curve.size(brightness));
+                format!("{},{}", x, y)
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        html! {
+            <div>
+                <div>{ "dot size by brightness" }</div>
+                <svg
+                width={editor.to_string()}
+                height={editor.to_string()}
+                style="display: block; overflow: visible; border: 1px solid #ccc; user-select: none;"
+                onmousemove=self.link.callback(|e: MouseEvent| Msg::DragCurve(e.client_y()))
+                onmouseup=self.link.callback(|_| Msg::EndCurveDrag)
+                onmouseleave=self.link.callback(|_| Msg::EndCurveDrag)>
+                    <polyline points={line} fill="none" stroke="black" stroke-width="2" />
+                    {
+                        for (0..RADIUS_CURVE_POINTS).map(|point| {
+                            let (x, y) = to_editor(
+                                RadiusCurve::brightness_at(point),
+                                self.settings.radius_curve.sizes[point],
+                            );
+
+                            html! {
+                                <circle
+                                cx={x.to_string()}
+                                cy={y.to_string()}
+                                r="6"
+                                fill="red"
+                                style="cursor: ns-resize;"
+                                onmousedown=self.link.callback(move |e: MouseEvent| Msg::StartCurveDrag(point, e.client_y()))/>
+                            }
+                        })
+                    }
+                </svg>
+                <button onclick=self.link.callback(|_| Msg::ResetRadiusCurve)>
+                    { "straight line" }
+                </button>
+            </div>
+        }
+    }
+
+    // swatches of a multi-plate mode's colors as seen with `color_vision`,
+    // and a warning with safer colors to switch to when any of them look alike
+    fn view_plate_colors(&self, colors: &[Color]) -> Html {
+        html! {
+            <div>
+                {
+                    if self.settings.color_vision != ColorVision::Typical {
+                        html! {
+                            <div>
+                                { format!("as seen with {}: ", self.settings.color_vision) }
+                                {
+                                    for colors.iter().map(|color| {
+                                        let seen = self.settings.color_vision.simulate(*color);
+                                        html! {
+                                            <span style={format!("display: inline-block; width: 1em; height: 1em; background: {};", seen)} />
+                                        }
+                                    })
+                                }
+                            </div>
+                        }
+                    } else {
+                        html! {}
+                    }
+                }
+                {
+                    match color::look_alike(colors) {
+                        Some((i, j, color_vision)) => html! {
+                            <div>
+                                { format!("colors {} and {} look alike with {} color vision ", i + 1, j + 1, color_vision.to_string().to_lowercase()) }
+                                <button onclick=self.link.callback(|_| Msg::UseColorBlindSafeInks)>
+                                    { "use color-blind safe colors" }
+                                </button>
+                            </div>
+                        },
+                        None => html! {},
+                    }
+                }
+            </div>
+        }
+    }
+
+    // redraws the layout preview, but only when the layout has changed since it was last drawn
+    fn draw_layout_preview(&mut self) {
+        let image = match Option::as_ref(&self.image) {
+            Some(image) => image,
+            None => {
+                self.layout_previewed = None;
+                return;
+            }
+        };
+
+        let canvas = match self.layout_preview.cast::<web_sys::HtmlCanvasElement>() {
+            Some(canvas) => canvas,
+            None => return,
+        };
+
+        // only these settings change how the image is laid out
+        let layout = Settings {
+            pages_width: self.settings.pages_width,
+            pages_height: self.settings.pages_height,
+            paper_size: self.settings.paper_size,
+            orientation: self.settings.orientation,
+            layout: self.settings.layout,
+            step_and_repeat: self.settings.step_and_repeat,
+            matrix: self.settings.matrix,
+            mask_blend: self.settings.mask_blend,
+            invert: self.settings.invert,
+            tone: self.settings.tone,
+            framing: self.settings.framing,
+            resampling: self.settings.resampling,
+            color_vision: self.settings.color_vision,
+            transparency: self.settings.transparency,
+            ..Settings::default()
+        };
+
+        let image_hash = Some(image.hash());
+        let mask_hash = Option::as_ref(&self.mask).map(|mask| mask.hash());
+
+        if let Some((previewed_image, previewed_mask, previewed_layout)) = &self.layout_previewed {
+            if *previewed_image == image_hash
+                && *previewed_mask == mask_hash
+                && *previewed_layout == layout
+            {
+                return;
+            }
+        }
+
+        let args = layout.rasterize_args(image, self.mask.as_deref());
+        if let Some((mut preview, scale)) = rasterize::layout_preview(args) {
+            self.settings.color_vision.simulate_image(&mut preview);
+
+            if let Err(e) = draw_on_canvas(&canvas, &preview) {
+                console::log_2(&"could not draw the layout preview".into(), &e);
+                return;
+            }
+
+            // the square is sized by the scale, so it has to be drawn again too
+            if (scale - self.layout_preview_scale).abs() > f32::EPSILON {
+                self.link.send_message(Msg::LayoutPreviewScaled(scale));
+            }
+        }
+
+        self.layout_previewed = Some((image_hash, mask_hash, layout));
+    }
+
+    // outputs are tagged with the settings of the render that made the callback,
+    // which are the settings a backend's props came from
+    fn outputs_callback(&self) -> Callback<Rc<Vec<u8>>> {
+        let settings = self.settings.clone();
+        self.link
+            .callback(move |outputs| Msg::OutputsReady(Box::new(settings.clone()), outputs))
+    }
+
+    // restore whatever settings were in use the last time the app was open
+    fn load_settings(&self) -> Option<Settings> {
+        let storage = self.storage.as_ref()?;
+        let Json(settings) = storage.restore(SETTINGS_STORAGE_KEY);
+        settings.ok()
+    }
+
+    // the decoded mask, or why it couldn't be decoded
+    // decodes `file` in the browser, for formats rat can't decode itself, and sends
+    // the message `decoded` makes of the image, or of why it couldn't be used
+    fn decode_in_browser(
+        &self,
+        file: FileData,
+        what: &'static str,
+        decoded: impl FnOnce(FileData, Result<image::DynamicImage, String>) -> Msg + 'static,
+    ) {
+        console::log_1(&format!("decoding {} in the browser", what).into());
+
+        let link = self.link.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let image = match bitmap::decode(&file.content).await {
+                Ok(i) => rasterize::check_image(&i)
+                    .map(|_| i)
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(format!("could not decode {}: {:?}", what, e)),
+            };
+            link.send_message(decoded(file, image));
+        });
+    }
+
+    fn use_mask(&mut self, decoded: Result<image::DynamicImage, String>) -> ShouldRender {
+        match decoded {
+            Ok(i) => {
+                self.mask = Rc::new(Some(SourceImage::new(&i)));
+                self.image_error = None;
+            }
+            Err(e) => {
+                console::log_2(&"could not use mask".into(), &e.as_str().into());
+                self.image_error = Some(e);
+            }
+        }
+
+        true
+    }
+
+    // `file` as the source image, once it is decoded, or why it couldn't be
+    fn use_source_file(
+        &mut self,
+        file: FileData,
+        decoded: Result<image::DynamicImage, String>,
+    ) -> ShouldRender {
+        match decoded {
+            Ok(i) => {
+                self.image = Rc::new(Some(SourceImage::new(&i)));
+                self.frames = Rc::new(decode_frames(&file.content));
+                self.source_file = Some(file);
+                self.image_error = None;
+                self.outputs = Rc::default();
+                self.outputs_settings = None;
+            }
+            Err(e) => {
+                console::log_2(&"could not use image".into(), &e.as_str().into());
+                self.image_error = Some(e);
+            }
+        }
+
+        true
+    }
+
+    fn save_settings(&mut self) {
+        if let Some(storage) = &mut self.storage {
+            storage.store(SETTINGS_STORAGE_KEY, Json(&self.settings));
+        }
+    }
+
+    fn load_presets(&self) -> BTreeMap<String, Settings> {
+        self.storage
+            .as_ref()
+            .and_then(|storage| {
+                let Json(presets) = storage.restore(PRESETS_STORAGE_KEY);
+                presets.ok()
+            })
+            .unwrap_or_default()
+    }
+
+    fn save_presets(&mut self) {
+        if let Some(storage) = &mut self.storage {
+            storage.store(PRESETS_STORAGE_KEY, Json(&self.presets));
+        }
+    }
+
+    // the source image's filename without its extension,
+    // so "portrait.jpg" produces "portrait_1.png", "portrait_pages.zip", etc.
+    fn output_name(&self) -> String {
+        self.source_file
+            .as_ref()
+            .and_then(|file| Path::new(&file.name).file_stem())
+            .and_then(|stem| stem.to_str())
+            .filter(|stem| !stem.is_empty())
+            .unwrap_or("rat")
+            .to_string()
+    }
+}
+
+// turn the zip of previously generated outputs back into object urls for the page gallery,
+// for the files with any of the given extensions, and for the "download all" zip
+fn restore_outputs(zip: &[u8], extensions: &[&str]) -> (Vec<(String, String)>, Option<String>) {
+    if zip.is_empty() {
+        return (vec![], None);
+    }
+    let outputs = match archive::read_zip(zip) {
+        Ok(outputs) => outputs,
+        Err(e) => {
+            console::log_1(&format!("could not read restored outputs: {}", e).into());
+            return (vec![], None);
+        }
+    };
+
+    let mime_type = |filename: &str| match filename.rsplit('.').next() {
+        Some("png") => MimeType::Png,
+        Some("jpg") => MimeType::Jpeg,
+        Some("webp") => MimeType::Webp,
+        Some("gif") => MimeType::Gif,
+        _ => MimeType::Svg,
+    };
+
+    let image_urls = outputs
+        .iter()
+        .filter(|(filename, _)| {
+            extensions
+                .iter()
+                .any(|extension| filename.ends_with(&format!(".{}", extension)))
+        })
+        .map(|(filename, bytes)| {
+            let url = bytes_to_object_url(bytes, &mime_type(filename).to_string()).unwrap();
+            (filename.clone(), url)
+        })
+        .collect::<Vec<_>>();
+
+    // outputs of another backend
+    if image_urls.is_empty() {
+        return (vec![], None);
+    }
+
+    let zip_url = bytes_to_object_url(zip, &MimeType::Zip.to_string()).ok();
 
-    let options =
-        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    (image_urls, zip_url)
+}
 
+fn zip(files: &[(String, Vec<u8>)], compression: ZipCompression) -> Result<Vec<u8>, ZipError> {
+    let mut zip = ZipWriter::new(compression);
     for (filename, bytes) in files {
-        zip.start_file(filename, options)?;
-        zip.write_all(&bytes)?;
+        zip.add(filename, bytes)?;
+    }
+    zip.finish()
+}
+
+fn settings_from_url() -> Option<Settings> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    Settings::from_url_fragment(&hash)
+}
+
+// a source image, or why it can't be rasterized
+fn decode_image(bytes: &[u8]) -> Result<image::DynamicImage, String> {
+    let image = image::load_from_memory(bytes).map_err(|e| e.to_string())?;
+    rasterize::check_image(&image).map_err(|e| e.to_string())?;
+
+    Ok(image)
+}
+
+// a frame of an animated source, and how long it is shown for
+struct SourceFrame {
+    image: SourceImage,
+    delay_ms: u32,
+}
+
+// every frame of an animated gif or png, up to `MAX_SOURCE_FRAMES`.
+// anything else, including an animation of one frame, is a still and has none
+fn decode_frames(bytes: &[u8]) -> Vec<SourceFrame> {
+    let frames = match image::guess_format(bytes) {
+        Ok(image::ImageFormat::Gif) => image::codecs::gif::GifDecoder::new(Cursor::new(bytes))
+            .map(|decoder| decoder.into_frames()),
+        Ok(image::ImageFormat::Png) => {
+            match image::codecs::png::PngDecoder::new(Cursor::new(bytes)) {
+                Ok(decoder) if decoder.is_apng() => Ok(decoder.apng().into_frames()),
+                _ => return vec![],
+            }
+        }
+        _ => return vec![],
+    };
+
+    // a frame that won't decode ends the animation where it is
+    let frames = frames
+        .into_iter()
+        .flatten()
+        .map_while(Result::ok)
+        .take(MAX_SOURCE_FRAMES)
+        .map(|frame| {
+            let (numerator, denominator) = frame.delay().numer_denom_ms();
+            SourceFrame {
+                delay_ms: numerator / denominator.max(1),
+                image: SourceImage::new(&image::DynamicImage::ImageRgba8(frame.into_buffer())),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if frames.len() > 1 {
+        frames
+    } else {
+        vec![]
     }
+}
+
+// like the file input, we only take the first image out of
+// whatever was dropped or pasted. some browsers give heic photos no type at all
+fn first_image_file(files: Option<web_sys::FileList>) -> Vec<File> {
+    let mut res = vec![];
 
-    zip.finish()?;
+    if let Some(files) = files {
+        for i in 0..files.length() {
+            if let Some(file) = files.get(i) {
+                let name = file.name().to_lowercase();
+                if file.type_().starts_with("image/")
+                    || [".heic", ".heif", ".avif"]
+                        .iter()
+                        .any(|extension| name.ends_with(extension))
+                {
+                    res.push(file);
+                    break;
+                }
+            }
+        }
+    }
 
-    Ok(())
+    res
 }
 
-fn encode_image_as_png_bytes(image: ImageBuffer<Rgba<u8>, Vec<u8>>) -> Vec<u8> {
+fn encode_image_as_png_bytes(
+    image: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    color_profile: ColorProfile,
+) -> Vec<u8> {
     let (x, y) = image.dimensions();
 
     let mut w = Cursor::new(Vec::new());
     let as_png = image::png::PngEncoder::new(&mut w);
 
-    let page_as_bytes = image.into_raw();
-
     as_png
-        .encode(&page_as_bytes, x, y, image::ColorType::Rgba8)
+        .encode(image.as_raw(), x, y, image::ColorType::Rgba8)
         .unwrap();
 
-    w.into_inner()
+    color_profile.tag_png(w.into_inner())
+}
+
+// a page, as a png tagged with its color profile and the settings it was made with
+fn output_png_bytes(image: &ImageBuffer<Rgba<u8>, Vec<u8>>, settings: &Settings) -> Vec<u8> {
+    settings.tag_png(encode_image_as_png_bytes(image, settings.color_profile))
+}
+
+fn svg_bytes(document: svg::Document, settings: &Settings) -> Vec<u8> {
+    let document = settings.tag_svg(settings.color_profile.tag_svg(document));
+    let mut svg_string: Vec<u8> = Vec::new();
+    svg::write(&mut svg_string, &document).unwrap();
+    svg_string
+}
+
+// each frame is shown for its delay, in milliseconds
+fn encode_frames_as_gif_bytes(frames: impl IntoIterator<Item = (RgbaImage, u32)>) -> Vec<u8> {
+    let mut gif = vec![];
+
+    {
+        let mut encoder = image::codecs::gif::GifEncoder::new(&mut gif);
+        encoder
+            .set_repeat(image::codecs::gif::Repeat::Infinite)
+            .unwrap();
+        encoder
+            .encode_frames(frames.into_iter().map(|(frame, delay_ms)| {
+                image::Frame::from_parts(
+                    frame,
+                    0,
+                    0,
+                    image::Delay::from_numer_denom_ms(delay_ms, 1),
+                )
+            }))
+            .unwrap();
+    }
+
+    gif
+}
+
+// the canvas is resized to fit the image
+fn draw_on_canvas(
+    canvas: &web_sys::HtmlCanvasElement,
+    image: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+) -> Result<(), wasm_bindgen::JsValue> {
+    let (width, height) = image.dimensions();
+    canvas.set_width(width);
+    canvas.set_height(height);
+
+    let context = canvas
+        .get_context("2d")?
+        .ok_or_else(|| wasm_bindgen::JsValue::from_str("no 2d canvas context"))?
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()?;
+
+    let image_data = web_sys::ImageData::new_with_u8_clamped_array_and_sh(
+        Clamped(image.as_raw()),
+        width,
+        height,
+    )?;
+
+    context.put_image_data(&image_data, 0.0, 0.0)
 }
 
 fn bytes_to_object_url(slice: &[u8], mime_type: &str) -> Result<String, wasm_bindgen::JsValue> {
-    let mut blob_properties = web_sys::BlobPropertyBag::new();
+    let blob_properties = web_sys::BlobPropertyBag::new();
 
-    blob_properties.type_(mime_type);
+    blob_properties.set_type(mime_type);
 
     let bytearray = js_sys::Uint8Array::from(slice);
 
@@ -827,6 +5140,59 @@ fn bytes_to_object_url(slice: &[u8], mime_type: &str) -> Result<String, wasm_bin
     web_sys::Url::create_object_url_with_blob(&blob)
 }
 
+// the browser keeps an object url's blob until the url is revoked,
+// so every url made for outputs that are no longer shown has to be
+fn revoke_object_url(url: &str) {
+    // this only fails for urls that aren't object urls, which hold nothing to free
+    let _ = web_sys::Url::revoke_object_url(url);
+}
+
+// revokes `urls` but for any also in `kept`, which are still being shown
+fn revoke_object_urls(urls: &[(String, String)], kept: Option<&[(String, String)]>) {
+    for (_, url) in urls {
+        let is_kept = kept.is_some_and(|kept| kept.iter().any(|(_, kept_url)| kept_url == url));
+        if !is_kept {
+            revoke_object_url(url);
+        }
+    }
+}
+
+// revokes a backend's outputs as they are replaced, but for the pages pinned to compare with
+fn revoke_outputs(
+    image_urls: &[(String, String)],
+    zip_url: &Option<String>,
+    pinned: Option<&[(String, String)]>,
+) {
+    revoke_object_urls(image_urls, pinned);
+    if let Some(zip_url) = zip_url {
+        revoke_object_url(zip_url);
+    }
+}
+
+// `capabilities::capabilities()`, the backends, and what the browser
+// running rat can do, as text to paste into a bug report
+fn about() -> String {
+    let backends = Backend::backends()
+        .map(|backend| backend.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let threads = web_sys::window()
+        .map(|window| window.navigator().hardware_concurrency().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let simd = js_sys::WebAssembly::validate(&js_sys::Uint8Array::from(&SIMD_PROBE_MODULE[..]))
+        .unwrap_or(false);
+
+    format!(
+        "{}\nbackends: {}\nbrowser cores: {}\nbrowser supports simd: {}",
+        capabilities::capabilities(),
+        backends,
+        threads,
+        if simd { "yes" } else { "no" }
+    )
+}
+
 fn main() {
     yew::start_app::<Model>();
 }
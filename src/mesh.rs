@@ -0,0 +1,163 @@
+// triangle meshes of simple solids, written out as binary stl for 3d printing and cnc carving.
+// coordinates are in millimeters, with z up
+pub struct Mesh {
+    triangles: Vec<[[f32; 3]; 3]>,
+}
+
+impl Mesh {
+    pub fn new() -> Self {
+        Mesh { triangles: vec![] }
+    }
+
+    // `outline` raised straight up from `bottom` to `top`. outlines have to be convex
+    pub fn prism(&mut self, outline: &[(f32, f32)], bottom: f32, top: f32) {
+        let outline = match counterclockwise(outline) {
+            Some(outline) => outline,
+            None => return,
+        };
+        let at = |(x, y): (f32, f32), z: f32| [x, y, z];
+
+        for i in 1..outline.len() - 1 {
+            // facing down underneath, and up on top
+            self.triangles.push([
+                at(outline[0], bottom),
+                at(outline[i + 1], bottom),
+                at(outline[i], bottom),
+            ]);
+            self.triangles.push([
+                at(outline[0], top),
+                at(outline[i], top),
+                at(outline[i + 1], top),
+            ]);
+        }
+
+        for (i, &a) in outline.iter().enumerate() {
+            let b = outline[(i + 1) % outline.len()];
+            self.triangles
+                .push([at(a, bottom), at(b, bottom), at(b, top)]);
+            self.triangles.push([at(a, bottom), at(b, top), at(a, top)]);
+        }
+    }
+
+    // `outline` at `bottom`, narrowing to a point over its middle at `top`.
+    // outlines have to be convex
+    pub fn pyramid(&mut self, outline: &[(f32, f32)], bottom: f32, top: f32) {
+        let outline = match counterclockwise(outline) {
+            Some(outline) => outline,
+            None => return,
+        };
+        let at = |(x, y): (f32, f32), z: f32| [x, y, z];
+        let count = outline.len() as f32;
+        let apex = [
+            outline.iter().map(|(x, _)| x).sum::<f32>() / count,
+            outline.iter().map(|(_, y)| y).sum::<f32>() / count,
+            top,
+        ];
+
+        for i in 1..outline.len() - 1 {
+            self.triangles.push([
+                at(outline[0], bottom),
+                at(outline[i + 1], bottom),
+                at(outline[i], bottom),
+            ]);
+        }
+
+        for (i, &a) in outline.iter().enumerate() {
+            let b = outline[(i + 1) % outline.len()];
+            self.triangles.push([at(a, bottom), at(b, bottom), apex]);
+        }
+    }
+
+    // an 80 byte header, the number of triangles, then each triangle's normal,
+    // corners and an unused attribute, all little endian
+    pub fn to_stl(&self) -> Vec<u8> {
+        let mut stl = Vec::with_capacity(84 + self.triangles.len() * 50);
+
+        let mut header = [0u8; 80];
+        let name = b"rat relief";
+        header[..name.len()].copy_from_slice(name);
+        stl.extend_from_slice(&header);
+        stl.extend_from_slice(&(self.triangles.len() as u32).to_le_bytes());
+
+        for triangle in &self.triangles {
+            for value in normal(triangle).iter().chain(triangle.iter().flatten()) {
+                stl.extend_from_slice(&value.to_le_bytes());
+            }
+            stl.extend_from_slice(&0u16.to_le_bytes());
+        }
+
+        stl
+    }
+}
+
+impl Default for Mesh {
+    fn default() -> Self {
+        Mesh::new()
+    }
+}
+
+// a circle as a polygon of `segments` sides
+pub fn circle_outline((x, y): (f32, f32), radius: f32, segments: usize) -> Vec<(f32, f32)> {
+    (0..segments)
+        .map(|i| {
+            let angle = i as f32 / segments as f32 * std::f32::consts::TAU;
+            (x + radius * angle.cos(), y + radius * angle.sin())
+        })
+        .collect()
+}
+
+// a stroke from `from` to `to`, reaching half its width past each end
+// so the segments of a path overlap where they meet
+pub fn segment_outline(from: (f32, f32), to: (f32, f32), width: f32) -> Vec<(f32, f32)> {
+    let half = width / 2.0;
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = (dx * dx + dy * dy).sqrt();
+    let (along_x, along_y) = if length > f32::EPSILON {
+        (dx / length * half, dy / length * half)
+    } else {
+        (half, 0.0)
+    };
+    let (across_x, across_y) = (-along_y, along_x);
+
+    vec![
+        (from.0 - along_x - across_x, from.1 - along_y - across_y),
+        (to.0 + along_x - across_x, to.1 + along_y - across_y),
+        (to.0 + along_x + across_x, to.1 + along_y + across_y),
+        (from.0 - along_x + across_x, from.1 - along_y + across_y),
+    ]
+}
+
+// `outline` turning counterclockwise seen from above, so faces built from it point outwards,
+// or nothing if it has no area
+fn counterclockwise(outline: &[(f32, f32)]) -> Option<Vec<(f32, f32)>> {
+    let twice_area: f32 = outline
+        .iter()
+        .zip(outline.iter().cycle().skip(1))
+        .map(|((x1, y1), (x2, y2))| x1 * y2 - x2 * y1)
+        .sum();
+
+    if outline.len() < 3 || twice_area.abs() <= f32::EPSILON {
+        None
+    } else if twice_area > 0.0 {
+        Some(outline.to_vec())
+    } else {
+        Some(outline.iter().rev().copied().collect())
+    }
+}
+
+fn normal([a, b, c]: &[[f32; 3]; 3]) -> [f32; 3] {
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let n = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+    let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+
+    if length > 0.0 {
+        [n[0] / length, n[1] / length, n[2] / length]
+    } else {
+        [0.0, 0.0, 0.0]
+    }
+}
@@ -0,0 +1,180 @@
+use crate::archive::{self, ZipError};
+use crate::settings::Settings;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// bump this whenever the layout of the bundle or of `Settings` changes
+// in a way that older versions of rat can't read
+pub const PROJECT_FORMAT_VERSION: u32 = 1;
+
+pub const MANIFEST_FILENAME: &str = "project.json";
+pub const IMAGE_DIRECTORY: &str = "image";
+pub const OUTPUTS_DIRECTORY: &str = "outputs";
+
+// a project bundle is a zip containing:
+//
+// project.json         - this manifest
+// image/<filename>     - the original, unmodified source image
+// outputs/<filename>   - whatever the last rasterization produced, if anything
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectManifest {
+    pub format_version: u32,
+    pub rat_version: String,
+    pub image_filename: String,
+    pub settings: Settings,
+    // what `outputs/` was made with. `settings` may have been changed since the last run
+    #[serde(default)]
+    pub outputs_settings: Option<Settings>,
+}
+
+impl ProjectManifest {
+    pub fn new(
+        rat_version: &str,
+        image_filename: &str,
+        settings: Settings,
+        outputs_settings: Option<Settings>,
+    ) -> Self {
+        ProjectManifest {
+            format_version: PROJECT_FORMAT_VERSION,
+            rat_version: rat_version.trim().to_string(),
+            image_filename: image_filename.to_string(),
+            settings,
+            outputs_settings,
+        }
+    }
+}
+
+// lay out the manifest, source image and outputs as the
+// (path, bytes) pairs that get written into the bundle zip
+pub fn project_files(
+    manifest: &ProjectManifest,
+    image_bytes: &[u8],
+    outputs: &[(String, Vec<u8>)],
+) -> serde_json::Result<Vec<(String, Vec<u8>)>> {
+    let mut files = Vec::with_capacity(outputs.len() + 2);
+
+    files.push((
+        MANIFEST_FILENAME.to_string(),
+        serde_json::to_vec_pretty(manifest)?,
+    ));
+
+    files.push((
+        format!("{}/{}", IMAGE_DIRECTORY, manifest.image_filename),
+        image_bytes.to_vec(),
+    ));
+
+    for (filename, bytes) in outputs {
+        files.push((format!("{}/{}", OUTPUTS_DIRECTORY, filename), bytes.clone()));
+    }
+
+    Ok(files)
+}
+
+// everything needed to put the app back into the state it was in
+// when the project was exported
+pub struct Project {
+    pub manifest: ProjectManifest,
+    pub image_bytes: Vec<u8>,
+    pub outputs: Vec<(String, Vec<u8>)>,
+}
+
+#[derive(Debug)]
+pub enum ProjectError {
+    Archive(ZipError),
+    Json(serde_json::Error),
+    MissingManifest,
+    MissingImage(String),
+    UnsupportedVersion(u64),
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProjectError::Archive(e) => write!(f, "could not read project archive: {}", e),
+            ProjectError::Json(e) => write!(f, "could not read {}: {}", MANIFEST_FILENAME, e),
+            ProjectError::MissingManifest => {
+                write!(f, "project does not contain its {}", MANIFEST_FILENAME)
+            }
+            ProjectError::MissingImage(filename) => {
+                write!(f, "project does not contain its image {}", filename)
+            }
+            ProjectError::UnsupportedVersion(version) => write!(
+                f,
+                "project format version {} is newer than this version of rat supports ({})",
+                version, PROJECT_FORMAT_VERSION
+            ),
+        }
+    }
+}
+
+impl From<ZipError> for ProjectError {
+    fn from(e: ZipError) -> Self {
+        ProjectError::Archive(e)
+    }
+}
+
+impl From<serde_json::Error> for ProjectError {
+    fn from(e: serde_json::Error) -> Self {
+        ProjectError::Json(e)
+    }
+}
+
+pub fn import_project(bytes: &[u8]) -> Result<Project, ProjectError> {
+    let files = archive::read_zip(bytes)?;
+
+    let manifest_json = files
+        .iter()
+        .find(|(name, _)| name == MANIFEST_FILENAME)
+        .map(|(_, bytes)| bytes)
+        .ok_or(ProjectError::MissingManifest)?;
+
+    let manifest_value: serde_json::Value = serde_json::from_slice(manifest_json)?;
+    let manifest: ProjectManifest = serde_json::from_value(migrate_manifest(manifest_value)?)?;
+
+    let image_path = format!("{}/{}", IMAGE_DIRECTORY, manifest.image_filename);
+    let outputs_prefix = format!("{}/", OUTPUTS_DIRECTORY);
+
+    let mut image_bytes = None;
+    let mut outputs = vec![];
+
+    for (name, bytes) in files {
+        if name == image_path {
+            image_bytes = Some(bytes);
+        } else if let Some(filename) = name.strip_prefix(&outputs_prefix) {
+            outputs.push((filename.to_string(), bytes));
+        }
+    }
+
+    let image_bytes = image_bytes.ok_or(ProjectError::MissingImage(image_path))?;
+
+    Ok(Project {
+        manifest,
+        image_bytes,
+        outputs,
+    })
+}
+
+// bring a manifest written by an older version of rat up to the current format.
+// fields added to `Settings` after a format version was released are filled in
+// from `Settings::default()` by serde, so a migration step is only needed when
+// something is renamed, removed, or changes meaning.
+fn migrate_manifest(mut manifest: serde_json::Value) -> Result<serde_json::Value, ProjectError> {
+    // manifests written before versioning was introduced have no version field
+    let version = manifest
+        .get("format_version")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0);
+
+    if version > PROJECT_FORMAT_VERSION as u64 {
+        return Err(ProjectError::UnsupportedVersion(version));
+    }
+
+    if let Some(manifest) = manifest.as_object_mut() {
+        manifest.insert(
+            "format_version".to_string(),
+            serde_json::Value::from(PROJECT_FORMAT_VERSION),
+        );
+    }
+
+    Ok(manifest)
+}
@@ -1,13 +1,89 @@
-use image::{GenericImageView, ImageBuffer, Rgba, SubImage};
+use crate::color::{self, Color, Gradient, Inks, Palette};
+use crate::draw::{self, CircleAlgorithm};
+use crate::edges;
+use crate::mesh::{self, Mesh};
+use crate::resample;
+use crate::stipple::{self, XorShift};
+use crate::tsp;
+use crate::voronoi::Stippler;
+use image::{GenericImageView, ImageBuffer, Pixel, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryInto;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::ops::Deref;
+use std::rc::Rc;
 use std::slice::Iter;
+use svg::Node;
 
 pub const PIXELS_PER_INCH: f32 = 72.0;
+// characters for `rasterize_text`, from lightest to darkest
+pub const DEFAULT_CHARACTER_RAMP: &str = " .:-=+*#%@";
 const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+// bytes per pixel of an `RgbaImage`
+const CHANNELS: usize = 4;
+// fewer, bigger cells are much faster to sample and draw
+const DRAFT_MIN_SQUARE_SIZE: f32 = 12.0;
+// waves are drawn as polylines with a point every `WAVE_STEP` pixels
+const WAVE_STEP: f32 = 0.5;
+const WAVE_STROKE_WIDTH_PERCENTAGE: f32 = 0.1;
+// fully dark squares fit this many periods of their row's wave
+const MAX_WAVE_CYCLES_PER_SQUARE: f32 = 3.0;
+const MILLIMETERS_PER_INCH: f32 = 25.4;
+// g-code pen heights and drawing speed, in millimeters and millimeters per minute
+const GCODE_PEN_UP_Z: f32 = 5.0;
+const GCODE_PEN_DOWN_Z: f32 = 0.0;
+const GCODE_FEED_RATE: f32 = 3000.0;
+// the width laser software draws cut lines at, which it reads as "cut" rather than "fill"
+const LASER_HAIRLINE_MILLIMETERS: f32 = 0.1;
+// the sides of the polygons round dots are raised from in reliefs
+const RELIEF_CIRCLE_SEGMENTS: usize = 24;
+// postscript measures pages in points
+const POINTS_PER_INCH: f32 = 72.0;
+// monospace characters are about twice as tall as they are wide,
+// so text cells are `square_size` wide and this many times as tall
+const CHARACTER_HEIGHT_PER_WIDTH: f32 = 2.0;
+// from the top of a line of text to its baseline, as a fraction of the line height
+const CHARACTER_BASELINE: f32 = 0.8;
+// crosshatch directions, in degrees, in the order they are layered on as squares get darker
+const HATCH_ANGLES: [f32; 4] = [45.0, -45.0, 0.0, 90.0];
+const HATCH_LINES_PER_SQUARE: f32 = 3.0;
+const HATCH_STROKE_WIDTH_PERCENTAGE: f32 = 0.08;
+// control points of `RadiusCurve`, evenly spaced from black to white
+pub const RADIUS_CURVE_POINTS: usize = 5;
+// long sides over short sides of `DotShape::Ellipse` and `DotShape::Dash`
+const ELLIPSE_ASPECT: f32 = 2.0;
+const DASH_ASPECT: f32 = 4.0;
+// ellipses are drawn on image pages as polygons with this many corners
+const ELLIPSE_OUTLINE_POINTS: usize = 32;
+// sobel gradients weaker than this are too flat to follow, out of a possible 4
+const MIN_CONTOUR_GRADIENT: f32 = 0.05;
+// the factors `RasterizeArgs::supersampling` can be set to
+pub const SUPERSAMPLING_FACTORS: [u32; 3] = [1, 2, 4];
+// each preview on a contact sheet is scaled down to fit in this many pixels either way,
+// with this many pixels of white between them
+const CONTACT_SHEET_CELL_PIXELS: u32 = 320;
+const CONTACT_SHEET_GAP_PIXELS: u32 = 8;
+// the most columns and rows a contact sheet can have
+pub const MAX_CONTACT_SHEET_STEPS: u32 = 6;
+// frames of an animated gif of the whole page grid are scaled down to fit in this many pixels
+const ANIMATION_FRAME_PIXELS: u32 = 640;
+// the layout preview is scaled down to fit in this many pixels either way
+const LAYOUT_PREVIEW_MAX_PIXELS: u32 = 480;
+// an automatic palette is picked from at most this many of the image's pixels
+const PALETTE_MAX_SAMPLES: u32 = 4096;
 
+#[derive(Clone, Copy)]
 pub struct RasterizeArgs<'a> {
-    pub image: &'a image::DynamicImage,
+    pub image: &'a RgbaImage,
+    // blended over `image` with `mask_blend`
+    pub mask: Option<&'a RgbaImage>,
     pub paper_width_pixels: f32,
     pub paper_height_pixels: f32,
     pub pages_width: u32,
@@ -16,404 +92,6560 @@ pub struct RasterizeArgs<'a> {
     pub min_radius_percentage: f32,
     pub max_radius_percentage: f32,
     pub color_depth: ColorDepth,
+    pub layout: Layout,
+    pub step_and_repeat: StepAndRepeat,
+    pub border: Border,
+    pub matrix: ParameterMatrix,
+    pub gradient: Gradient,
+    pub output_pixels_per_inch: f32,
+    pub draft: bool,
+    pub circle_algorithm: CircleAlgorithm,
+    pub dither: Dither,
+    pub pattern: Pattern,
+    pub mask_blend: MaskBlend,
+    pub line_angle: f32,
+    pub invert: ChannelInvert,
+    pub inks: Inks,
+    // only draw the shapes of this ink, see `RasterizeArgs::ink_layer`
+    pub ink_layer: Option<usize>,
+    pub row_order: RowOrder,
+    pub palette: Palette,
+    pub transparency: Transparency,
+    pub tone: Tone,
+    // svg dots get `data-brightness`, `data-cell` and `data-page` attributes,
+    // for scripts that work with the dots afterwards
+    pub dot_metadata: bool,
+    // maps the brightness of a grid square to the size of its dot
+    pub radius_curve: RadiusCurve,
+    // how pixels' brightness is worked out from their color
+    pub luma: Luma,
+    // randomness in the size and position of grid dots
+    pub jitter: Jitter,
+    // what grid dots are drawn as
+    pub dot_shape: DotShape,
+    // turn non-circular grid dots along the image's contours, rather than to `line_angle`
+    pub follow_contours: bool,
+    // strokes along the outlines in the image, drawn over the pattern
+    pub edges: Edges,
+    // turns, flips and crops the image (and mask) before anything else
+    pub framing: Framing,
+    // how the image is scaled onto the page grid
+    pub resampling: Resampling,
+    // how much smaller svg pages are made
+    pub svg_optimization: SvgOptimization,
+    // grid dots too small to print are left out
+    pub min_dot_size: MinDotSize,
+    // image pages are drawn this many times bigger each way and shrunk back down,
+    // for smooth edges without antialiasing. 1 draws them as they are
+    pub supersampling: u32,
 }
 
-pub fn rasterize_image(args: RasterizeArgs) -> Vec<ImageBuffer<Rgba<u8>, Vec<u8>>> {
-    let image = args.image;
-    let pages_width = args.pages_width;
-    let pages_height = args.pages_height;
-    let paper_width_pixels = args.paper_width_pixels;
-    let paper_height_pixels = args.paper_height_pixels;
-    let min_radius_percentage = args.min_radius_percentage;
-    let max_radius_percentage = args.max_radius_percentage;
-    let square_size = args.square_size;
-    let max_radius = max_radius(square_size);
-    let adjusted_max_radius = max_radius * max_radius_percentage;
-    let adjusted_min_radius = max_radius * min_radius_percentage;
-    let half_square_size = (square_size / 2.0).floor() as i32;
-    let square_size_floor = square_size.floor() as u32;
-    let color_depth = args.color_depth;
-    let pages_width_pixels = (pages_width as f32 * paper_width_pixels).ceil() as u32;
-    let pages_height_pixels = (pages_height as f32 * paper_height_pixels).ceil() as u32;
-
-    let image_scaled_to_fit_on_pages = image.resize(
-        pages_width_pixels,
-        pages_height_pixels,
-        image::imageops::Nearest,
+// the pages of a run, left-right top-bottom.
+// each page is only rasterized when it is pulled from the stream, so callers can
+// save or show pages one at a time instead of holding on to all of them at once.
+// pages skipped over with `nth` (or `skip`) are never rasterized at all
+pub struct PageStream<'a, T> {
+    pages: Box<dyn Iterator<Item = T> + 'a>,
+}
+
+impl<'a, T: 'a> PageStream<'a, T> {
+    fn new(pages: impl Iterator<Item = T> + 'a) -> Self {
+        PageStream {
+            pages: Box::new(pages),
+        }
+    }
+
+    fn empty() -> Self {
+        PageStream::new(std::iter::empty())
+    }
+
+    // each of `regions` made into a page by `draw` as it is pulled from the stream
+    fn drawn<R: 'a>(regions: impl Iterator<Item = R> + 'a, draw: impl FnMut(R) -> T + 'a) -> Self {
+        PageStream::new(Drawn { regions, draw })
+    }
+
+    // the pages of each of `runs` in turn, as for the tiles of `Layout::Matrix`
+    fn chain(runs: impl Iterator<Item = PageStream<'a, T>> + 'a) -> Self {
+        PageStream::new(Chained { runs, run: None })
+    }
+
+    // a stream of the one thing `make` makes, for outputs that aren't split into pages
+    pub fn single(make: impl FnOnce() -> T + 'a) -> Self {
+        let mut make = Some(make);
+        PageStream::drawn(std::iter::once(()), move |_| {
+            (make.take().expect("a single page is only made once"))()
+        })
+    }
+
+    // each page and its index made into something else by `f`, still only when it is
+    // pulled from the stream, so that skipping over pages doesn't make them either
+    pub fn map_pages<U: 'a>(self, mut f: impl FnMut(usize, T) -> U + 'a) -> PageStream<'a, U> {
+        PageStream::drawn(self.enumerate(), move |(i, page)| f(i, page))
+    }
+}
+
+impl<'a, T> Iterator for PageStream<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.pages.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.pages.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<T> {
+        self.pages.nth(n)
+    }
+}
+
+// see `PageStream::drawn`. regions are skipped without being drawn
+struct Drawn<I, F> {
+    regions: I,
+    draw: F,
+}
+
+impl<I: Iterator, T, F: FnMut(I::Item) -> T> Iterator for Drawn<I, F> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.regions.next().map(&mut self.draw)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.regions.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<T> {
+        self.regions.nth(n).map(&mut self.draw)
+    }
+}
+
+// see `PageStream::chain`
+struct Chained<'a, T, I> {
+    runs: I,
+    run: Option<PageStream<'a, T>>,
+}
+
+impl<'a, T, I: Iterator<Item = PageStream<'a, T>>> Iterator for Chained<'a, T, I> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.nth(0)
+    }
+
+    fn nth(&mut self, mut n: usize) -> Option<T> {
+        loop {
+            if let Some(run) = &mut self.run {
+                match run.size_hint() {
+                    // whole runs are skipped over when it's known how many pages they have
+                    (pages, Some(upper)) if pages == upper => {
+                        if n < pages {
+                            return run.nth(n);
+                        }
+                        n -= pages;
+                    }
+                    _ => {
+                        for page in run.by_ref() {
+                            if n == 0 {
+                                return Some(page);
+                            }
+                            n -= 1;
+                        }
+                    }
+                }
+            }
+
+            self.run = Some(self.runs.next()?);
+        }
+    }
+}
+
+// one page's part of the laid out image, see `LaidOutImage::page`.
+// `x` and `y` are where the page is in the laid out image
+struct PageView {
+    image: RgbaImage,
+    x: u32,
+    y: u32,
+}
+
+impl PageView {
+    fn new(image: RgbaImage, x: u32, y: u32) -> Self {
+        PageView { image, x, y }
+    }
+
+    // `width` pixels of row `y`, starting at `x`, straight from the image's buffer
+    fn row(&self, x: u32, y: u32, width: u32) -> impl Iterator<Item = Rgba<u8>> + '_ {
+        let start = (y as usize * self.image.width() as usize + x as usize) * CHANNELS;
+        let end = start + width as usize * CHANNELS;
+
+        self.image.as_raw()[start..end]
+            .chunks_exact(CHANNELS)
+            .map(|pixel| *Rgba::from_slice(pixel))
+    }
+}
+
+impl GenericImageView for PageView {
+    type Pixel = Rgba<u8>;
+    type InnerImageView = RgbaImage;
+
+    fn dimensions(&self) -> (u32, u32) {
+        self.image.dimensions()
+    }
+
+    fn bounds(&self) -> (u32, u32, u32, u32) {
+        (self.x, self.y, self.image.width(), self.image.height())
+    }
+
+    fn get_pixel(&self, x: u32, y: u32) -> Rgba<u8> {
+        *self.image.get_pixel(x, y)
+    }
+
+    fn inner(&self) -> &RgbaImage {
+        &self.image
+    }
+}
+
+// spare page buffers. pages handed back with `recycle` once they have been
+// saved are drawn over again for later pages, instead of allocating every page anew.
+// clones share their buffers
+#[derive(Clone, Default)]
+pub struct PagePool {
+    buffers: Rc<RefCell<Vec<Vec<u8>>>>,
+}
+
+impl PagePool {
+    // a blank, fully transparent page
+    fn page(&self, width: u32, height: u32) -> RgbaImage {
+        let mut buffer = self.buffers.borrow_mut().pop().unwrap_or_default();
+        buffer.clear();
+        buffer.resize(width as usize * height as usize * CHANNELS, 0);
+
+        RgbaImage::from_raw(width, height, buffer).expect("buffer is the size of the page")
+    }
+
+    pub fn recycle(&self, page: RgbaImage) {
+        self.buffers.borrow_mut().push(page.into_raw());
+    }
+}
+
+pub fn rasterize_image(args: RasterizeArgs) -> PageStream<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+    rasterize_image_observed(args, PagePool::default(), Box::new(|_, _| {}))
+}
+
+// like `rasterize_image`, but drawing onto pages from `pool`
+pub fn rasterize_image_pooled(
+    args: RasterizeArgs,
+    pool: PagePool,
+) -> PageStream<ImageBuffer<Rgba<u8>, Vec<u8>>> {
+    rasterize_image_observed(args, pool, Box::new(|_, _| {}))
+}
+
+// frames of every page being drawn shape by shape, in the same order
+// as the svg backend writes them out, so users can check what a plotter
+// is going to do before starting it.
+// each page gets about `frame_count` frames, the last one being the finished page
+pub fn rasterize_plot_preview(args: RasterizeArgs, frame_count: u32) -> Vec<Vec<RgbaImage>> {
+    // the matrix layout's tiles are separate runs, not one plot
+    if args.layout == Layout::Matrix {
+        return vec![];
+    }
+
+    // frames are taken of the page as it is drawn, so it has to be drawn at its own size
+    let args = RasterizeArgs {
+        output_pixels_per_inch: PIXELS_PER_INCH,
+        supersampling: 1,
+        ..args
+    };
+
+    // count the shapes on every page first, to spread the frames evenly over them
+    let mut shape_counts: Vec<usize> = vec![];
+    let pages = rasterize_image_observed(
+        args,
+        PagePool::default(),
+        Box::new(|page_index, _| {
+            if shape_counts.len() <= page_index {
+                shape_counts.resize(page_index + 1, 0);
+            }
+            shape_counts[page_index] += 1;
+        }),
+    )
+    .collect::<Vec<_>>();
+
+    let mut frames = vec![vec![]; pages.len()];
+    let mut shapes_drawn = vec![0; pages.len()];
+    rasterize_image_observed(
+        args,
+        PagePool::default(),
+        Box::new(|page_index, page| {
+            let shapes_per_frame = (shape_counts[page_index] / frame_count.max(1) as usize).max(1);
+
+            shapes_drawn[page_index] += 1;
+            if shapes_drawn[page_index] == shapes_per_frame {
+                shapes_drawn[page_index] = 0;
+                frames[page_index].push(on_white(page));
+            }
+        }),
+    )
+    .for_each(drop);
+
+    for (page_frames, page) in frames.iter_mut().zip(pages) {
+        page_frames.push(on_white(&page));
+    }
+
+    frames
+}
+
+// pages are drawn on a transparent background
+fn on_white(page: &RgbaImage) -> RgbaImage {
+    let mut flattened = ImageBuffer::from_pixel(page.width(), page.height(), WHITE);
+    image::imageops::overlay(&mut flattened, page, 0, 0);
+    flattened
+}
+
+// called with the page index and the page so far, see `rasterize_image_observed`.
+// boxed, so that the matrix layout passing it on to each tile doesn't
+// instantiate the function again for every level of borrowing
+type ShapeObserver<'b> = Box<dyn FnMut(usize, &RgbaImage) + 'b>;
+
+// every page at once, drawn on rayon's global thread pool since pages don't depend on
+// each other. they come out the same, and in the same order, as from `rasterize_image`
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+pub fn rasterize_image_parallel(args: RasterizeArgs) -> Vec<RgbaImage> {
+    use rayon::prelude::*;
+
+    if check_image(args.image).is_err() {
+        return vec![];
+    }
+
+    let args = args.with_draft_overrides().with_palette();
+    let page_limit = args.page_limit();
+
+    if args.layout == Layout::Matrix {
+        return args
+            .matrix_tiles()
+            .into_par_iter()
+            .take(page_limit)
+            .flat_map_iter(rasterize_image)
+            .collect();
+    }
+
+    let laid_out = LaidOutImage::new(&args);
+    let (scaled_image_width_pixels, scaled_image_height_pixels) = laid_out.dimensions();
+    let pages = page_regions(&args, scaled_image_width_pixels, scaled_image_height_pixels);
+
+    pages
+        .into_par_iter()
+        .take(page_limit)
+        .enumerate()
+        .map(|(page_index, region)| {
+            draw_image_page(
+                &args,
+                &laid_out,
+                region,
+                page_index,
+                &PagePool::default(),
+                &mut |_, _| {},
+            )
+        })
+        .collect()
+}
+
+// in a browser, which can't start threads without wasm threads, the pages are
+// drawn one after another instead
+#[cfg(all(feature = "parallel", target_arch = "wasm32"))]
+pub fn rasterize_image_parallel(args: RasterizeArgs) -> Vec<RgbaImage> {
+    rasterize_image(args).collect()
+}
+
+// `rasterize_image`, drawing onto pages from `pool` and calling `after_shape` with
+// the page index and the page so far every time a shape is drawn
+fn rasterize_image_observed<'a, 'b>(
+    args: RasterizeArgs<'a>,
+    pool: PagePool,
+    mut after_shape: ShapeObserver<'b>,
+) -> PageStream<'b, ImageBuffer<Rgba<u8>, Vec<u8>>>
+where
+    'a: 'b,
+{
+    if check_image(args.image).is_err() {
+        return PageStream::empty();
+    }
+
+    let args = args.with_draft_overrides().with_palette();
+    let page_limit = args.page_limit();
+
+    if args.layout == Layout::Matrix {
+        // every tile is a single page, so each is drawn in full as it is reached
+        return PageStream::drawn(
+            args.matrix_tiles().into_iter().take(page_limit),
+            move |tile| {
+                rasterize_image_observed(tile, pool.clone(), Box::new(&mut after_shape))
+                    .next()
+                    .expect("every tile is a page")
+            },
+        );
+    }
+
+    let laid_out = LaidOutImage::new(&args);
+    let (scaled_image_width_pixels, scaled_image_height_pixels) = laid_out.dimensions();
+
+    // calculate pages, left-right top-bottom
+    let pages = page_regions(&args, scaled_image_width_pixels, scaled_image_height_pixels);
+
+    PageStream::drawn(
+        pages.into_iter().take(page_limit).enumerate(),
+        move |(page_index, region)| {
+            draw_image_page(
+                &args,
+                &laid_out,
+                region,
+                page_index,
+                &pool,
+                &mut after_shape,
+            )
+        },
+    )
+}
+
+// draws one page of the laid out image, see `rasterize_image_observed`.
+// `region` is (x, y, width, height) of the page in the laid out image
+fn draw_image_page(
+    args: &RasterizeArgs,
+    laid_out: &LaidOutImage,
+    (page_offset_x, page_offset_y, page_width_pixels, page_height_pixels): (u32, u32, u32, u32),
+    page_index: usize,
+    pool: &PagePool,
+    after_shape: &mut dyn FnMut(usize, &RgbaImage),
+) -> RgbaImage {
+    let border = args.border;
+    // sampling always happens at `PIXELS_PER_INCH`,
+    // but pages can be drawn at a higher resolution for print
+    let render_scale = args.output_pixels_per_inch / PIXELS_PER_INCH;
+    let supersampling = args.supersampling.max(1);
+    let circle_algorithm = args.circle_algorithm;
+    let (scaled_image_width_pixels, scaled_image_height_pixels) = laid_out.dimensions();
+
+    // each page is its own sub image
+    let page = laid_out.page((
+        page_offset_x,
+        page_offset_y,
+        page_width_pixels,
+        page_height_pixels,
+    ));
+
+    let width = (page_width_pixels as f32 * render_scale).ceil() as u32;
+    let height = (page_height_pixels as f32 * render_scale).ceil() as u32;
+    let draw_scale = render_scale * supersampling as f32;
+    let paints = page_paints(args, &page, (page_offset_x, page_offset_y), draw_scale);
+
+    // create a dupe of this page on which we will draw circles,
+    // at the output resolution rather than the sampling resolution
+    let mut target_page = pool.page(width * supersampling, height * supersampling);
+
+    let mut shape_start = 0;
+    for &shape_end in &paints.shape_ends {
+        for paint in &paints.paints[shape_start..shape_end] {
+            paint.draw(&mut target_page, 0, circle_algorithm);
+        }
+        after_shape(page_index, &target_page);
+        shape_start = shape_end;
+    }
+
+    draw_border_on_image_page(
+        &mut target_page,
+        border,
+        (scaled_image_width_pixels, scaled_image_height_pixels),
+        (page_offset_x, page_offset_y),
+        draw_scale,
+        0,
+    );
+
+    if supersampling == 1 {
+        return target_page;
+    }
+
+    let mut shrunk_page = pool.page(width, height);
+    draw::shrink(&target_page, supersampling, &mut shrunk_page);
+    pool.recycle(target_page);
+
+    shrunk_page
+}
+
+// rows of the laid out image resampled at a time by `voronoi_stippler`
+const DENSITY_BAND_ROWS: u32 = 256;
+
+// rows of an image page drawn at a time by `rasterize_png`
+const PNG_STRIP_ROWS: u32 = 256;
+
+// `rasterize_image`'s pages, encoded as pngs. each page is drawn and encoded
+// a strip of rows at a time, so only a strip is ever held as raw pixels,
+// however big the page or however high its resolution
+pub fn rasterize_png(args: RasterizeArgs) -> PageStream<Vec<u8>> {
+    if check_image(args.image).is_err() {
+        return PageStream::empty();
+    }
+
+    let args = args.with_draft_overrides().with_palette();
+    let page_limit = args.page_limit();
+
+    if args.layout == Layout::Matrix {
+        return PageStream::chain(
+            args.matrix_tiles()
+                .into_iter()
+                .take(page_limit)
+                .map(rasterize_png),
+        );
+    }
+
+    let laid_out = LaidOutImage::new(&args);
+    let (scaled_image_width_pixels, scaled_image_height_pixels) = laid_out.dimensions();
+
+    let pages = page_regions(&args, scaled_image_width_pixels, scaled_image_height_pixels);
+    let render_scale = args.output_pixels_per_inch / PIXELS_PER_INCH;
+    let supersampling = args.supersampling.max(1);
+    let draw_scale = render_scale * supersampling as f32;
+    let mut strip_buffer = vec![];
+    let mut shrunk_buffer = vec![];
+
+    PageStream::drawn(
+        pages.into_iter().take(page_limit),
+        move |(page_offset_x, page_offset_y, page_width_pixels, page_height_pixels)| {
+            let page = laid_out.page((
+                page_offset_x,
+                page_offset_y,
+                page_width_pixels,
+                page_height_pixels,
+            ));
+            let paints = page_paints(&args, &page, (page_offset_x, page_offset_y), draw_scale);
+
+            let width = (page_width_pixels as f32 * render_scale).ceil() as u32;
+            let height = (page_height_pixels as f32 * render_scale).ceil() as u32;
+            let mut png = PngWriter::new(width, height);
+
+            for top in (0..height).step_by(PNG_STRIP_ROWS as usize) {
+                let rows = PNG_STRIP_ROWS.min(height - top);
+                // strips are drawn supersampled, then shrunk down to the page's size
+                let (strip_width, strip_rows, strip_top) = (
+                    width * supersampling,
+                    rows * supersampling,
+                    top * supersampling,
+                );
+
+                let mut buffer = std::mem::take(&mut strip_buffer);
+                buffer.clear();
+                buffer.resize(strip_width as usize * strip_rows as usize * CHANNELS, 0);
+                let mut strip = RgbaImage::from_raw(strip_width, strip_rows, buffer)
+                    .expect("buffer is the size of the strip");
+
+                // everything is drawn in the same order as on a whole page, leaving out
+                // what can't reach the strip, so the strips join up into the same page
+                for paint in &paints.paints {
+                    let (paint_top, paint_bottom) = paint.rows();
+                    if paint_bottom >= strip_top as f32
+                        && paint_top <= (strip_top + strip_rows) as f32
+                    {
+                        paint.draw(&mut strip, strip_top, args.circle_algorithm);
+                    }
+                }
+
+                draw_border_on_image_page(
+                    &mut strip,
+                    args.border,
+                    (scaled_image_width_pixels, scaled_image_height_pixels),
+                    (page_offset_x, page_offset_y),
+                    draw_scale,
+                    strip_top,
+                );
+
+                if supersampling == 1 {
+                    png.write_rows(strip.as_raw());
+                } else {
+                    let mut buffer = std::mem::take(&mut shrunk_buffer);
+                    buffer.clear();
+                    buffer.resize(width as usize * rows as usize * CHANNELS, 0);
+                    let mut shrunk = RgbaImage::from_raw(width, rows, buffer)
+                        .expect("buffer is the size of the strip");
+                    draw::shrink(&strip, supersampling, &mut shrunk);
+                    png.write_rows(shrunk.as_raw());
+                    shrunk_buffer = shrunk.into_raw();
+                }
+                strip_buffer = strip.into_raw();
+            }
+
+            png.finish()
+        },
+    )
+}
+
+// one thing drawn onto an image page, in the page's output pixels
+enum Paint {
+    Circle {
+        center: (f32, f32),
+        radius: f32,
+        color: Rgba<u8>,
+    },
+    Segment {
+        from: (f32, f32),
+        to: (f32, f32),
+        width: f32,
+        color: Rgba<u8>,
+    },
+    Polygon {
+        outline: Vec<(f32, f32)>,
+        color: Rgba<u8>,
+    },
+}
+
+impl Paint {
+    // the rows it can touch, with a pixel to spare for rounding and antialiasing
+    fn rows(&self) -> (f32, f32) {
+        let (top, bottom) = match self {
+            Paint::Circle { center, radius, .. } => (center.1 - radius, center.1 + radius),
+            Paint::Segment {
+                from, to, width, ..
+            } => (
+                from.1.min(to.1) - width / 2.0,
+                from.1.max(to.1) + width / 2.0,
+            ),
+            Paint::Polygon { outline, .. } => outline.iter().fold(
+                (f32::INFINITY, f32::NEG_INFINITY),
+                |(top, bottom), point| (top.min(point.1), bottom.max(point.1)),
+            ),
+        };
+
+        (top - 1.0, bottom + 1.0)
+    }
+
+    // draws it onto `target`, which holds the page's rows from `top` down
+    fn draw(&self, target: &mut RgbaImage, top: u32, circle_algorithm: CircleAlgorithm) {
+        let up = |(x, y): (f32, f32)| (x, y - top as f32);
+
+        match self {
+            Paint::Circle {
+                center,
+                radius,
+                color,
+            } => draw::draw_circle(target, up(*center), *radius, *color, circle_algorithm),
+            Paint::Segment {
+                from,
+                to,
+                width,
+                color,
+            } => draw::draw_segment(target, up(*from), up(*to), *width, *color, circle_algorithm),
+            Paint::Polygon { outline, color } => draw::draw_polygon(
+                target,
+                &outline.iter().copied().map(up).collect::<Vec<_>>(),
+                *color,
+                circle_algorithm,
+            ),
+        }
+    }
+}
+
+// everything drawn on one image page, in the order it is drawn
+struct PagePaints {
+    paints: Vec<Paint>,
+    // where each shape ends in `paints`, for `rasterize_plot_preview`
+    // to show the page as each one is finished
+    shape_ends: Vec<usize>,
+}
+
+impl PagePaints {
+    fn end_shape(&mut self) {
+        self.shape_ends.push(self.paints.len());
+    }
+}
+
+// what gets drawn on a page of `rasterize_image`, scaled from sampling coordinates
+// to the output resolution
+fn page_paints(
+    args: &RasterizeArgs,
+    page: &PageView,
+    (page_offset_x, page_offset_y): (u32, u32),
+    render_scale: f32,
+) -> PagePaints {
+    let scale = |(x, y): (f32, f32)| (x * render_scale, y * render_scale);
+    let mut paints = PagePaints {
+        paints: vec![],
+        shape_ends: vec![],
+    };
+
+    // divide into squares
+    match args.pattern {
+        Pattern::Grid => {
+            for dot in grid_dots(page, args) {
+                if !args.draws(dot.color) {
+                    continue;
+                }
+
+                // write the sampling as a shape to the target page
+                paints.paints.push(match args.dot_shape {
+                    DotShape::Circle => Paint::Circle {
+                        center: scale(dot.center),
+                        radius: dot.radius * render_scale,
+                        color: dot.color,
+                    },
+                    DotShape::Dash => {
+                        let (from, to, width) = dot.dash();
+                        Paint::Segment {
+                            from: scale(from),
+                            to: scale(to),
+                            width: width * render_scale,
+                            color: dot.color,
+                        }
+                    }
+                    shape => Paint::Polygon {
+                        outline: dot.outline(shape).into_iter().map(scale).collect(),
+                        color: dot.color,
+                    },
+                });
+                paints.end_shape();
+            }
+        }
+        Pattern::Stipple => {
+            let (min_distance, stipple_radius) =
+                stipple_spacing(args.square_size, args.max_radius_percentage);
+
+            for (x, y) in
+                stipple_points(page, page_offset_x, page_offset_y, min_distance, args.luma)
+            {
+                let pixel = page.get_pixel(x as u32, y as u32);
+                let color = dot_color(args, pixel, brightness(pixel, args.luma));
+                if !args.draws(color) {
+                    continue;
+                }
+
+                paints.paints.push(Paint::Circle {
+                    center: scale((x, y)),
+                    radius: stipple_radius * render_scale,
+                    color,
+                });
+                paints.end_shape();
+            }
+        }
+        Pattern::Lines => {
+            for segment in line_segments(page, args) {
+                if !args.draws(segment.color) {
+                    continue;
+                }
+
+                paints.paints.push(Paint::Segment {
+                    from: scale(segment.from),
+                    to: scale(segment.to),
+                    width: segment.width * render_scale,
+                    color: segment.color,
+                });
+                paints.end_shape();
+            }
+        }
+        Pattern::Waves | Pattern::Spiral => {
+            let waves = if args.pattern == Pattern::Spiral {
+                vec![spiral(page, args)]
+            } else {
+                waves(page, args)
+            };
+
+            for wave in waves {
+                if !args.draws(wave.color) {
+                    continue;
+                }
+
+                for segment in wave.points.windows(2) {
+                    paints.paints.push(Paint::Segment {
+                        from: scale(segment[0]),
+                        to: scale(segment[1]),
+                        width: wave.width * render_scale,
+                        color: wave.color,
+                    });
+                }
+                paints.end_shape();
+            }
+        }
+        Pattern::Crosshatch => {
+            for hatch in hatches(page, args) {
+                if !args.draws(hatch.color) {
+                    continue;
+                }
+
+                for (from, to) in hatch.lines {
+                    paints.paints.push(Paint::Segment {
+                        from: scale(from),
+                        to: scale(to),
+                        width: hatch.width * render_scale,
+                        color: hatch.color,
+                    });
+                }
+                paints.end_shape();
+            }
+        }
+    }
+
+    let edge_color = edge_color(args);
+    for stroke in page_edges(page, args) {
+        for segment in stroke.windows(2) {
+            paints.paints.push(Paint::Segment {
+                from: scale(segment[0]),
+                to: scale(segment[1]),
+                width: args.edges.width * render_scale,
+                color: edge_color,
+            });
+        }
+        paints.end_shape();
+    }
+
+    paints
+}
+
+// every page in one html file that prints as it is: each page an inline svg,
+// which is as big as it is on paper, with a page break after it, and the printed page
+// the paper's size with no margins. so a browser's print makes the same pages
+// the pngs and svgs are, without rat having to write pdfs
+pub fn rasterize_print_html(args: RasterizeArgs, title: &str) -> String {
+    let inches = |pixels: f32| pixels / PIXELS_PER_INCH;
+    let title = title
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+
+    let mut html = format!(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>{}</title>\n\
+         <style>\n\
+         @page {{ size: {}in {}in; margin: 0; }}\n\
+         html, body {{ margin: 0; padding: 0; }}\n\
+         .page {{ overflow: hidden; break-after: page; page-break-after: always; }}\n\
+         .page:last-child {{ break-after: auto; page-break-after: auto; }}\n\
+         .page svg {{ display: block; }}\n\
+         </style>\n\
+         </head>\n\
+         <body>\n",
+        title,
+        inches(args.paper_width_pixels),
+        inches(args.paper_height_pixels),
     );
 
-    let (scaled_image_width_pixels, scaled_image_height_pixels) =
-        image_scaled_to_fit_on_pages.dimensions();
+    for document in rasterize_svg(args) {
+        html.push_str("<div class=\"page\">\n");
+        html.push_str(&document.to_string());
+        html.push_str("\n</div>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+pub fn rasterize_svg(args: RasterizeArgs) -> PageStream<svg::Document> {
+    if check_image(args.image).is_err() {
+        return PageStream::empty();
+    }
+
+    let args = args.with_draft_overrides().with_palette();
+    let page_limit = args.page_limit();
+
+    if args.layout == Layout::Matrix {
+        // each tile is a single page
+        return PageStream::chain(
+            args.matrix_tiles()
+                .into_iter()
+                .take(page_limit)
+                .enumerate()
+                .map(|(tile, tile_args)| svg_pages(tile_args, tile + 1)),
+        );
+    }
+
+    svg_pages(args, 1)
+}
+
+// an empty svg page of `width_pixels` by `height_pixels`, sized in inches as well,
+// so that it opens and prints at the size it is on paper
+fn svg_page(width_pixels: u32, height_pixels: u32) -> svg::Document {
+    svg::Document::new()
+        .set(
+            "width",
+            format!("{}in", width_pixels as f32 / PIXELS_PER_INCH),
+        )
+        .set(
+            "height",
+            format!("{}in", height_pixels as f32 / PIXELS_PER_INCH),
+        )
+        .set("viewBox", (0, 0, width_pixels, height_pixels))
+}
+
+// the svg pages of a layout other than `Layout::Matrix`, numbered from `first_page`
+fn svg_pages(args: RasterizeArgs, first_page: usize) -> PageStream<svg::Document> {
+    let page_limit = args.page_limit();
+    let border = args.border;
+    let laid_out = LaidOutImage::new(&args);
+
+    let (scaled_image_width_pixels, scaled_image_height_pixels) = laid_out.dimensions();
+
+    // calculate pages, left-right top-bottom
+    let pages = page_regions(&args, scaled_image_width_pixels, scaled_image_height_pixels);
+
+    PageStream::drawn(
+        pages.into_iter().take(page_limit).enumerate(),
+        move |(i, (page_offset_x, page_offset_y, page_width_pixels, page_height_pixels))| {
+            let page_number = first_page + i;
+
+            // each page is its own sub image
+            let page = laid_out.page((
+                page_offset_x,
+                page_offset_y,
+                page_width_pixels,
+                page_height_pixels,
+            ));
+
+            // create a dupe of this page on which we will draw circles
+
+            let mut svg_document = svg_page(page_width_pixels, page_height_pixels);
+
+            // the one shape every grid dot is a copy of
+            if args.pattern == Pattern::Grid {
+                if let Some(symbol) = dot_symbol(args.dot_shape, args.svg_optimization) {
+                    svg_document = svg_document
+                        .set("xmlns:xlink", "http://www.w3.org/1999/xlink")
+                        .add(symbol);
+                }
+            }
+
+            // divide into squares, with each ink of a duotone, tritone or palette in its own group
+            match (args.layer_inks().len(), args.ink_layer) {
+                (ink_count, None) if ink_count > 0 => {
+                    for ink in 0..ink_count {
+                        let mut group =
+                            svg::node::element::Group::new().set("id", format!("ink-{}", ink + 1));
+                        draw_svg_shapes(
+                            &mut group,
+                            &page,
+                            (page_offset_x, page_offset_y),
+                            page_number,
+                            &args.ink_layer(ink),
+                        );
+                        svg_document = svg_document.add(group);
+                    }
+                }
+                _ => draw_svg_shapes(
+                    &mut svg_document,
+                    &page,
+                    (page_offset_x, page_offset_y),
+                    page_number,
+                    &args,
+                ),
+            }
+
+            draw_border_on_svg_page(
+                svg_document,
+                border,
+                (scaled_image_width_pixels, scaled_image_height_pixels),
+                (page_offset_x, page_offset_y),
+            )
+        },
+    )
+}
+
+// draws the page's shapes into `parent`, the document itself or one ink's group
+fn draw_svg_shapes<N: svg::Node>(
+    parent: &mut N,
+    page: &PageView,
+    (page_offset_x, page_offset_y): (u32, u32),
+    page_number: usize,
+    args: &RasterizeArgs,
+) {
+    // divide into squares
+    match args.pattern {
+        Pattern::Grid => {
+            let optimization = args.svg_optimization;
+            let paint = if args.dot_shape == DotShape::Dash {
+                "stroke"
+            } else {
+                "fill"
+            };
+            let mut groups = ColorGroups::new(paint);
+
+            for dot in in_row_order(grid_dots(page, args), args, |dot| dot.center, |_| {}) {
+                if !args.draws(dot.color) {
+                    continue;
+                }
+                if optimization.enabled && optimization.round(dot.radius) <= 0.0 {
+                    continue;
+                }
+
+                // write the sampling as a shape to the target page
+                // <circle cx="50" cy="50" r="50"/>
+                let mut shape = svg_dot(&dot, args.dot_shape, optimization);
+
+                if args.dot_metadata {
+                    with_dot_metadata(&mut shape, dot.brightness, dot.cell, page_number);
+                }
+
+                if optimization.enabled {
+                    groups.add(dot.color, shape);
+                } else {
+                    parent.append(shape);
+                }
+            }
+
+            groups.append_to(parent);
+        }
+        Pattern::Stipple => {
+            let optimization = args.svg_optimization;
+            let round = |value: f32| optimization.round(value);
+            let mut groups = ColorGroups::new("fill");
+            let (min_distance, stipple_radius) =
+                stipple_spacing(args.square_size, args.max_radius_percentage);
+
+            for (x, y) in in_row_order(
+                stipple_points(page, page_offset_x, page_offset_y, min_distance, args.luma),
+                args,
+                |point| *point,
+                |_| {},
+            ) {
+                let pixel = page.get_pixel(x as u32, y as u32);
+                let color = dot_color(args, pixel, brightness(pixel, args.luma));
+                if !args.draws(color) {
+                    continue;
+                }
+
+                let mut circle = svg::node::element::Circle::new()
+                    .set("cx", round(x))
+                    .set("cy", round(y))
+                    .set("r", round(stipple_radius));
+
+                if !optimization.enabled {
+                    paint_shape(&mut circle, "fill", color);
+                }
+
+                if args.dot_metadata {
+                    // the square the stipple falls in
+                    let cell = ((x / args.square_size) as u32, (y / args.square_size) as u32);
+                    with_dot_metadata(&mut circle, brightness(pixel, args.luma), cell, page_number);
+                }
+
+                if optimization.enabled {
+                    groups.add(color, circle);
+                } else {
+                    parent.append(circle);
+                }
+            }
+
+            groups.append_to(parent);
+        }
+        Pattern::Lines => {
+            for segment in in_row_order(
+                line_segments(page, args),
+                args,
+                |segment| segment.from,
+                LineSegment::reverse,
+            ) {
+                if !args.draws(segment.color) {
+                    continue;
+                }
+
+                let round = |value: f32| args.svg_optimization.round(value);
+                let mut line = svg::node::element::Line::new()
+                    .set("x1", round(segment.from.0))
+                    .set("y1", round(segment.from.1))
+                    .set("x2", round(segment.to.0))
+                    .set("y2", round(segment.to.1))
+                    .set("stroke", Color::from_rgba(segment.color).to_string())
+                    .set("stroke-width", segment.width)
+                    .set("stroke-linecap", "round");
+
+                if segment.color[3] < 255 {
+                    line = line.set("stroke-opacity", segment.color[3] as f32 / 255.0);
+                }
+
+                parent.append(line);
+            }
+        }
+        Pattern::Waves | Pattern::Spiral => {
+            let waves = if args.pattern == Pattern::Spiral {
+                vec![spiral(page, args)]
+            } else {
+                waves(page, args)
+            };
+
+            for wave in in_row_order(waves, args, Wave::start, Wave::reverse) {
+                if !args.draws(wave.color) {
+                    continue;
+                }
+
+                let mut data = svg::node::element::path::Data::new();
+                for (i, point) in wave.points.iter().enumerate() {
+                    let point = args.svg_optimization.round_point(*point);
+                    data = if i == 0 {
+                        data.move_to(point)
+                    } else {
+                        data.line_to(point)
+                    };
+                }
+
+                let mut path = svg::node::element::Path::new()
+                    .set("d", data)
+                    .set("fill", "none")
+                    .set("stroke", Color::from_rgba(wave.color).to_string())
+                    .set("stroke-width", wave.width)
+                    .set("stroke-linejoin", "round");
+
+                if wave.color[3] < 255 {
+                    path = path.set("stroke-opacity", wave.color[3] as f32 / 255.0);
+                }
+
+                parent.append(path);
+            }
+        }
+        Pattern::Crosshatch => {
+            for hatch in in_row_order(hatches(page, args), args, Hatch::start, Hatch::reverse) {
+                if !args.draws(hatch.color) {
+                    continue;
+                }
+
+                let mut data = svg::node::element::path::Data::new();
+                for (from, to) in hatch.lines {
+                    data = data
+                        .move_to(args.svg_optimization.round_point(from))
+                        .line_to(args.svg_optimization.round_point(to));
+                }
+
+                let mut path = svg::node::element::Path::new()
+                    .set("d", data)
+                    .set("fill", "none")
+                    .set("stroke", Color::from_rgba(hatch.color).to_string())
+                    .set("stroke-width", hatch.width)
+                    .set("stroke-linecap", "round");
+
+                if hatch.color[3] < 255 {
+                    path = path.set("stroke-opacity", hatch.color[3] as f32 / 255.0);
+                }
+
+                parent.append(path);
+            }
+        }
+    }
+
+    let edges = page_edges(page, args);
+    if !edges.is_empty() {
+        let color = edge_color(args);
+
+        let mut data = svg::node::element::path::Data::new();
+        for stroke in edges {
+            for (i, point) in stroke.into_iter().enumerate() {
+                let point = args.svg_optimization.round_point(point);
+                data = if i == 0 {
+                    data.move_to(point)
+                } else {
+                    data.line_to(point)
+                };
+            }
+        }
+
+        let mut path = svg::node::element::Path::new()
+            .set("d", data)
+            .set("fill", "none")
+            .set("stroke", Color::from_rgba(color).to_string())
+            .set("stroke-width", args.edges.width)
+            .set("stroke-linecap", "round")
+            .set("stroke-linejoin", "round");
+
+        if color[3] < 255 {
+            path = path.set("stroke-opacity", color[3] as f32 / 255.0);
+        }
+
+        parent.append(path);
+    }
+}
+
+// a grid dot as an svg element of its shape, turned to its angle.
+// optimized dots are left unpainted, for the group they go in to paint
+fn svg_dot(
+    dot: &Dot,
+    shape: DotShape,
+    optimization: SvgOptimization,
+) -> svg::node::element::Element {
+    let round = |value: f32| optimization.round(value);
+    let (half_length, half_width) = shape.half_extents(dot.radius);
+    let rotate = format!(
+        "rotate({} {} {})",
+        round(dot.angle.to_degrees()),
+        round(dot.center.0),
+        round(dot.center.1)
+    );
+
+    // placed copies of the shape's symbol, stretched and turned to fit
+    if dot_symbol(shape, optimization).is_some() {
+        return svg::node::element::Use::new()
+            .set("xlink:href", format!("#{}", DOT_SYMBOL_ID))
+            .set(
+                "transform",
+                format!(
+                    "translate({} {}) rotate({}) scale({} {})",
+                    round(dot.center.0),
+                    round(dot.center.1),
+                    round(dot.angle.to_degrees()),
+                    round(half_length),
+                    round(half_width)
+                ),
+            )
+            .into();
+    }
+
+    let mut element: svg::node::element::Element = match shape {
+        DotShape::Circle => svg::node::element::Circle::new()
+            .set("cx", round(dot.center.0))
+            .set("cy", round(dot.center.1))
+            .set("r", round(dot.radius))
+            .into(),
+        DotShape::Square => svg::node::element::Rectangle::new()
+            .set("x", round(dot.center.0 - half_length))
+            .set("y", round(dot.center.1 - half_width))
+            .set("width", round(half_length * 2.0))
+            .set("height", round(half_width * 2.0))
+            .set("transform", rotate)
+            .into(),
+        DotShape::Ellipse => svg::node::element::Ellipse::new()
+            .set("cx", round(dot.center.0))
+            .set("cy", round(dot.center.1))
+            .set("rx", round(half_length))
+            .set("ry", round(half_width))
+            .set("transform", rotate)
+            .into(),
+        DotShape::Dash => {
+            let (from, to, width) = dot.dash();
+            svg::node::element::Line::new()
+                .set("x1", round(from.0))
+                .set("y1", round(from.1))
+                .set("x2", round(to.0))
+                .set("y2", round(to.1))
+                .set("stroke-width", round(width))
+                .set("stroke-linecap", "round")
+                .into()
+        }
+    };
+
+    if !optimization.enabled {
+        let paint = if shape == DotShape::Dash {
+            "stroke"
+        } else {
+            "fill"
+        };
+        paint_shape(&mut element, paint, dot.color);
+    }
+
+    element
+}
+
+const DOT_SYMBOL_ID: &str = "dot";
+
+// optimized squares and ellipses are each drawn once, two units across,
+// as a symbol for every dot to be a copy of. circles and dashes are
+// as small as they are going to get, so they are drawn as they are
+fn dot_symbol(
+    shape: DotShape,
+    optimization: SvgOptimization,
+) -> Option<svg::node::element::Symbol> {
+    if !optimization.enabled {
+        return None;
+    }
+
+    let symbol = svg::node::element::Symbol::new()
+        .set("id", DOT_SYMBOL_ID)
+        .set("overflow", "visible");
+
+    match shape {
+        DotShape::Square => Some(
+            symbol.add(
+                svg::node::element::Rectangle::new()
+                    .set("x", -1)
+                    .set("y", -1)
+                    .set("width", 2)
+                    .set("height", 2),
+            ),
+        ),
+        DotShape::Ellipse => Some(symbol.add(svg::node::element::Circle::new().set("r", 1))),
+        DotShape::Circle | DotShape::Dash => None,
+    }
+}
+
+// `paint` is the attribute the color goes in, "fill" or "stroke"
+fn paint_shape<N: svg::Node>(shape: &mut N, paint: &str, color: Rgba<u8>) {
+    shape.assign(paint, Color::from_rgba(color).to_string());
+    if color[3] < 255 {
+        shape.assign(format!("{}-opacity", paint), color[3] as f32 / 255.0);
+    }
+}
+
+// shapes of the same color, gathered into one group that paints them all,
+// so that each shape doesn't have to repeat its color. a color with only one
+// shape isn't worth a group, so that shape is painted itself. shapes come out
+// in the order their colors first turn up, so shapes of different colors can
+// end up stacked differently than they were drawn
+struct ColorGroups {
+    paint: &'static str,
+    indices: HashMap<[u8; 4], usize>,
+    colors: Vec<(Rgba<u8>, Vec<svg::node::element::Element>)>,
+}
+
+impl ColorGroups {
+    fn new(paint: &'static str) -> Self {
+        ColorGroups {
+            paint,
+            indices: HashMap::new(),
+            colors: vec![],
+        }
+    }
+
+    // `shape` unpainted
+    fn add(&mut self, color: Rgba<u8>, shape: impl Into<svg::node::element::Element>) {
+        let colors = &mut self.colors;
+        let index = *self.indices.entry(color.0).or_insert_with(|| {
+            colors.push((color, vec![]));
+            colors.len() - 1
+        });
+
+        colors[index].1.push(shape.into());
+    }
+
+    fn append_to<N: svg::Node>(self, parent: &mut N) {
+        for (color, mut shapes) in self.colors {
+            if shapes.len() == 1 {
+                let mut shape = shapes.remove(0);
+                paint_shape(&mut shape, self.paint, color);
+                parent.append(shape);
+                continue;
+            }
+
+            let mut group = svg::node::element::Group::new();
+            paint_shape(&mut group, self.paint, color);
+            for shape in shapes {
+                group.append(shape);
+            }
+            parent.append(group);
+        }
+    }
+}
+
+fn with_dot_metadata<N: svg::Node>(
+    dot: &mut N,
+    brightness: f32,
+    (column, row): (u32, u32),
+    page_number: usize,
+) {
+    dot.assign("data-brightness", format!("{:.3}", brightness));
+    dot.assign("data-cell", format!("{},{}", column, row));
+    dot.assign("data-page", page_number);
+}
+
+// each page's shapes as g-code for a pen plotter or engraver, in `RasterizeArgs::row_order`.
+// dots are a single touch of the pen, dashes a single stroke,
+// everything else is drawn as its outline
+pub fn rasterize_gcode(args: RasterizeArgs) -> PageStream<String> {
+    if check_image(args.image).is_err() {
+        return PageStream::empty();
+    }
+
+    let args = args.with_draft_overrides().with_palette();
+    let page_limit = args.page_limit();
+
+    if args.layout == Layout::Matrix {
+        return PageStream::chain(
+            args.matrix_tiles()
+                .into_iter()
+                .take(page_limit)
+                .map(rasterize_gcode),
+        );
+    }
+
+    let laid_out = LaidOutImage::new(&args);
+
+    let (scaled_image_width_pixels, scaled_image_height_pixels) = laid_out.dimensions();
+
+    let pages = page_regions(&args, scaled_image_width_pixels, scaled_image_height_pixels);
+
+    PageStream::drawn(
+        pages.into_iter().take(page_limit),
+        move |(page_offset_x, page_offset_y, page_width_pixels, page_height_pixels)| {
+            let page = laid_out.page((
+                page_offset_x,
+                page_offset_y,
+                page_width_pixels,
+                page_height_pixels,
+            ));
+
+            let strokes = page_strokes(&page, (page_offset_x, page_offset_y), &args);
+
+            gcode(strokes.iter().map(Vec::as_slice), page_height_pixels)
+        },
+    )
+}
+
+// the paths a pen takes to draw a page, one pen down each, in `RasterizeArgs::row_order`
+fn page_strokes(
+    page: &PageView,
+    (page_offset_x, page_offset_y): (u32, u32),
+    args: &RasterizeArgs,
+) -> Vec<Vec<(f32, f32)>> {
+    let mut strokes: Vec<Vec<(f32, f32)>> = match args.pattern {
+        Pattern::Grid => in_row_order(grid_dots(page, args), args, |dot| dot.center, |_| {})
+            .into_iter()
+            .filter(|dot| args.draws(dot.color))
+            .map(|dot| match args.dot_shape {
+                DotShape::Dash => {
+                    let (from, to, _) = dot.dash();
+                    vec![from, to]
+                }
+                _ => vec![dot.center],
+            })
+            .collect(),
+        Pattern::Stipple => {
+            let (min_distance, _) = stipple_spacing(args.square_size, args.max_radius_percentage);
+
+            in_row_order(
+                stipple_points(page, page_offset_x, page_offset_y, min_distance, args.luma),
+                args,
+                |point| *point,
+                |_| {},
+            )
+            .into_iter()
+            .filter(|(x, y)| {
+                let pixel = page.get_pixel(*x as u32, *y as u32);
+                args.draws(dot_color(args, pixel, brightness(pixel, args.luma)))
+            })
+            .map(|point| vec![point])
+            .collect()
+        }
+        Pattern::Lines => in_row_order(
+            line_segments(page, args),
+            args,
+            |segment| segment.from,
+            LineSegment::reverse,
+        )
+        .into_iter()
+        .filter(|segment| args.draws(segment.color))
+        .map(|segment| vec![segment.from, segment.to])
+        .collect(),
+        Pattern::Waves | Pattern::Spiral => {
+            let waves = if args.pattern == Pattern::Spiral {
+                vec![spiral(page, args)]
+            } else {
+                waves(page, args)
+            };
+
+            in_row_order(waves, args, Wave::start, Wave::reverse)
+                .into_iter()
+                .filter(|wave| args.draws(wave.color))
+                .map(|wave| wave.points)
+                .collect()
+        }
+        Pattern::Crosshatch => {
+            in_row_order(hatches(page, args), args, Hatch::start, Hatch::reverse)
+                .into_iter()
+                .filter(|hatch| args.draws(hatch.color))
+                .flat_map(|hatch| hatch.lines)
+                .map(|(from, to)| vec![from, to])
+                .collect()
+        }
+    };
+
+    // the edge layer goes on last, as it does in the other backends
+    strokes.extend(page_edges(page, args));
+    strokes
+}
+
+// millimeters from the bottom left corner of the page,
+// lifting the pen between strokes
+fn gcode<'s>(strokes: impl Iterator<Item = &'s [(f32, f32)]>, page_height_pixels: u32) -> String {
+    let to_millimeters = |pixels: f32| pixels / PIXELS_PER_INCH * MILLIMETERS_PER_INCH;
+    let page_height = page_height_pixels as f32;
+
+    let mut gcode = vec![
+        "G21 ; millimeters".to_string(),
+        "G90 ; absolute positioning".to_string(),
+        format!("G0 Z{:.2}", GCODE_PEN_UP_Z),
+    ];
+
+    for stroke in strokes.filter(|stroke| !stroke.is_empty()) {
+        for (i, (x, y)) in stroke.iter().enumerate() {
+            let (x, y) = (to_millimeters(*x), to_millimeters(page_height - y));
+
+            if i == 0 {
+                gcode.push(format!("G0 X{:.2} Y{:.2}", x, y));
+                gcode.push(format!(
+                    "G1 Z{:.2} F{:.0}",
+                    GCODE_PEN_DOWN_Z, GCODE_FEED_RATE
+                ));
+            } else {
+                gcode.push(format!("G1 X{:.2} Y{:.2}", x, y));
+            }
+        }
+
+        gcode.push(format!("G0 Z{:.2}", GCODE_PEN_UP_Z));
+    }
+
+    gcode.push("G0 X0 Y0".to_string());
+    gcode.push(String::new());
+
+    gcode.join("\n")
+}
+
+// each page as an svg for laser cutters and engravers, the way lightburn and inkscape
+// take them: sized in millimeters, with every shape as an outline to follow rather
+// than a fill, and the shapes of each color in their own group, which those programs
+// make into a layer. outlines are brought in by half of `Laser::kerf`, so holes come
+// out the size of the dots once the beam has burned away its width.
+// lines, waves and hatches are strokes to engrave, and are left as they are
+pub fn rasterize_laser_svg(args: RasterizeArgs, laser: Laser) -> PageStream<svg::Document> {
+    if check_image(args.image).is_err() {
+        return PageStream::empty();
+    }
+
+    let args = args.with_draft_overrides().with_palette();
+    let page_limit = args.page_limit();
+
+    if args.layout == Layout::Matrix {
+        return PageStream::chain(
+            args.matrix_tiles()
+                .into_iter()
+                .take(page_limit)
+                .map(move |tile_args| rasterize_laser_svg(tile_args, laser)),
+        );
+    }
+
+    let border = args.border;
+    let laid_out = LaidOutImage::new(&args);
+
+    let (scaled_image_width_pixels, scaled_image_height_pixels) = laid_out.dimensions();
+
+    let pages = page_regions(&args, scaled_image_width_pixels, scaled_image_height_pixels);
+
+    PageStream::drawn(
+        pages.into_iter().take(page_limit),
+        move |(page_offset_x, page_offset_y, page_width_pixels, page_height_pixels)| {
+            let page = laid_out.page((
+                page_offset_x,
+                page_offset_y,
+                page_width_pixels,
+                page_height_pixels,
+            ));
+
+            let to_millimeters = |pixels: f32| pixels / PIXELS_PER_INCH * MILLIMETERS_PER_INCH;
+            let to_pixels = |millimeters: f32| millimeters / MILLIMETERS_PER_INCH * PIXELS_PER_INCH;
+            let half_kerf = to_pixels(laser.kerf / 2.0);
+            let hairline = to_pixels(LASER_HAIRLINE_MILLIMETERS);
+
+            let offset = (page_offset_x, page_offset_y);
+            let scaled_image_dimensions = (scaled_image_width_pixels, scaled_image_height_pixels);
+            let marks = page_marks(&page, offset, &args)
+                .into_iter()
+                .chain(edge_marks(&page, &args))
+                .chain(border_marks(border, scaled_image_dimensions, offset));
+
+            // by color, in the order each color is first drawn
+            let mut groups: Vec<(Color, svg::node::element::Group)> = vec![];
+            for mark in marks {
+                let (color, element): (_, svg::node::element::Element) = match mark {
+                    Mark::Circle {
+                        center,
+                        radius,
+                        color,
+                    } => {
+                        if radius <= half_kerf {
+                            continue;
+                        }
+
+                        let circle = svg::node::element::Circle::new()
+                            .set("cx", center.0)
+                            .set("cy", center.1)
+                            .set("r", radius - half_kerf);
+                        (color, circle.into())
+                    }
+                    Mark::Fill { outline, color } => {
+                        let outline = inset_outline(&outline, half_kerf);
+                        if outline.is_empty() {
+                            continue;
+                        }
+
+                        let points = outline
+                            .iter()
+                            .map(|(x, y)| format!("{},{}", x, y))
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        let polygon = svg::node::element::Polygon::new().set("points", points);
+                        (color, polygon.into())
+                    }
+                    Mark::Stroke {
+                        points,
+                        width,
+                        color,
+                    } => {
+                        let mut data = svg::node::element::path::Data::new();
+                        for (i, point) in points.iter().enumerate() {
+                            data = if i == 0 {
+                                data.move_to(*point)
+                            } else {
+                                data.line_to(*point)
+                            };
+                        }
+
+                        let path = svg::node::element::Path::new()
+                            .set("d", data)
+                            .set("stroke-width", width)
+                            .set("stroke-linecap", "round")
+                            .set("stroke-linejoin", "round");
+                        (color, path.into())
+                    }
+                };
+
+                let color = Color::from_rgba(color);
+                match groups
+                    .iter_mut()
+                    .find(|(group_color, _)| *group_color == color)
+                {
+                    Some((_, group)) => group.append(element),
+                    None => {
+                        let mut group = svg::node::element::Group::new()
+                            .set(
+                                "id",
+                                format!("layer-{}", color.to_string().trim_start_matches('#')),
+                            )
+                            .set("fill", "none")
+                            .set("stroke", color.to_string())
+                            .set("stroke-width", hairline);
+                        group.append(element);
+                        groups.push((color, group));
+                    }
+                }
+            }
+
+            let mut svg_document = svg::Document::new()
+                .set(
+                    "width",
+                    format!("{}mm", to_millimeters(page_width_pixels as f32)),
+                )
+                .set(
+                    "height",
+                    format!("{}mm", to_millimeters(page_height_pixels as f32)),
+                )
+                .set("viewBox", (0, 0, page_width_pixels, page_height_pixels));
+            for (_, group) in groups {
+                svg_document = svg_document.add(group);
+            }
+
+            svg_document
+        },
+    )
+}
+
+// a convex outline brought in towards its middle by `distance`, or nothing if that leaves nothing
+fn inset_outline(outline: &[(f32, f32)], distance: f32) -> Vec<(f32, f32)> {
+    if outline.is_empty() {
+        return vec![];
+    }
+
+    let count = outline.len() as f32;
+    let middle = (
+        outline.iter().map(|(x, _)| x).sum::<f32>() / count,
+        outline.iter().map(|(_, y)| y).sum::<f32>() / count,
+    );
+
+    let mut inset = vec![];
+    for (x, y) in outline {
+        let (dx, dy) = (x - middle.0, y - middle.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length <= distance {
+            return vec![];
+        }
+
+        let scale = (length - distance) / length;
+        inset.push((middle.0 + dx * scale, middle.1 + dy * scale));
+    }
+
+    inset
+}
+
+// each page as encapsulated postscript, for print shops and rips that would rather
+// not take svg. the page's bounding box is in points, and everything is drawn in
+// page pixels, like svg. postscript has no transparency, so translucent colors
+// are mixed with the white of the paper instead
+pub fn rasterize_eps(args: RasterizeArgs) -> PageStream<String> {
+    if check_image(args.image).is_err() {
+        return PageStream::empty();
+    }
+
+    let args = args.with_draft_overrides().with_palette();
+    let page_limit = args.page_limit();
+
+    if args.layout == Layout::Matrix {
+        return PageStream::chain(
+            args.matrix_tiles()
+                .into_iter()
+                .take(page_limit)
+                .map(rasterize_eps),
+        );
+    }
+
+    let border = args.border;
+    let laid_out = LaidOutImage::new(&args);
+
+    let (scaled_image_width_pixels, scaled_image_height_pixels) = laid_out.dimensions();
+
+    let pages = page_regions(&args, scaled_image_width_pixels, scaled_image_height_pixels);
+
+    PageStream::drawn(
+        pages.into_iter().take(page_limit),
+        move |(page_offset_x, page_offset_y, page_width_pixels, page_height_pixels)| {
+            let page = laid_out.page((
+                page_offset_x,
+                page_offset_y,
+                page_width_pixels,
+                page_height_pixels,
+            ));
+
+            let mut eps = Eps::new(page_width_pixels, page_height_pixels);
+            let offset = (page_offset_x, page_offset_y);
+            let scaled_image_dimensions = (scaled_image_width_pixels, scaled_image_height_pixels);
+
+            for mark in page_marks(&page, offset, &args)
+                .iter()
+                .chain(&edge_marks(&page, &args))
+                .chain(&border_marks(border, scaled_image_dimensions, offset))
+            {
+                eps.mark(mark);
+            }
+
+            eps.finish()
+        },
+    )
+}
+
+// each page as a dxf drawing in millimeters, for cam software driving cnc routers and
+// laser cutters. dots are circles, and everything else is the center line of its
+// stroke or the outline of its fill. each ink of a duotone, tritone or palette is on
+// its own layer, as are the edges and the border, so they can be cut or skipped separately
+pub fn rasterize_dxf(args: RasterizeArgs) -> PageStream<String> {
+    if check_image(args.image).is_err() {
+        return PageStream::empty();
+    }
+
+    let args = args.with_draft_overrides().with_palette();
+    let page_limit = args.page_limit();
+
+    if args.layout == Layout::Matrix {
+        return PageStream::chain(
+            args.matrix_tiles()
+                .into_iter()
+                .take(page_limit)
+                .map(rasterize_dxf),
+        );
+    }
+
+    let border = args.border;
+    let laid_out = LaidOutImage::new(&args);
+
+    let (scaled_image_width_pixels, scaled_image_height_pixels) = laid_out.dimensions();
+
+    let pages = page_regions(&args, scaled_image_width_pixels, scaled_image_height_pixels);
+
+    PageStream::drawn(
+        pages.into_iter().take(page_limit),
+        move |(page_offset_x, page_offset_y, page_width_pixels, page_height_pixels)| {
+            let page = laid_out.page((
+                page_offset_x,
+                page_offset_y,
+                page_width_pixels,
+                page_height_pixels,
+            ));
+
+            let mut dxf = Dxf::new(page_height_pixels);
+            let offset = (page_offset_x, page_offset_y);
+            let scaled_image_dimensions = (scaled_image_width_pixels, scaled_image_height_pixels);
+
+            match (args.layer_inks().len(), args.ink_layer) {
+                (ink_count, None) if ink_count > 0 => {
+                    for ink in 0..ink_count {
+                        let layer = format!("ink-{}", ink + 1);
+                        for mark in page_marks(&page, offset, &args.ink_layer(ink)) {
+                            dxf.mark(&layer, &mark);
+                        }
+                    }
+                }
+                _ => {
+                    for mark in page_marks(&page, offset, &args) {
+                        dxf.mark("pattern", &mark);
+                    }
+                }
+            }
+            for mark in edge_marks(&page, &args) {
+                dxf.mark("edges", &mark);
+            }
+            for mark in border_marks(border, scaled_image_dimensions, offset) {
+                dxf.mark("border", &mark);
+            }
+
+            dxf.finish()
+        },
+    )
+}
+
+// one page of dxf, as its entities are added. it keeps to what ac1009 (autocad r12)
+// has, which every cam program reads, and says that its units are millimeters
+struct Dxf {
+    page_height_pixels: u32,
+    layers: Vec<String>,
+    entities: Vec<String>,
+}
+
+impl Dxf {
+    fn new(page_height_pixels: u32) -> Self {
+        Dxf {
+            page_height_pixels,
+            layers: vec![],
+            entities: vec![],
+        }
+    }
+
+    // a group code and its value, on lines of their own
+    fn pair(lines: &mut Vec<String>, code: u16, value: impl fmt::Display) {
+        lines.push(code.to_string());
+        lines.push(value.to_string());
+    }
+
+    // millimeters from the bottom left corner of the page
+    fn point(&mut self, (x, y): (f32, f32), x_code: u16) {
+        let to_millimeters = |pixels: f32| pixels / PIXELS_PER_INCH * MILLIMETERS_PER_INCH;
+        let y = self.page_height_pixels as f32 - y;
+
+        Dxf::pair(
+            &mut self.entities,
+            x_code,
+            format!("{:.3}", to_millimeters(x)),
+        );
+        Dxf::pair(
+            &mut self.entities,
+            x_code + 10,
+            format!("{:.3}", to_millimeters(y)),
+        );
+    }
+
+    fn entity(&mut self, kind: &str, layer: &str) {
+        if !self.layers.iter().any(|known| known == layer) {
+            self.layers.push(layer.to_string());
+        }
+
+        Dxf::pair(&mut self.entities, 0, kind);
+        Dxf::pair(&mut self.entities, 8, layer);
+    }
+
+    fn circle(&mut self, layer: &str, center: (f32, f32), radius: f32) {
+        self.entity("CIRCLE", layer);
+        self.point(center, 10);
+        Dxf::pair(
+            &mut self.entities,
+            40,
+            format!("{:.3}", radius / PIXELS_PER_INCH * MILLIMETERS_PER_INCH),
+        );
+    }
+
+    fn polyline(&mut self, layer: &str, points: &[(f32, f32)], closed: bool) {
+        if let [from, to] = points {
+            if !closed {
+                self.entity("LINE", layer);
+                self.point(*from, 10);
+                self.point(*to, 11);
+                return;
+            }
+        }
+
+        self.entity("POLYLINE", layer);
+        // vertices follow
+        Dxf::pair(&mut self.entities, 66, 1);
+        Dxf::pair(&mut self.entities, 70, if closed { 1 } else { 0 });
+        for point in points {
+            self.entity("VERTEX", layer);
+            self.point(*point, 10);
+        }
+        self.entity("SEQEND", layer);
+    }
+
+    fn mark(&mut self, layer: &str, mark: &Mark) {
+        match mark {
+            Mark::Circle { center, radius, .. } => self.circle(layer, *center, *radius),
+            Mark::Fill { outline, .. } => {
+                if !outline.is_empty() {
+                    self.polyline(layer, outline, true);
+                }
+            }
+            Mark::Stroke { points, width, .. } => match points.as_slice() {
+                [] => {}
+                // a single point is a dot as wide as the stroke
+                [point] => self.circle(layer, *point, width / 2.0),
+                points => self.polyline(layer, points, false),
+            },
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut lines = vec![];
+
+        Dxf::pair(&mut lines, 0, "SECTION");
+        Dxf::pair(&mut lines, 2, "HEADER");
+        Dxf::pair(&mut lines, 9, "$ACADVER");
+        // R12 has no header variable for units, so the coordinates, in millimeters,
+        // are unitless to whatever reads them. R12 is what plotters and cutters all read
+        Dxf::pair(&mut lines, 1, "AC1009");
+        Dxf::pair(&mut lines, 0, "ENDSEC");
+
+        Dxf::pair(&mut lines, 0, "SECTION");
+        Dxf::pair(&mut lines, 2, "TABLES");
+        Dxf::pair(&mut lines, 0, "TABLE");
+        Dxf::pair(&mut lines, 2, "LAYER");
+        Dxf::pair(&mut lines, 70, self.layers.len());
+        for layer in &self.layers {
+            Dxf::pair(&mut lines, 0, "LAYER");
+            Dxf::pair(&mut lines, 2, layer);
+            Dxf::pair(&mut lines, 70, 0);
+            // black on white, white on black
+            Dxf::pair(&mut lines, 62, 7);
+            Dxf::pair(&mut lines, 6, "CONTINUOUS");
+        }
+        Dxf::pair(&mut lines, 0, "ENDTAB");
+        Dxf::pair(&mut lines, 0, "ENDSEC");
+
+        Dxf::pair(&mut lines, 0, "SECTION");
+        Dxf::pair(&mut lines, 2, "ENTITIES");
+        lines.extend(self.entities);
+        Dxf::pair(&mut lines, 0, "ENDSEC");
+        Dxf::pair(&mut lines, 0, "EOF");
+        lines.push(String::new());
+
+        lines.join("\n")
+    }
+}
+
+// each page as a binary stl of a relief, for cnc carving or 3d printing: a plate
+// the size of the page, with the pattern standing up from it. grid dots are as tall
+// as their squares are dark, and every other shape is the full height of the relief
+pub fn rasterize_stl(args: RasterizeArgs, relief: Relief) -> PageStream<Vec<u8>> {
+    if check_image(args.image).is_err() {
+        return PageStream::empty();
+    }
+
+    let args = args.with_draft_overrides().with_palette();
+    let page_limit = args.page_limit();
+
+    if args.layout == Layout::Matrix {
+        return PageStream::chain(
+            args.matrix_tiles()
+                .into_iter()
+                .take(page_limit)
+                .map(move |tile_args| rasterize_stl(tile_args, relief)),
+        );
+    }
+
+    let border = args.border;
+    let laid_out = LaidOutImage::new(&args);
+
+    let (scaled_image_width_pixels, scaled_image_height_pixels) = laid_out.dimensions();
+
+    let pages = page_regions(&args, scaled_image_width_pixels, scaled_image_height_pixels);
+
+    PageStream::drawn(
+        pages.into_iter().take(page_limit),
+        move |(page_offset_x, page_offset_y, page_width_pixels, page_height_pixels)| {
+            let page = laid_out.page((
+                page_offset_x,
+                page_offset_y,
+                page_width_pixels,
+                page_height_pixels,
+            ));
+
+            let to_millimeters = |pixels: f32| pixels / PIXELS_PER_INCH * MILLIMETERS_PER_INCH;
+            let page_width = to_millimeters(page_width_pixels as f32);
+            let page_height = to_millimeters(page_height_pixels as f32);
+
+            let mut mesh = Mesh::new();
+            mesh.prism(
+                &[
+                    (0.0, 0.0),
+                    (page_width, 0.0),
+                    (page_width, page_height),
+                    (0.0, page_height),
+                ],
+                0.0,
+                relief.base,
+            );
+
+            // millimeters from the bottom left corner of the page
+            let mut raise = |outline: Vec<(f32, f32)>, height: f32| {
+                if height <= 0.0 {
+                    return;
+                }
+
+                let outline = outline
+                    .into_iter()
+                    .map(|(x, y)| (to_millimeters(x), page_height - to_millimeters(y)))
+                    .collect::<Vec<_>>();
+                let top = relief.base + height;
+                match relief.shape {
+                    ReliefShape::Cylinder => mesh.prism(&outline, relief.base, top),
+                    ReliefShape::Cone => mesh.pyramid(&outline, relief.base, top),
+                }
+            };
+
+            let offset = (page_offset_x, page_offset_y);
+            let scaled_image_dimensions = (scaled_image_width_pixels, scaled_image_height_pixels);
+
+            // grid dots stand as tall as their squares are dark, so they aren't marks
+            let mut marks = vec![];
+            if args.pattern == Pattern::Grid {
+                for dot in grid_dots(&page, &args) {
+                    if !args.draws(dot.color) {
+                        continue;
+                    }
+
+                    let outline = match args.dot_shape {
+                        DotShape::Circle => {
+                            mesh::circle_outline(dot.center, dot.radius, RELIEF_CIRCLE_SEGMENTS)
+                        }
+                        DotShape::Dash => {
+                            let (from, to, width) = dot.dash();
+                            mesh::segment_outline(from, to, width)
+                        }
+                        shape => dot.outline(shape),
+                    };
+                    raise(
+                        outline,
+                        relief.height * (1.0 - dot.brightness).clamp(0.0, 1.0),
+                    );
+                }
+            } else {
+                marks = page_marks(&page, offset, &args);
+            }
+            marks.extend(edge_marks(&page, &args));
+            marks.extend(border_marks(border, scaled_image_dimensions, offset));
+
+            for mark in marks {
+                match mark {
+                    Mark::Circle { center, radius, .. } => raise(
+                        mesh::circle_outline(center, radius, RELIEF_CIRCLE_SEGMENTS),
+                        relief.height,
+                    ),
+                    Mark::Fill { outline, .. } => raise(outline, relief.height),
+                    Mark::Stroke { points, width, .. } => {
+                        if let [point] = points.as_slice() {
+                            raise(
+                                mesh::circle_outline(*point, width / 2.0, RELIEF_CIRCLE_SEGMENTS),
+                                relief.height,
+                            );
+                        }
+                        for segment in points.windows(2) {
+                            raise(
+                                mesh::segment_outline(segment[0], segment[1], width),
+                                relief.height,
+                            );
+                        }
+                    }
+                }
+            }
+
+            mesh.to_stl()
+        },
+    )
+}
+
+// how many of the grid dots that would have been drawn on every page
+// are left out for being smaller than `RasterizeArgs::min_dot_size`
+pub fn culled_dot_count(args: RasterizeArgs) -> usize {
+    if check_image(args.image).is_err()
+        || args.pattern != Pattern::Grid
+        || !args.min_dot_size.enabled
+    {
+        return 0;
+    }
+
+    let args = args.with_draft_overrides().with_palette();
+    let page_limit = args.page_limit();
+
+    if args.layout == Layout::Matrix {
+        return args
+            .matrix_tiles()
+            .into_iter()
+            .take(page_limit)
+            .map(culled_dot_count)
+            .sum();
+    }
+
+    // every dot, however small, to count the ones that are left out
+    let every_dot_args = RasterizeArgs {
+        min_dot_size: MinDotSize {
+            enabled: false,
+            ..args.min_dot_size
+        },
+        ..args
+    };
+
+    let laid_out = LaidOutImage::new(&args);
+
+    let (scaled_image_width_pixels, scaled_image_height_pixels) = laid_out.dimensions();
+
+    page_regions(&args, scaled_image_width_pixels, scaled_image_height_pixels)
+        .into_iter()
+        .take(page_limit)
+        .map(
+            |(page_offset_x, page_offset_y, page_width_pixels, page_height_pixels)| {
+                let page = laid_out.page((
+                    page_offset_x,
+                    page_offset_y,
+                    page_width_pixels,
+                    page_height_pixels,
+                ));
+
+                grid_dots(&page, &every_dot_args)
+                    .into_iter()
+                    .filter(|dot| args.draws(dot.color) && !args.min_dot_size.keeps(dot.radius))
+                    .count()
+            },
+        )
+        .sum()
+}
+
+// each page's dots as csv, one line each of where its center is and how big and what color
+// it is, for people driving their own tools from the pattern: drilling panels, setting
+// nails for string art. positions are millimeters from the bottom left corner of the page,
+// like g-code, and the dots are in `RasterizeArgs::row_order`. patterns of lines have no dots
+pub fn rasterize_dot_map(args: RasterizeArgs) -> PageStream<String> {
+    if check_image(args.image).is_err() {
+        return PageStream::empty();
+    }
+
+    let args = args.with_draft_overrides().with_palette();
+    let page_limit = args.page_limit();
+
+    if args.layout == Layout::Matrix {
+        return PageStream::chain(
+            args.matrix_tiles()
+                .into_iter()
+                .take(page_limit)
+                .map(rasterize_dot_map),
+        );
+    }
+
+    let laid_out = LaidOutImage::new(&args);
+
+    let (scaled_image_width_pixels, scaled_image_height_pixels) = laid_out.dimensions();
+
+    let pages = page_regions(&args, scaled_image_width_pixels, scaled_image_height_pixels);
+
+    PageStream::drawn(
+        pages.into_iter().take(page_limit),
+        move |(page_offset_x, page_offset_y, page_width_pixels, page_height_pixels)| {
+            let page = laid_out.page((
+                page_offset_x,
+                page_offset_y,
+                page_width_pixels,
+                page_height_pixels,
+            ));
+
+            // grid dots of every shape are measured as the circle they stand in for
+            let dots: Vec<((f32, f32), f32, Rgba<u8>)> = if args.pattern == Pattern::Grid {
+                in_row_order(grid_dots(&page, &args), &args, |dot| dot.center, |_| {})
+                    .into_iter()
+                    .filter(|dot| args.draws(dot.color))
+                    .map(|dot| (dot.center, dot.radius, dot.color))
+                    .collect()
+            } else {
+                page_marks(&page, (page_offset_x, page_offset_y), &args)
+                    .into_iter()
+                    .filter_map(|mark| match mark {
+                        Mark::Circle {
+                            center,
+                            radius,
+                            color,
+                        } => Some((center, radius, color)),
+                        _ => None,
+                    })
+                    .collect()
+            };
+
+            let to_millimeters = |pixels: f32| pixels / PIXELS_PER_INCH * MILLIMETERS_PER_INCH;
+            let mut csv = vec!["x_mm,y_mm,radius_mm,color".to_string()];
+            for ((x, y), radius, color) in dots {
+                csv.push(format!(
+                    "{:.2},{:.2},{:.2},{}",
+                    to_millimeters(x),
+                    to_millimeters(page_height_pixels as f32 - y),
+                    to_millimeters(radius),
+                    Color::from_rgba(color)
+                ));
+            }
+            csv.push(String::new());
+
+            csv.join("\n")
+        },
+    )
+}
+
+// a shape on a page, for the backends that write out outlines
+// rather than svg elements or pixels
+enum Mark {
+    Circle {
+        center: (f32, f32),
+        radius: f32,
+        color: Rgba<u8>,
+    },
+    // a filled polygon
+    Fill {
+        outline: Vec<(f32, f32)>,
+        color: Rgba<u8>,
+    },
+    // a round capped and joined line through `points`
+    Stroke {
+        points: Vec<(f32, f32)>,
+        width: f32,
+        color: Rgba<u8>,
+    },
+}
+
+// the page's pattern, as `draw_svg_shapes` draws it
+fn page_marks(
+    page: &PageView,
+    (page_offset_x, page_offset_y): (u32, u32),
+    args: &RasterizeArgs,
+) -> Vec<Mark> {
+    let mut marks = vec![];
+
+    match args.pattern {
+        Pattern::Grid => {
+            for dot in in_row_order(grid_dots(page, args), args, |dot| dot.center, |_| {}) {
+                if !args.draws(dot.color) {
+                    continue;
+                }
+
+                marks.push(match args.dot_shape {
+                    DotShape::Circle => Mark::Circle {
+                        center: dot.center,
+                        radius: dot.radius,
+                        color: dot.color,
+                    },
+                    DotShape::Dash => {
+                        let (from, to, width) = dot.dash();
+                        Mark::Stroke {
+                            points: vec![from, to],
+                            width,
+                            color: dot.color,
+                        }
+                    }
+                    shape => Mark::Fill {
+                        outline: dot.outline(shape),
+                        color: dot.color,
+                    },
+                });
+            }
+        }
+        Pattern::Stipple => {
+            let (min_distance, stipple_radius) =
+                stipple_spacing(args.square_size, args.max_radius_percentage);
+
+            for (x, y) in in_row_order(
+                stipple_points(page, page_offset_x, page_offset_y, min_distance, args.luma),
+                args,
+                |point| *point,
+                |_| {},
+            ) {
+                let pixel = page.get_pixel(x as u32, y as u32);
+                let color = dot_color(args, pixel, brightness(pixel, args.luma));
+                if args.draws(color) {
+                    marks.push(Mark::Circle {
+                        center: (x, y),
+                        radius: stipple_radius,
+                        color,
+                    });
+                }
+            }
+        }
+        Pattern::Lines => {
+            for segment in in_row_order(
+                line_segments(page, args),
+                args,
+                |segment| segment.from,
+                LineSegment::reverse,
+            ) {
+                if args.draws(segment.color) {
+                    marks.push(Mark::Stroke {
+                        points: vec![segment.from, segment.to],
+                        width: segment.width,
+                        color: segment.color,
+                    });
+                }
+            }
+        }
+        Pattern::Waves | Pattern::Spiral => {
+            let waves = if args.pattern == Pattern::Spiral {
+                vec![spiral(page, args)]
+            } else {
+                waves(page, args)
+            };
+
+            for wave in in_row_order(waves, args, Wave::start, Wave::reverse) {
+                if args.draws(wave.color) {
+                    marks.push(Mark::Stroke {
+                        points: wave.points,
+                        width: wave.width,
+                        color: wave.color,
+                    });
+                }
+            }
+        }
+        Pattern::Crosshatch => {
+            for hatch in in_row_order(hatches(page, args), args, Hatch::start, Hatch::reverse) {
+                if !args.draws(hatch.color) {
+                    continue;
+                }
+
+                for (from, to) in hatch.lines {
+                    marks.push(Mark::Stroke {
+                        points: vec![from, to],
+                        width: hatch.width,
+                        color: hatch.color,
+                    });
+                }
+            }
+        }
+    }
+
+    marks
+}
+
+// the page's edge layer, drawn over the pattern
+fn edge_marks(page: &PageView, args: &RasterizeArgs) -> Vec<Mark> {
+    let color = edge_color(args);
+
+    page_edges(page, args)
+        .into_iter()
+        .map(|points| Mark::Stroke {
+            points,
+            width: args.edges.width,
+            color,
+        })
+        .collect()
+}
+
+// the border's rectangles on one page
+fn border_marks(
+    border: Border,
+    (artwork_width, artwork_height): (u32, u32),
+    (page_offset_x, page_offset_y): (u32, u32),
+) -> Vec<Mark> {
+    let color = Rgba([border.color.r, border.color.g, border.color.b, 255]);
+
+    border
+        .rects(artwork_width, artwork_height)
+        .into_iter()
+        .map(|(x, y, width, height)| {
+            let left = (x - page_offset_x as i32) as f32;
+            let top = (y - page_offset_y as i32) as f32;
+            let (right, bottom) = (left + width as f32, top + height as f32);
+
+            Mark::Fill {
+                outline: vec![(left, top), (right, top), (right, bottom), (left, bottom)],
+                color,
+            }
+        })
+        .collect()
+}
+
+// one page of postscript, as it is drawn
+struct Eps {
+    lines: Vec<String>,
+}
+
+impl Eps {
+    fn new(page_width_pixels: u32, page_height_pixels: u32) -> Self {
+        let points = |pixels: u32| pixels as f32 / PIXELS_PER_INCH * POINTS_PER_INCH;
+        let (width, height) = (points(page_width_pixels), points(page_height_pixels));
+
+        Eps {
+            lines: vec![
+                "%!PS-Adobe-3.0 EPSF-3.0".to_string(),
+                format!("%%BoundingBox: 0 0 {} {}", width.ceil(), height.ceil()),
+                format!("%%HiResBoundingBox: 0 0 {:.2} {:.2}", width, height),
+                "%%Creator: rat".to_string(),
+                "%%Pages: 1".to_string(),
+                "%%EndComments".to_string(),
+                "%%Page: 1 1".to_string(),
+                "gsave".to_string(),
+                // page pixels, from the top left corner like svg
+                format!(
+                    "0 {:.2} translate {} {} scale",
+                    height,
+                    POINTS_PER_INCH / PIXELS_PER_INCH,
+                    -POINTS_PER_INCH / PIXELS_PER_INCH
+                ),
+                "1 setlinecap 1 setlinejoin".to_string(),
+            ],
+        }
+    }
+
+    fn color(&mut self, color: Rgba<u8>) {
+        let alpha = color[3] as f32 / 255.0;
+        let on_paper = |channel: u8| channel as f32 / 255.0 * alpha + (1.0 - alpha);
+
+        self.lines.push(format!(
+            "{:.3} {:.3} {:.3} setrgbcolor",
+            on_paper(color[0]),
+            on_paper(color[1]),
+            on_paper(color[2])
+        ));
+    }
+
+    fn circle(&mut self, (x, y): (f32, f32), radius: f32, color: Rgba<u8>) {
+        self.color(color);
+        self.lines.push(format!(
+            "newpath {:.2} {:.2} {:.2} 0 360 arc fill",
+            x, y, radius
+        ));
+    }
+
+    fn path(&mut self, points: &[(f32, f32)]) {
+        self.lines.push("newpath".to_string());
+        for (i, (x, y)) in points.iter().enumerate() {
+            let operator = if i == 0 { "moveto" } else { "lineto" };
+            self.lines.push(format!("{:.2} {:.2} {}", x, y, operator));
+        }
+    }
+
+    fn fill(&mut self, outline: &[(f32, f32)], color: Rgba<u8>) {
+        if outline.is_empty() {
+            return;
+        }
+
+        self.color(color);
+        self.path(outline);
+        self.lines.push("closepath fill".to_string());
+    }
+
+    // a single point is a dot as wide as the stroke, like a round capped svg line
+    fn stroke(&mut self, points: &[(f32, f32)], width: f32, color: Rgba<u8>) {
+        match points {
+            [] => {}
+            [point] => self.circle(*point, width / 2.0, color),
+            _ => {
+                self.color(color);
+                self.path(points);
+                self.lines.push(format!("{:.2} setlinewidth stroke", width));
+            }
+        }
+    }
+
+    fn mark(&mut self, mark: &Mark) {
+        match mark {
+            Mark::Circle {
+                center,
+                radius,
+                color,
+            } => self.circle(*center, *radius, *color),
+            Mark::Fill { outline, color } => self.fill(outline, *color),
+            Mark::Stroke {
+                points,
+                width,
+                color,
+            } => self.stroke(points, *width, *color),
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.lines.push("grestore".to_string());
+        self.lines.push("showpage".to_string());
+        self.lines.push("%%EOF".to_string());
+        self.lines.push(String::new());
+
+        self.lines.join("\n")
+    }
+}
+
+// puts `shapes` in `RasterizeArgs::row_order`, by where each one starts.
+// serpentine goes right to left along every other row, drawing those rows' shapes
+// backwards with `reverse`, so the pen ends each row where the next one begins
+fn in_row_order<T>(
+    shapes: Vec<T>,
+    args: &RasterizeArgs,
+    start: impl Fn(&T) -> (f32, f32),
+    reverse: impl Fn(&mut T),
+) -> Vec<T> {
+    if args.row_order == RowOrder::RowMajor {
+        return shapes;
+    }
+
+    let mut rows: BTreeMap<i64, Vec<T>> = BTreeMap::new();
+    for shape in shapes {
+        let row = (start(&shape).1 / args.square_size).floor() as i64;
+        rows.entry(row).or_default().push(shape);
+    }
+
+    let mut ordered = vec![];
+    for (i, mut row) in rows.into_values().enumerate() {
+        row.sort_by(|a, b| {
+            start(a)
+                .0
+                .partial_cmp(&start(b).0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        if i % 2 == 1 {
+            row.reverse();
+            row.iter_mut().for_each(&reverse);
+        }
+
+        ordered.extend(row);
+    }
+
+    ordered
+}
+
+// weighted Voronoi stippling runs over the whole page grid at once, see `voronoi::Stippler`.
+// it is relaxed a round at a time by the caller, then its points are cut up into pages
+// with `rasterize_points_image` or `rasterize_points_svg`
+pub fn voronoi_stippler(args: RasterizeArgs, voronoi: Voronoi) -> Stippler {
+    let args = args.for_points();
+
+    if check_image(args.image).is_err() {
+        return Stippler::new(0, 0, vec![], 0, 1);
+    }
+
+    let laid_out = LaidOutImage::new(&args);
+    let (width, height) = laid_out.dimensions();
+
+    // the laid out image is resampled a band of rows at a time, and only each pixel's
+    // darkness is kept, as a byte, a quarter of the size of the pixels themselves
+    let mut density = Vec::with_capacity(width as usize * height as usize);
+    for top in (0..height).step_by(DENSITY_BAND_ROWS as usize) {
+        let band = laid_out.region((0, top, width, DENSITY_BAND_ROWS.min(height - top)));
+        density.extend(
+            band.pixels()
+                .map(|pixel| ((1.0 - brightness(*pixel, args.luma)) * 255.0).round() as u8),
+        );
+    }
+
+    Stippler::new(width, height, density, voronoi.points, 1)
+}
+
+pub fn rasterize_points_image<'a>(
+    args: RasterizeArgs<'a>,
+    points: &'a [(f32, f32)],
+) -> PageStream<'a, ImageBuffer<Rgba<u8>, Vec<u8>>> {
+    let args = args.for_points();
+
+    if check_image(args.image).is_err() {
+        return PageStream::empty();
+    }
+
+    let laid_out = LaidOutImage::new(&args);
+    let (image_width_pixels, image_height_pixels) = laid_out.dimensions();
+    let render_scale = args.output_pixels_per_inch / PIXELS_PER_INCH;
+    let supersampling = args.supersampling.max(1);
+    let draw_scale = render_scale * supersampling as f32;
+    let (_, dot_radius) = stipple_spacing(args.square_size, args.max_radius_percentage);
+
+    let pages = page_regions(&args, image_width_pixels, image_height_pixels);
+
+    PageStream::drawn(
+        pages.into_iter().take(args.page_limit()),
+        move |(page_x, page_y, page_width_pixels, page_height_pixels)| {
+            let width = (page_width_pixels as f32 * render_scale).ceil() as u32;
+            let height = (page_height_pixels as f32 * render_scale).ceil() as u32;
+            let mut target_page = ImageBuffer::<Rgba<u8>, Vec<u8>>::new(
+                width * supersampling,
+                height * supersampling,
+            );
+
+            let page = (page_x, page_y, page_width_pixels, page_height_pixels);
+            let page_image = laid_out.region(page);
+            for (x, y) in points_on_page(points, page) {
+                let pixel = *page_image.get_pixel(x as u32 - page_x, y as u32 - page_y);
+                let color = dot_color(&args, pixel, brightness(pixel, args.luma));
+
+                draw::draw_circle(
+                    &mut target_page,
+                    (
+                        (x - page_x as f32) * draw_scale,
+                        (y - page_y as f32) * draw_scale,
+                    ),
+                    dot_radius * draw_scale,
+                    color,
+                    args.circle_algorithm,
+                );
+            }
+
+            draw_border_on_image_page(
+                &mut target_page,
+                args.border,
+                (image_width_pixels, image_height_pixels),
+                (page_x, page_y),
+                draw_scale,
+                0,
+            );
+
+            if supersampling == 1 {
+                return target_page;
+            }
+
+            let mut shrunk_page = RgbaImage::new(width, height);
+            draw::shrink(&target_page, supersampling, &mut shrunk_page);
+
+            shrunk_page
+        },
+    )
+}
+
+pub fn rasterize_points_svg<'a>(
+    args: RasterizeArgs<'a>,
+    points: &'a [(f32, f32)],
+) -> PageStream<'a, svg::Document> {
+    let args = args.for_points();
+
+    if check_image(args.image).is_err() {
+        return PageStream::empty();
+    }
+
+    let laid_out = LaidOutImage::new(&args);
+    let (image_width_pixels, image_height_pixels) = laid_out.dimensions();
+    let (_, dot_radius) = stipple_spacing(args.square_size, args.max_radius_percentage);
+
+    let pages = page_regions(&args, image_width_pixels, image_height_pixels);
+
+    PageStream::drawn(
+        pages.into_iter().take(args.page_limit()),
+        move |(page_x, page_y, page_width_pixels, page_height_pixels)| {
+            let mut svg_document = svg_page(page_width_pixels, page_height_pixels);
+
+            let page = (page_x, page_y, page_width_pixels, page_height_pixels);
+            let page_image = laid_out.region(page);
+            for (x, y) in points_on_page(points, page) {
+                let pixel = *page_image.get_pixel(x as u32 - page_x, y as u32 - page_y);
+                let color = dot_color(&args, pixel, brightness(pixel, args.luma));
+
+                let mut circle = svg::node::element::Circle::new()
+                    .set("cx", x - page_x as f32)
+                    .set("cy", y - page_y as f32)
+                    .set("r", dot_radius)
+                    .set("fill", Color::from_rgba(color).to_string());
+
+                if color[3] < 255 {
+                    circle = circle.set("fill-opacity", color[3] as f32 / 255.0);
+                }
+
+                svg_document = svg_document.add(circle);
+            }
+
+            draw_border_on_svg_page(
+                svg_document,
+                args.border,
+                (image_width_pixels, image_height_pixels),
+                (page_x, page_y),
+            )
+        },
+    )
+}
+
+// a page's points joined up into one path, see `tsp`
+pub struct Tour {
+    // in page coordinates, in drawing order
+    path: Vec<(f32, f32)>,
+    page_width_pixels: u32,
+    page_height_pixels: u32,
+    width: f32,
+    color: Rgba<u8>,
+}
+
+// one tour per page, for `Tour::to_svg` and `Tour::to_gcode`
+pub fn rasterize_points_tours<'a>(
+    args: RasterizeArgs<'a>,
+    points: &'a [(f32, f32)],
+) -> PageStream<'a, Tour> {
+    let args = args.for_points();
+
+    if check_image(args.image).is_err() {
+        return PageStream::empty();
+    }
+
+    let laid_out = LaidOutImage::new(&args);
+    let (image_width_pixels, image_height_pixels) = laid_out.dimensions();
+    let stroke_width = (args.square_size * WAVE_STROKE_WIDTH_PERCENTAGE).max(1.0);
+
+    let pages = page_regions(&args, image_width_pixels, image_height_pixels);
+
+    PageStream::drawn(
+        pages.into_iter().take(args.page_limit()),
+        move |(page_x, page_y, page_width_pixels, page_height_pixels)| {
+            let page = (page_x, page_y, page_width_pixels, page_height_pixels);
+            let page_points = points_on_page(points, page)
+                .map(|(x, y)| (x - page_x as f32, y - page_y as f32))
+                .collect::<Vec<_>>();
+
+            let page_image = laid_out.region(page);
+            let pixels = page_points
+                .iter()
+                .map(|(x, y)| *page_image.get_pixel(*x as u32, *y as u32))
+                .collect::<Vec<_>>();
+            let color = if pixels.is_empty() {
+                BLACK
+            } else {
+                let brightness = average_brightness(&pixels, args.luma);
+                dot_color(&args, average_color(&pixels), brightness)
+            };
+
+            Tour {
+                path: tsp::tour(&page_points),
+                page_width_pixels,
+                page_height_pixels,
+                width: stroke_width,
+                color,
+            }
+        },
+    )
+}
+
+impl Tour {
+    pub fn to_svg(&self) -> svg::Document {
+        let svg_document = svg_page(self.page_width_pixels, self.page_height_pixels);
+
+        if self.path.is_empty() {
+            return svg_document;
+        }
+
+        let mut data = svg::node::element::path::Data::new();
+        for (i, point) in self.path.iter().enumerate() {
+            data = if i == 0 {
+                data.move_to(*point)
+            } else {
+                data.line_to(*point)
+            };
+        }
+
+        let mut path = svg::node::element::Path::new()
+            .set("d", data)
+            .set("fill", "none")
+            .set("stroke", Color::from_rgba(self.color).to_string())
+            .set("stroke-width", self.width)
+            .set("stroke-linejoin", "round")
+            .set("stroke-linecap", "round");
+
+        if self.color[3] < 255 {
+            path = path.set("stroke-opacity", self.color[3] as f32 / 255.0);
+        }
+
+        svg_document.add(path)
+    }
+
+    // millimeters from the bottom left corner of the page,
+    // with a single pen down for the whole path
+    pub fn to_gcode(&self) -> String {
+        gcode(
+            std::iter::once(self.path.as_slice()),
+            self.page_height_pixels,
+        )
+    }
+}
+
+// a page of characters, see `rasterize_text`
+pub struct TextPage {
+    // top to bottom
+    rows: Vec<String>,
+    // of each row
+    colors: Vec<Rgba<u8>>,
+    page_width_pixels: u32,
+    page_height_pixels: u32,
+    character_width: f32,
+}
+
+// every cell of the image as a character from `ramp` (lightest to darkest),
+// picked by the cell's brightness, one `TextPage` per page
+pub fn rasterize_text<'a>(args: RasterizeArgs<'a>, ramp: &'a str) -> PageStream<'a, TextPage> {
+    if check_image(args.image).is_err() {
+        return PageStream::empty();
+    }
+
+    let args = args.with_draft_overrides().with_palette();
+    let page_limit = args.page_limit();
+
+    if args.layout == Layout::Matrix {
+        return PageStream::chain(
+            args.matrix_tiles()
+                .into_iter()
+                .take(page_limit)
+                .map(move |tile| rasterize_text(tile, ramp)),
+        );
+    }
+
+    let ramp = if ramp.is_empty() {
+        DEFAULT_CHARACTER_RAMP.chars().collect::<Vec<_>>()
+    } else {
+        ramp.chars().collect::<Vec<_>>()
+    };
+
+    let laid_out = LaidOutImage::new(&args);
+    let (image_width_pixels, image_height_pixels) = laid_out.dimensions();
+    let character_width = args.square_size;
+    let character_height = args.square_size * CHARACTER_HEIGHT_PER_WIDTH;
+
+    let pages = page_regions(&args, image_width_pixels, image_height_pixels);
+
+    PageStream::drawn(
+        pages.into_iter().take(page_limit),
+        move |(page_x, page_y, page_width_pixels, page_height_pixels)| {
+            let page = laid_out.page((page_x, page_y, page_width_pixels, page_height_pixels));
+            let sums = PageSums::new(&page, args.luma);
+            let columns = (page_width_pixels as f32 / character_width).ceil() as u32;
+            let lines = (page_height_pixels as f32 / character_height).ceil() as u32;
+
+            let mut rows = vec![];
+            let mut colors = vec![];
+
+            for line in 0..lines {
+                let top = (line as f32 * character_height) as u32;
+                let bottom = (((line + 1) as f32 * character_height) as u32)
+                    .clamp(top + 1, page_height_pixels);
+
+                let row = (0..columns)
+                    .map(|column| {
+                        let left = (column as f32 * character_width) as u32;
+                        let right = (((column + 1) as f32 * character_width) as u32)
+                            .clamp(left + 1, page_width_pixels);
+
+                        let (_, brightness) = sums.average((left, top, right, bottom));
+                        let darkness = 1.0 - brightness;
+                        let index = (darkness * (ramp.len() - 1) as f32).round() as usize;
+                        ramp[index.min(ramp.len() - 1)]
+                    })
+                    .collect::<String>();
+
+                let (color, brightness) = sums.average((0, top, page_width_pixels, bottom));
+                colors.push(dot_color(&args, color, brightness));
+                rows.push(row);
+            }
+
+            TextPage {
+                rows,
+                colors,
+                page_width_pixels,
+                page_height_pixels,
+                character_width,
+            }
+        },
+    )
+}
+
+impl TextPage {
+    pub fn to_text(&self) -> String {
+        let mut text = self.rows.join("\n");
+        text.push('\n');
+        text
+    }
+
+    // each row is stretched to exactly its columns' width,
+    // whatever monospace font the viewer ends up using
+    pub fn to_svg(&self) -> svg::Document {
+        let mut svg_document = svg_page(self.page_width_pixels, self.page_height_pixels);
+        let character_height = self.character_width * CHARACTER_HEIGHT_PER_WIDTH;
+
+        for (line, (row, color)) in self.rows.iter().zip(&self.colors).enumerate() {
+            // svg collapses runs of ordinary spaces, but not of non-breaking ones
+            let escaped = row
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace(' ', "\u{a0}");
+
+            let mut text = svg::node::element::Text::new()
+                .set("x", 0)
+                .set("y", (line as f32 + CHARACTER_BASELINE) * character_height)
+                .set("font-family", "monospace")
+                .set("font-size", character_height)
+                .set(
+                    "textLength",
+                    row.chars().count() as f32 * self.character_width,
+                )
+                .set("lengthAdjust", "spacingAndGlyphs")
+                .set("fill", Color::from_rgba(*color).to_string())
+                .add(svg::node::Text::new(escaped));
+
+            if color[3] < 255 {
+                text = text.set("fill-opacity", color[3] as f32 / 255.0);
+            }
+
+            svg_document = svg_document.add(text);
+        }
+
+        svg_document
+    }
+}
+
+// the points that fall on the page at (x, y, width, height)
+fn points_on_page(
+    points: &[(f32, f32)],
+    (page_x, page_y, page_width_pixels, page_height_pixels): (u32, u32, u32, u32),
+) -> impl Iterator<Item = (f32, f32)> + '_ {
+    let (left, top) = (page_x as f32, page_y as f32);
+    let right = left + page_width_pixels as f32;
+    let bottom = top + page_height_pixels as f32;
+
+    points
+        .iter()
+        .copied()
+        .filter(move |&(x, y)| x >= left && x < right && y >= top && y < bottom)
+}
+
+// the border is laid out over the whole artwork, so each page
+// draws only the part of it that falls on that page
+// `target_page` holds the page's rows from `top` down
+fn draw_border_on_image_page(
+    target_page: &mut ImageBuffer<Rgba<u8>, Vec<u8>>,
+    border: Border,
+    (artwork_width, artwork_height): (u32, u32),
+    (page_offset_x, page_offset_y): (u32, u32),
+    render_scale: f32,
+    top: u32,
+) {
+    for (x, y, width, height) in border.rects(artwork_width, artwork_height) {
+        // scale the artwork coordinates before moving them onto this page,
+        // so neighboring pages round the border to the same pixels
+        let scale = |v: i32| (v as f32 * render_scale).round() as i32;
+        let left = scale(x) - scale(page_offset_x as i32);
+        let right = scale(x + width as i32) - scale(page_offset_x as i32);
+        let bottom = scale(y + height as i32) - scale(page_offset_y as i32) - top as i32;
+        let top = scale(y) - scale(page_offset_y as i32) - top as i32;
+
+        imageproc::drawing::draw_filled_rect_mut(
+            target_page,
+            imageproc::rect::Rect::at(left, top)
+                .of_size((right - left).max(1) as u32, (bottom - top).max(1) as u32),
+            border.color.to_rgba(),
+        );
+    }
+}
+
+// anything outside of the page's viewBox is clipped away
+fn draw_border_on_svg_page(
+    mut svg_document: svg::Document,
+    border: Border,
+    (artwork_width, artwork_height): (u32, u32),
+    (page_offset_x, page_offset_y): (u32, u32),
+) -> svg::Document {
+    for (x, y, width, height) in border.rects(artwork_width, artwork_height) {
+        let rect = svg::node::element::Rectangle::new()
+            .set("x", x - page_offset_x as i32)
+            .set("y", y - page_offset_y as i32)
+            .set("width", width)
+            .set("height", height)
+            .set("fill", border.color.to_string());
+
+        svg_document = svg_document.add(rect);
+    }
+
+    svg_document
+}
+
+impl<'a> RasterizeArgs<'a> {
+    // draft mode trades quality for speed, for rapid iteration on settings.
+    // everything draft mode changes about a run is overridden here,
+    // except for only rendering the first page, see `page_limit`.
+    fn with_draft_overrides(self) -> Self {
+        if !self.draft {
+            return self;
+        }
+
+        RasterizeArgs {
+            square_size: self.square_size.max(DRAFT_MIN_SQUARE_SIZE),
+            output_pixels_per_inch: PIXELS_PER_INCH,
+            supersampling: 1,
+            circle_algorithm: match self.circle_algorithm {
+                CircleAlgorithm::Antialiased => CircleAlgorithm::Scanline,
+                algorithm => algorithm,
+            },
+            ..self
+        }
+    }
+
+    // picks the colors of an automatic palette from the image
+    fn with_palette(self) -> Self {
+        if self.color_depth != ColorDepth::Palette || !self.palette.automatic {
+            return self;
+        }
+
+        let framed_image;
+        let image = if self.framing.is_identity() {
+            self.image
+        } else {
+            framed_image = self.framing.apply(self.image);
+            &framed_image
+        };
+
+        let (width, height) = image.dimensions();
+        let step =
+            ((width as f32 * height as f32 / PALETTE_MAX_SAMPLES as f32).sqrt() as u32).max(1);
+
+        let mut pixels = vec![];
+        for y in (0..height).step_by(step as usize) {
+            for x in (0..width).step_by(step as usize) {
+                let pixel = *image.get_pixel(x, y);
+                // fully transparent pixels don't get drawn
+                if pixel[3] > 0 {
+                    pixels.push(Color::from_rgba(pixel));
+                }
+            }
+        }
+
+        let mut palette = Palette {
+            automatic: false,
+            ..self.palette
+        };
+        for (i, color) in color::kmeans(&pixels, self.palette.colors().len())
+            .into_iter()
+            .enumerate()
+        {
+            palette.colors[i] = color;
+        }
+
+        RasterizeArgs { palette, ..self }
+    }
+
+    // the inks that are each drawn as their own layer, if any
+    pub fn layer_inks(&self) -> &[Color] {
+        match self.color_depth {
+            ColorDepth::Duotone | ColorDepth::Tritone => {
+                &self.inks.colors[..self.color_depth.ink_count().unwrap_or(0)]
+            }
+            ColorDepth::Palette => self.palette.colors(),
+            _ => &[],
+        }
+    }
+
+    // the folder of each ink's layers in the zip of a run's outputs,
+    // numbered and named after its color, like `plate1_00ffff`
+    pub fn plate_folders(&self) -> Vec<String> {
+        self.layer_inks()
+            .iter()
+            .enumerate()
+            .map(|(ink, color)| {
+                format!(
+                    "plate{}_{}",
+                    ink + 1,
+                    color.to_string().trim_start_matches('#')
+                )
+            })
+            .collect()
+    }
+
+    // a single ink's layer of a duotone, tritone or palette print
+    pub fn ink_layer(self, ink: usize) -> Self {
+        RasterizeArgs {
+            ink_layer: Some(ink),
+            ..self
+        }
+    }
+
+    // whether a shape of `color` belongs on this run's layer
+    fn draws(&self, color: Rgba<u8>) -> bool {
+        match self.ink_layer {
+            Some(ink) => self.layer_inks().get(ink).map(|ink| ink.to_rgba()) == Some(color),
+            None => true,
+        }
+    }
+
+    // the entire page grid as one big page, so the dot field is continuous
+    // instead of restarting at every page boundary
+    pub fn whole_poster(self) -> Self {
+        RasterizeArgs {
+            paper_width_pixels: self.paper_width_pixels * self.pages_width as f32,
+            paper_height_pixels: self.paper_height_pixels * self.pages_height as f32,
+            pages_width: 1,
+            pages_height: 1,
+            ..self
+        }
+    }
+
+    // the same layout drawn `scale` times the size, everything measured in pixels with it
+    fn scaled(self, scale: f32) -> Self {
+        RasterizeArgs {
+            paper_width_pixels: self.paper_width_pixels * scale,
+            paper_height_pixels: self.paper_height_pixels * scale,
+            square_size: self.square_size * scale,
+            step_and_repeat: StepAndRepeat {
+                spacing: self.step_and_repeat.spacing * scale,
+                ..self.step_and_repeat
+            },
+            border: Border {
+                thickness: self.border.thickness * scale,
+                inset: self.border.inset * scale,
+                ..self.border
+            },
+            edges: Edges {
+                width: self.edges.width * scale,
+                ..self.edges
+            },
+            min_dot_size: MinDotSize {
+                radius: self.min_dot_size.radius * scale,
+                ..self.min_dot_size
+            },
+            ..self
+        }
+    }
+
+    // point sets are always laid out over the whole page grid,
+    // the matrix layout has no single set of points to show
+    fn for_points(self) -> Self {
+        let args = self.with_draft_overrides().with_palette();
+
+        RasterizeArgs {
+            layout: match args.layout {
+                Layout::Matrix => Layout::Fit,
+                layout => layout,
+            },
+            ..args
+        }
+    }
+
+    fn page_limit(&self) -> usize {
+        if self.draft {
+            1
+        } else {
+            usize::MAX
+        }
+    }
+
+    // in the matrix layout every page is a tile showing the whole image,
+    // rendered with its own settings interpolated between `matrix.from` and
+    // `matrix.to`, left-right top-bottom.
+    // each tile is then rasterized as an ordinary single page.
+    fn matrix_tiles(&self) -> Vec<RasterizeArgs<'a>> {
+        let tiles = self.pages_width * self.pages_height;
+
+        (0..tiles)
+            .map(|tile| {
+                let t = if tiles > 1 {
+                    tile as f32 / (tiles - 1) as f32
+                } else {
+                    0.0
+                };
+
+                let value = self.matrix.from + (self.matrix.to - self.matrix.from) * t;
+
+                let mut tile_args = RasterizeArgs {
+                    pages_width: 1,
+                    pages_height: 1,
+                    layout: Layout::Fit,
+                    ..*self
+                };
+
+                match self.matrix.parameter {
+                    MatrixParameter::SquareSize => {
+                        tile_args.square_size = value.max(1.0);
+                    }
+                    MatrixParameter::MaxRadiusPercentage => {
+                        tile_args.max_radius_percentage =
+                            (value / 100.0).max(tile_args.min_radius_percentage);
+                    }
+                }
+
+                tile_args
+            })
+            .collect()
+    }
+}
+
+// the source image, masked and arranged on the full extent of the page grid.
+// for a poster the laid out image can be many times the size of the source, so it is
+// never made in full. each page's part of it is resampled straight from the source instead
+struct LaidOutImage<'a> {
+    // the source, framed, toned, inverted and masked, but not yet scaled
+    source: Cow<'a, RgbaImage>,
+    width: u32,
+    height: u32,
+    // (x, y, width, height) of each scaled copy of the source, in the order they are laid down
+    copies: Vec<(u32, u32, u32, u32)>,
+    // step and repeat lays its copies on blank paper, where fit's one copy is the whole layout
+    on_paper: bool,
+    filter: image::imageops::FilterType,
+    transparency: Transparency,
+}
+
+impl<'a> LaidOutImage<'a> {
+    fn new(args: &RasterizeArgs<'a>) -> Self {
+        let mut source = Cow::Borrowed(args.image);
+
+        if !args.framing.is_identity() {
+            source = Cow::Owned(args.framing.apply(&source));
+        }
+
+        if !args.tone.is_identity() {
+            source = Cow::Owned(args.tone.apply(&source));
+        }
+
+        if args.invert.is_any() {
+            source = Cow::Owned(invert_channels(&source, args.invert));
+        }
+
+        if let Some(mask) = args.mask {
+            // framed the same way as the image, so that they still line up
+            source = Cow::Owned(if args.framing.is_identity() {
+                apply_mask(&source, mask, args.mask_blend)
+            } else {
+                apply_mask(&source, &args.framing.apply(mask), args.mask_blend)
+            });
+        }
+
+        let pages_width_pixels = (args.pages_width as f32 * args.paper_width_pixels).ceil() as u32;
+        let pages_height_pixels =
+            (args.pages_height as f32 * args.paper_height_pixels).ceil() as u32;
+
+        let (width, height, copies) = match args.layout {
+            Layout::Fit => {
+                let (width, height) =
+                    fit_dimensions(source.dimensions(), pages_width_pixels, pages_height_pixels);
+                (width, height, vec![(0, 0, width, height)])
+            }
+            Layout::StepAndRepeat => (
+                pages_width_pixels,
+                pages_height_pixels,
+                args.step_and_repeat.copies(
+                    source.dimensions(),
+                    pages_width_pixels,
+                    pages_height_pixels,
+                ),
+            ),
+            // matrix tiles are laid out as individual `Fit` pages
+            Layout::Matrix => unreachable!(),
+        };
+
+        LaidOutImage {
+            source,
+            width,
+            height,
+            copies,
+            on_paper: args.layout == Layout::StepAndRepeat,
+            filter: args.resampling.filter(),
+            transparency: args.transparency,
+        }
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    // the part of the laid out image at (x, y, width, height), the same
+    // pixels as if the whole of it had been made and the part cut out
+    fn region(&self, (x, y, width, height): (u32, u32, u32, u32)) -> RgbaImage {
+        let region = if self.on_paper {
+            // blank paper samples as "no dot"
+            let mut region = ImageBuffer::from_pixel(width, height, WHITE);
+
+            for &(copy_x, copy_y, copy_width, copy_height) in &self.copies {
+                let left = x.max(copy_x);
+                let top = y.max(copy_y);
+                let right = (x + width).min(copy_x + copy_width);
+                let bottom = (y + height).min(copy_y + copy_height);
+
+                if left < right && top < bottom {
+                    let part = resample::resize_window(
+                        &self.source,
+                        (copy_width, copy_height),
+                        self.filter,
+                        (left - copy_x, top - copy_y, right - left, bottom - top),
+                    );
+                    image::imageops::overlay(&mut region, &part, left - x, top - y);
+                }
+            }
+
+            region
+        } else {
+            resample::resize_window(
+                &self.source,
+                (self.width, self.height),
+                self.filter,
+                (x, y, width, height),
+            )
+        };
+
+        // after the layout, so that it also covers the gaps around the image
+        self.transparency.apply(region)
+    }
+
+    // one page of the laid out image, at `region`
+    fn page(&self, region: (u32, u32, u32, u32)) -> PageView {
+        PageView::new(self.region(region), region.0, region.1)
+    }
+
+    // the whole of the laid out image, scaled down by `scale` for previews
+    fn preview(&self, scale: f32) -> RgbaImage {
+        let scaled = |value: u32| ((value as f32 * scale).round() as u32).max(1);
+        let preview = LaidOutImage {
+            source: Cow::Borrowed(&*self.source),
+            width: scaled(self.width),
+            height: scaled(self.height),
+            copies: self
+                .copies
+                .iter()
+                .map(|&(x, y, width, height)| {
+                    (
+                        (x as f32 * scale) as u32,
+                        (y as f32 * scale) as u32,
+                        scaled(width),
+                        scaled(height),
+                    )
+                })
+                .collect(),
+            on_paper: self.on_paper,
+            filter: image::imageops::Triangle,
+            transparency: self.transparency,
+        };
+
+        preview.region((0, 0, preview.width, preview.height))
+    }
+}
+
+// (x, y, width, height) of each page that has any of the laid out image on it,
+// left-right top-bottom
+fn page_regions(
+    args: &RasterizeArgs,
+    scaled_image_width_pixels: u32,
+    scaled_image_height_pixels: u32,
+) -> Vec<(u32, u32, u32, u32)> {
+    let paper_width_pixels = args.paper_width_pixels;
+    let paper_height_pixels = args.paper_height_pixels;
+
+    let mut pages_pairs = Vec::with_capacity(
+        (args.pages_width * args.pages_height)
+            .try_into()
+            .expect("pages_width * pages_height was not able to fit into a usize!"),
+    );
+
+    for page_y in 0..args.pages_height {
+        for page_x in 0..args.pages_width {
+            pages_pairs.push((page_x, page_y));
+        }
+    }
+
+    pages_pairs
+        .into_iter()
+        .filter_map(|(page_x, page_y)| {
+            let current_pixel_x = span_start(page_x, paper_width_pixels);
+            let current_pixel_y = span_start(page_y, paper_height_pixels);
+
+            // each page runs up to where the next one starts, so that neighbors share
+            // no pixels and skip none. the last row and column take whatever is left,
+            // and pages past the edge of a scaled image that can't fill the grid are left out
+            let span = |page: u32, pages: u32, paper_pixels: f32, extent: u32, start: u32| {
+                let end = if page + 1 < pages {
+                    span_start(page + 1, paper_pixels).min(extent)
+                } else {
+                    extent
+                };
+                end.checked_sub(start).filter(|span| *span > 0)
+            };
+
+            let x_span = span(
+                page_x,
+                args.pages_width,
+                paper_width_pixels,
+                scaled_image_width_pixels,
+                current_pixel_x,
+            );
+            let y_span = span(
+                page_y,
+                args.pages_height,
+                paper_height_pixels,
+                scaled_image_height_pixels,
+                current_pixel_y,
+            );
+
+            if let (Some(x_span), Some(y_span)) = (x_span, y_span) {
+                Some((current_pixel_x, current_pixel_y, x_span, y_span))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// fractional bits of the fixed point positions in `span_start`
+const FIXED_POINT_BITS: u32 = 16;
+
+// the whole pixel that the `index`th of a run of `size` pixel spans (pages or cells) starts on.
+// worked out in fixed point from the start of the run rather than stepped to in floats,
+// so the hundredth span lands as exactly as the first and neighbors line up
+fn span_start(index: u32, size: f32) -> u32 {
+    let size = (size.max(0.0) as f64 * (1u64 << FIXED_POINT_BITS) as f64).round() as u64;
+    ((index as u64 * size) >> FIXED_POINT_BITS) as u32
+}
+
+// the largest grid suggested, per side
+const MAX_SUGGESTED_PAGES: u32 = 8;
+// below this fraction of the page grid covered by the image,
+// a different grid is worth suggesting
+const MIN_GRID_COVERAGE: f32 = 0.5;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputError {
+    EmptyImage { width: u32, height: u32 },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InputError::EmptyImage { width, height } => write!(
+                f,
+                "the image is {}x{} pixels, it needs at least one pixel in each direction",
+                width, height
+            ),
+        }
+    }
+}
+
+// images that can't be rasterized at all
+pub fn check_image<I: GenericImageView>(image: &I) -> Result<(), InputError> {
+    let (width, height) = image.dimensions();
+
+    if width == 0 || height == 0 {
+        Err(InputError::EmptyImage { width, height })
+    } else {
+        Ok(())
+    }
+}
+
+// a loaded image, converted to rgba once so that rasterizing never has to,
+// with a hash of its pixels for telling whether it has changed
+pub struct SourceImage {
+    pixels: RgbaImage,
+    hash: u64,
+}
+
+impl SourceImage {
+    pub fn new(image: &image::DynamicImage) -> Self {
+        let pixels = image.to_rgba8();
+
+        let mut hasher = DefaultHasher::new();
+        pixels.dimensions().hash(&mut hasher);
+        pixels.as_raw().hash(&mut hasher);
+
+        Self {
+            pixels,
+            hash: hasher.finish(),
+        }
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl Deref for SourceImage {
+    type Target = RgbaImage;
+
+    fn deref(&self) -> &RgbaImage {
+        &self.pixels
+    }
+}
+
+// the source image as it is laid out on the page grid (the first tile, for the matrix layout),
+// scaled down to fit `LAYOUT_PREVIEW_MAX_PIXELS`, and the scale it was drawn at,
+// so that one square of `square_size` is `square_size * scale` preview pixels across
+pub fn layout_preview(args: RasterizeArgs) -> Option<(RgbaImage, f32)> {
+    check_image(args.image).ok()?;
+
+    let args = if args.layout == Layout::Matrix {
+        *args.matrix_tiles().first()?
+    } else {
+        args
+    };
+
+    let laid_out = LaidOutImage::new(&args);
+    let (width, height) = laid_out.dimensions();
+    let scale = (LAYOUT_PREVIEW_MAX_PIXELS as f32 / width.max(height) as f32).min(1.0);
+
+    Some((laid_out.preview(scale), scale))
+}
+
+// a preview of the whole page grid for every combination of `sheet`'s square sizes and
+// largest dot sizes, on one white sheet, so settings can be picked without a run for each.
+// previews are drawn one at a time, then shrunk to fit `CONTACT_SHEET_CELL_PIXELS`
+pub fn contact_sheet(args: RasterizeArgs, sheet: ContactSheet) -> Option<RgbaImage> {
+    check_image(args.image).ok()?;
+
+    let args = RasterizeArgs {
+        // draft mode's bigger squares would undo the sweep
+        draft: false,
+        ..args
+    };
+
+    let square_sizes = sheet.square_sizes();
+    let max_radius_percentages = sheet.max_radius_percentages();
+
+    let mut contact_sheet: Option<RgbaImage> = None;
+    for (row, max_radius_percentage) in max_radius_percentages.iter().enumerate() {
+        for (column, square_size) in square_sizes.iter().enumerate() {
+            let cell_args = RasterizeArgs {
+                square_size: square_size.max(1.0),
+                max_radius_percentage: (max_radius_percentage / 100.0)
+                    .max(args.min_radius_percentage),
+                ..args
+            };
+
+            let cell = poster_preview(cell_args, CONTACT_SHEET_CELL_PIXELS)?;
+
+            // every preview is of the same page grid, so they all come out the same size
+            let sheet_image = contact_sheet.get_or_insert_with(|| {
+                ImageBuffer::from_pixel(
+                    square_sizes.len() as u32 * (cell.width() + CONTACT_SHEET_GAP_PIXELS)
+                        + CONTACT_SHEET_GAP_PIXELS,
+                    max_radius_percentages.len() as u32
+                        * (cell.height() + CONTACT_SHEET_GAP_PIXELS)
+                        + CONTACT_SHEET_GAP_PIXELS,
+                    WHITE,
+                )
+            });
+            image::imageops::replace(
+                sheet_image,
+                &cell,
+                CONTACT_SHEET_GAP_PIXELS
+                    + column as u32 * (cell.width() + CONTACT_SHEET_GAP_PIXELS),
+                CONTACT_SHEET_GAP_PIXELS + row as u32 * (cell.height() + CONTACT_SHEET_GAP_PIXELS),
+            );
+        }
+    }
+
+    contact_sheet
+}
+
+// the whole page grid drawn as one page, on white, to fit in `max_pixels` either way.
+// it is laid out that small to begin with, not drawn full size and shrunk, unless
+// that would make its squares smaller than a pixel
+fn poster_preview(args: RasterizeArgs, max_pixels: u32) -> Option<RgbaImage> {
+    let poster = args.whole_poster();
+    let scale = (max_pixels as f32 / poster.paper_width_pixels.max(poster.paper_height_pixels))
+        .max(1.0 / args.square_size)
+        .min(1.0);
+    let args = RasterizeArgs {
+        layout: match args.layout {
+            Layout::Matrix => Layout::Fit,
+            layout => layout,
+        },
+        ink_layer: None,
+        output_pixels_per_inch: PIXELS_PER_INCH,
+        supersampling: 1,
+        ..poster.scaled(scale)
+    };
+
+    let page = rasterize_image(args).next()?;
+    let scale = (max_pixels as f32 / page.width().max(page.height()) as f32).min(1.0);
+
+    Some(image::imageops::resize(
+        &on_white(&page),
+        ((page.width() as f32 * scale).round() as u32).max(1),
+        ((page.height() as f32 * scale).round() as u32).max(1),
+        image::imageops::Triangle,
+    ))
+}
+
+// a frame of an animation of the whole page grid. frames drawn with the same settings
+// from images of the same size are all the same size
+pub fn animation_frame(args: RasterizeArgs) -> Option<RgbaImage> {
+    check_image(args.image).ok()?;
+    poster_preview(args, ANIMATION_FRAME_PIXELS)
+}
+
+// `frames` animation frames of the whole page grid being made, the last of which
+// is the finished poster. for sharing a preview of a print before it is printed.
+// each frame is only drawn when it is pulled from the stream
+pub fn build_up(args: RasterizeArgs, build_up: BuildUp, frames: u32) -> PageStream<RgbaImage> {
+    if check_image(args.image).is_err() {
+        return PageStream::empty();
+    }
+
+    match build_up {
+        BuildUp::None => PageStream::empty(),
+        BuildUp::Grow => PageStream::new((1..=frames).filter_map(move |frame| {
+            let grown = frame as f32 / frames as f32;
+            animation_frame(RasterizeArgs {
+                min_radius_percentage: args.min_radius_percentage * grown,
+                max_radius_percentage: args.max_radius_percentage * grown,
+                ..args
+            })
+        })),
+        // the poster is drawn once and uncovered a row at a time
+        BuildUp::Sweep => {
+            let poster = match animation_frame(args) {
+                Some(poster) => poster,
+                None => return PageStream::empty(),
+            };
+            let rows = (args.whole_poster().paper_height_pixels / args.square_size.max(1.0))
+                .ceil()
+                .max(1.0);
+
+            PageStream::new((1..=frames).map(move |frame| {
+                let swept_rows = (rows * frame as f32 / frames as f32).round();
+                let swept = (swept_rows / rows * poster.height() as f32).round() as u32;
+
+                let mut image = poster.clone();
+                for (_, y, pixel) in image.enumerate_pixels_mut() {
+                    if y >= swept {
+                        *pixel = WHITE;
+                    }
+                }
+                image
+            }))
+        }
+    }
+}
+
+// what the assembly map and instructions are called in the zip of a run's outputs
+pub const ASSEMBLY_MAP_FILENAME: &str = "assembly_map.svg";
+pub const ASSEMBLY_INSTRUCTIONS_FILENAME: &str = "README.txt";
+
+// where a page goes in the assembled print
+struct PagePlacement {
+    column: u32,
+    row: u32,
+    // (x, y, width, height), within the laid out image
+    region: (u32, u32, u32, u32),
+}
+
+// each page of the assembled print, left-right top-bottom, and the size of the
+// whole print. the matrix layout's pages aren't assembled, so have no placements
+fn page_placements(args: &RasterizeArgs) -> Option<(Vec<PagePlacement>, (u32, u32))> {
+    check_image(args.image).ok()?;
+    if args.layout == Layout::Matrix {
+        return None;
+    }
+
+    let (width, height) = laid_out_dimensions(args);
+    let regions = page_regions(args, width, height);
+
+    let mut columns = regions.iter().map(|region| region.0).collect::<Vec<_>>();
+    columns.sort_unstable();
+    columns.dedup();
+    let mut rows = regions.iter().map(|region| region.1).collect::<Vec<_>>();
+    rows.sort_unstable();
+    rows.dedup();
+
+    let placements = regions
+        .into_iter()
+        .take(args.page_limit())
+        .map(|region| PagePlacement {
+            column: columns.binary_search(&region.0).unwrap_or(0) as u32,
+            row: rows.binary_search(&region.1).unwrap_or(0) as u32,
+            region,
+        })
+        .collect();
+
+    Some((placements, (width, height)))
+}
+
+// the page grid as an svg, each page outlined and numbered where it goes in the assembled print
+pub fn assembly_map(args: RasterizeArgs) -> Option<svg::Document> {
+    let (placements, (width, height)) = page_placements(&args)?;
+    let line_width = width.max(height) as f32 / 400.0;
+
+    let mut document = svg::Document::new().set("viewBox", (0, 0, width, height));
+    for (i, placement) in placements.into_iter().enumerate() {
+        let (x, y, page_width, page_height) = placement.region;
+        let outline = svg::node::element::Rectangle::new()
+            .set("x", x)
+            .set("y", y)
+            .set("width", page_width)
+            .set("height", page_height)
+            .set("fill", "none")
+            .set("stroke", "black")
+            .set("stroke-width", line_width);
+
+        let number = svg::node::element::Text::new()
+            .set("x", x as f32 + page_width as f32 / 2.0)
+            .set("y", y as f32 + page_height as f32 / 2.0)
+            .set("text-anchor", "middle")
+            .set("dominant-baseline", "central")
+            .set("font-family", "sans-serif")
+            .set("font-size", page_width.min(page_height) as f32 / 3.0)
+            .add(svg::node::Text::new((i + 1).to_string()));
+
+        document = document.add(outline).add(number);
+    }
+
+    Some(document)
+}
+
+// a readme for the zip of a run's outputs: which page goes where,
+// and how big each page and the whole assembled print come out
+// `plate_folders` are the folders each ink's layer was written to, if any were
+pub fn assembly_instructions(
+    args: RasterizeArgs,
+    output_name: &str,
+    summary: &str,
+    plate_folders: &[String],
+) -> String {
+    let size = |pixels: f32| {
+        format!(
+            "{:.2}in ({:.0}mm)",
+            pixels / PIXELS_PER_INCH,
+            pixels / PIXELS_PER_INCH * MILLIMETERS_PER_INCH
+        )
+    };
+    let by = |width: u32, height: u32| format!("{} x {}", size(width as f32), size(height as f32));
+
+    let mut readme = format!("{}\n\n", summary);
+    readme.push_str(&format!(
+        "print on {} x {} paper, at 100% scale with no margins.\n\
+         each page's files are named after its number, like {}_1\n\n",
+        size(args.paper_width_pixels),
+        size(args.paper_height_pixels),
+        output_name,
+    ));
+
+    if !plate_folders.is_empty() {
+        readme.push_str(&format!(
+            "each ink is printed from its own plates, which are in {}.\n\
+             every folder has the same pages, named the same way. the pages outside them\n\
+             are all the inks together, to check the plates against\n\n",
+            plate_folders.join(", "),
+        ));
+    }
+
+    let (placements, (width, height)) = match page_placements(&args) {
+        Some(placements) => placements,
+        None => {
+            readme.push_str(
+                "each page is a variation of the parameter matrix, \
+                 on its own rather than part of a bigger print\n",
+            );
+            return readme;
+        }
+    };
+
+    let columns = placements
+        .iter()
+        .map(|placement| placement.column + 1)
+        .max();
+    let rows = placements.iter().map(|placement| placement.row + 1).max();
+    readme.push_str(&format!(
+        "{} pages, {} across and {} down, making a {} print.\n\
+         lay them out left to right and top to bottom, as {} shows:\n\n",
+        placements.len(),
+        columns.unwrap_or(0),
+        rows.unwrap_or(0),
+        by(width, height),
+        ASSEMBLY_MAP_FILENAME,
+    ));
+
+    for (i, placement) in placements.into_iter().enumerate() {
+        let (_, _, page_width, page_height) = placement.region;
+        readme.push_str(&format!(
+            "page {}: column {}, row {}, {}\n",
+            i + 1,
+            placement.column + 1,
+            placement.row + 1,
+            by(page_width, page_height)
+        ));
+    }
+
+    readme
+}
+
+// with very wide or very tall images, `Fit` leaves most of the page grid blank.
+// in that case, this is the grid (columns, rows) that the image covers best,
+// preferring fewer pages
+pub fn suggested_pages(
+    image: &RgbaImage,
+    framing: Framing,
+    paper_size: PaperSize,
+    orientation: Orientation,
+    pages_width: u32,
+    pages_height: u32,
+) -> Option<(u32, u32)> {
+    check_image(image).ok()?;
+
+    let (width, height) = framing.dimensions(image.width(), image.height());
+    let image_aspect_ratio = width as f32 / height as f32;
+    let paper_aspect_ratio =
+        paper_size.width_pixels(orientation) / paper_size.height_pixels(orientation);
+
+    // the fraction of the grid the image covers once it is scaled to fit
+    let coverage = |columns: u32, rows: u32| {
+        let grid_aspect_ratio = columns as f32 / rows as f32 * paper_aspect_ratio;
+        (image_aspect_ratio / grid_aspect_ratio).min(grid_aspect_ratio / image_aspect_ratio)
+    };
+
+    let current_coverage = coverage(pages_width, pages_height);
+    if current_coverage >= MIN_GRID_COVERAGE {
+        return None;
+    }
+
+    let mut best = (pages_width, pages_height);
+    let mut best_coverage = current_coverage;
+    for rows in 1..=MAX_SUGGESTED_PAGES {
+        for columns in 1..=MAX_SUGGESTED_PAGES {
+            let coverage = coverage(columns, rows);
+            let fewer_pages = columns * rows < best.0 * best.1;
+
+            if coverage > best_coverage + f32::EPSILON
+                || ((coverage - best_coverage).abs() <= f32::EPSILON && fewer_pages)
+            {
+                best = (columns, rows);
+                best_coverage = coverage;
+            }
+        }
+    }
+
+    if best == (pages_width, pages_height) {
+        None
+    } else {
+        Some(best)
+    }
+}
+
+// runs bigger than this are warned about before they are started. browsers give a tab
+// somewhere between one and four gigabytes, and wasm can't address more than four
+const ESTIMATE_WARNING_BYTES: u64 = 1 << 30;
+// rough costs of a run, from the benches, allowing for wasm in a browser being slower
+const ESTIMATE_SECONDS_PER_DOT: f32 = 5e-6;
+const ESTIMATE_SECONDS_PER_PIXEL: f32 = 5e-8;
+// an encoded page is about this fraction of the size of its pixels
+const ESTIMATE_ENCODED_FRACTION: u64 = 4;
+
+// about how big a run will be, worked out from the settings without rasterizing anything
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Estimate {
+    pub pages: usize,
+    // about one per square, for patterns that aren't dots too
+    pub dots: u64,
+    // the most memory the run holds at once, in bytes
+    pub peak_bytes: u64,
+    pub seconds: f32,
+}
+
+impl Estimate {
+    // whether the run is likely to run out of memory in a browser
+    pub fn is_too_big(&self) -> bool {
+        self.peak_bytes > ESTIMATE_WARNING_BYTES
+    }
+}
+
+// the pages, dots, memory and time a run with `args` will take. memory is the
+// source image, the page's part of the laid out image and the page being drawn,
+// as raw pixels, along with every page made so far, which is held encoded
+// both for its preview and in the zip
+pub fn estimate(args: RasterizeArgs) -> Option<Estimate> {
+    check_image(args.image).ok()?;
+
+    let args = args.with_draft_overrides();
+    let runs = if args.layout == Layout::Matrix {
+        args.matrix_tiles()
+    } else {
+        vec![args]
+    };
+
+    let mut estimate = Estimate::default();
+    let source_bytes = args.image.width() as u64 * args.image.height() as u64 * CHANNELS as u64;
+    let mut largest_page_bytes = 0;
+    let mut encoded_bytes = 0;
+
+    let pages = runs
+        .iter()
+        .flat_map(|run| {
+            let (width, height) = laid_out_dimensions(run);
+
+            page_regions(run, width, height)
+                .into_iter()
+                .map(move |region| (run, region))
+        })
+        .take(args.page_limit());
+
+    for (run, (_, _, width, height)) in pages {
+        let (columns, rows) = cells_across((width, height), run.square_size);
+        let dots = columns as u64 * rows as u64;
+
+        let render_scale = run.output_pixels_per_inch / PIXELS_PER_INCH;
+        let supersampling = run.supersampling.max(1) as u64;
+        let page_pixels = (width as f32 * render_scale).ceil() as u64
+            * (height as f32 * render_scale).ceil() as u64;
+        let page_bytes = page_pixels * CHANNELS as u64;
+        // supersampled pages are drawn bigger, then shrunk into a page of their own size
+        let pixels = page_pixels * supersampling * supersampling;
+        let drawn_bytes = if supersampling > 1 {
+            pixels * CHANNELS as u64 + page_bytes
+        } else {
+            page_bytes
+        };
+        // the page as sampled, and the table its squares are averaged from
+        let sampled_bytes =
+            width as u64 * height as u64 * CHANNELS as u64 + PageSums::bytes(width, height);
+
+        estimate.pages += 1;
+        estimate.dots += dots;
+        estimate.seconds +=
+            dots as f32 * ESTIMATE_SECONDS_PER_DOT + pixels as f32 * ESTIMATE_SECONDS_PER_PIXEL;
+        largest_page_bytes = largest_page_bytes.max(sampled_bytes + drawn_bytes);
+        encoded_bytes += 2 * page_bytes / ESTIMATE_ENCODED_FRACTION;
+    }
+
+    estimate.peak_bytes = source_bytes + largest_page_bytes + encoded_bytes;
+
+    Some(estimate)
+}
+
+// the size of the `LaidOutImage`, without laying anything out
+fn laid_out_dimensions(args: &RasterizeArgs) -> (u32, u32) {
+    let pages_width_pixels = (args.pages_width as f32 * args.paper_width_pixels).ceil() as u32;
+    let pages_height_pixels = (args.pages_height as f32 * args.paper_height_pixels).ceil() as u32;
+
+    match args.layout {
+        Layout::Fit => fit_dimensions(
+            args.framing
+                .dimensions(args.image.width(), args.image.height()),
+            pages_width_pixels,
+            pages_height_pixels,
+        ),
+        // step and repeat fills the whole grid, and matrix tiles are laid out as `Fit` pages
+        Layout::StepAndRepeat | Layout::Matrix => (pages_width_pixels, pages_height_pixels),
+    }
+}
+
+// the source image with `mask` stretched over it and blended in,
+// before anything is laid out or sampled
+fn apply_mask(image: &RgbaImage, mask: &RgbaImage, blend: MaskBlend) -> RgbaImage {
+    let (width, height) = image.dimensions();
+    let mask = image::imageops::resize(mask, width, height, image::imageops::Triangle);
+    let mut masked = image.clone();
+
+    for (x, y, pixel) in masked.enumerate_pixels_mut() {
+        *pixel = blend.apply(*pixel, *mask.get_pixel(x, y));
+    }
+
+    masked
+}
+
+fn invert_channels(image: &RgbaImage, invert: ChannelInvert) -> RgbaImage {
+    let mut inverted = image.clone();
+
+    for pixel in inverted.pixels_mut() {
+        *pixel = invert.apply(*pixel);
+    }
+
+    inverted
+}
+
+// the size an image of `image_width` x `image_height` is scaled to
+// so that it fits in `width` x `height`, keeping its aspect ratio,
+// rounded the same way as `DynamicImage::resize`
+fn fit_dimensions((image_width, image_height): (u32, u32), width: u32, height: u32) -> (u32, u32) {
+    let (image_width, image_height) = (image_width as u64, image_height as u64);
+    let (width, height) = (width as u64, height as u64);
+
+    let (fit_width, fit_height) = if width * image_height <= image_width * height {
+        (width, (image_height * width / image_width).max(1))
+    } else {
+        ((image_width * height / image_height).max(1), height)
+    };
+
+    (
+        fit_width.min(u32::MAX as u64) as u32,
+        fit_height.min(u32::MAX as u64) as u32,
+    )
+}
+
+fn dot_color(args: &RasterizeArgs, average_color: Rgba<u8>, average_brightness: f32) -> Rgba<u8> {
+    match args.color_depth {
+        ColorDepth::Rgb => average_color,
+        ColorDepth::Grayscale => BLACK,
+        ColorDepth::TrueGrayscale => {
+            let level = (average_brightness.clamp(0.0, 1.0) * 255.0).round() as u8;
+            Rgba([level, level, level, 255])
+        }
+        ColorDepth::Gradient => args.gradient.at(average_brightness).to_rgba(),
+        ColorDepth::Duotone | ColorDepth::Tritone => {
+            let count = args.color_depth.ink_count().unwrap_or(1);
+            args.inks.colors[args.inks.index_at(count, average_brightness)].to_rgba()
+        }
+        ColorDepth::Palette => args
+            .palette
+            .nearest(Color::from_rgba(average_color))
+            .to_rgba(),
+    }
+}
+
+fn average_color(pixels: &[Rgba<u8>]) -> Rgba<u8> {
+    let mut r: usize = 0;
+    let mut g: usize = 0;
+    let mut b: usize = 0;
+    let mut a: usize = 0;
+
+    let pixels_len = pixels.len();
+
+    for pixel in pixels {
+        r += pixel[0] as usize;
+        g += pixel[1] as usize;
+        b += pixel[2] as usize;
+        a += pixel[3] as usize;
+    }
+
+    Rgba([
+        (r / pixels_len) as u8,
+        (g / pixels_len) as u8,
+        (b / pixels_len) as u8,
+        (a / pixels_len) as u8,
+    ])
+}
+
+fn average_brightness(pixels: &[Rgba<u8>], luma: Luma) -> f32 {
+    let i = pixels.len() as f32;
+    let mut brightness_nominal = brightness(pixels[0], luma);
+
+    for pixel in &pixels[1..] {
+        brightness_nominal += brightness(*pixel, luma);
+    }
+
+    brightness_nominal / i
+}
+
+// this function takes the adjusted min and max radii,
+// and clamps the calculated radius so that:
+// calculated_radius >= min_radius && calculated_radius <= max_radius.
+// by default, the min_radius_percentage is 0, which allows a 0-radius dot (meaning no
+// dot will be drawn), and the max_radius_percentage is 100, allowing the maximum radius
+// for a given brightness to be the "theoretical" max radius as calculated in the
+// `max_radius` function
+fn radius(
+    average_brightness: f32,
+    adjusted_min_radius: f32,
+    adjusted_max_radius: f32,
+    curve: RadiusCurve,
+) -> f32 {
+    let calculated_radius = curve.size(average_brightness) * adjusted_max_radius;
+
+    if calculated_radius < adjusted_min_radius {
+        adjusted_min_radius
+    } else {
+        calculated_radius
+    }
+}
+
+// stippled dots are all the same size, packed at most `min_distance` apart.
+// `square_size` stays the knob for how coarse the result is
+fn stipple_spacing(square_size: f32, max_radius_percentage: f32) -> (f32, f32) {
+    let min_distance = square_size / 2.0;
+    let radius = min_distance / 2.0 * max_radius_percentage;
+
+    (min_distance, radius)
+}
+
+// dot centers for one page, denser where the page is darker.
+// seeded by the page's position, so rerunning with the same settings
+// gives the same dots
+fn stipple_points<I: GenericImageView<Pixel = Rgba<u8>>>(
+    page: &I,
+    page_offset_x: u32,
+    page_offset_y: u32,
+    min_distance: f32,
+    luma: Luma,
+) -> Vec<(f32, f32)> {
+    let (width, height) = page.dimensions();
+
+    stipple::poisson_disk_points(
+        width,
+        height,
+        min_distance,
+        page_offset_x.wrapping_mul(31).wrapping_add(page_offset_y),
+        |x, y| 1.0 - brightness(page.get_pixel(x, y), luma),
+    )
+}
+
+// a dot of `Pattern::Grid`, in page sampling coordinates
+struct Dot {
+    center: (f32, f32),
+    radius: f32,
+    color: Rgba<u8>,
+    // the average brightness of the square, before dithering
+    brightness: f32,
+    // (column, row) of the square on the page
+    cell: (u32, u32),
+    // the direction of the long side of non-circular dots, in radians
+    angle: f32,
+}
+
+impl Dot {
+    // unit vectors along and across the dot's angle
+    fn axes(&self) -> ((f32, f32), (f32, f32)) {
+        let (sin, cos) = self.angle.sin_cos();
+        ((cos, sin), (-sin, cos))
+    }
+
+    // the corners of a square dot, or the outline of an elliptical one
+    fn outline(&self, shape: DotShape) -> Vec<(f32, f32)> {
+        let (half_length, half_width) = shape.half_extents(self.radius);
+        let (along, across) = self.axes();
+        let point = |a: f32, b: f32| {
+            (
+                self.center.0 + along.0 * a + across.0 * b,
+                self.center.1 + along.1 * a + across.1 * b,
+            )
+        };
+
+        match shape {
+            DotShape::Ellipse => (0..ELLIPSE_OUTLINE_POINTS)
+                .map(|i| {
+                    let t = i as f32 / ELLIPSE_OUTLINE_POINTS as f32 * std::f32::consts::TAU;
+                    point(half_length * t.cos(), half_width * t.sin())
+                })
+                .collect(),
+            _ => vec![
+                point(-half_length, -half_width),
+                point(half_length, -half_width),
+                point(half_length, half_width),
+                point(-half_length, half_width),
+            ],
+        }
+    }
+
+    // the ends and width of a dash dot's stroke. its round caps reach the rest of its length
+    fn dash(&self) -> ((f32, f32), (f32, f32), f32) {
+        let (half_length, half_width) = DotShape::Dash.half_extents(self.radius);
+        let (along, _) = self.axes();
+        let reach = half_length - half_width;
+
+        (
+            (
+                self.center.0 - along.0 * reach,
+                self.center.1 - along.1 * reach,
+            ),
+            (
+                self.center.0 + along.0 * reach,
+                self.center.1 + along.1 * reach,
+            ),
+            half_width * 2.0,
+        )
+    }
+}
+
+// the dots of `Pattern::Grid` for one page, one per square, left-right top-bottom.
+// every other row is shifted over by half a square
+fn grid_dots(page: &PageView, args: &RasterizeArgs) -> Vec<Dot> {
+    let square_size = args.square_size;
+    let max_radius = max_radius(square_size);
+    let adjusted_max_radius = max_radius * args.max_radius_percentage;
+    let adjusted_min_radius = max_radius * args.min_radius_percentage;
+    let half_square_size = (square_size / 2.0).floor() as i32;
+
+    let (squares_width, squares_height) = cells_across(page.dimensions(), square_size);
+    let mut ditherer = Ditherer::new(args.dither, squares_width, squares_height);
+
+    let mut dots = Vec::with_capacity((squares_width * squares_height) as usize);
+
+    let (page_offset_x, page_offset_y, _, _) = page.bounds();
+    let mut rng = args.jitter.rng(page_offset_x, page_offset_y);
+    let line_angle = args.line_angle.to_radians();
+
+    for_each_cell(&PageSums::new(page, args.luma), args, true, |cell| {
+        if args.transparency.skip_transparent && cell.transparent {
+            return;
+        }
+
+        let radius = radius(
+            ditherer.quantize(cell.column, cell.row, cell.brightness),
+            adjusted_min_radius,
+            adjusted_max_radius,
+            args.radius_curve,
+        );
+
+        let center = (
+            (cell.left as i32 + half_square_size) as f32,
+            (cell.top as i32 + half_square_size) as f32,
+        );
+
+        let angle = if args.follow_contours && args.dot_shape != DotShape::Circle {
+            contour_angle(page, center, half_square_size.max(1) as u32, args.luma)
+                .unwrap_or(line_angle)
+        } else {
+            line_angle
+        };
+
+        let mut dot = Dot {
+            center,
+            radius,
+            color: cell.color,
+            brightness: cell.brightness,
+            cell: (cell.column, cell.row),
+            angle,
+        };
+
+        if args.jitter.is_any() {
+            args.jitter.apply(&mut dot, square_size, &mut rng);
+        }
+
+        if args.min_dot_size.keeps(dot.radius) {
+            dots.push(dot);
+        }
+    });
+
+    // past 100%, dots spill over onto their neighbors and merge into solid fields.
+    // the biggest dots go down first, so the smaller ones stay visible on top of them
+    if args.max_radius_percentage > 1.0 {
+        dots.sort_by(|a, b| {
+            b.radius
+                .partial_cmp(&a.radius)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    dots
+}
+
+// one square of a page's grid, with what was sampled from under it
+struct Cell {
+    column: u32,
+    row: u32,
+    left: u32,
+    top: u32,
+    right: u32,
+    bottom: u32,
+    brightness: f32,
+    color: Rgba<u8>,
+    // every pixel under it is fully transparent
+    transparent: bool,
+}
+
+// running totals of a page's pixels (a summed-area table), each the total of every pixel
+// above and to the left of it. the total of any rectangle of the page is then the
+// difference of the totals at its corners, so averaging a square takes four lookups
+// however many pixels it covers
+struct PageSums {
+    width: u32,
+    height: u32,
+    // a row and a column wider than the page, so the totals start from 0
+    stride: usize,
+    colors: ColorTotals,
+    brightness: Vec<f64>,
+}
+
+// the color totals of a `PageSums`, as u32 when even a page of pure white fits in them,
+// which it does up to about 16 million pixels, for half the memory of u64 totals
+enum ColorTotals {
+    Narrow(Vec<[u32; CHANNELS]>),
+    Wide(Vec<[u64; CHANNELS]>),
+}
+
+impl ColorTotals {
+    fn get(&self, i: usize) -> [u64; CHANNELS] {
+        match self {
+            ColorTotals::Narrow(totals) => totals[i].map(u64::from),
+            ColorTotals::Wide(totals) => totals[i],
+        }
+    }
+}
+
+impl PageSums {
+    fn new(page: &PageView, luma: Luma) -> Self {
+        let (width, height) = page.dimensions();
+        let stride = width as usize + 1;
+        let colors = if Self::fits_u32(width, height) {
+            ColorTotals::Narrow(summed_colors(page, stride))
+        } else {
+            ColorTotals::Wide(summed_colors(page, stride))
+        };
+        let mut brightnesses = vec![0.0; stride * (height as usize + 1)];
+
+        for y in 0..height as usize {
+            let mut row_brightness = 0.0;
+
+            for (x, pixel) in page.row(0, y as u32, width).enumerate() {
+                row_brightness += brightness(pixel, luma) as f64;
+
+                let i = (y + 1) * stride + x + 1;
+                brightnesses[i] = brightnesses[i - stride] + row_brightness;
+            }
+        }
+
+        PageSums {
+            width,
+            height,
+            stride,
+            colors,
+            brightness: brightnesses,
+        }
+    }
+
+    fn fits_u32(width: u32, height: u32) -> bool {
+        width as u64 * height as u64 * u8::MAX as u64 <= u32::MAX as u64
+    }
+
+    // the memory taken by the table of a page of `width` x `height` pixels
+    fn bytes(width: u32, height: u32) -> u64 {
+        let total_bytes = if Self::fits_u32(width, height) { 4 } else { 8 };
+        let per_pixel = CHANNELS as u64 * total_bytes + std::mem::size_of::<f64>() as u64;
+        (width as u64 + 1) * (height as u64 + 1) * per_pixel
+    }
+
+    // the average color and brightness of the pixels of `rectangle`,
+    // from (left, top) up to but not including (right, bottom)
+    fn average(&self, rectangle: (u32, u32, u32, u32)) -> (Rgba<u8>, f32) {
+        let (left, top, right, bottom) = rectangle;
+        let count = (right - left) as u64 * (bottom - top) as u64;
+        let color = self
+            .color_total(rectangle)
+            .map(|total| (total / count) as u8);
+
+        let [top_left, top_right, bottom_left, bottom_right] = self
+            .corners(rectangle)
+            .map(|corner| self.brightness[corner]);
+        let brightness = (bottom_right + top_left - top_right - bottom_left) / count as f64;
+
+        // the differences of big totals can land a hair outside of 0..=1
+        (Rgba(color), brightness.clamp(0.0, 1.0) as f32)
+    }
+
+    // whether every pixel of `rectangle` is fully transparent
+    fn is_transparent(&self, rectangle: (u32, u32, u32, u32)) -> bool {
+        self.color_total(rectangle)[3] == 0
+    }
+
+    fn color_total(&self, rectangle: (u32, u32, u32, u32)) -> [u64; CHANNELS] {
+        let [top_left, top_right, bottom_left, bottom_right] = self
+            .corners(rectangle)
+            .map(|corner| self.colors.get(corner));
+
+        let mut total = [0; CHANNELS];
+        for (channel, total) in total.iter_mut().enumerate() {
+            *total = bottom_right[channel] + top_left[channel]
+                - top_right[channel]
+                - bottom_left[channel];
+        }
+
+        total
+    }
+
+    fn corners(&self, (left, top, right, bottom): (u32, u32, u32, u32)) -> [usize; 4] {
+        let at = |x: u32, y: u32| y as usize * self.stride + x as usize;
+
+        [
+            at(left, top),
+            at(right, top),
+            at(left, bottom),
+            at(right, bottom),
+        ]
+    }
+}
+
+// the color totals of a `PageSums` table
+fn summed_colors<T>(page: &PageView, stride: usize) -> Vec<[T; CHANNELS]>
+where
+    T: Copy + Default + From<u8> + std::ops::Add<Output = T>,
+{
+    let (width, height) = page.dimensions();
+    let mut colors = vec![[T::default(); CHANNELS]; stride * (height as usize + 1)];
+
+    for y in 0..height as usize {
+        let mut row_color = [T::default(); CHANNELS];
+
+        for (x, pixel) in page.row(0, y as u32, width).enumerate() {
+            for (total, channel) in row_color.iter_mut().zip(pixel.0) {
+                *total = *total + T::from(channel);
+            }
+
+            let i = (y + 1) * stride + x + 1;
+            let above = colors[i - stride];
+            for (total, (above, row)) in colors[i].iter_mut().zip(above.iter().zip(row_color)) {
+                *total = *above + row;
+            }
+        }
+    }
+
+    colors
+}
+
+// the average color and brightness of a whole page, without a `PageSums` to look it up in
+fn page_average(page: &PageView, luma: Luma) -> (Rgba<u8>, f32) {
+    let (width, height) = page.dimensions();
+    let mut color = [0u64; CHANNELS];
+    let mut total_brightness = 0.0f64;
+
+    for y in 0..height {
+        for pixel in page.row(0, y, width) {
+            for (total, channel) in color.iter_mut().zip(pixel.0) {
+                *total += channel as u64;
+            }
+            total_brightness += brightness(pixel, luma) as f64;
+        }
+    }
+
+    let count = width as u64 * height as u64;
+    let color = color.map(|total| (total / count) as u8);
+    let average_brightness = total_brightness / count as f64;
+
+    (Rgba(color), average_brightness.clamp(0.0, 1.0) as f32)
+}
+
+// how many columns and rows of `square_size` squares it takes to cover a page
+// of `width` x `height` pixels
+fn cells_across((width, height): (u32, u32), square_size: f32) -> (u32, u32) {
+    (
+        (width as f32 / square_size).ceil() as u32,
+        (height as f32 / square_size).ceil() as u32,
+    )
+}
+
+// calls `f` with each of a page's squares, left-right top-bottom.
+// this is the one place squares are laid out and sampled, so every pattern and every
+// backend sees the same squares. squares run up to where the next one starts, and are cut
+// short by the edges of the page. with `stagger`, every other row is shifted over by half
+// a square, and a square pushed off the page altogether is skipped
+fn for_each_cell(sums: &PageSums, args: &RasterizeArgs, stagger: bool, mut f: impl FnMut(&Cell)) {
+    let (width, height) = (sums.width, sums.height);
+    let square_size = args.square_size;
+    let half_square_size = (square_size / 2.0).floor() as u32;
+    let (squares_width, squares_height) = cells_across((width, height), square_size);
+
+    for row in 0..squares_height {
+        let top = span_start(row, square_size);
+        let bottom = (span_start(row + 1, square_size).max(top + 1)).min(height);
+        let shift = if stagger && row % 2 == 1 {
+            half_square_size
+        } else {
+            0
+        };
+
+        for column in 0..squares_width {
+            let start = span_start(column, square_size);
+            let left = start + shift;
+            let right = (left + (span_start(column + 1, square_size) - start).max(1)).min(width);
+
+            if left >= right || top >= bottom {
+                continue;
+            }
+
+            let square = (left, top, right, bottom);
+            let (color, brightness) = sums.average(square);
+            let cell = Cell {
+                column,
+                row,
+                left,
+                top,
+                right,
+                bottom,
+                brightness,
+                color: dot_color(args, color, brightness),
+                transparent: sums.is_transparent(square),
+            };
+
+            f(&cell);
+        }
+    }
+}
+
+// the strokes of the edge layer for one page, in page coordinates.
+// none if it is off, or if it isn't in the ink layer being drawn
+fn page_edges<I: GenericImageView<Pixel = Rgba<u8>>>(
+    page: &I,
+    args: &RasterizeArgs,
+) -> Vec<Vec<(f32, f32)>> {
+    if !args.edges.enabled || !args.draws(edge_color(args)) {
+        return vec![];
+    }
+
+    let (width, height) = page.dimensions();
+    let brightness = page
+        .pixels()
+        .map(|(_, _, pixel)| brightness(pixel, args.luma))
+        .collect::<Vec<_>>();
+
+    edges::edge_strokes(&brightness, width, height, args.edges.threshold)
+}
+
+// edges are drawn in the color of the darkest dots
+fn edge_color(args: &RasterizeArgs) -> Rgba<u8> {
+    dot_color(args, BLACK, 0.0)
+}
+
+// the direction the image's contours run through `center`, along which brightness
+// doesn't change, from a sobel filter over pixels `step` apart. `None` where the image is flat
+fn contour_angle<I: GenericImageView<Pixel = Rgba<u8>>>(
+    page: &I,
+    center: (f32, f32),
+    step: u32,
+    luma: Luma,
+) -> Option<f32> {
+    let (width, height) = page.dimensions();
+    let (x, y) = (center.0 as i64, center.1 as i64);
+
+    let (gradient_x, gradient_y) = edges::sobel(|dx, dy| {
+        let sample_x = (x + dx as i64 * step as i64).clamp(0, width as i64 - 1);
+        let sample_y = (y + dy as i64 * step as i64).clamp(0, height as i64 - 1);
+        brightness(page.get_pixel(sample_x as u32, sample_y as u32), luma)
+    });
+
+    if gradient_x.hypot(gradient_y) < MIN_CONTOUR_GRADIENT {
+        None
+    } else {
+        // contours run square to the gradient
+        Some(gradient_y.atan2(gradient_x) + std::f32::consts::FRAC_PI_2)
+    }
+}
+
+// a piece of a halftone line, with a single stroke width and color
+struct LineSegment {
+    from: (f32, f32),
+    to: (f32, f32),
+    width: f32,
+    color: Rgba<u8>,
+}
+
+impl LineSegment {
+    fn reverse(&mut self) {
+        std::mem::swap(&mut self.from, &mut self.to);
+    }
+}
+
+// the lines of `Pattern::Lines` for one page, in page coordinates.
+// each line is cut into `square_size` long segments, each sampling
+// the square of the page around its middle
+fn line_segments(page: &PageView, args: &RasterizeArgs) -> Vec<LineSegment> {
+    let (width, height) = page.dimensions();
+    let square_size = args.square_size;
+    let max_width = square_size * args.max_radius_percentage;
+    let min_width = square_size * args.min_radius_percentage;
+    let angle = args.line_angle.to_radians();
+    let direction = (angle.cos(), angle.sin());
+    let normal = (-direction.1, direction.0);
+
+    // how far along `direction` and `normal` the page extends
+    let corners = [
+        (0.0, 0.0),
+        (width as f32, 0.0),
+        (0.0, height as f32),
+        (width as f32, height as f32),
+    ];
+    let extent = |axis: (f32, f32)| {
+        corners
+            .iter()
+            .map(|(x, y)| x * axis.0 + y * axis.1)
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| {
+                (min.min(v), max.max(v))
+            })
+    };
+    let (along_start, along_end) = extent(direction);
+    let (across_start, across_end) = extent(normal);
+
+    let sums = PageSums::new(page, args.luma);
+    let mut segments = vec![];
+
+    let mut across = (across_start / square_size).floor() * square_size + square_size / 2.0;
+    while across < across_end {
+        let mut along = (along_start / square_size).floor() * square_size;
+
+        while along < along_end {
+            let point = |t: f32| {
+                (
+                    across * normal.0 + t * direction.0,
+                    across * normal.1 + t * direction.1,
+                )
+            };
+            let from = point(along);
+            let to = point(along + square_size);
+            let middle = point(along + square_size / 2.0);
+            along += square_size;
+
+            if middle.0 < 0.0
+                || middle.1 < 0.0
+                || middle.0 >= width as f32
+                || middle.1 >= height as f32
+            {
+                continue;
+            }
+
+            let left = (middle.0 - square_size / 2.0).max(0.0) as u32;
+            let top = (middle.1 - square_size / 2.0).max(0.0) as u32;
+            let right = ((middle.0 + square_size / 2.0) as u32).clamp(left + 1, width);
+            let bottom = ((middle.1 + square_size / 2.0) as u32).clamp(top + 1, height);
+
+            let (average_color, average_brightness) = sums.average((left, top, right, bottom));
+            let stroke_width = ((1.0 - average_brightness) * max_width).max(min_width);
+
+            if stroke_width > 0.0 {
+                segments.push(LineSegment {
+                    from,
+                    to,
+                    width: stroke_width,
+                    color: dot_color(args, average_color, average_brightness),
+                });
+            }
+        }
+
+        across += square_size;
+    }
+
+    segments
+}
+
+// a single row's wave, as a polyline in page coordinates
+struct Wave {
+    points: Vec<(f32, f32)>,
+    width: f32,
+    color: Rgba<u8>,
+}
+
+impl Wave {
+    fn start(&self) -> (f32, f32) {
+        self.points.first().copied().unwrap_or((0.0, 0.0))
+    }
+
+    fn reverse(&mut self) {
+        self.points.reverse();
+    }
+}
+
+// the waves of `Pattern::Waves` for one page, one per row of squares.
+// darkness is interpolated between the middles of neighboring squares,
+// and the wave's phase carries on across them, so each row is one smooth line
+fn waves(page: &PageView, args: &RasterizeArgs) -> Vec<Wave> {
+    let width = page.width();
+    let square_size = args.square_size;
+    let max_amplitude = square_size / 2.0 * args.max_radius_percentage;
+    let min_amplitude = square_size / 2.0 * args.min_radius_percentage;
+    let stroke_width = (square_size * WAVE_STROKE_WIDTH_PERCENTAGE).max(1.0);
+
+    let sums = PageSums::new(page, args.luma);
+
+    // the top and bottom of each row, and each of its squares' darkness
+    let mut rows: Vec<(u32, u32, Vec<f32>)> = vec![];
+    for_each_cell(&sums, args, false, |cell| {
+        if cell.column == 0 {
+            rows.push((cell.top, cell.bottom, vec![]));
+        }
+        if let Some((_, _, darkness)) = rows.last_mut() {
+            darkness.push(1.0 - cell.brightness);
+        }
+    });
+
+    rows.into_iter()
+        .map(|(top, bottom, darkness)| {
+            let middle_y = (top + bottom) as f32 / 2.0;
+
+            // darkness at `x`, between the middles of the squares on either side of it
+            let darkness_at = |x: f32| {
+                let position = (x / square_size - 0.5).max(0.0);
+                let index = (position as usize).min(darkness.len() - 1);
+                let next = (index + 1).min(darkness.len() - 1);
+                let t = (position - index as f32).min(1.0);
+                darkness[index] + (darkness[next] - darkness[index]) * t
+            };
+
+            let mut points = vec![];
+            let mut phase = 0.0f32;
+            let mut x = 0.0;
+            while x <= width as f32 {
+                let darkness = darkness_at(x);
+                let amplitude = (darkness * max_amplitude).max(min_amplitude);
+                points.push((x, middle_y + amplitude * phase.sin()));
+
+                let cycles_per_square = 1.0 + darkness * (MAX_WAVE_CYCLES_PER_SQUARE - 1.0);
+                phase += std::f32::consts::TAU * cycles_per_square * WAVE_STEP / square_size;
+                x += WAVE_STEP;
+            }
+
+            let (row_color, row_brightness) = sums.average((0, top, width, bottom));
+
+            Wave {
+                points,
+                width: stroke_width,
+                color: dot_color(args, row_color, row_brightness),
+            }
+        })
+        .collect()
+}
+
+// the hatching of a single square of `Pattern::Crosshatch`, in page coordinates
+struct Hatch {
+    lines: Vec<((f32, f32), (f32, f32))>,
+    width: f32,
+    color: Rgba<u8>,
+}
+
+impl Hatch {
+    fn start(&self) -> (f32, f32) {
+        self.lines.first().map_or((0.0, 0.0), |(from, _)| *from)
+    }
+
+    fn reverse(&mut self) {
+        self.lines.reverse();
+        for (from, to) in self.lines.iter_mut() {
+            std::mem::swap(from, to);
+        }
+    }
+}
+
+// the hatches of `Pattern::Crosshatch` for one page, one per non-blank square.
+// darker squares layer on more of `HATCH_ANGLES`. every square's lines are cut
+// from the same page-wide families of parallel lines, so neighboring squares
+// with the same layers join up into continuous strokes
+fn hatches(page: &PageView, args: &RasterizeArgs) -> Vec<Hatch> {
+    let square_size = args.square_size;
+    let spacing = square_size / HATCH_LINES_PER_SQUARE;
+    let stroke_width = (square_size * HATCH_STROKE_WIDTH_PERCENTAGE).max(1.0);
+
+    let mut hatches = vec![];
+
+    for_each_cell(&PageSums::new(page, args.luma), args, false, |cell| {
+        let darkness = (1.0 - cell.brightness).max(args.min_radius_percentage);
+        let layers =
+            ((darkness * HATCH_ANGLES.len() as f32).round() as usize).min(HATCH_ANGLES.len());
+
+        if layers == 0 {
+            return;
+        }
+
+        let square = (
+            cell.left as f32,
+            cell.top as f32,
+            cell.right as f32,
+            cell.bottom as f32,
+        );
+        let lines = HATCH_ANGLES[..layers]
+            .iter()
+            .flat_map(|angle| hatch_lines(square, angle.to_radians(), spacing))
+            .collect::<Vec<_>>();
+
+        hatches.push(Hatch {
+            lines,
+            width: stroke_width,
+            color: cell.color,
+        });
+    });
+
+    hatches
+}
+
+// the lines at `angle` and `spacing` apart from each other that cross
+// the `(left, top, right, bottom)` square, clipped to it.
+// lines sit halfway between multiples of `spacing` so that the horizontal
+// and vertical ones don't run along square edges
+fn hatch_lines(
+    (left, top, right, bottom): (f32, f32, f32, f32),
+    angle: f32,
+    spacing: f32,
+) -> Vec<((f32, f32), (f32, f32))> {
+    let direction = (angle.cos(), angle.sin());
+    let normal = (-direction.1, direction.0);
+
+    let corners = [(left, top), (right, top), (left, bottom), (right, bottom)];
+    let across = corners.iter().map(|(x, y)| x * normal.0 + y * normal.1);
+    let across_start = across.clone().fold(f32::INFINITY, f32::min);
+    let across_end = across.fold(f32::NEG_INFINITY, f32::max);
+
+    // how far along `direction` a line from `origin` stays within `min..max` on one axis
+    let slab = |origin: f32, direction: f32, min: f32, max: f32| {
+        if direction.abs() < f32::EPSILON {
+            if origin >= min && origin <= max {
+                Some((f32::NEG_INFINITY, f32::INFINITY))
+            } else {
+                None
+            }
+        } else {
+            let a = (min - origin) / direction;
+            let b = (max - origin) / direction;
+            Some((a.min(b), a.max(b)))
+        }
+    };
+
+    let mut lines = vec![];
+    let mut offset = ((across_start / spacing - 0.5).ceil() + 0.5) * spacing;
+    while offset < across_end {
+        let origin = (offset * normal.0, offset * normal.1);
+        offset += spacing;
+
+        if let (Some((x_start, x_end)), Some((y_start, y_end))) = (
+            slab(origin.0, direction.0, left, right),
+            slab(origin.1, direction.1, top, bottom),
+        ) {
+            let start = x_start.max(y_start);
+            let end = x_end.min(y_end);
+            if start < end {
+                lines.push((
+                    (
+                        origin.0 + start * direction.0,
+                        origin.1 + start * direction.1,
+                    ),
+                    (origin.0 + end * direction.0, origin.1 + end * direction.1),
+                ));
+            }
+        }
+    }
+
+    lines
+}
+
+// the single line of `Pattern::Spiral` for one page, in page coordinates.
+// an archimedean spiral out from the middle of the page, `square_size` between turns,
+// that wobbles like `Pattern::Waves` where the image is darker. it stays within
+// the largest circle that fits on the page, so it is one unbroken path
+fn spiral(page: &PageView, args: &RasterizeArgs) -> Wave {
+    let (width, height) = page.dimensions();
+    let square_size = args.square_size;
+    let max_amplitude = square_size / 2.0 * args.max_radius_percentage;
+    let min_amplitude = square_size / 2.0 * args.min_radius_percentage;
+    let stroke_width = (square_size * WAVE_STROKE_WIDTH_PERCENTAGE).max(1.0);
+    let (squares_width, squares_height) = cells_across(page.dimensions(), square_size);
+
+    let mut darkness = Vec::with_capacity((squares_width * squares_height) as usize);
+
+    for_each_cell(&PageSums::new(page, args.luma), args, false, |cell| {
+        darkness.push(1.0 - cell.brightness);
+    });
+
+    let darkness_at = |(x, y): (f32, f32)| {
+        let square_x = ((x / square_size) as u32).min(squares_width - 1);
+        let square_y = ((y / square_size) as u32).min(squares_height - 1);
+        darkness[(square_y * squares_width + square_x) as usize]
+    };
+
+    let center = (width as f32 / 2.0, height as f32 / 2.0);
+    // leave room for the wobble of the outermost turn
+    let max_radius = center.0.min(center.1) - max_amplitude.max(min_amplitude);
+
+    let mut points = vec![];
+    let mut phase = 0.0f32;
+    let mut angle = 0.0f32;
+    loop {
+        let radius = square_size * angle / std::f32::consts::TAU;
+        if radius > max_radius {
+            break;
+        }
+
+        let direction = (angle.cos(), angle.sin());
+        let on_spiral = (
+            center.0 + radius * direction.0,
+            center.1 + radius * direction.1,
+        );
+        let darkness = darkness_at(on_spiral);
+        let amplitude = (darkness * max_amplitude).max(min_amplitude) * phase.sin();
+        points.push((
+            on_spiral.0 + amplitude * direction.0,
+            on_spiral.1 + amplitude * direction.1,
+        ));
+
+        // step a roughly constant distance along the spiral
+        angle += WAVE_STEP / radius.max(WAVE_STEP);
+        let cycles_per_square = 1.0 + darkness * (MAX_WAVE_CYCLES_PER_SQUARE - 1.0);
+        phase += std::f32::consts::TAU * cycles_per_square * WAVE_STEP / square_size;
+    }
+
+    let (page_color, page_brightness) = page_average(page, args.luma);
+
+    Wave {
+        points,
+        width: stroke_width,
+        color: dot_color(args, page_color, page_brightness),
+    }
+}
+
+// a2 + b2 = diameter
+// diameter = sqrt(a2 + b2)
+// radius = diameter / 2
+// this is the minimum radius of a circle necessary to fully enclose a square
+// it is the "theoretical" max radius for any given raster square
+// is clamped by the function `radius`
+fn max_radius(square_size: f32) -> f32 {
+    (square_size.powf(2.0) * 2.0).sqrt() / 2.0
+}
+
+fn brightness(pixel: Rgba<u8>, luma: Luma) -> f32 {
+    let r = pixel[0] as f32 / 255.0;
+    let g = pixel[1] as f32 / 255.0;
+    let b = pixel[2] as f32 / 255.0;
+
+    match luma {
+        Luma::Rec601 => 0.299 * r + 0.587 * g + 0.114 * b,
+        Luma::Rec709 => 0.2126 * r + 0.7152 * g + 0.0722 * b,
+        Luma::Average => (r + g + b) / 3.0,
+        Luma::Value => r.max(g).max(b),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ColorDepth {
+    Rgb,
+    Grayscale,
+    // like `Grayscale`, but each dot is the gray of its square's average luminance,
+    // rather than black, for smoother tones with big squares
+    TrueGrayscale,
+    // dots are colored along `RasterizeArgs::gradient` by their brightness
+    Gradient,
+    // the tonal range is split between the first two (or all three) `RasterizeArgs::inks`,
+    // each printed as its own layer
+    Duotone,
+    Tritone,
+    // every shape is snapped to the nearest of `RasterizeArgs::palette`,
+    // each color printed as its own layer
+    Palette,
+}
+
+impl ColorDepth {
+    pub fn ink_count(self) -> Option<usize> {
+        match self {
+            ColorDepth::Duotone => Some(2),
+            ColorDepth::Tritone => Some(3),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ColorDepth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ColorDepth::Rgb => "RGB",
+            ColorDepth::Grayscale => "Grayscale",
+            ColorDepth::TrueGrayscale => "True grayscale",
+            ColorDepth::Gradient => "Gradient",
+            ColorDepth::Duotone => "Duotone",
+            ColorDepth::Tritone => "Tritone",
+            ColorDepth::Palette => "Palette",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// quantizes each cell's brightness to either a full dot or no dot,
+// spreading the difference out over the neighboring cells
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Dither {
+    // dot size follows brightness
+    None,
+    FloydSteinberg,
+    // 4x4 Bayer matrix thresholding
+    Ordered,
+}
+
+impl Dither {
+    pub fn from_string(s: &str) -> Option<Dither> {
+        match s {
+            "None" => Some(Dither::None),
+            "Floyd-Steinberg" => Some(Dither::FloydSteinberg),
+            "Ordered" => Some(Dither::Ordered),
+            _ => None,
+        }
+    }
+
+    pub fn dithers() -> Iter<'static, Self> {
+        const DITHERS: [Dither; 3] = [Dither::None, Dither::FloydSteinberg, Dither::Ordered];
+        DITHERS.iter()
+    }
+}
+
+impl fmt::Display for Dither {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Dither::None => "None",
+            Dither::FloydSteinberg => "Floyd-Steinberg",
+            Dither::Ordered => "Ordered",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// how `build_up` shows a poster being made
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BuildUp {
+    None,
+    // every dot grows from nothing to its size
+    Grow,
+    // rows of squares are added top to bottom
+    Sweep,
+}
+
+impl BuildUp {
+    pub fn from_string(s: &str) -> Option<BuildUp> {
+        match s {
+            "None" => Some(BuildUp::None),
+            "Dots grow" => Some(BuildUp::Grow),
+            "Rows sweep in" => Some(BuildUp::Sweep),
+            _ => None,
+        }
+    }
+
+    pub fn build_ups() -> Iter<'static, Self> {
+        const BUILD_UPS: [BuildUp; 3] = [BuildUp::None, BuildUp::Grow, BuildUp::Sweep];
+        BUILD_UPS.iter()
+    }
+}
+
+impl fmt::Display for BuildUp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            BuildUp::None => "None",
+            BuildUp::Grow => "Dots grow",
+            BuildUp::Sweep => "Rows sweep in",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// the order the vector and g-code backends draw each page's shapes in
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum RowOrder {
+    // as each pattern makes them, mostly left to right along each row
+    RowMajor,
+    // boustrophedon: every other row right to left, so there is no travel back across the page
+    Serpentine,
+}
+
+impl RowOrder {
+    pub fn from_string(s: &str) -> Option<RowOrder> {
+        match s {
+            "Row major" => Some(RowOrder::RowMajor),
+            "Serpentine" => Some(RowOrder::Serpentine),
+            _ => None,
+        }
+    }
+
+    pub fn row_orders() -> Iter<'static, Self> {
+        const ROW_ORDERS: [RowOrder; 2] = [RowOrder::RowMajor, RowOrder::Serpentine];
+        ROW_ORDERS.iter()
+    }
+}
+
+impl fmt::Display for RowOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            RowOrder::RowMajor => "Row major",
+            RowOrder::Serpentine => "Serpentine",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// the filter the source image is scaled onto the page grid with.
+// `Nearest` skips pixels when shrinking, which shows up as banding in the dot sizes.
+// the sharper filters can leave faint halos around hard edges
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Resampling {
+    Nearest,
+    // bilinear, smooth without halos
+    Triangle,
+    CatmullRom,
+    Lanczos3,
+}
+
+impl Resampling {
+    pub fn from_string(s: &str) -> Option<Resampling> {
+        match s {
+            "Nearest" => Some(Resampling::Nearest),
+            "Triangle" => Some(Resampling::Triangle),
+            "Catmull-Rom" => Some(Resampling::CatmullRom),
+            "Lanczos3" => Some(Resampling::Lanczos3),
+            _ => None,
+        }
+    }
+
+    pub fn resamplings() -> Iter<'static, Self> {
+        const RESAMPLINGS: [Resampling; 4] = [
+            Resampling::Nearest,
+            Resampling::Triangle,
+            Resampling::CatmullRom,
+            Resampling::Lanczos3,
+        ];
+        RESAMPLINGS.iter()
+    }
+
+    fn filter(self) -> image::imageops::FilterType {
+        match self {
+            Resampling::Nearest => image::imageops::Nearest,
+            Resampling::Triangle => image::imageops::Triangle,
+            Resampling::CatmullRom => image::imageops::CatmullRom,
+            Resampling::Lanczos3 => image::imageops::Lanczos3,
+        }
+    }
+}
+
+impl fmt::Display for Resampling {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Resampling::Nearest => "Nearest",
+            Resampling::Triangle => "Triangle",
+            Resampling::CatmullRom => "Catmull-Rom",
+            Resampling::Lanczos3 => "Lanczos3",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// how the brightness of a pixel is worked out from its channels.
+// the standard lumas weigh green most, the way eyes do
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Luma {
+    // standard definition video, and what sampling always used before
+    Rec601,
+    // hd video and srgb
+    Rec709,
+    // every channel counts the same
+    Average,
+    // the brightest channel, as in hsv, so saturated colors come out light
+    Value,
+}
+
+impl Luma {
+    pub fn from_string(s: &str) -> Option<Luma> {
+        match s {
+            "Rec. 601" => Some(Luma::Rec601),
+            "Rec. 709" => Some(Luma::Rec709),
+            "Average" => Some(Luma::Average),
+            "Value" => Some(Luma::Value),
+            _ => None,
+        }
+    }
+
+    pub fn lumas() -> Iter<'static, Self> {
+        const LUMAS: [Luma; 4] = [Luma::Rec601, Luma::Rec709, Luma::Average, Luma::Value];
+        LUMAS.iter()
+    }
+}
+
+impl fmt::Display for Luma {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Luma::Rec601 => "Rec. 601",
+            Luma::Rec709 => "Rec. 709",
+            Luma::Average => "Average",
+            Luma::Value => "Value",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// the color space that png and svg outputs declare. some print rips
+// shift the colors of files that don't say what their colors mean
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ColorProfile {
+    // no color space information at all
+    Untagged,
+    Srgb,
+}
+
+impl ColorProfile {
+    pub fn from_string(s: &str) -> Option<ColorProfile> {
+        match s {
+            "None" => Some(ColorProfile::Untagged),
+            "sRGB" => Some(ColorProfile::Srgb),
+            _ => None,
+        }
+    }
+
+    pub fn color_profiles() -> Iter<'static, Self> {
+        const COLOR_PROFILES: [ColorProfile; 2] = [ColorProfile::Untagged, ColorProfile::Srgb];
+        COLOR_PROFILES.iter()
+    }
+
+    // adds the chunks for the profile to an encoded png, right after its header.
+    // along with `sRGB`, the spec recommends `gAMA` and `cHRM` with the same meaning
+    // for decoders that don't understand it
+    pub fn tag_png(self, png: Vec<u8>) -> Vec<u8> {
+        if self == ColorProfile::Untagged {
+            return png;
+        }
+
+        let mut chromaticities = vec![];
+        // white point, then red, green and blue, times 100000
+        for value in [31270u32, 32900, 64000, 33000, 30000, 60000, 15000, 6000] {
+            chromaticities.extend_from_slice(&value.to_be_bytes());
+        }
+
+        let mut chunks = vec![];
+        // perceptual rendering intent
+        chunks.extend(png_chunk(b"sRGB", &[0]));
+        chunks.extend(png_chunk(b"gAMA", &45455u32.to_be_bytes()));
+        chunks.extend(png_chunk(b"cHRM", &chromaticities));
+
+        insert_png_chunks(png, &chunks)
+    }
+
+    pub fn tag_svg(self, document: svg::Document) -> svg::Document {
+        match self {
+            ColorProfile::Untagged => document,
+            ColorProfile::Srgb => document.set("color-interpolation", "sRGB"),
+        }
+    }
+}
+
+impl fmt::Display for ColorProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ColorProfile::Untagged => "None",
+            ColorProfile::Srgb => "sRGB",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// idat chunks are written out once this much of the image has been deflated
+const PNG_IDAT_BYTES: usize = 1 << 16;
+
+// a png, written a few rows at a time, so the whole image never has to be held
+// as raw pixels. rgba with 8 bits a channel, filtered and deflated the way
+// `image`'s png encoder does by default
+pub struct PngWriter {
+    width: u32,
+    rows: deflate::write::ZlibEncoder<IdatChunks>,
+    // the row being filtered, kept to be reused for the next
+    filtered_row: Vec<u8>,
+}
+
+impl PngWriter {
+    pub fn new(width: u32, height: u32) -> Self {
+        let mut header = vec![];
+        header.extend_from_slice(&width.to_be_bytes());
+        header.extend_from_slice(&height.to_be_bytes());
+        // 8 bit rgba, deflated, filtered a row at a time, not interlaced
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend(png_chunk(b"IHDR", &header));
+
+        PngWriter {
+            width,
+            rows: deflate::write::ZlibEncoder::new(
+                IdatChunks {
+                    png,
+                    pending: vec![],
+                },
+                deflate::Compression::Fast,
+            ),
+            filtered_row: vec![],
+        }
+    }
+
+    // whole rows of rgba pixels, top to bottom
+    pub fn write_rows(&mut self, pixels: &[u8]) {
+        let row_bytes = self.width as usize * CHANNELS;
+
+        for row in pixels.chunks_exact(row_bytes.max(1)) {
+            // `Sub` filtering, each byte as the difference from the same channel of
+            // the pixel to its left, which deflates far better on flat runs of color
+            let filtered = &mut self.filtered_row;
+            filtered.clear();
+            filtered.push(1);
+            filtered.extend(row.iter().enumerate().map(
+                |(i, byte)| match i.checked_sub(CHANNELS) {
+                    Some(left) => byte.wrapping_sub(row[left]),
+                    None => *byte,
+                },
+            ));
+
+            self.rows
+                .write_all(filtered)
+                .expect("writing to memory can't fail");
+        }
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        let mut chunks = self.rows.finish().expect("writing to memory can't fail");
+        chunks.write_idat();
+        chunks.png.extend(png_chunk(b"IEND", &[]));
+
+        chunks.png
+    }
+}
+
+// deflated rows, cut into idat chunks as they come
+struct IdatChunks {
+    png: Vec<u8>,
+    pending: Vec<u8>,
+}
+
+impl IdatChunks {
+    fn write_idat(&mut self) {
+        if !self.pending.is_empty() {
+            self.png.extend(png_chunk(b"IDAT", &self.pending));
+            self.pending.clear();
+        }
+    }
+}
+
+impl Write for IdatChunks {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.pending.extend_from_slice(bytes);
+        if self.pending.len() >= PNG_IDAT_BYTES {
+            self.write_idat();
+        }
+
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// a page as a jpeg of `quality` from 1 to 100. jpegs have no transparency,
+// so anything transparent comes out as the white of the paper
+pub fn encode_jpeg(page: &RgbaImage, quality: u8) -> Vec<u8> {
+    let flattened = ImageBuffer::from_fn(page.width(), page.height(), |x, y| {
+        let [r, g, b, a] = page.get_pixel(x, y).0;
+        let over_white =
+            |channel: u8| ((channel as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+        image::Rgb([over_white(r), over_white(g), over_white(b)])
+    });
+
+    let mut jpeg = vec![];
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, quality.clamp(1, 100))
+        .encode(
+            flattened.as_raw(),
+            page.width(),
+            page.height(),
+            image::ColorType::Rgb8,
+        )
+        .expect("jpegs encode into memory");
+
+    jpeg
+}
+
+// puts encoded `chunks` into an encoded png, right after its header
+pub fn insert_png_chunks(png: Vec<u8>, chunks: &[u8]) -> Vec<u8> {
+    // the 8 byte signature, then the length, type, 13 bytes of data and crc of `IHDR`
+    const HEADER_END: usize = 8 + 4 + 4 + 13 + 4;
+
+    if png.get(12..16) != Some(b"IHDR") {
+        return png;
+    }
+
+    let mut tagged = Vec::with_capacity(png.len() + chunks.len());
+    tagged.extend_from_slice(&png[..HEADER_END]);
+    tagged.extend_from_slice(chunks);
+    tagged.extend_from_slice(&png[HEADER_END..]);
+
+    tagged
+}
+
+pub fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+
+    let mut chunk = Vec::with_capacity(data.len() + 12);
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(kind);
+    chunk.extend_from_slice(data);
+    chunk.extend_from_slice(&crc.finalize().to_be_bytes());
+
+    chunk
+}
+
+const BAYER_4X4: [[f32; 4]; 4] = [
+    [0.0, 8.0, 2.0, 10.0],
+    [12.0, 4.0, 14.0, 6.0],
+    [3.0, 11.0, 1.0, 9.0],
+    [15.0, 7.0, 13.0, 5.0],
+];
+
+// dithering state for one page of cells.
+// cells must be quantized left-right top-bottom, for the error diffusion to work
+struct Ditherer {
+    dither: Dither,
+    squares_width: u32,
+    // brightness error carried forward into cells that have not been quantized yet
+    errors: Vec<f32>,
+}
+
+impl Ditherer {
+    fn new(dither: Dither, squares_width: u32, squares_height: u32) -> Self {
+        let errors = if dither == Dither::FloydSteinberg {
+            vec![0.0; (squares_width * squares_height) as usize]
+        } else {
+            vec![]
+        };
+
+        Ditherer {
+            dither,
+            squares_width,
+            errors,
+        }
+    }
+
+    // the brightness to size the cell's dot by
+    fn quantize(&mut self, square_x: u32, square_y: u32, brightness: f32) -> f32 {
+        match self.dither {
+            Dither::None => brightness,
+            Dither::Ordered => {
+                let threshold =
+                    (BAYER_4X4[(square_y % 4) as usize][(square_x % 4) as usize] + 0.5) / 16.0;
+
+                if brightness < threshold {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            Dither::FloydSteinberg => {
+                let index = (square_y * self.squares_width + square_x) as usize;
+                let brightness = brightness + self.errors[index];
+                let quantized = if brightness < 0.5 { 0.0 } else { 1.0 };
+                let error = brightness - quantized;
+
+                let x = square_x as i64;
+                let y = square_y as i64;
+                for &(dx, dy, weight) in &[
+                    (1, 0, 7.0 / 16.0),
+                    (-1, 1, 3.0 / 16.0),
+                    (0, 1, 5.0 / 16.0),
+                    (1, 1, 1.0 / 16.0),
+                ] {
+                    let (x, y) = (x + dx, y + dy);
+                    if x < 0 || x >= self.squares_width as i64 {
+                        continue;
+                    }
+
+                    let index = (y * self.squares_width as i64 + x) as usize;
+                    if let Some(e) = self.errors.get_mut(index) {
+                        *e += error * weight;
+                    }
+                }
+
+                quantized
+            }
+        }
+    }
+}
+
+// how a second image is combined with the source image
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MaskBlend {
+    // darkens the source by the mask, e.g. for textures
+    Multiply,
+    // lightens the source by the mask
+    Screen,
+    // fades the source to white where the mask is dark, e.g. for vignettes
+    Mask,
+}
+
+impl MaskBlend {
+    pub fn from_string(s: &str) -> Option<MaskBlend> {
+        match s {
+            "Multiply" => Some(MaskBlend::Multiply),
+            "Screen" => Some(MaskBlend::Screen),
+            "Mask" => Some(MaskBlend::Mask),
+            _ => None,
+        }
+    }
+
+    pub fn blends() -> Iter<'static, Self> {
+        const BLENDS: [MaskBlend; 3] = [MaskBlend::Multiply, MaskBlend::Screen, MaskBlend::Mask];
+        BLENDS.iter()
+    }
+
+    fn apply(self, pixel: Rgba<u8>, mask: Rgba<u8>) -> Rgba<u8> {
+        let mut blended = pixel;
+
+        match self {
+            MaskBlend::Multiply => {
+                for i in 0..3 {
+                    blended[i] = (pixel[i] as u16 * mask[i] as u16 / 255) as u8;
+                }
+            }
+            MaskBlend::Screen => {
+                for i in 0..3 {
+                    blended[i] =
+                        255 - ((255 - pixel[i] as u16) * (255 - mask[i] as u16) / 255) as u8;
+                }
+            }
+            MaskBlend::Mask => {
+                let keep = brightness(mask, Luma::Rec601);
+                for i in 0..3 {
+                    blended[i] = (pixel[i] as f32 * keep + 255.0 * (1.0 - keep)).round() as u8;
+                }
+            }
+        }
+
+        blended
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Channel {
+    Red,
+    Green,
+    Blue,
+}
+
+impl Channel {
+    pub fn channels() -> Iter<'static, Self> {
+        const CHANNELS: [Channel; 3] = [Channel::Red, Channel::Green, Channel::Blue];
+        CHANNELS.iter()
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Channel::Red => 0,
+            Channel::Green => 1,
+            Channel::Blue => 2,
+        }
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Channel::Red => "red",
+            Channel::Green => "green",
+            Channel::Blue => "blue",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// which channels of the source image are flipped (`255 - value`) before sampling,
+// e.g. inverting only blue for duotone and psychedelic effects
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChannelInvert {
+    pub red: bool,
+    pub green: bool,
+    pub blue: bool,
+}
+
+impl ChannelInvert {
+    pub fn get(self, channel: Channel) -> bool {
+        match channel {
+            Channel::Red => self.red,
+            Channel::Green => self.green,
+            Channel::Blue => self.blue,
+        }
+    }
+
+    pub fn toggle(&mut self, channel: Channel) {
+        match channel {
+            Channel::Red => self.red = !self.red,
+            Channel::Green => self.green = !self.green,
+            Channel::Blue => self.blue = !self.blue,
+        }
+    }
+
+    fn is_any(self) -> bool {
+        self.red || self.green || self.blue
+    }
+
+    fn apply(self, pixel: Rgba<u8>) -> Rgba<u8> {
+        let mut inverted = pixel;
+
+        for channel in Channel::channels() {
+            if self.get(*channel) {
+                inverted[channel.index()] = 255 - pixel[channel.index()];
+            }
+        }
+
+        inverted
+    }
+}
+
+// how big a grid dot is, as a fraction of the biggest it can be, by the brightness
+// of its square. `sizes` are at `RADIUS_CURVE_POINTS` evenly spaced brightnesses,
+// from black to white, and the default is a straight line from 1 down to 0.
+// between them the curve is a monotone cubic, so it never overshoots the points
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RadiusCurve {
+    pub sizes: [f32; RADIUS_CURVE_POINTS],
+}
+
+impl Default for RadiusCurve {
+    fn default() -> Self {
+        let mut sizes = [0.0; RADIUS_CURVE_POINTS];
+        for (i, size) in sizes.iter_mut().enumerate() {
+            *size = 1.0 - i as f32 / (RADIUS_CURVE_POINTS - 1) as f32;
+        }
+
+        RadiusCurve { sizes }
+    }
+}
+
+impl RadiusCurve {
+    // the brightness of control point `i`
+    pub fn brightness_at(i: usize) -> f32 {
+        i as f32 / (RADIUS_CURVE_POINTS - 1) as f32
+    }
+
+    pub fn size(&self, brightness: f32) -> f32 {
+        let spacing = 1.0 / (RADIUS_CURVE_POINTS - 1) as f32;
+        let position = brightness.clamp(0.0, 1.0) / spacing;
+        let i = (position.floor() as usize).min(RADIUS_CURVE_POINTS - 2);
+        let t = position - i as f32;
+
+        let (y0, y1) = (self.sizes[i], self.sizes[i + 1]);
+        let tangents = self.tangents();
+        let rise = y1 - y0;
+
+        // hermite, written as the straight line between the points plus how far
+        // the tangents bend it, which is nothing at all for a straight curve
+        let bend = t
+            * (1.0 - t)
+            * ((1.0 - t) * (tangents[i] * spacing - rise) - t * (tangents[i + 1] * spacing - rise));
+
+        (y0 + t * rise + bend).clamp(0.0, 1.0)
+    }
+
+    // fritsch-carlson tangents, flattened at peaks and valleys and limited
+    // wherever they would make a segment overshoot
+    fn tangents(&self) -> [f32; RADIUS_CURVE_POINTS] {
+        let spacing = 1.0 / (RADIUS_CURVE_POINTS - 1) as f32;
+
+        let mut slopes = [0.0; RADIUS_CURVE_POINTS - 1];
+        for (i, slope) in slopes.iter_mut().enumerate() {
+            *slope = (self.sizes[i + 1] - self.sizes[i]) / spacing;
+        }
+
+        let mut tangents = [0.0; RADIUS_CURVE_POINTS];
+        tangents[0] = slopes[0];
+        tangents[RADIUS_CURVE_POINTS - 1] = slopes[RADIUS_CURVE_POINTS - 2];
+        for i in 1..RADIUS_CURVE_POINTS - 1 {
+            tangents[i] = if slopes[i - 1] * slopes[i] > 0.0 {
+                (slopes[i - 1] + slopes[i]) / 2.0
+            } else {
+                0.0
+            };
+        }
+
+        for (i, slope) in slopes.iter().enumerate() {
+            if *slope == 0.0 {
+                tangents[i] = 0.0;
+                tangents[i + 1] = 0.0;
+                continue;
+            }
+
+            let (a, b) = (tangents[i] / slope, tangents[i + 1] / slope);
+            let length = (a * a + b * b).sqrt();
+            if length > 3.0 {
+                tangents[i] = 3.0 / length * a * slope;
+                tangents[i + 1] = 3.0 / length * b * slope;
+            }
+        }
+
+        tangents
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CropSide {
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
+
+impl CropSide {
+    pub fn sides() -> Iter<'static, Self> {
+        const SIDES: [CropSide; 4] = [
+            CropSide::Left,
+            CropSide::Top,
+            CropSide::Right,
+            CropSide::Bottom,
+        ];
+        SIDES.iter()
+    }
+}
+
+impl fmt::Display for CropSide {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            CropSide::Left => "left",
+            CropSide::Top => "top",
+            CropSide::Right => "right",
+            CropSide::Bottom => "bottom",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// framing of the source image before anything else happens to it, so subjects can be
+// framed without an image editor. the image is turned clockwise by `quarter_turns`,
+// flipped, and then cropped. crops are the fraction of the turned and flipped image
+// cut off that side, and the mask is framed along with the image
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Framing {
+    pub quarter_turns: u32,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    pub crop_left: f32,
+    pub crop_top: f32,
+    pub crop_right: f32,
+    pub crop_bottom: f32,
+}
+
+impl Framing {
+    pub fn crop(self, side: CropSide) -> f32 {
+        match side {
+            CropSide::Left => self.crop_left,
+            CropSide::Top => self.crop_top,
+            CropSide::Right => self.crop_right,
+            CropSide::Bottom => self.crop_bottom,
+        }
+    }
+
+    pub fn set_crop(&mut self, side: CropSide, fraction: f32) {
+        let fraction = fraction.clamp(0.0, 1.0);
+        match side {
+            CropSide::Left => self.crop_left = fraction,
+            CropSide::Top => self.crop_top = fraction,
+            CropSide::Right => self.crop_right = fraction,
+            CropSide::Bottom => self.crop_bottom = fraction,
+        }
+    }
+
+    fn is_identity(self) -> bool {
+        self == Framing::default()
+    }
+
+    // narrows the crop to the rectangle between the corners `from` and `to`, given as
+    // fractions of the image as it is already framed, like a region picked out on a preview
+    pub fn crop_to(&mut self, from: (f32, f32), to: (f32, f32)) {
+        let span = |a: f32, b: f32| (a.min(b).clamp(0.0, 1.0), a.max(b).clamp(0.0, 1.0));
+        let (left, right) = span(from.0, to.0);
+        let (top, bottom) = span(from.1, to.1);
+
+        let width = (1.0 - self.crop_left - self.crop_right).max(0.0);
+        let height = (1.0 - self.crop_top - self.crop_bottom).max(0.0);
+
+        self.crop_left += left * width;
+        self.crop_right += (1.0 - right) * width;
+        self.crop_top += top * height;
+        self.crop_bottom += (1.0 - bottom) * height;
+    }
+
+    // the size of an image of `width` x `height` once it is framed
+    pub fn dimensions(self, width: u32, height: u32) -> (u32, u32) {
+        let (width, height) = if self.quarter_turns % 2 == 1 {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        let (_, _, width, height) = self.crop_region(width, height);
+
+        (width, height)
+    }
+
+    // (x, y, width, height) of what is left after cropping the turned image.
+    // crops that meet in the middle still leave a pixel
+    fn crop_region(self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let span = |extent: u32, start: f32, end: f32| {
+            let from = ((extent as f32 * start).round() as u32).min(extent.saturating_sub(1));
+            let to = ((extent as f32 * (1.0 - end)).round() as u32)
+                .max(from + 1)
+                .min(extent);
+            (from, to.saturating_sub(from))
+        };
+
+        let (x, width) = span(width, self.crop_left, self.crop_right);
+        let (y, height) = span(height, self.crop_top, self.crop_bottom);
+
+        (x, y, width, height)
+    }
+
+    fn apply(self, image: &RgbaImage) -> RgbaImage {
+        let mut framed = match self.quarter_turns % 4 {
+            1 => image::imageops::rotate90(image),
+            2 => image::imageops::rotate180(image),
+            3 => image::imageops::rotate270(image),
+            _ => image.clone(),
+        };
+
+        if self.flip_horizontal {
+            image::imageops::flip_horizontal_in_place(&mut framed);
+        }
+        if self.flip_vertical {
+            image::imageops::flip_vertical_in_place(&mut framed);
+        }
+
+        let (x, y, width, height) = self.crop_region(framed.width(), framed.height());
+        image::imageops::crop_imm(&framed, x, y, width, height).to_image()
+    }
+}
+
+// tone curve adjustments to the source image before sampling,
+// since prints so often come out washed out or blown out.
+// `brightness` and `contrast` go from -1 to 1, and 0 leaves the image as it is.
+// a `gamma` over 1 lightens the midtones and under 1 darkens them
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Tone {
+    pub gamma: f32,
+    pub brightness: f32,
+    pub contrast: f32,
+}
+
+impl Default for Tone {
+    fn default() -> Self {
+        Tone {
+            gamma: 1.0,
+            brightness: 0.0,
+            contrast: 0.0,
+        }
+    }
+}
+
+impl Tone {
+    fn is_identity(self) -> bool {
+        self == Tone::default()
+    }
+
+    fn adjust(self, value: f32) -> f32 {
+        let value = (value + self.brightness).clamp(0.0, 1.0);
+        // a contrast of 1 is 4 times the slope around the middle gray, and -1 a quarter of it
+        let value = ((value - 0.5) * (self.contrast * 2.0).exp2() + 0.5).clamp(0.0, 1.0);
+
+        value.powf(1.0 / self.gamma.max(f32::EPSILON))
+    }
+
+    fn apply(self, image: &RgbaImage) -> RgbaImage {
+        // every channel goes through the same curve, so work it out once for each value
+        let mut curve = [0; 256];
+        for (value, adjusted) in curve.iter_mut().enumerate() {
+            *adjusted = (self.adjust(value as f32 / 255.0) * 255.0).round() as u8;
+        }
+
+        let mut toned = image.clone();
+
+        for pixel in toned.pixels_mut() {
+            for channel in Channel::channels() {
+                pixel[channel.index()] = curve[pixel[channel.index()] as usize];
+            }
+        }
+
+        toned
+    }
+}
+
+impl fmt::Display for MaskBlend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            MaskBlend::Multiply => "Multiply",
+            MaskBlend::Screen => "Screen",
+            MaskBlend::Mask => "Mask",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// how dots are arranged on the page
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Pattern {
+    // one dot per cell, sized by the cell's brightness
+    Grid,
+    // same-size dots scattered with blue-noise spacing, denser where the image is darker
+    Stipple,
+    // parallel lines `square_size` apart at `line_angle`, thicker where the image is darker
+    Lines,
+    // a sine wave along every row of squares, swinging wider and faster where the image is darker
+    Waves,
+    // one to four directions of hatching per square, more where the image is darker
+    Crosshatch,
+    // one continuous spiral per page, for plotters, wobbling where the image is darker
+    Spiral,
+}
+
+impl Pattern {
+    pub fn from_string(s: &str) -> Option<Pattern> {
+        match s {
+            "Grid" => Some(Pattern::Grid),
+            "Stipple" => Some(Pattern::Stipple),
+            "Lines" => Some(Pattern::Lines),
+            "Waves" => Some(Pattern::Waves),
+            "Crosshatch" => Some(Pattern::Crosshatch),
+            "Spiral" => Some(Pattern::Spiral),
+            _ => None,
+        }
+    }
+
+    pub fn patterns() -> Iter<'static, Self> {
+        const PATTERNS: [Pattern; 6] = [
+            Pattern::Grid,
+            Pattern::Stipple,
+            Pattern::Lines,
+            Pattern::Waves,
+            Pattern::Crosshatch,
+            Pattern::Spiral,
+        ];
+        PATTERNS.iter()
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Pattern::Grid => "Grid",
+            Pattern::Stipple => "Stipple",
+            Pattern::Lines => "Lines",
+            Pattern::Waves => "Waves",
+            Pattern::Crosshatch => "Crosshatch",
+            Pattern::Spiral => "Spiral",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// what each dot of `Pattern::Grid` is drawn as. every shape covers the same area
+// as the circle it replaces, so tones come out the same whichever is picked
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DotShape {
+    Circle,
+    Square,
+    // twice as long as it is wide
+    Ellipse,
+    // a short stroke with round ends, four times as long as it is wide
+    Dash,
+}
+
+impl DotShape {
+    pub fn from_string(s: &str) -> Option<DotShape> {
+        match s {
+            "Circle" => Some(DotShape::Circle),
+            "Square" => Some(DotShape::Square),
+            "Ellipse" => Some(DotShape::Ellipse),
+            "Dash" => Some(DotShape::Dash),
+            _ => None,
+        }
+    }
+
+    pub fn dot_shapes() -> Iter<'static, Self> {
+        const DOT_SHAPES: [DotShape; 4] = [
+            DotShape::Circle,
+            DotShape::Square,
+            DotShape::Ellipse,
+            DotShape::Dash,
+        ];
+        DOT_SHAPES.iter()
+    }
+
+    // (half the length along the dot's angle, half the width across it)
+    // of a dot with the area of a circle of `radius`
+    fn half_extents(self, radius: f32) -> (f32, f32) {
+        match self {
+            DotShape::Circle => (radius, radius),
+            DotShape::Square => {
+                let half_side = radius * std::f32::consts::PI.sqrt() / 2.0;
+                (half_side, half_side)
+            }
+            DotShape::Ellipse => (
+                radius * ELLIPSE_ASPECT.sqrt(),
+                radius / ELLIPSE_ASPECT.sqrt(),
+            ),
+            DotShape::Dash => {
+                let half_width = radius * (std::f32::consts::PI / DASH_ASPECT).sqrt() / 2.0;
+                (half_width * DASH_ASPECT, half_width)
+            }
+        }
+    }
+}
+
+impl fmt::Display for DotShape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            DotShape::Circle => "Circle",
+            DotShape::Square => "Square",
+            DotShape::Ellipse => "Ellipse",
+            DotShape::Dash => "Dash",
+        };
+        write!(f, "{}", s)
+    }
+}
 
-    let mut pages_pairs = Vec::with_capacity(
-        (pages_width * pages_height)
-            .try_into()
-            .expect("pages_width * pages_height was not able to fit into a usize!"),
-    );
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Layout {
+    Fit,
+    StepAndRepeat,
+    Matrix,
+}
 
-    for page_y in 0..pages_height {
-        for page_x in 0..pages_width {
-            pages_pairs.push((page_x, page_y));
+impl Layout {
+    pub fn from_string(s: &str) -> Option<Layout> {
+        match s {
+            "Fit" => Some(Layout::Fit),
+            "Step and repeat" => Some(Layout::StepAndRepeat),
+            "Settings matrix" => Some(Layout::Matrix),
+            _ => None,
         }
     }
+}
 
-    // calculate pages, left-right top-bottom
-    // each page is its own sub image
-    let pages = pages_pairs.into_iter().filter_map(|(page_x, page_y)| {
-        let current_pixel_x: u32 = (page_x as f32 * paper_width_pixels).floor() as u32;
-        let current_pixel_y: u32 = (page_y as f32 * paper_height_pixels).floor() as u32;
-
-        // this is kind of horrific and I'm not sure it does exactly what I want.
-        // for example if you configure 2x2 pages, and the scaled image can't fit
-        let x_span =
-            if current_pixel_x + (paper_width_pixels.floor() as u32) < scaled_image_width_pixels {
-                Some(paper_width_pixels.floor() as u32)
-            } else {
-                scaled_image_width_pixels.checked_sub(current_pixel_x)
-            };
-
-        let y_span = if current_pixel_y + (paper_height_pixels.floor() as u32)
-            < scaled_image_height_pixels
-        {
-            Some(paper_height_pixels.floor() as u32)
-        } else {
-            scaled_image_height_pixels.checked_sub(current_pixel_y)
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Layout::Fit => "Fit",
+            Layout::StepAndRepeat => "Step and repeat",
+            Layout::Matrix => "Settings matrix",
         };
+        write!(f, "{}", s)
+    }
+}
 
-        if let (Some(x_span), Some(y_span)) = (x_span, y_span) {
-            let page = SubImage::new(
-                &image_scaled_to_fit_on_pages,
-                current_pixel_x,
-                current_pixel_y,
-                x_span,
-                y_span,
-            );
+// random variation in the size and position of grid dots, for a less mechanical look.
+// `radius` and `position` are fractions: dots grow or shrink by up to `radius` of their size,
+// and move by up to `position` of half a square either way.
+// the same seed always gives the same dots
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Jitter {
+    pub radius: f32,
+    pub position: f32,
+    pub seed: u32,
+}
 
-            Some(page)
-        } else {
-            None
+impl Default for Jitter {
+    fn default() -> Self {
+        Jitter {
+            radius: 0.0,
+            position: 0.0,
+            seed: 1,
         }
-    });
+    }
+}
+
+impl Jitter {
+    fn is_any(self) -> bool {
+        self.radius > 0.0 || self.position > 0.0
+    }
 
-    let mut pixels_in_square = Vec::with_capacity(square_size.powi(2).ceil() as usize);
+    // a generator for the page at this offset, so that pages don't all jitter the same way
+    fn rng(self, page_offset_x: u32, page_offset_y: u32) -> XorShift {
+        XorShift::new(
+            self.seed.wrapping_mul(2_654_435_761)
+                ^ page_offset_x.wrapping_mul(31).wrapping_add(page_offset_y),
+        )
+    }
 
-    pages
-        .map(|page| {
-            // create a dupe of this page on which we will draw circles
-            let (page_width_pixels, page_height_pixels) = page.dimensions();
-            let mut target_page =
-                ImageBuffer::<Rgba<u8>, Vec<u8>>::new(page_width_pixels, page_height_pixels);
-
-            let squares_width = (page_width_pixels as f32 / square_size).ceil() as u32;
-            let squares_height = (page_height_pixels as f32 / square_size).ceil() as u32;
-
-            // divide into squares
-            for square_y in 0..squares_height {
-                for square_x in 0..squares_width {
-                    let current_pixel_x: u32 = if square_y % 2 == 0 {
-                        (square_x as f32 * square_size).floor() as u32
-                    } else {
-                        (square_x as f32 * square_size).floor() as u32 + half_square_size as u32
-                    };
-                    let current_pixel_y: u32 = (square_y as f32 * square_size).floor() as u32;
+    fn apply(self, dot: &mut Dot, square_size: f32, rng: &mut XorShift) {
+        // from -1 to 1
+        let mut spread = || rng.next_f32() * 2.0 - 1.0;
 
-                    let x_span = if current_pixel_x + square_size_floor < page_width_pixels {
-                        Some(square_size_floor)
-                    } else {
-                        page_width_pixels.checked_sub(current_pixel_x)
-                    };
+        dot.radius = (dot.radius * (1.0 + spread() * self.radius)).max(0.0);
 
-                    let y_span = if current_pixel_y + (square_size_floor) < page_height_pixels {
-                        Some(square_size_floor)
-                    } else {
-                        page_height_pixels.checked_sub(current_pixel_y)
-                    };
+        let reach = self.position * square_size / 2.0;
+        dot.center.0 += spread() * reach;
+        dot.center.1 += spread() * reach;
+    }
+}
+
+// settings for weighted Voronoi stippling, see `voronoi::Stippler`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Voronoi {
+    // across the whole page grid
+    pub points: u32,
+    pub iterations: u32,
+}
+
+impl Default for Voronoi {
+    fn default() -> Self {
+        Voronoi {
+            points: 4000,
+            iterations: 20,
+        }
+    }
+}
 
-                    // if the span is nonzero and within the boundary
-                    if let (Some(x_span), Some(y_span)) = (x_span, y_span) {
-                        // for a given square, sample the square form the source page
-                        // getting radius and color
-                        let square =
-                            SubImage::new(&page, current_pixel_x, current_pixel_y, x_span, y_span);
+// repeat the image in a grid of `columns` x `rows` cells spanning all pages,
+// for sticker sheets and repeated motifs.
+// each copy is scaled to fit `scale` of its cell and centered in it,
+// with `spacing` pixels of blank paper between cells.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StepAndRepeat {
+    pub columns: u32,
+    pub rows: u32,
+    pub scale: f32,
+    pub spacing: f32,
+}
 
-                        pixels_in_square.clear();
-                        pixels_in_square.extend(square.pixels().map(|(_, _, pixel)| pixel));
+impl Default for StepAndRepeat {
+    fn default() -> Self {
+        StepAndRepeat {
+            columns: 2,
+            rows: 2,
+            scale: 1.0,
+            spacing: 0.0,
+        }
+    }
+}
 
-                        let average_pixel_color = match color_depth {
-                            ColorDepth::Rgb => average_color(&pixels_in_square),
-                            ColorDepth::Grayscale => BLACK,
-                        };
+impl StepAndRepeat {
+    // (x, y, width, height) of each copy of an image of `image_width` x `image_height`
+    // on a layout of `width` x `height`, in the order they are laid down.
+    // copies can run off the right and bottom of the layout
+    fn copies(
+        self,
+        (image_width, image_height): (u32, u32),
+        width: u32,
+        height: u32,
+    ) -> Vec<(u32, u32, u32, u32)> {
+        let columns = self.columns.max(1);
+        let rows = self.rows.max(1);
+        let spacing = self.spacing.max(0.0);
 
-                        let average_brightness = average_brightness(&pixels_in_square);
+        let cell_width = (width as f32 - spacing * (columns - 1) as f32) / columns as f32;
+        let cell_height = (height as f32 - spacing * (rows - 1) as f32) / rows as f32;
 
-                        let radius =
-                            radius(average_brightness, adjusted_min_radius, adjusted_max_radius);
+        let tile_width = (cell_width * self.scale).floor();
+        let tile_height = (cell_height * self.scale).floor();
 
-                        // write the sampling as a circle to the target page
-                        let circle_center = (
-                            current_pixel_x as i32 + half_square_size,
-                            current_pixel_y as i32 + half_square_size,
-                        );
+        if tile_width < 1.0 || tile_height < 1.0 {
+            return vec![];
+        }
 
-                        imageproc::drawing::draw_filled_circle_mut(
-                            &mut target_page,
-                            circle_center,
-                            radius as i32,
-                            average_pixel_color,
-                        );
-                    }
-                }
+        let (tile_width, tile_height) = fit_dimensions(
+            (image_width, image_height),
+            tile_width as u32,
+            tile_height as u32,
+        );
+
+        let mut copies = vec![];
+        for row in 0..rows {
+            for column in 0..columns {
+                let cell_x = column as f32 * (cell_width + spacing);
+                let cell_y = row as f32 * (cell_height + spacing);
+
+                let x = cell_x + (cell_width - tile_width as f32) / 2.0;
+                let y = cell_y + (cell_height - tile_height as f32) / 2.0;
+
+                copies.push((
+                    x.max(0.0).floor() as u32,
+                    y.max(0.0).floor() as u32,
+                    tile_width,
+                    tile_height,
+                ));
             }
+        }
 
-            target_page
-        })
-        .collect::<Vec<_>>()
+        copies
+    }
 }
 
-pub fn rasterize_svg(args: RasterizeArgs) -> Vec<svg::Document> {
-    let image = args.image;
-    let pages_width = args.pages_width;
-    let pages_height = args.pages_height;
-    let paper_width_pixels = args.paper_width_pixels;
-    let paper_height_pixels = args.paper_height_pixels;
-    let square_size = args.square_size;
-    let max_radius = max_radius(square_size);
-    let min_radius_percentage = args.min_radius_percentage;
-    let max_radius_percentage = args.max_radius_percentage;
-    let adjusted_max_radius = max_radius * max_radius_percentage;
-    let adjusted_min_radius = max_radius * min_radius_percentage;
-    let half_square_size = (square_size / 2.0).floor() as i32;
-    let square_size_floor = square_size.floor() as u32;
-    // let color_depth = args.color_depth;
-    let pages_width_pixels = (pages_width as f32 * paper_width_pixels).ceil() as u32;
-    let pages_height_pixels = (pages_height as f32 * paper_height_pixels).ceil() as u32;
-
-    let image_scaled_to_fit_on_pages = image.resize(
-        pages_width_pixels,
-        pages_height_pixels,
-        image::imageops::Nearest,
-    );
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MatrixParameter {
+    SquareSize,
+    MaxRadiusPercentage,
+}
 
-    let (scaled_image_width_pixels, scaled_image_height_pixels) =
-        image_scaled_to_fit_on_pages.dimensions();
+impl MatrixParameter {
+    pub fn from_string(s: &str) -> Option<MatrixParameter> {
+        match s {
+            "Square size" => Some(MatrixParameter::SquareSize),
+            "Maximum raster percentage" => Some(MatrixParameter::MaxRadiusPercentage),
+            _ => None,
+        }
+    }
+}
 
-    let mut pages_pairs = Vec::with_capacity(
-        (pages_width * pages_height)
-            .try_into()
-            .expect("pages_width * pages_height was not able to fit into a usize!"),
-    );
+impl fmt::Display for MatrixParameter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            MatrixParameter::SquareSize => "Square size",
+            MatrixParameter::MaxRadiusPercentage => "Maximum raster percentage",
+        };
+        write!(f, "{}", s)
+    }
+}
 
-    for page_y in 0..pages_height {
-        for page_x in 0..pages_width {
-            pages_pairs.push((page_x, page_y));
+// the parameter that varies across the tiles of the matrix layout,
+// in the same units the user enters it in:
+// pixels for the square size, percent for the maximum raster percentage
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParameterMatrix {
+    pub parameter: MatrixParameter,
+    pub from: f32,
+    pub to: f32,
+}
+
+impl Default for ParameterMatrix {
+    fn default() -> Self {
+        ParameterMatrix {
+            parameter: MatrixParameter::SquareSize,
+            from: 8.0,
+            to: 32.0,
         }
     }
+}
 
-    // calculate pages, left-right top-bottom
-    // each page is its own sub image
-    let pages = pages_pairs.into_iter().filter_map(|(page_x, page_y)| {
-        let current_pixel_x: u32 = (page_x as f32 * paper_width_pixels).floor() as u32;
-        let current_pixel_y: u32 = (page_y as f32 * paper_height_pixels).floor() as u32;
-
-        // this is kind of horrific and I'm not sure it does exactly what I want.
-        // for example if you configure 2x2 pages, and the scaled image can't fit
-        let x_span =
-            if current_pixel_x + (paper_width_pixels.floor() as u32) < scaled_image_width_pixels {
-                Some(paper_width_pixels.floor() as u32)
-            } else {
-                scaled_image_width_pixels.checked_sub(current_pixel_x)
-            };
+// a layer of thin strokes along the outlines in the image, on top of the pattern,
+// for detail like eyes and lettering that is lost when squares are big.
+// `threshold` is how sharp a change in brightness has to be to count as an edge,
+// from 0 to 4, and `width` is the width of the strokes in pixels
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Edges {
+    pub enabled: bool,
+    pub threshold: f32,
+    pub width: f32,
+}
 
-        let y_span = if current_pixel_y + (paper_height_pixels.floor() as u32)
-            < scaled_image_height_pixels
-        {
-            Some(paper_height_pixels.floor() as u32)
-        } else {
-            scaled_image_height_pixels.checked_sub(current_pixel_y)
-        };
+impl Default for Edges {
+    fn default() -> Self {
+        Edges {
+            enabled: false,
+            threshold: 0.5,
+            width: 1.0,
+        }
+    }
+}
 
-        if let (Some(x_span), Some(y_span)) = (x_span, y_span) {
-            let page = SubImage::new(
-                &image_scaled_to_fit_on_pages,
-                current_pixel_x,
-                current_pixel_y,
-                x_span,
-                y_span,
-            );
+// smaller svg pages, for pages of many thousands of shapes. numbers are rounded to
+// `precision` decimal places, dots too small to show are left out, dots of the
+// same color are grouped, so their color is written once rather than on each,
+// and square and ellipse dots are copies of a single symbol
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SvgOptimization {
+    pub enabled: bool,
+    pub precision: u8,
+}
 
-            Some(page)
-        } else {
-            None
+impl SvgOptimization {
+    // `value` rounded to `precision`, or as it is if svgs aren't being optimized
+    pub fn round(&self, value: f32) -> f32 {
+        if !self.enabled {
+            return value;
         }
-    });
 
-    let mut pixels_in_square = Vec::with_capacity(square_size.powi(2).ceil() as usize);
+        let scale = 10f32.powi(self.precision as i32);
+        (value * scale).round() / scale
+    }
 
-    pages
-        .map(|page| {
-            // create a dupe of this page on which we will draw circles
-            let (page_width_pixels, page_height_pixels) = page.dimensions();
+    fn round_point(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (self.round(x), self.round(y))
+    }
+}
 
-            let mut svg_document = svg::Document::new();
-            svg_document =
-                svg_document.set("viewBox", (0, 0, page_width_pixels, page_height_pixels));
+impl Default for SvgOptimization {
+    fn default() -> Self {
+        SvgOptimization {
+            enabled: false,
+            precision: 2,
+        }
+    }
+}
 
-            let squares_width = (page_width_pixels as f32 / square_size).ceil() as u32;
-            let squares_height = (page_height_pixels as f32 / square_size).ceil() as u32;
+// grid dots smaller than a printer can put down, left out altogether rather than drawn
+// as specks that come out as noise or not at all, along with dots of no size at all.
+// `radius` is in millimeters, whatever the scale of the page
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MinDotSize {
+    pub enabled: bool,
+    pub radius: f32,
+}
 
-            // divide into squares
-            for square_y in 0..squares_height {
-                for square_x in 0..squares_width {
-                    let current_pixel_x: u32 = if square_y % 2 == 0 {
-                        (square_x as f32 * square_size).floor() as u32
-                    } else {
-                        (square_x as f32 * square_size).floor() as u32 + half_square_size as u32
-                    };
-                    let current_pixel_y: u32 = (square_y as f32 * square_size).floor() as u32;
+impl MinDotSize {
+    // whether a dot of `radius` pixels is drawn
+    fn keeps(&self, radius: f32) -> bool {
+        !self.enabled
+            || (radius > 0.0 && radius >= self.radius / MILLIMETERS_PER_INCH * PIXELS_PER_INCH)
+    }
+}
 
-                    let x_span = if current_pixel_x + square_size_floor < page_width_pixels {
-                        Some(square_size_floor)
-                    } else {
-                        page_width_pixels.checked_sub(current_pixel_x)
-                    };
+impl Default for MinDotSize {
+    fn default() -> Self {
+        MinDotSize {
+            enabled: false,
+            radius: 0.2,
+        }
+    }
+}
 
-                    let y_span = if current_pixel_y + (square_size_floor) < page_height_pixels {
-                        Some(square_size_floor)
-                    } else {
-                        page_height_pixels.checked_sub(current_pixel_y)
-                    };
+// svg for laser cutters and engravers, see `rasterize_laser_svg`.
+// `kerf` is the width of material the beam burns away, in millimeters
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Laser {
+    pub enabled: bool,
+    pub kerf: f32,
+}
 
-                    // if the span is nonzero and within the boundary
-                    if let (Some(x_span), Some(y_span)) = (x_span, y_span) {
-                        // for a given square, sample the square form the source page
-                        // getting radius and color
-                        let square =
-                            SubImage::new(&page, current_pixel_x, current_pixel_y, x_span, y_span);
-
-                        pixels_in_square.clear();
-                        pixels_in_square.extend(square.pixels().map(|(_, _, pixel)| pixel));
-
-                        // TODO figure out how to add fill color to SVG
-                        /*
-                        let average_pixel_color = match color_depth {
-                            ColorDepth::RGB => average_color(&pixels_in_square),
-                            ColorDepth::Grayscale => BLACK,
-                        };
-                        */
-
-                        let average_brightness = average_brightness(&pixels_in_square);
-
-                        let radius =
-                            radius(average_brightness, adjusted_min_radius, adjusted_max_radius);
-
-                        // write the sampling as a circle to the target page
-                        let circle_center = (
-                            current_pixel_x as i32 + half_square_size,
-                            current_pixel_y as i32 + half_square_size,
-                        );
+impl Default for Laser {
+    fn default() -> Self {
+        Laser {
+            enabled: false,
+            kerf: 0.1,
+        }
+    }
+}
 
-                        // <circle cx="50" cy="50" r="50"/>
-                        let circle = svg::node::element::Circle::new()
-                            .set("cx", circle_center.0)
-                            .set("cy", circle_center.1)
-                            .set("r", radius);
+// the settings swept over by `contact_sheet`, in the same units the user enters them in:
+// pixels for the square size, going up from column to column, and percent for
+// the largest dots, going up from row to row. there are `steps` columns and rows
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ContactSheet {
+    pub enabled: bool,
+    pub square_size_from: f32,
+    pub square_size_to: f32,
+    pub max_radius_from: f32,
+    pub max_radius_to: f32,
+    pub steps: u32,
+}
 
-                        svg_document = svg_document.add(circle);
-                    }
-                }
-            }
+impl Default for ContactSheet {
+    fn default() -> Self {
+        ContactSheet {
+            enabled: false,
+            square_size_from: 8.0,
+            square_size_to: 32.0,
+            max_radius_from: 50.0,
+            max_radius_to: 150.0,
+            steps: 4,
+        }
+    }
+}
 
-            svg_document
+impl ContactSheet {
+    pub fn square_sizes(&self) -> Vec<f32> {
+        sweep(self.square_size_from, self.square_size_to, self.steps)
+    }
+
+    pub fn max_radius_percentages(&self) -> Vec<f32> {
+        sweep(self.max_radius_from, self.max_radius_to, self.steps)
+    }
+
+    // which settings each column and row of the sheet was drawn with
+    pub fn legend(&self) -> String {
+        let list = |values: Vec<f32>| {
+            values
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        format!(
+            "columns, left to right, have squares of {} pixels.\n\
+             rows, top to bottom, have largest dots of {} percent.\n",
+            list(self.square_sizes()),
+            list(self.max_radius_percentages()),
+        )
+    }
+}
+
+// `steps` values evenly spaced from `from` to `to`, to a tenth, so they read back exactly
+fn sweep(from: f32, to: f32, steps: u32) -> Vec<f32> {
+    let steps = steps.clamp(1, MAX_CONTACT_SHEET_STEPS);
+
+    (0..steps)
+        .map(|step| {
+            let t = if steps > 1 {
+                step as f32 / (steps - 1) as f32
+            } else {
+                0.0
+            };
+
+            ((from + (to - from) * t) * 10.0).round() / 10.0
         })
         .collect()
 }
 
-fn average_color(pixels: &[Rgba<u8>]) -> Rgba<u8> {
-    let mut r: usize = pixels[0][0] as usize;
-    let mut g: usize = pixels[0][1] as usize;
-    let mut b: usize = pixels[0][2] as usize;
-    let mut a: usize = pixels[0][3] as usize;
-
-    let pixels_len = pixels.len();
+// a relief of the pattern, see `rasterize_stl`. a plate `base` millimeters thick,
+// with the pattern standing up to `height` millimeters above it
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Relief {
+    pub enabled: bool,
+    pub base: f32,
+    pub height: f32,
+    pub shape: ReliefShape,
+}
 
-    for pixel in pixels {
-        r += pixel[0] as usize;
-        g += pixel[1] as usize;
-        b += pixel[2] as usize;
-        a += pixel[3] as usize;
+impl Default for Relief {
+    fn default() -> Self {
+        Relief {
+            enabled: false,
+            base: 3.0,
+            height: 2.0,
+            shape: ReliefShape::Cylinder,
+        }
     }
+}
 
-    Rgba([
-        (r / pixels_len) as u8,
-        (g / pixels_len) as u8,
-        (b / pixels_len) as u8,
-        (a / pixels_len) as u8,
-    ])
+// what the shapes of a relief rise as. cones can be carved with a v-bit
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ReliefShape {
+    // straight sided, as tall as the relief all over
+    Cylinder,
+    // narrowing to a point
+    Cone,
 }
 
-fn average_brightness(pixels: &[Rgba<u8>]) -> f32 {
-    let i = pixels.len() as f32;
-    let mut brightness_nominal = brightness(pixels[0]);
+impl ReliefShape {
+    pub fn from_string(s: &str) -> Option<ReliefShape> {
+        match s {
+            "Cylinder" => Some(ReliefShape::Cylinder),
+            "Cone" => Some(ReliefShape::Cone),
+            _ => None,
+        }
+    }
 
-    for pixel in &pixels[1..] {
-        brightness_nominal += brightness(*pixel);
+    pub fn relief_shapes() -> Iter<'static, Self> {
+        const RELIEF_SHAPES: [ReliefShape; 2] = [ReliefShape::Cylinder, ReliefShape::Cone];
+        RELIEF_SHAPES.iter()
     }
+}
 
-    brightness_nominal / i
+impl fmt::Display for ReliefShape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ReliefShape::Cylinder => "Cylinder",
+            ReliefShape::Cone => "Cone",
+        };
+        write!(f, "{}", s)
+    }
 }
 
-// this function takes the adjusted min and max radii,
-// and clamps the calculated radius so that:
-// calculated_radius >= min_radius && calculated_radius <= max_radius.
-// by default, the min_radius_percentage is 0, which allows a 0-radius dot (meaning no
-// dot will be drawn), and the max_radius_percentage is 100, allowing the maximum radius
-// for a given brightness to be the "theoretical" max radius as calculated in the
-// `max_radius` function
-fn radius(average_brightness: f32, adjusted_min_radius: f32, adjusted_max_radius: f32) -> f32 {
-    let calculated_radius = (1.0 - average_brightness) * adjusted_max_radius;
+// how the image backend's pages are encoded. jpegs and webps are a small fraction
+// of the size of rgba pngs, for previews and sharing, but are lossy
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PageEncoding {
+    pub format: PageFormat,
+    // jpeg and webp quality, from 1 to 100
+    pub quality: u8,
+}
 
-    if calculated_radius < adjusted_min_radius {
-        adjusted_min_radius
-    } else {
-        calculated_radius
+impl Default for PageEncoding {
+    fn default() -> Self {
+        PageEncoding {
+            format: PageFormat::Png,
+            quality: 85,
+        }
     }
 }
 
-// a2 + b2 = diameter
-// diameter = sqrt(a2 + b2)
-// radius = diameter / 2
-// this is the minimum radius of a circle necessary to fully enclose a square
-// it is the "theoretical" max radius for any given raster square
-// is clamped by the function `radius`
-fn max_radius(square_size: f32) -> f32 {
-    (square_size.powf(2.0) * 2.0).sqrt() / 2.0
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PageFormat {
+    Png,
+    Jpeg,
+    // encoded by the browser, as the image crate can't
+    Webp,
 }
 
-fn brightness(pixel: Rgba<u8>) -> f32 {
-    let r = pixel[0] as f32 / 255.0;
-    let g = pixel[1] as f32 / 255.0;
-    let b = pixel[2] as f32 / 255.0;
+impl PageFormat {
+    pub fn from_string(s: &str) -> Option<PageFormat> {
+        match s {
+            "PNG" => Some(PageFormat::Png),
+            "JPEG" => Some(PageFormat::Jpeg),
+            "WebP" => Some(PageFormat::Webp),
+            _ => None,
+        }
+    }
 
-    0.299 * r + 0.587 * g + 0.114 * b
-}
+    pub fn page_formats() -> Iter<'static, Self> {
+        const PAGE_FORMATS: [PageFormat; 3] = [PageFormat::Png, PageFormat::Jpeg, PageFormat::Webp];
+        PAGE_FORMATS.iter()
+    }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum ColorDepth {
-    Rgb,
-    Grayscale,
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PageFormat::Png => "png",
+            PageFormat::Jpeg => "jpg",
+            PageFormat::Webp => "webp",
+        }
+    }
 }
 
-impl fmt::Display for ColorDepth {
+impl fmt::Display for PageFormat {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let s = match self {
-            ColorDepth::Rgb => "RGB",
-            ColorDepth::Grayscale => "Grayscale",
+            PageFormat::Png => "PNG",
+            PageFormat::Jpeg => "JPEG",
+            PageFormat::Webp => "WebP",
         };
         write!(f, "{}", s)
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+// how the transparent parts of the source image are sampled.
+// by default their color channels are sampled as if they were opaque
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Transparency {
+    // composite the image over `background` before sampling
+    pub composite: bool,
+    pub background: Color,
+    // fully transparent squares get no dot at all
+    pub skip_transparent: bool,
+}
+
+impl Default for Transparency {
+    fn default() -> Self {
+        Transparency {
+            composite: false,
+            background: Color::new(255, 255, 255),
+            skip_transparent: false,
+        }
+    }
+}
+
+impl Transparency {
+    fn apply(self, mut image: RgbaImage) -> RgbaImage {
+        if !self.composite && !self.skip_transparent {
+            return image;
+        }
+
+        for pixel in image.pixels_mut() {
+            let alpha = pixel[3];
+
+            *pixel = if alpha == 0 && self.skip_transparent {
+                // white, so that the patterns without squares leave it blank too
+                Rgba([255, 255, 255, 0])
+            } else if self.composite {
+                self.background
+                    .mix(Color::from_rgba(*pixel), alpha as f32 / 255.0)
+                    .to_rgba()
+            } else {
+                *pixel
+            };
+        }
+
+        image
+    }
+}
+
+// a frame drawn around the full extent of the artwork, continuing across
+// page boundaries, for trimming and framing assembled posters.
+// `thickness` and `inset` (from the edge of the artwork) are in pixels,
+// and a thickness of 0 draws no border at all.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Border {
+    pub thickness: f32,
+    pub inset: f32,
+    pub color: Color,
+}
+
+impl Default for Border {
+    fn default() -> Self {
+        Border {
+            thickness: 0.0,
+            inset: 0.0,
+            color: Color::BLACK,
+        }
+    }
+}
+
+impl Border {
+    // the four sides of the border as (x, y, width, height) rectangles,
+    // in the coordinates of the whole artwork
+    fn rects(self, width: u32, height: u32) -> Vec<(i32, i32, u32, u32)> {
+        let thickness = self.thickness.round().max(0.0) as i32;
+        let inset = self.inset.round().max(0.0) as i32;
+        let inner_width = width as i32 - 2 * inset;
+        let inner_height = height as i32 - 2 * inset;
+
+        if thickness == 0 || inner_width <= 0 || inner_height <= 0 {
+            return vec![];
+        }
+
+        let thickness = thickness.min(inner_width).min(inner_height);
+        let right = inset + inner_width - thickness;
+        let bottom = inset + inner_height - thickness;
+
+        vec![
+            // top
+            (inset, inset, inner_width as u32, thickness as u32),
+            // bottom
+            (inset, bottom, inner_width as u32, thickness as u32),
+            // left
+            (inset, inset, thickness as u32, inner_height as u32),
+            // right
+            (right, inset, thickness as u32, inner_height as u32),
+        ]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum PaperSize {
     USLetter,
     A4,
@@ -507,7 +6739,7 @@ impl Size {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Orientation {
     Portrait,
     Landscape,
@@ -0,0 +1,162 @@
+// `image::imageops::resize`, a window at a time. a window's pixels come out exactly as
+// they would from resizing the whole image and cropping them out, but only the window,
+// and the source pixels it's made from, are ever held. resizing is two passes, down
+// the columns then along the rows, rounded to whole values in between, as `resize` does
+use image::imageops::FilterType;
+use image::{Rgba, RgbaImage};
+
+// the part of `image` resized to `new_width` x `new_height` that is at `x`, `y`,
+// `width` x `height`. the window has to be inside the resized image
+pub fn resize_window(
+    image: &RgbaImage,
+    (new_width, new_height): (u32, u32),
+    filter: FilterType,
+    (x, y, width, height): (u32, u32, u32, u32),
+) -> RgbaImage {
+    if width == 0 || height == 0 {
+        return RgbaImage::new(width, height);
+    }
+
+    let (image_width, image_height) = image.dimensions();
+    let columns = (x..x + width)
+        .map(|out| Weights::new(image_width, new_width, out, filter))
+        .collect::<Vec<_>>();
+    let rows = (y..y + height)
+        .map(|out| Weights::new(image_height, new_height, out, filter))
+        .collect::<Vec<_>>();
+
+    // only the source columns that the window's columns are made from are resized down
+    let left = columns.iter().map(|column| column.start).min().unwrap_or(0);
+    let right = columns.iter().map(Weights::end).max().unwrap_or(0);
+
+    let mut resized_columns = RgbaImage::new(right - left, height);
+    for (row_y, row) in rows.iter().enumerate() {
+        for column_x in 0..right - left {
+            let pixel = row.apply(|source_y| *image.get_pixel(left + column_x, source_y));
+            resized_columns.put_pixel(column_x, row_y as u32, pixel);
+        }
+    }
+
+    let mut window = RgbaImage::new(width, height);
+    for (window_x, column) in columns.iter().enumerate() {
+        for window_y in 0..height {
+            let pixel =
+                column.apply(|source_x| *resized_columns.get_pixel(source_x - left, window_y));
+            window.put_pixel(window_x as u32, window_y, pixel);
+        }
+    }
+
+    window
+}
+
+// how much each source pixel from `start` on counts towards one resized pixel
+struct Weights {
+    start: u32,
+    weights: Vec<f32>,
+    sum: f32,
+}
+
+impl Weights {
+    // the weights of resized pixel `out`, resizing `size` pixels to `new_size`
+    fn new(size: u32, new_size: u32, out: u32, filter: FilterType) -> Self {
+        let (kernel, support): (fn(f32) -> f32, f32) = match filter {
+            FilterType::Nearest => (|_| 1.0, 0.0),
+            FilterType::Triangle => (triangle, 1.0),
+            FilterType::CatmullRom => (catmull_rom, 2.0),
+            FilterType::Gaussian => (gaussian, 3.0),
+            FilterType::Lanczos3 => (lanczos3, 3.0),
+        };
+
+        let ratio = size as f32 / new_size as f32;
+        let scale = ratio.max(1.0);
+        let source_support = support * scale;
+        let center = (out as f32 + 0.5) * ratio;
+
+        let start = ((center - source_support).floor() as i64).clamp(0, size as i64 - 1) as u32;
+        let end =
+            ((center + source_support).ceil() as i64).clamp(start as i64 + 1, size as i64) as u32;
+
+        // kernels are centered on the middle of a pixel
+        let center = center - 0.5;
+        let weights = (start..end)
+            .map(|i| kernel((i as f32 - center) / scale))
+            .collect::<Vec<_>>();
+        let sum = weights.iter().sum();
+
+        Weights {
+            start,
+            weights,
+            sum,
+        }
+    }
+
+    fn end(&self) -> u32 {
+        self.start + self.weights.len() as u32
+    }
+
+    // the resized pixel, from `pixel(i)` giving source pixel `i`
+    fn apply(&self, pixel: impl Fn(u32) -> Rgba<u8>) -> Rgba<u8> {
+        let mut total = [0.0f32; 4];
+        for (i, weight) in self.weights.iter().enumerate() {
+            let pixel = pixel(self.start + i as u32);
+            for (total, channel) in total.iter_mut().zip(pixel.0) {
+                *total += channel as f32 * weight;
+            }
+        }
+
+        Rgba(total.map(|total| (total / self.sum).clamp(0.0, 255.0).round() as u8))
+    }
+}
+
+fn triangle(x: f32) -> f32 {
+    if x.abs() < 1.0 {
+        1.0 - x.abs()
+    } else {
+        0.0
+    }
+}
+
+// the cubic spline of Mitchell and Netravali, with b = 0 and c = 0.5
+fn catmull_rom(x: f32) -> f32 {
+    let (b, c) = (0.0, 0.5);
+    let a = x.abs();
+
+    let k = if a < 1.0 {
+        (12.0 - 9.0 * b - 6.0 * c) * a.powi(3)
+            + (-18.0 + 12.0 * b + 6.0 * c) * a.powi(2)
+            + (6.0 - 2.0 * b)
+    } else if a < 2.0 {
+        (-b - 6.0 * c) * a.powi(3)
+            + (6.0 * b + 30.0 * c) * a.powi(2)
+            + (-12.0 * b - 48.0 * c) * a
+            + (8.0 * b + 24.0 * c)
+    } else {
+        0.0
+    };
+
+    k / 6.0
+}
+
+// a standard deviation of 0.5
+fn gaussian(x: f32) -> f32 {
+    let r: f32 = 0.5;
+    ((2.0 * std::f32::consts::PI).sqrt() * r).recip() * (-x.powi(2) / (2.0 * r.powi(2))).exp()
+}
+
+fn lanczos3(x: f32) -> f32 {
+    if x.abs() < 3.0 {
+        sinc(x) * sinc(x / 3.0)
+    } else {
+        0.0
+    }
+}
+
+fn sinc(t: f32) -> f32 {
+    let a = t * std::f32::consts::PI;
+
+    if t == 0.0 {
+        1.0
+    } else {
+        a.sin() / a
+    }
+}
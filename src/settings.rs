@@ -0,0 +1,418 @@
+use crate::archive::ZipCompression;
+use crate::color::{ColorVision, Gradient, Inks, Palette};
+use crate::draw::CircleAlgorithm;
+use crate::rasterize;
+use crate::rasterize::{
+    Border, BuildUp, ChannelInvert, ColorDepth, ColorProfile, ContactSheet, Dither, DotShape,
+    Edges, Framing, Jitter, Laser, Layout, Luma, MaskBlend, MinDotSize, Orientation, PageEncoding,
+    PaperSize, ParameterMatrix, Pattern, RadiusCurve, RasterizeArgs, Relief, Resampling, RowOrder,
+    StepAndRepeat, SvgOptimization, Tone, Transparency, Voronoi, DEFAULT_CHARACTER_RAMP,
+    PIXELS_PER_INCH,
+};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use svg::node::element::Element;
+use svg::Node;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Backend {
+    Image,
+    Svg,
+    // weighted Voronoi stippling, outputs both png and svg
+    Voronoi,
+    // characters picked by brightness, outputs both txt and svg
+    Text,
+}
+
+impl Backend {
+    pub fn backends() -> std::slice::Iter<'static, Self> {
+        const BACKENDS: [Backend; 4] = [
+            Backend::Image,
+            Backend::Svg,
+            Backend::Voronoi,
+            Backend::Text,
+        ];
+        BACKENDS.iter()
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Backend::Image => "Image",
+            Backend::Svg => "SVG",
+            Backend::Voronoi => "Voronoi stipple",
+            Backend::Text => "Text",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// every user-controllable parameter of a run, in a form
+// that can be written out alongside the outputs and read back in later.
+// missing fields are filled in from the defaults, so settings written by
+// older versions of rat keep loading as new parameters are added.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub pages_width: u32,
+    pub pages_height: u32,
+    pub square_size: f32,
+    pub min_radius_percentage: f32,
+    pub max_radius_percentage: f32,
+    pub paper_size: PaperSize,
+    pub orientation: Orientation,
+    pub backend: Backend,
+    pub color_depth: ColorDepth,
+    pub layout: Layout,
+    pub step_and_repeat: StepAndRepeat,
+    pub border: Border,
+    pub matrix: ParameterMatrix,
+    pub gradient: Gradient,
+    pub output_pixels_per_inch: f32,
+    pub draft: bool,
+    pub whole_poster: bool,
+    pub circle_algorithm: CircleAlgorithm,
+    pub dither: Dither,
+    pub pattern: Pattern,
+    pub mask_blend: MaskBlend,
+    pub voronoi: Voronoi,
+    pub line_angle: f32,
+    pub plot_preview: bool,
+    pub invert: ChannelInvert,
+    pub tsp_path: bool,
+    pub character_ramp: String,
+    pub inks: Inks,
+    pub row_order: RowOrder,
+    pub gcode: bool,
+    pub eps: bool,
+    pub dxf: bool,
+    pub relief: Relief,
+    pub dot_map: bool,
+    pub print_html: bool,
+    pub laser: Laser,
+    pub palette: Palette,
+    pub color_vision: ColorVision,
+    pub transparency: Transparency,
+    pub tone: Tone,
+    pub dot_metadata: bool,
+    // bigger, higher contrast controls, for touch screens
+    pub high_contrast: bool,
+    // rasterize again on its own shortly after the settings or image change
+    pub auto_rasterize: bool,
+    pub radius_curve: RadiusCurve,
+    pub luma: Luma,
+    pub color_profile: ColorProfile,
+    pub page_encoding: PageEncoding,
+    pub zip_compression: ZipCompression,
+    pub jitter: Jitter,
+    pub dot_shape: DotShape,
+    pub follow_contours: bool,
+    pub edges: Edges,
+    pub framing: Framing,
+    pub resampling: Resampling,
+    pub svg_optimization: SvgOptimization,
+    pub min_dot_size: MinDotSize,
+    pub supersampling: u32,
+    pub contact_sheet: ContactSheet,
+    // with an animated source, a gif of every frame's whole poster as well as their pages
+    pub animated_gif: bool,
+    // a gif of the whole poster being made, from the image backend
+    pub build_up: BuildUp,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            pages_width: 1,
+            pages_height: 1,
+            square_size: 18.0,
+            min_radius_percentage: 0.0,
+            max_radius_percentage: 1.0,
+            paper_size: PaperSize::USLetter,
+            orientation: Orientation::Portrait,
+            backend: Backend::Image,
+            color_depth: ColorDepth::Rgb,
+            layout: Layout::Fit,
+            step_and_repeat: StepAndRepeat::default(),
+            border: Border::default(),
+            matrix: ParameterMatrix::default(),
+            gradient: Gradient::default(),
+            output_pixels_per_inch: PIXELS_PER_INCH,
+            draft: false,
+            whole_poster: false,
+            circle_algorithm: CircleAlgorithm::Imageproc,
+            dither: Dither::None,
+            pattern: Pattern::Grid,
+            mask_blend: MaskBlend::Multiply,
+            voronoi: Voronoi::default(),
+            line_angle: 0.0,
+            plot_preview: false,
+            invert: ChannelInvert::default(),
+            tsp_path: false,
+            character_ramp: DEFAULT_CHARACTER_RAMP.to_string(),
+            inks: Inks::default(),
+            row_order: RowOrder::RowMajor,
+            gcode: false,
+            eps: false,
+            dxf: false,
+            relief: Relief::default(),
+            dot_map: false,
+            print_html: false,
+            laser: Laser::default(),
+            palette: Palette::default(),
+            color_vision: ColorVision::Typical,
+            transparency: Transparency::default(),
+            tone: Tone::default(),
+            dot_metadata: false,
+            high_contrast: false,
+            auto_rasterize: false,
+            radius_curve: RadiusCurve::default(),
+            luma: Luma::Rec601,
+            color_profile: ColorProfile::Untagged,
+            page_encoding: PageEncoding::default(),
+            zip_compression: ZipCompression::Stored,
+            jitter: Jitter::default(),
+            dot_shape: DotShape::Circle,
+            follow_contours: false,
+            edges: Edges::default(),
+            framing: Framing::default(),
+            resampling: Resampling::Triangle,
+            svg_optimization: SvgOptimization::default(),
+            min_dot_size: MinDotSize::default(),
+            supersampling: 1,
+            contact_sheet: ContactSheet::default(),
+            animated_gif: false,
+            build_up: BuildUp::None,
+        }
+    }
+}
+
+// written into the zip of every run's outputs
+pub const SETTINGS_FILENAME: &str = "settings.json";
+
+const URL_FRAGMENT_KEY: &str = "settings=";
+// the png text chunk keyword the settings are written under
+pub const PNG_SETTINGS_KEYWORD: &str = "rat settings";
+
+impl Settings {
+    // these settings applied to `image`
+    pub fn rasterize_args<'a>(
+        &self,
+        image: &'a RgbaImage,
+        mask: Option<&'a RgbaImage>,
+    ) -> RasterizeArgs<'a> {
+        RasterizeArgs {
+            image,
+            mask,
+            paper_width_pixels: self.paper_size.width_pixels(self.orientation),
+            paper_height_pixels: self.paper_size.height_pixels(self.orientation),
+            pages_width: self.pages_width,
+            pages_height: self.pages_height,
+            square_size: self.square_size,
+            min_radius_percentage: self.min_radius_percentage,
+            max_radius_percentage: self.max_radius_percentage,
+            color_depth: self.color_depth,
+            layout: self.layout,
+            step_and_repeat: self.step_and_repeat,
+            border: self.border,
+            matrix: self.matrix,
+            gradient: self.gradient,
+            output_pixels_per_inch: self.output_pixels_per_inch,
+            draft: self.draft,
+            circle_algorithm: self.circle_algorithm,
+            dither: self.dither,
+            pattern: self.pattern,
+            mask_blend: self.mask_blend,
+            line_angle: self.line_angle,
+            invert: self.invert,
+            inks: self.inks,
+            ink_layer: None,
+            row_order: self.row_order,
+            palette: self.palette,
+            transparency: self.transparency,
+            tone: self.tone,
+            dot_metadata: self.dot_metadata,
+            radius_curve: self.radius_curve,
+            luma: self.luma,
+            jitter: self.jitter,
+            dot_shape: self.dot_shape,
+            follow_contours: self.follow_contours,
+            edges: self.edges,
+            framing: self.framing,
+            resampling: self.resampling,
+            svg_optimization: self.svg_optimization,
+            min_dot_size: self.min_dot_size,
+            supersampling: self.supersampling,
+        }
+    }
+
+    pub fn to_json(&self) -> Vec<u8> {
+        serde_json::to_vec_pretty(self).expect("settings are always serializable")
+    }
+
+    pub fn from_json(json: &[u8]) -> Result<Settings, String> {
+        serde_json::from_slice(json).map_err(|e| format!("invalid settings: {}", e))
+    }
+
+    // the settings that matter most for a print, and the rat that made it,
+    // for whoever finds one later and wants to make it again
+    pub fn summary(&self) -> String {
+        format!(
+            "rat {}: {} squares of {}px, dots {}% to {}%, {} {} {}x{} pages",
+            env!("RAT_VERSION").trim(),
+            self.pattern,
+            self.square_size,
+            self.min_radius_percentage * 100.0,
+            self.max_radius_percentage * 100.0,
+            self.paper_size,
+            self.orientation.to_string().to_lowercase(),
+            self.pages_width,
+            self.pages_height,
+        )
+    }
+
+    // adds the summary and every setting to an encoded png, as text chunks.
+    // text chunks are latin-1, so anything past ascii in the settings is \u escaped
+    pub fn tag_png(&self, png: Vec<u8>) -> Vec<u8> {
+        let json = String::from_utf8(self.to_json()).expect("json is utf-8");
+        let mut ascii_json = String::with_capacity(json.len());
+        for c in json.chars() {
+            if c.is_ascii() {
+                ascii_json.push(c);
+            } else {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    ascii_json.push_str(&format!("\\u{:04x}", unit));
+                }
+            }
+        }
+
+        let mut chunks = vec![];
+        chunks.extend(png_text_chunk(
+            "Software",
+            &format!("rat {}", env!("RAT_VERSION").trim()),
+        ));
+        chunks.extend(png_text_chunk("Description", &self.summary()));
+        chunks.extend(png_text_chunk(PNG_SETTINGS_KEYWORD, &ascii_json));
+
+        rasterize::insert_png_chunks(png, &chunks)
+    }
+
+    // adds the summary and every setting to an svg, as its description and metadata
+    pub fn tag_svg(&self, document: svg::Document) -> svg::Document {
+        let escape = |s: &str| {
+            s.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+        };
+        let json = String::from_utf8(self.to_json()).expect("json is utf-8");
+
+        let mut description = Element::new("desc");
+        description.append(svg::node::Text::new(escape(&self.summary())));
+        let mut metadata = Element::new("metadata");
+        metadata.append(svg::node::Text::new(escape(&json)));
+
+        document.add(description).add(metadata)
+    }
+
+    // settings encoded as a url fragment ("#settings=..."),
+    // so a configuration can be bookmarked or shared as a link
+    pub fn to_url_fragment(&self) -> String {
+        let json = serde_json::to_vec(self).expect("settings are always serializable");
+        format!(
+            "#{}{}",
+            URL_FRAGMENT_KEY,
+            base64::encode_config(json, base64::URL_SAFE_NO_PAD)
+        )
+    }
+
+    pub fn from_url_fragment(fragment: &str) -> Option<Settings> {
+        let encoded = fragment
+            .trim_start_matches('#')
+            .split('&')
+            .find_map(|part| part.strip_prefix(URL_FRAGMENT_KEY))?;
+
+        let json = base64::decode_config(encoded, base64::URL_SAFE_NO_PAD).ok()?;
+
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+// a png `tEXt` chunk, with anything that isn't ascii in `text` replaced
+fn png_text_chunk(keyword: &str, text: &str) -> Vec<u8> {
+    let mut data = keyword.as_bytes().to_vec();
+    data.push(0);
+    data.extend(
+        text.chars()
+            .map(|c| if c.is_ascii() { c as u8 } else { b'?' }),
+    );
+
+    rasterize::png_chunk(b"tEXt", &data)
+}
+
+// settings changes this close together, in milliseconds, are undone as one,
+// so dragging a slider doesn't take an undo for every step it passed through
+pub const HISTORY_MERGE_MS: f64 = 500.0;
+// the most changes that can be undone
+pub const HISTORY_LENGTH: usize = 100;
+
+// the settings as they were before each change, to undo back to,
+// and as they were before each undo, to redo
+#[derive(Default)]
+pub struct SettingsHistory {
+    undo: Vec<Settings>,
+    redo: Vec<Settings>,
+    // when the last change was recorded, see `HISTORY_MERGE_MS`
+    last_change: Option<f64>,
+}
+
+impl SettingsHistory {
+    // `before` is what the settings were before a change made at `now`
+    pub fn record(&mut self, before: Settings, now: f64) {
+        let merged = self
+            .last_change
+            .is_some_and(|last_change| now - last_change < HISTORY_MERGE_MS);
+
+        if !merged || self.undo.is_empty() {
+            self.undo.push(before);
+            if self.undo.len() > HISTORY_LENGTH {
+                self.undo.remove(0);
+            }
+        }
+
+        self.last_change = Some(now);
+        self.redo.clear();
+    }
+
+    // the settings to go back to from `current`
+    pub fn undo(&mut self, current: Settings) -> Option<Settings> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        self.last_change = None;
+        Some(previous)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // the settings `undo` went back from
+    pub fn redo(&mut self, current: Settings) -> Option<Settings> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        self.last_change = None;
+        Some(next)
+    }
+}
+
+// numbers typed by the user may have stray whitespace, or use a comma as
+// the decimal separator depending on their keyboard/locale ("18,5")
+pub fn parse_number<T: FromStr>(s: &str) -> Option<T> {
+    s.trim().replace(',', ".").parse::<T>().ok()
+}
@@ -0,0 +1,95 @@
+use yew::{
+    html, html::ChangeData, Callback, Component, ComponentLink, Html, Properties, ShouldRender,
+};
+
+// a number control that is easy to hit on a touch screen: big - and + buttons
+// around a text box the value can also be typed into, once it loses focus.
+// it doesn't keep the value itself, it sends every new one to `onchange`
+// as a string, to be parsed like anything else typed in
+pub struct Stepper {
+    link: ComponentLink<Self>,
+    props: StepperProps,
+}
+
+pub enum StepperMsg {
+    Decrement,
+    Increment,
+    Entered(String),
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct StepperProps {
+    pub name: String,
+    pub value: f32,
+    pub min: f32,
+    pub max: f32,
+    #[prop_or(1.0)]
+    pub step: f32,
+    pub onchange: Callback<String>,
+}
+
+impl Stepper {
+    fn step_to(&self, value: f32) {
+        let value = value.clamp(self.props.min, self.props.max);
+        self.props.onchange.emit(value.to_string());
+    }
+}
+
+impl Component for Stepper {
+    type Message = StepperMsg;
+    type Properties = StepperProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Stepper { link, props }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            StepperMsg::Decrement => self.step_to(self.props.value - self.props.step),
+            StepperMsg::Increment => self.step_to(self.props.value + self.props.step),
+            StepperMsg::Entered(s) => self.props.onchange.emit(s),
+        }
+
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.props != props {
+            self.props = props;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn view(&self) -> Html {
+        html! {
+            <span class="stepper">
+                <button
+                class="stepper-button"
+                aria-label={ format!("decrease {}", self.props.name) }
+                disabled={ self.props.value <= self.props.min }
+                onclick=self.link.callback(|_| StepperMsg::Decrement)>
+                    { "−" }
+                </button>
+                <input
+                type="text"
+                inputmode="decimal"
+                class="stepper-value"
+                name={ self.props.name.clone() }
+                value={ self.props.value.to_string() }
+                onchange=self.link.callback(|e: ChangeData| match e {
+                    ChangeData::Value(s) => StepperMsg::Entered(s),
+                    _ => unreachable!(),
+                })/>
+                <button
+                class="stepper-button"
+                aria-label={ format!("increase {}", self.props.name) }
+                disabled={ self.props.value >= self.props.max }
+                onclick=self.link.callback(|_| StepperMsg::Increment)>
+                    { "+" }
+                </button>
+            </span>
+        }
+    }
+}
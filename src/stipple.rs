@@ -0,0 +1,84 @@
+// blue-noise dot placement for stippling.
+// points are thrown at random and kept if no other point is within `min_distance`
+// (Poisson-disk sampling), with each point additionally kept only with the
+// probability given by `density`, so dark areas end up with more dots than light ones.
+
+// candidates thrown per `min_distance`-sized square of the area.
+// with many more, all but the lightest areas would fill up to the densest
+// possible packing, and the dot density would stop following the darkness
+const CANDIDATES_PER_CELL: f32 = 2.0;
+
+pub fn poisson_disk_points(
+    width: u32,
+    height: u32,
+    min_distance: f32,
+    seed: u32,
+    density: impl Fn(u32, u32) -> f32,
+) -> Vec<(f32, f32)> {
+    if width == 0 || height == 0 || min_distance <= 0.0 {
+        return vec![];
+    }
+
+    // a cell this size can hold at most one point,
+    // so only the neighboring cells need checking
+    let cell_size = min_distance / std::f32::consts::SQRT_2;
+    let grid_width = (width as f32 / cell_size).ceil() as usize;
+    let grid_height = (height as f32 / cell_size).ceil() as usize;
+    let mut grid: Vec<Option<(f32, f32)>> = vec![None; grid_width * grid_height];
+
+    let candidates = ((width as f32 * height as f32) / min_distance.powi(2) * CANDIDATES_PER_CELL)
+        .ceil() as usize;
+
+    let mut rng = XorShift::new(seed);
+    let mut points = vec![];
+    let min_distance_squared = min_distance * min_distance;
+
+    for _ in 0..candidates {
+        let x = rng.next_f32() * width as f32;
+        let y = rng.next_f32() * height as f32;
+
+        let density = density((x as u32).min(width - 1), (y as u32).min(height - 1));
+        if rng.next_f32() >= density {
+            continue;
+        }
+
+        let cell_x = ((x / cell_size) as usize).min(grid_width - 1);
+        let cell_y = ((y / cell_size) as usize).min(grid_height - 1);
+
+        let too_close = (cell_y.saturating_sub(2)..(cell_y + 3).min(grid_height)).any(|ny| {
+            (cell_x.saturating_sub(2)..(cell_x + 3).min(grid_width)).any(|nx| {
+                matches!(
+                    grid[ny * grid_width + nx],
+                    Some((px, py)) if (px - x).powi(2) + (py - y).powi(2) < min_distance_squared
+                )
+            })
+        });
+
+        if !too_close {
+            grid[cell_y * grid_width + cell_x] = Some((x, y));
+            points.push((x, y));
+        }
+    }
+
+    points
+}
+
+// small and deterministic, so the same settings always produce the same stipple
+pub(crate) struct XorShift(u32);
+
+impl XorShift {
+    pub(crate) fn new(seed: u32) -> Self {
+        // xorshift gets stuck at 0
+        XorShift(seed.max(1))
+    }
+
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+
+        (x >> 8) as f32 / (1u32 << 24) as f32
+    }
+}
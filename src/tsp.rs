@@ -0,0 +1,90 @@
+// orders points into a short path that visits each of them once ("TSP art"),
+// so a pen plotter can draw a whole page of stipples without lifting the pen.
+// the path starts from the point nearest the top left corner, greedily goes to
+// the nearest unvisited point, then 2-opt untangles it by reversing stretches
+// of the path wherever that makes it shorter.
+
+// each 2-opt pass is quadratic in the number of points,
+// and later passes find less and less to improve
+const MAX_TWO_OPT_PASSES: u32 = 8;
+
+pub fn tour(points: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let mut path = nearest_neighbor_path(points);
+    two_opt(&mut path);
+    path
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+fn nearest_neighbor_path(points: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    let mut path = Vec::with_capacity(points.len());
+    let mut visited = vec![false; points.len()];
+
+    let mut current = match points
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            distance(**a, (0.0, 0.0))
+                .partial_cmp(&distance(**b, (0.0, 0.0)))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .map(|(i, _)| i)
+    {
+        Some(start) => start,
+        None => return path,
+    };
+
+    loop {
+        visited[current] = true;
+        path.push(points[current]);
+
+        let mut nearest = None;
+        let mut nearest_distance = f32::INFINITY;
+        for (i, point) in points.iter().enumerate() {
+            if visited[i] {
+                continue;
+            }
+
+            let d = distance(points[current], *point);
+            if d < nearest_distance {
+                nearest = Some(i);
+                nearest_distance = d;
+            }
+        }
+
+        match nearest {
+            Some(next) => current = next,
+            None => return path,
+        }
+    }
+}
+
+// the path is open, so reversing a stretch that runs to its end
+// only swaps one edge rather than two
+fn two_opt(path: &mut [(f32, f32)]) {
+    let n = path.len();
+
+    for _ in 0..MAX_TWO_OPT_PASSES {
+        let mut improved = false;
+
+        for i in 0..n.saturating_sub(2) {
+            for j in i + 2..n {
+                let (a, b, c) = (path[i], path[i + 1], path[j]);
+
+                let before = distance(a, b) + path.get(j + 1).map_or(0.0, |d| distance(c, *d));
+                let after = distance(a, c) + path.get(j + 1).map_or(0.0, |d| distance(b, *d));
+
+                if after + f32::EPSILON < before {
+                    path[i + 1..=j].reverse();
+                    improved = true;
+                }
+            }
+        }
+
+        if !improved {
+            break;
+        }
+    }
+}
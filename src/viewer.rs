@@ -0,0 +1,390 @@
+use web_sys::{HtmlElement, HtmlImageElement, MouseEvent, WheelEvent};
+use yew::{html, Component, ComponentLink, Html, NodeRef, Properties, ShouldRender};
+
+// each wheel notch or zoom button press zooms by this much
+const ZOOM_STEP: f64 = 1.25;
+// how far out and in pages can be zoomed, as screen pixels per page pixel
+const MIN_SCALE: f64 = 0.05;
+const MAX_SCALE: f64 = 32.0;
+
+// a run's pages, one shown big enough to inspect in a `PageViewer`,
+// with a thumbnail of every page to pick which
+pub struct Gallery {
+    link: ComponentLink<Self>,
+    props: GalleryProps,
+    selected: usize,
+    // the folders of the plates left out of the page being shown, see `Gallery::plates`
+    hidden_plates: Vec<String>,
+}
+
+pub enum GalleryMsg {
+    Select(usize),
+    TogglePlate(String),
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct GalleryProps {
+    // (filename, object url) of each page
+    pub pages: Vec<(String, String)>,
+}
+
+impl Component for Gallery {
+    type Message = GalleryMsg;
+    type Properties = GalleryProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        Gallery {
+            link,
+            props,
+            selected: 0,
+            hidden_plates: vec![],
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            GalleryMsg::Select(selected) => {
+                self.selected = selected;
+                true
+            }
+            GalleryMsg::TogglePlate(folder) => {
+                if let Some(i) = self
+                    .hidden_plates
+                    .iter()
+                    .position(|hidden| *hidden == folder)
+                {
+                    self.hidden_plates.remove(i);
+                } else {
+                    self.hidden_plates.push(folder);
+                }
+                true
+            }
+        }
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.props != props {
+            self.props = props;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn view(&self) -> Html {
+        let (filename, url) = match self
+            .props
+            .pages
+            .get(self.selected)
+            .or_else(|| self.props.pages.last())
+        {
+            Some(page) => page,
+            None => return html! {},
+        };
+
+        // with any of its plates hidden, a page is shown as the rest of its plates
+        // printed over each other, rather than as the page with every ink
+        let plates = self.plates(filename);
+        let layers = plates
+            .iter()
+            .any(|(folder, _)| self.is_hidden(folder))
+            .then(|| {
+                plates
+                    .iter()
+                    .filter(|(folder, _)| !self.is_hidden(folder))
+                    .map(|(_, url)| url.to_string())
+                    .collect::<Vec<_>>()
+            });
+
+        html! {
+            <div>
+                <PageViewer url={url.to_string()} filename={filename.to_string()} layers={layers}/>
+
+                <div>
+                {
+                    for plates.iter().map(|(folder, _)| {
+                        let toggled = folder.to_string();
+                        html! {
+                            <label>
+                                <input
+                                type="checkbox"
+                                checked=!self.is_hidden(folder)
+                                onclick=self.link.callback(move |_| GalleryMsg::TogglePlate(toggled.clone()))/>
+                                { format!(" {} ", folder) }
+                            </label>
+                        }
+                    })
+                }
+                </div>
+
+                <div>
+                {
+                    for self.props.pages.iter().enumerate().map(|(i, (filename, url))| {
+                        html! {
+                            <div class="gallery-page">
+                                <img
+                                class="gallery-thumbnail"
+                                src={url.to_string()}
+                                alt={filename.to_string()}
+                                onclick=self.link.callback(move |_| GalleryMsg::Select(i))/>
+                                <a href={url.to_string()} download={filename.to_string()}>{"download"}</a>
+                            </div>
+                        }
+                    })
+                }
+                </div>
+            </div>
+        }
+    }
+}
+
+impl Gallery {
+    // the plates of the page named `filename`, as (folder, object url). each ink's layer
+    // of a page is named like the page, in a folder of its own
+    fn plates(&self, filename: &str) -> Vec<(&str, &str)> {
+        self.props
+            .pages
+            .iter()
+            .filter_map(|(plate, url)| {
+                let (folder, name) = plate.split_once('/')?;
+                (name == filename).then_some((folder, url.as_str()))
+            })
+            .collect()
+    }
+
+    fn is_hidden(&self, folder: &str) -> bool {
+        self.hidden_plates.iter().any(|hidden| hidden == folder)
+    }
+}
+
+// one page in a box it can be zoomed in with the wheel and dragged around in,
+// fitted to the box or at 100%, one page pixel to a screen pixel
+pub struct PageViewer {
+    link: ComponentLink<Self>,
+    props: PageViewerProps,
+    frame: NodeRef,
+    image: NodeRef,
+    // screen pixels per page pixel
+    scale: f64,
+    // where the page's top left corner is in the box
+    offset: (f64, f64),
+    // where the pointer was, while the page is being dragged
+    drag: Option<(i32, i32)>,
+}
+
+pub enum PageViewerMsg {
+    Fit,
+    ActualSize,
+    ZoomIn,
+    ZoomOut,
+    // zooming in or out by the wheel's direction, about where the pointer is on the screen
+    Wheel(f64, i32, i32),
+    StartDrag(i32, i32),
+    Drag(i32, i32),
+    EndDrag,
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct PageViewerProps {
+    pub url: String,
+    pub filename: String,
+    // object urls of images to show over each other in place of the page, inked
+    // like plates printed one over another, to check how they line up
+    #[prop_or_default]
+    pub layers: Option<Vec<String>>,
+}
+
+impl PageViewer {
+    // the box's size and the page's size, once the page has loaded
+    fn sizes(&self) -> Option<((f64, f64), (f64, f64))> {
+        let frame = self.frame.cast::<HtmlElement>()?;
+        let image = self.image.cast::<HtmlImageElement>()?;
+        if image.natural_width() == 0 {
+            return None;
+        }
+
+        Some((
+            (frame.client_width() as f64, frame.client_height() as f64),
+            (image.natural_width() as f64, image.natural_height() as f64),
+        ))
+    }
+
+    // `scale`, with the page centered in the box
+    fn center(&mut self, scale: f64) {
+        if let Some(((frame_width, frame_height), (width, height))) = self.sizes() {
+            self.scale = scale;
+            self.offset = (
+                (frame_width - width * scale) / 2.0,
+                (frame_height - height * scale) / 2.0,
+            );
+        }
+    }
+
+    // zooms by `factor`, keeping the page where `at` is in the box still
+    fn zoom(&mut self, factor: f64, at: (f64, f64)) {
+        let scale = (self.scale * factor).clamp(MIN_SCALE, MAX_SCALE);
+        let (x, y) = at;
+        let (offset_x, offset_y) = self.offset;
+
+        self.offset = (
+            x - (x - offset_x) * scale / self.scale,
+            y - (y - offset_y) * scale / self.scale,
+        );
+        self.scale = scale;
+    }
+
+    fn zoom_about_middle(&mut self, factor: f64) {
+        if let Some(((frame_width, frame_height), _)) = self.sizes() {
+            self.zoom(factor, (frame_width / 2.0, frame_height / 2.0));
+        }
+    }
+}
+
+impl Component for PageViewer {
+    type Message = PageViewerMsg;
+    type Properties = PageViewerProps;
+
+    fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        PageViewer {
+            link,
+            props,
+            frame: NodeRef::default(),
+            image: NodeRef::default(),
+            scale: 1.0,
+            offset: (0.0, 0.0),
+            drag: None,
+        }
+    }
+
+    fn update(&mut self, msg: Self::Message) -> ShouldRender {
+        match msg {
+            PageViewerMsg::Fit => {
+                if let Some(((frame_width, frame_height), (width, height))) = self.sizes() {
+                    let scale = (frame_width / width).min(frame_height / height);
+                    self.center(scale.clamp(MIN_SCALE, MAX_SCALE));
+                }
+            }
+            PageViewerMsg::ActualSize => self.center(1.0),
+            PageViewerMsg::ZoomIn => self.zoom_about_middle(ZOOM_STEP),
+            PageViewerMsg::ZoomOut => self.zoom_about_middle(1.0 / ZOOM_STEP),
+            PageViewerMsg::Wheel(delta, client_x, client_y) => {
+                let frame = match self.frame.cast::<HtmlElement>() {
+                    Some(frame) => frame,
+                    None => return false,
+                };
+                let bounds = frame.get_bounding_client_rect();
+                let at = (
+                    client_x as f64 - bounds.left(),
+                    client_y as f64 - bounds.top(),
+                );
+
+                let factor = if delta < 0.0 {
+                    ZOOM_STEP
+                } else {
+                    1.0 / ZOOM_STEP
+                };
+                self.zoom(factor, at);
+            }
+            PageViewerMsg::StartDrag(x, y) => self.drag = Some((x, y)),
+            PageViewerMsg::Drag(x, y) => {
+                let (last_x, last_y) = match self.drag {
+                    Some(last) => last,
+                    None => return false,
+                };
+                let (offset_x, offset_y) = self.offset;
+
+                self.offset = (
+                    offset_x + (x - last_x) as f64,
+                    offset_y + (y - last_y) as f64,
+                );
+                self.drag = Some((x, y));
+            }
+            PageViewerMsg::EndDrag => {
+                self.drag = None;
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        if self.props != props {
+            self.props = props;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn view(&self) -> Html {
+        let (x, y) = self.offset;
+        // zoomed in, page pixels are drawn as the squares they are, not smoothed over
+        let rendering = if self.scale >= 1.0 {
+            "pixelated"
+        } else {
+            "auto"
+        };
+        let style = format!(
+            "transform: translate({}px, {}px) scale({}); image-rendering: {};",
+            x, y, self.scale, rendering
+        );
+
+        html! {
+            <div>
+                <div>
+                    <button onclick=self.link.callback(|_| PageViewerMsg::Fit)>{ "fit" }</button>
+                    <button onclick=self.link.callback(|_| PageViewerMsg::ActualSize)>{ "100%" }</button>
+                    <button
+                    aria-label="zoom out"
+                    onclick=self.link.callback(|_| PageViewerMsg::ZoomOut)>
+                        { "−" }
+                    </button>
+                    <button
+                    aria-label="zoom in"
+                    onclick=self.link.callback(|_| PageViewerMsg::ZoomIn)>
+                        { "+" }
+                    </button>
+                    { format!(" {:.0}% ", self.scale * 100.0) }
+                    <a href={self.props.url.clone()} download={self.props.filename.clone()}>
+                        { format!("download {}", self.props.filename) }
+                    </a>
+                </div>
+
+                <div
+                ref=self.frame.clone()
+                class="page-viewer"
+                onwheel=self.link.callback(|e: WheelEvent| {
+                    e.prevent_default();
+                    PageViewerMsg::Wheel(e.delta_y(), e.client_x(), e.client_y())
+                })
+                onmousedown=self.link.callback(|e: MouseEvent| PageViewerMsg::StartDrag(e.client_x(), e.client_y()))
+                onmousemove=self.link.callback(|e: MouseEvent| PageViewerMsg::Drag(e.client_x(), e.client_y()))
+                onmouseup=self.link.callback(|_| PageViewerMsg::EndDrag)
+                onmouseleave=self.link.callback(|_| PageViewerMsg::EndDrag)>
+                    // the page is still there under its layers, to be sized by
+                    <img
+                    ref=self.image.clone()
+                    src={self.props.url.clone()}
+                    alt={self.props.filename.clone()}
+                    draggable="false"
+                    style={ if self.props.layers.is_some() {
+                        format!("{} visibility: hidden;", style)
+                    } else {
+                        style.clone()
+                    } }
+                    onload=self.link.callback(|_| PageViewerMsg::Fit)/>
+                    {
+                        for self.props.layers.iter().flatten().map(|url| html! {
+                            <img
+                            src={url.to_string()}
+                            alt=""
+                            draggable="false"
+                            style={ format!("{} mix-blend-mode: multiply;", style) }/>
+                        })
+                    }
+                </div>
+            </div>
+        }
+    }
+}
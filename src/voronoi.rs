@@ -0,0 +1,180 @@
+// weighted Voronoi stippling (Secord, 2002).
+// points start out scattered by darkness, then each round of Lloyd's relaxation
+// moves every point to the darkness-weighted centroid of its Voronoi cell,
+// the area of the image closer to it than to any other point.
+// after a few rounds the points are evenly spaced relative to the local darkness.
+use crate::stipple::XorShift;
+
+// attempts per point when scattering the starting points,
+// so a (nearly) blank image can't keep us looking forever
+const PLACEMENT_ATTEMPTS_PER_POINT: u32 = 100;
+
+pub struct Stippler {
+    width: u32,
+    height: u32,
+    // darkness of every pixel, 0 to 255, left-right top-bottom.
+    // a byte rather than a float each, as there is one for every pixel of the page grid
+    density: Vec<u8>,
+    points: Vec<(f32, f32)>,
+    iterations: u32,
+}
+
+impl Stippler {
+    pub fn new(width: u32, height: u32, density: Vec<u8>, point_count: u32, seed: u32) -> Self {
+        assert_eq!(density.len(), (width * height) as usize);
+
+        let mut rng = XorShift::new(seed);
+        let mut points = Vec::with_capacity(point_count as usize);
+
+        if width > 0 && height > 0 {
+            let mut attempts = 0;
+            while points.len() < point_count as usize
+                && attempts < point_count * PLACEMENT_ATTEMPTS_PER_POINT
+            {
+                attempts += 1;
+
+                let x = rng.next_f32() * width as f32;
+                let y = rng.next_f32() * height as f32;
+                let index = (y as u32).min(height - 1) * width + (x as u32).min(width - 1);
+
+                if rng.next_f32() < weight(density[index as usize]) {
+                    points.push((x, y));
+                }
+            }
+        }
+
+        Stippler {
+            width,
+            height,
+            density,
+            points,
+            iterations: 0,
+        }
+    }
+
+    pub fn points(&self) -> &[(f32, f32)] {
+        &self.points
+    }
+
+    // rounds of relaxation done so far
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    // one round of Lloyd's relaxation
+    pub fn relax(&mut self) {
+        self.iterations += 1;
+
+        if self.points.is_empty() {
+            return;
+        }
+
+        let buckets = Buckets::new(self.width, self.height, &self.points);
+        // (total weight, weighted x, weighted y) of each point's cell
+        let mut centroids = vec![(0.0f32, 0.0f32, 0.0f32); self.points.len()];
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let weight = weight(self.density[(y * self.width + x) as usize]);
+                // blank pixels don't move anything
+                if weight <= 0.0 {
+                    continue;
+                }
+
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let nearest = buckets.nearest(&self.points, px, py);
+                let centroid = &mut centroids[nearest];
+                centroid.0 += weight;
+                centroid.1 += weight * px;
+                centroid.2 += weight * py;
+            }
+        }
+
+        for (point, (weight, weighted_x, weighted_y)) in self.points.iter_mut().zip(centroids) {
+            if weight > 0.0 {
+                *point = (weighted_x / weight, weighted_y / weight);
+            }
+        }
+    }
+}
+
+fn weight(density: u8) -> f32 {
+    density as f32 / 255.0
+}
+
+// points sorted into a coarse grid, so finding the nearest point
+// only has to look at the few buckets around a pixel
+struct Buckets {
+    bucket_size: f32,
+    columns: usize,
+    rows: usize,
+    buckets: Vec<Vec<usize>>,
+}
+
+impl Buckets {
+    fn new(width: u32, height: u32, points: &[(f32, f32)]) -> Self {
+        // about one point per bucket
+        let bucket_size = ((width as f32 * height as f32) / points.len() as f32)
+            .sqrt()
+            .max(1.0);
+        let columns = (width as f32 / bucket_size).ceil().max(1.0) as usize;
+        let rows = (height as f32 / bucket_size).ceil().max(1.0) as usize;
+        let mut buckets = vec![vec![]; columns * rows];
+
+        for (i, &(x, y)) in points.iter().enumerate() {
+            let column = ((x / bucket_size) as usize).min(columns - 1);
+            let row = ((y / bucket_size) as usize).min(rows - 1);
+            buckets[row * columns + column].push(i);
+        }
+
+        Buckets {
+            bucket_size,
+            columns,
+            rows,
+            buckets,
+        }
+    }
+
+    // searches rings of buckets outward from the one (x, y) is in,
+    // until no unsearched bucket could hold anything closer
+    fn nearest(&self, points: &[(f32, f32)], x: f32, y: f32) -> usize {
+        let column = ((x / self.bucket_size) as usize).min(self.columns - 1) as isize;
+        let row = ((y / self.bucket_size) as usize).min(self.rows - 1) as isize;
+
+        let mut nearest = 0;
+        let mut nearest_distance = f32::INFINITY;
+
+        for ring in 0..self.columns.max(self.rows) as isize {
+            for r in (row - ring)..=(row + ring) {
+                for c in (column - ring)..=(column + ring) {
+                    let on_ring = (r - row).abs() == ring || (c - column).abs() == ring;
+                    if !on_ring
+                        || r < 0
+                        || c < 0
+                        || r >= self.rows as isize
+                        || c >= self.columns as isize
+                    {
+                        continue;
+                    }
+
+                    for &i in &self.buckets[r as usize * self.columns + c as usize] {
+                        let (px, py) = points[i];
+                        let distance = (px - x).powi(2) + (py - y).powi(2);
+                        if distance < nearest_distance {
+                            nearest = i;
+                            nearest_distance = distance;
+                        }
+                    }
+                }
+            }
+
+            // everything in the next ring is at least this far away
+            let next_ring_distance = ring as f32 * self.bucket_size;
+            if nearest_distance <= next_ring_distance * next_ring_distance {
+                break;
+            }
+        }
+
+        nearest
+    }
+}
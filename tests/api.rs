@@ -0,0 +1,73 @@
+// the js api in src/api.rs, driven from rust: settings go in as json, and the pages
+// come out as files. these need no particular image format, so unlike the corpus
+// and golden tests they run with a plain:
+//
+//     cargo test
+
+use rat::rasterize::Pattern;
+use rat::settings::{self, Settings};
+
+mod common;
+use common::{gradient, png};
+
+#[test]
+fn api_takes_settings_json() {
+    // settings written by an older rat, without most fields
+    let settings = Settings::from_json(br#"{"pattern": "Lines", "pages_width": 2}"#).unwrap();
+    assert_eq!(settings.pattern, Pattern::Lines);
+    assert_eq!(settings.square_size, Settings::default().square_size);
+    assert_eq!(
+        Settings::from_json(&settings.to_json()),
+        Ok(settings.clone())
+    );
+
+    for (width, height) in [(48, 32), (1, 1), (2000, 1)] {
+        let name = format!("{}x{}", width, height);
+        let bytes = png(&gradient(width, height));
+        let pngs = rat::api::pngs(&bytes, &settings).unwrap();
+        let svgs = rat::api::svgs(&bytes, &settings).unwrap();
+
+        assert!(!pngs.is_empty(), "{}", name);
+        assert_eq!(pngs.len(), svgs.len(), "{}", name);
+        for png in pngs {
+            assert!(image::load_from_memory(&png).is_ok(), "{}", name);
+        }
+    }
+
+    assert!(Settings::from_json(br#"{"square_size": "big"}"#).is_err());
+    assert!(rat::api::pngs(b"not an image", &settings).is_err());
+}
+
+#[test]
+fn outputs_carry_their_settings() {
+    let settings = Settings {
+        pattern: Pattern::Lines,
+        character_ramp: "░▒▓█".to_string(),
+        ..Settings::default()
+    };
+    let bytes = &png(&gradient(48, 32));
+
+    let png = &rat::api::pngs(bytes, &settings).unwrap()[0];
+    // each chunk is its length, type, data and crc, after the 8 byte signature
+    let mut texts = vec![];
+    let mut at = 8;
+    while at + 8 <= png.len() {
+        let length = u32::from_be_bytes(png[at..at + 4].try_into().unwrap()) as usize;
+        if &png[at + 4..at + 8] == b"tEXt" {
+            texts.push(&png[at + 8..at + 8 + length]);
+        }
+        at += length + 12;
+    }
+    let keyword = format!("{}\0", settings::PNG_SETTINGS_KEYWORD);
+    let json = texts
+        .iter()
+        .find_map(|text| text.strip_prefix(keyword.as_bytes()))
+        .expect("no settings in the png");
+    assert!(json.is_ascii());
+    assert_eq!(Settings::from_json(json), Ok(settings.clone()));
+
+    let svg = &rat::api::svgs(bytes, &settings).unwrap()[0];
+    assert!(svg.contains("<metadata>"));
+    assert!(svg.contains(&settings.summary()));
+    assert!(image::load_from_memory(png).is_ok());
+}
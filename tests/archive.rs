@@ -0,0 +1,120 @@
+// the "download all" zip, written and read back by `rat::archive`. run with:
+//
+//     cargo test --test archive
+
+use rat::archive::{self, ZipCompression, ZipError, ZipWriter};
+
+// a few pages' worth of svg, as repetitive as real ones, so they shrink when deflated
+fn svg_pages() -> Vec<Vec<u8>> {
+    (1..=4)
+        .map(|page| {
+            let dots = (0..page * 50)
+                .map(|i| {
+                    format!(
+                        "<circle cx=\"{}\" cy=\"{}\" r=\"3\"/>",
+                        i % 40 * 6,
+                        i / 40 * 6
+                    )
+                })
+                .collect::<String>();
+            format!("<svg xmlns=\"http://www.w3.org/2000/svg\">{}</svg>", dots).into_bytes()
+        })
+        .collect()
+}
+
+#[test]
+fn zips_list_every_file_whatever_their_compression() {
+    let svgs = svg_pages();
+    let zip = |compression| {
+        let mut writer = ZipWriter::new(compression);
+        for (i, svg) in svgs.iter().enumerate() {
+            writer.add(&format!("page_{}.svg", i + 1), svg).unwrap();
+        }
+        writer.finish().unwrap()
+    };
+    let stored = zip(ZipCompression::Stored);
+
+    for compression in ZipCompression::zip_compressions() {
+        let zip = zip(*compression);
+        let files = archive::read_zip(&zip).unwrap();
+        assert_eq!(files.len(), svgs.len(), "{}", compression);
+
+        for (i, (svg, (filename, bytes))) in svgs.iter().zip(&files).enumerate() {
+            assert_eq!(*filename, format!("page_{}.svg", i + 1), "{}", compression);
+            assert_eq!(bytes, svg, "{}", compression);
+
+            // svgs always shrink, so are only stored when asked to be
+            let in_zip = zip
+                .windows(svg.len())
+                .any(|window| window == svg.as_slice());
+            assert_eq!(
+                in_zip,
+                *compression == ZipCompression::Stored,
+                "{}",
+                compression
+            );
+        }
+        if *compression != ZipCompression::Stored {
+            assert!(zip.len() < stored.len(), "{}", compression);
+        }
+    }
+}
+
+// exporting a project reads a run's files back out of its "download all" zip
+#[test]
+fn zips_read_back_what_was_written() {
+    let files = svg_pages()
+        .into_iter()
+        .enumerate()
+        .map(|(i, svg)| (format!("page_{}.svg", i + 1), svg))
+        .chain(std::iter::once(("empty.txt".to_string(), vec![])))
+        .collect::<Vec<_>>();
+
+    for compression in ZipCompression::zip_compressions() {
+        let mut writer = ZipWriter::new(*compression);
+        for (filename, bytes) in &files {
+            writer.add(filename, bytes).unwrap();
+        }
+        let zip = writer.finish().unwrap();
+
+        assert_eq!(archive::read_zip(&zip).unwrap(), files, "{}", compression);
+        assert_eq!(
+            archive::read_zip(&zip[..zip.len() - 1]),
+            Err(ZipError::Unreadable),
+            "{}",
+            compression
+        );
+    }
+}
+
+// past the 65535 files a zip without zip64 can list, adding is an error, not a wrap around
+#[test]
+fn zips_refuse_files_past_their_limit() {
+    let mut writer = ZipWriter::new(ZipCompression::Stored);
+    for i in 0..u16::MAX {
+        writer.add(&i.to_string(), &[]).unwrap();
+    }
+    assert_eq!(writer.add("one too many", &[]), Err(ZipError::TooManyFiles));
+
+    let zip = writer.finish().unwrap();
+    assert_eq!(archive::read_zip(&zip).unwrap().len(), u16::MAX as usize);
+}
+
+// a damaged zip is an error, never a panic or a wrong file
+#[test]
+fn zips_that_are_damaged_are_unreadable() {
+    let mut writer = ZipWriter::new(ZipCompression::Stored);
+    writer.add("page_1.svg", &svg_pages()[0]).unwrap();
+    let zip = writer.finish().unwrap();
+
+    // a flipped bit in the file's bytes, past its 30 byte local header and name
+    let mut flipped = zip.clone();
+    flipped[30 + "page_1.svg".len()] ^= 1;
+    assert_eq!(archive::read_zip(&flipped), Err(ZipError::Unreadable));
+
+    // a central directory offset far past the end
+    let mut offset = zip.clone();
+    let end = offset.len() - 22;
+    offset[end + 16..end + 20].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(archive::read_zip(&offset), Err(ZipError::Unreadable));
+}
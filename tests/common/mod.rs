@@ -0,0 +1,79 @@
+// fixtures shared by the test files that rasterize, see tests/corpus.rs.
+// each test file is its own crate and uses only some of them
+#![allow(dead_code)]
+
+use image::codecs::png::PngEncoder;
+use image::{ColorType, ImageBuffer, Rgba, RgbaImage};
+use rat::color::{Gradient, Inks, Palette};
+use rat::draw::CircleAlgorithm;
+use rat::rasterize::{
+    self, Border, ChannelInvert, ColorDepth, Dither, DotShape, Edges, Framing, Jitter, Layout,
+    Luma, MaskBlend, MinDotSize, ParameterMatrix, Pattern, RadiusCurve, RasterizeArgs, Resampling,
+    RowOrder, StepAndRepeat, SvgOptimization, Tone, Transparency,
+};
+
+pub const PAPER_WIDTH_PIXELS: f32 = 144.0;
+pub const PAPER_HEIGHT_PIXELS: f32 = 108.0;
+
+// a colorful gradient that fades out to fully transparent on the right
+pub fn gradient(width: u32, height: u32) -> RgbaImage {
+    ImageBuffer::from_fn(width, height, |x, y| {
+        let v = (y * 255 / height) as u8;
+        Rgba([v, 255 - v, v / 2, 255 - (x * 255 / width) as u8])
+    })
+}
+
+// `image` as the bytes of a png file, as the api is given them
+pub fn png(image: &RgbaImage) -> Vec<u8> {
+    let mut bytes = vec![];
+    PngEncoder::new(&mut bytes)
+        .encode(image, image.width(), image.height(), ColorType::Rgba8)
+        .unwrap();
+    bytes
+}
+
+pub fn args(image: &RgbaImage, pattern: Pattern) -> RasterizeArgs<'_> {
+    RasterizeArgs {
+        image,
+        mask: None,
+        paper_width_pixels: PAPER_WIDTH_PIXELS,
+        paper_height_pixels: PAPER_HEIGHT_PIXELS,
+        pages_width: 2,
+        pages_height: 2,
+        square_size: 6.0,
+        min_radius_percentage: 0.0,
+        max_radius_percentage: 1.0,
+        color_depth: ColorDepth::Rgb,
+        layout: Layout::Fit,
+        step_and_repeat: StepAndRepeat::default(),
+        border: Border::default(),
+        matrix: ParameterMatrix::default(),
+        gradient: Gradient::default(),
+        output_pixels_per_inch: rasterize::PIXELS_PER_INCH,
+        draft: false,
+        circle_algorithm: CircleAlgorithm::Scanline,
+        dither: Dither::None,
+        pattern,
+        mask_blend: MaskBlend::Multiply,
+        line_angle: 45.0,
+        invert: ChannelInvert::default(),
+        inks: Inks::default(),
+        ink_layer: None,
+        row_order: RowOrder::RowMajor,
+        palette: Palette::default(),
+        transparency: Transparency::default(),
+        tone: Tone::default(),
+        dot_metadata: false,
+        radius_curve: RadiusCurve::default(),
+        luma: Luma::Rec601,
+        jitter: Jitter::default(),
+        dot_shape: DotShape::Circle,
+        follow_contours: false,
+        edges: Edges::default(),
+        framing: Framing::default(),
+        resampling: Resampling::Triangle,
+        svg_optimization: SvgOptimization::default(),
+        min_dot_size: MinDotSize::default(),
+        supersampling: 1,
+    }
+}
@@ -0,0 +1,1227 @@
+// runs the whole pipeline over small images in the formats and pixel layouts
+// that have tripped it up before, checking that nothing panics and that the
+// outputs have the shape they should.
+//
+// the corpus is encoded here rather than checked in, so every image is small,
+// deterministic and easy to add to. run with:
+//
+//     cargo test --features corpus-tests --test corpus
+
+use image::codecs::gif::GifEncoder;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::{ColorType, GenericImageView, ImageBuffer, Rgba, RgbaImage};
+use rat::draw::CircleAlgorithm;
+use rat::rasterize::{
+    self, Border, BuildUp, ChannelInvert, ColorDepth, ColorProfile, Dither, DotShape, Edges,
+    Framing, Jitter, Laser, Layout, Luma, MinDotSize, Pattern, RadiusCurve, RasterizeArgs, Relief,
+    ReliefShape, RowOrder, SvgOptimization, Tone, Transparency,
+};
+use rat::stipple;
+
+mod common;
+use common::{args, PAPER_HEIGHT_PIXELS, PAPER_WIDTH_PIXELS};
+
+// (name, encoded bytes)
+fn corpus() -> Vec<(&'static str, Vec<u8>)> {
+    vec![
+        ("transparent png", transparent_png()),
+        ("grayscale jpeg", grayscale_jpeg()),
+        ("indexed gif", indexed_gif()),
+        ("16-bit png", sixteen_bit_png()),
+        ("1x1 png", one_by_one_png()),
+        ("very wide png", very_wide_png()),
+    ]
+}
+
+// fades out to fully transparent on the right
+fn transparent_png() -> Vec<u8> {
+    let (width, height) = (48, 32);
+    let pixels = ImageBuffer::from_fn(width, height, |x, y| {
+        let v = (y * 255 / height) as u8;
+        Rgba([v, 255 - v, v / 2, 255 - (x * 255 / width) as u8])
+    });
+
+    let mut bytes = vec![];
+    PngEncoder::new(&mut bytes)
+        .encode(&pixels, width, height, ColorType::Rgba8)
+        .unwrap();
+    bytes
+}
+
+fn grayscale_jpeg() -> Vec<u8> {
+    let (width, height) = (40, 30);
+    let pixels = ImageBuffer::from_fn(width, height, |x, y| {
+        image::Luma([((x + y) * 255 / (width + height)) as u8])
+    });
+
+    let mut bytes = vec![];
+    JpegEncoder::new(&mut bytes)
+        .encode(&pixels, width, height, ColorType::L8)
+        .unwrap();
+    bytes
+}
+
+// gif frames are always quantized down to a palette
+fn indexed_gif() -> Vec<u8> {
+    let (width, height) = (32, 32);
+    let pixels = ImageBuffer::from_fn(width, height, |x, y| {
+        Rgba([(x * 8) as u8, (y * 8) as u8, ((x ^ y) * 8) as u8, 255])
+    });
+
+    let mut bytes = vec![];
+    GifEncoder::new(&mut bytes)
+        .encode(&pixels, width, height, ColorType::Rgba8)
+        .unwrap();
+    bytes
+}
+
+fn sixteen_bit_png() -> Vec<u8> {
+    let (width, height) = (36, 24);
+    let pixels: ImageBuffer<Rgba<u16>, Vec<u16>> = ImageBuffer::from_fn(width, height, |x, _| {
+        let v = (x * 65535 / width) as u16;
+        Rgba([v, v, v, 65535])
+    });
+
+    // png stores samples big endian
+    let samples = pixels
+        .into_raw()
+        .into_iter()
+        .flat_map(|sample| sample.to_be_bytes())
+        .collect::<Vec<_>>();
+
+    let mut bytes = vec![];
+    PngEncoder::new(&mut bytes)
+        .encode(&samples, width, height, ColorType::Rgba16)
+        .unwrap();
+    bytes
+}
+
+fn one_by_one_png() -> Vec<u8> {
+    let mut bytes = vec![];
+    PngEncoder::new(&mut bytes)
+        .encode(&[0, 0, 0, 255], 1, 1, ColorType::Rgba8)
+        .unwrap();
+    bytes
+}
+
+// scales down to less than a pixel tall on the page grid
+fn very_wide_png() -> Vec<u8> {
+    let (width, height) = (2000, 1);
+    let pixels = ImageBuffer::from_fn(width, height, |x, _| image::Luma([(x % 256) as u8]));
+
+    let mut bytes = vec![];
+    PngEncoder::new(&mut bytes)
+        .encode(&pixels, width, height, ColorType::L8)
+        .unwrap();
+    bytes
+}
+
+#[test]
+fn corpus_decodes() {
+    for (name, bytes) in corpus() {
+        let image = image::load_from_memory(&bytes)
+            .unwrap_or_else(|e| panic!("{} did not decode: {}", name, e));
+
+        assert!(rasterize::check_image(&image).is_ok(), "{}", name);
+    }
+}
+
+#[test]
+fn image_backend_pages_fit_the_page_grid() {
+    for (name, bytes) in corpus() {
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+
+        for pattern in Pattern::patterns() {
+            let pages = rasterize::rasterize_image(args(&image, *pattern)).collect::<Vec<_>>();
+
+            assert!(!pages.is_empty(), "{} {}: no pages", name, pattern);
+            assert!(
+                pages.len() <= 4,
+                "{} {}: {} pages",
+                name,
+                pattern,
+                pages.len()
+            );
+
+            for page in pages {
+                let (width, height) = page.dimensions();
+                assert!(
+                    width as f32 <= PAPER_WIDTH_PIXELS && height as f32 <= PAPER_HEIGHT_PIXELS,
+                    "{} {}: page is {}x{}",
+                    name,
+                    pattern,
+                    width,
+                    height
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn svg_backend_matches_image_backend() {
+    for (name, bytes) in corpus() {
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+
+        for pattern in Pattern::patterns() {
+            let pages = rasterize::rasterize_image(args(&image, *pattern)).collect::<Vec<_>>();
+            let documents = rasterize::rasterize_svg(args(&image, *pattern)).collect::<Vec<_>>();
+
+            assert_eq!(pages.len(), documents.len(), "{} {}", name, pattern);
+
+            for document in documents {
+                assert!(
+                    document.to_string().starts_with("<svg"),
+                    "{} {}",
+                    name,
+                    pattern
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn gcode_backend_matches_image_backend() {
+    for (name, bytes) in corpus() {
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+
+        for pattern in Pattern::patterns() {
+            let pages = rasterize::rasterize_image(args(&image, *pattern)).count();
+
+            for row_order in RowOrder::row_orders() {
+                let programs = rasterize::rasterize_gcode(RasterizeArgs {
+                    row_order: *row_order,
+                    ..args(&image, *pattern)
+                })
+                .collect::<Vec<_>>();
+
+                assert_eq!(pages, programs.len(), "{} {} {}", name, pattern, row_order);
+
+                for program in programs {
+                    assert!(
+                        program.starts_with("G21"),
+                        "{} {} {}",
+                        name,
+                        pattern,
+                        row_order
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn eps_backend_matches_image_backend() {
+    for (name, bytes) in corpus() {
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+
+        for pattern in Pattern::patterns() {
+            let pages = rasterize::rasterize_image(args(&image, *pattern)).collect::<Vec<_>>();
+            let documents = rasterize::rasterize_eps(args(&image, *pattern)).collect::<Vec<_>>();
+
+            assert_eq!(pages.len(), documents.len(), "{} {}", name, pattern);
+
+            for (page, document) in pages.iter().zip(documents) {
+                // pages are drawn at 72 pixels per inch, the same as points
+                let bounding_box = format!("%%BoundingBox: 0 0 {} {}", page.width(), page.height());
+                assert!(
+                    document.starts_with("%!PS-Adobe-3.0 EPSF-3.0"),
+                    "{} {}",
+                    name,
+                    pattern
+                );
+                assert!(
+                    document.contains(&bounding_box),
+                    "{} {}: no {}",
+                    name,
+                    pattern,
+                    bounding_box
+                );
+                assert!(
+                    document.trim_end().ends_with("%%EOF"),
+                    "{} {}",
+                    name,
+                    pattern
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn dxf_backend_matches_image_backend() {
+    for (name, bytes) in corpus() {
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+
+        for pattern in Pattern::patterns() {
+            let pages = rasterize::rasterize_image(args(&image, *pattern)).count();
+            let drawings = rasterize::rasterize_dxf(args(&image, *pattern)).collect::<Vec<_>>();
+
+            assert_eq!(pages, drawings.len(), "{} {}", name, pattern);
+
+            for drawing in drawings {
+                let lines = drawing.lines().collect::<Vec<_>>();
+                // group codes and values come in pairs
+                assert_eq!(lines.len() % 2, 0, "{} {}", name, pattern);
+                assert_eq!(
+                    lines[lines.len() - 2..],
+                    ["0", "EOF"],
+                    "{} {}",
+                    name,
+                    pattern
+                );
+            }
+        }
+    }
+
+    // a duotone's inks are each on their own layer
+    let (_, bytes) = &corpus()[0];
+    let image = image::load_from_memory(bytes).unwrap().to_rgba8();
+    let drawing = rasterize::rasterize_dxf(RasterizeArgs {
+        color_depth: ColorDepth::Duotone,
+        ..args(&image, Pattern::Grid)
+    })
+    .next()
+    .unwrap();
+    assert!(drawing.contains("CIRCLE\n8\nink-1") || drawing.contains("CIRCLE\n8\nink-2"));
+    assert!(!drawing.contains("\npattern\n"));
+}
+
+// every solid of a relief is closed, so each edge of one triangle is
+// the same edge of another, going the other way
+#[test]
+fn stl_reliefs_are_watertight() {
+    let (_, bytes) = &corpus()[0];
+    let image = image::load_from_memory(bytes).unwrap().to_rgba8();
+
+    for pattern in Pattern::patterns() {
+        for shape in ReliefShape::relief_shapes() {
+            let relief = Relief {
+                enabled: true,
+                shape: *shape,
+                ..Relief::default()
+            };
+            let pages = rasterize::rasterize_image(args(&image, *pattern)).count();
+            let stls = rasterize::rasterize_stl(args(&image, *pattern), relief).collect::<Vec<_>>();
+            assert_eq!(pages, stls.len(), "{} {}", pattern, shape);
+
+            for stl in stls {
+                let count = u32::from_le_bytes(stl[80..84].try_into().unwrap()) as usize;
+                assert_eq!(stl.len(), 84 + count * 50, "{} {}", pattern, shape);
+
+                let mut edges = std::collections::HashMap::new();
+                for triangle in stl[84..].chunks(50) {
+                    let corner = |i: usize| {
+                        let at = 12 + i * 12;
+                        triangle[at..at + 12].to_vec()
+                    };
+                    for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+                        *edges.entry((corner(a), corner(b))).or_insert(0i32) += 1;
+                        *edges.entry((corner(b), corner(a))).or_insert(0i32) -= 1;
+                    }
+                }
+                assert!(
+                    edges.values().all(|count| *count == 0),
+                    "{} {}: open edges",
+                    pattern,
+                    shape
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn dot_maps_list_every_grid_dot() {
+    for (name, bytes) in corpus() {
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        let args = args(&image, Pattern::Grid);
+
+        let documents = rasterize::rasterize_svg(args).collect::<Vec<_>>();
+        let maps = rasterize::rasterize_dot_map(args).collect::<Vec<_>>();
+        assert_eq!(documents.len(), maps.len(), "{}", name);
+
+        for (document, map) in documents.iter().zip(maps) {
+            let mut lines = map.lines();
+            assert_eq!(lines.next(), Some("x_mm,y_mm,radius_mm,color"), "{}", name);
+
+            let dots = lines.collect::<Vec<_>>();
+            assert_eq!(
+                dots.len(),
+                document.to_string().matches("<circle").count(),
+                "{}",
+                name
+            );
+            for dot in dots {
+                let fields = dot.split(',').collect::<Vec<_>>();
+                assert_eq!(fields.len(), 4, "{}: {}", name, dot);
+                assert!(
+                    fields[..3].iter().all(|field| field.parse::<f32>().is_ok()),
+                    "{}: {}",
+                    name,
+                    dot
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn laser_svgs_are_outlines_in_millimeters() {
+    let (_, bytes) = &corpus()[0];
+    let image = image::load_from_memory(bytes).unwrap().to_rgba8();
+    let laser = Laser {
+        enabled: true,
+        ..Laser::default()
+    };
+
+    for pattern in Pattern::patterns() {
+        let pages = rasterize::rasterize_image(args(&image, *pattern)).collect::<Vec<_>>();
+        let documents =
+            rasterize::rasterize_laser_svg(args(&image, *pattern), laser).collect::<Vec<_>>();
+        assert_eq!(pages.len(), documents.len(), "{}", pattern);
+
+        for (page, document) in pages.iter().zip(documents) {
+            let document = document.to_string();
+            let width = format!("{}mm", page.width() as f32 / 72.0 * 25.4);
+            assert!(document.contains(&width), "{}: not {} wide", pattern, width);
+            assert!(!document.contains("fill=\"#"), "{}: filled shapes", pattern);
+        }
+    }
+
+    // each ink of a duotone is its own layer
+    let document = rasterize::rasterize_laser_svg(
+        RasterizeArgs {
+            color_depth: ColorDepth::Duotone,
+            ..args(&image, Pattern::Grid)
+        },
+        laser,
+    )
+    .next()
+    .unwrap()
+    .to_string();
+    assert_eq!(document.matches("<g").count(), 2);
+}
+
+#[test]
+fn assembly_instructions_place_every_page() {
+    for (name, bytes) in corpus() {
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        let args = args(&image, Pattern::Grid);
+        let pages = rasterize::rasterize_image(args).count();
+
+        let map = rasterize::assembly_map(args).unwrap().to_string();
+        assert_eq!(map.matches("<rect").count(), pages, "{}", name);
+
+        let instructions = rasterize::assembly_instructions(args, "corpus", "summary", &[]);
+        assert!(instructions.starts_with("summary"), "{}", name);
+        assert!(
+            instructions.contains(&format!("{} pages", pages)),
+            "{}: {}",
+            name,
+            instructions
+        );
+        for page in 1..=pages {
+            assert!(
+                instructions.contains(&format!("page {}: column", page)),
+                "{}: {}",
+                name,
+                instructions
+            );
+        }
+    }
+
+    // matrix pages aren't put together, so there is nothing to map
+    let (_, bytes) = &corpus()[0];
+    let image = image::load_from_memory(bytes).unwrap().to_rgba8();
+    let args = RasterizeArgs {
+        layout: Layout::Matrix,
+        ..args(&image, Pattern::Grid)
+    };
+    assert!(rasterize::assembly_map(args).is_none());
+    assert!(rasterize::assembly_instructions(args, "corpus", "summary", &[]).contains("matrix"));
+
+    // the plates' folders are only mentioned when there are some to point to
+    let args = RasterizeArgs {
+        color_depth: ColorDepth::Duotone,
+        ..args
+    };
+    let folders = args.plate_folders();
+    let instructions = rasterize::assembly_instructions(args, "corpus", "summary", &folders);
+    assert!(folders
+        .iter()
+        .all(|folder| instructions.contains(folder.as_str())));
+    assert!(instructions.lines().all(|line| !line.starts_with(' ')));
+    assert!(!rasterize::assembly_instructions(args, "corpus", "summary", &[]).contains("plate"));
+}
+
+#[test]
+fn jpeg_pages_decode_to_the_same_size() {
+    for (name, bytes) in corpus() {
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+
+        for page in rasterize::rasterize_image(args(&image, Pattern::Grid)) {
+            let jpeg = rasterize::encode_jpeg(&page, 85);
+            assert_eq!(
+                image::guess_format(&jpeg).unwrap(),
+                image::ImageFormat::Jpeg,
+                "{}",
+                name
+            );
+
+            let decoded = image::load_from_memory(&jpeg).unwrap();
+            assert_eq!(decoded.dimensions(), page.dimensions(), "{}", name);
+        }
+    }
+}
+
+#[test]
+fn animation_frames_of_the_same_size_of_image_are_the_same_size() {
+    let (_, bytes) = &corpus()[0];
+    let image = image::load_from_memory(bytes).unwrap().to_rgba8();
+    let mut inverted = image.clone();
+    image::imageops::invert(&mut inverted);
+
+    for layout in [Layout::Fit, Layout::Matrix] {
+        let frame = |image| {
+            rasterize::animation_frame(RasterizeArgs {
+                layout,
+                ..args(image, Pattern::Grid)
+            })
+            .unwrap()
+        };
+        let (first, second) = (frame(&image), frame(&inverted));
+
+        assert_eq!(first.dimensions(), second.dimensions(), "{:?}", layout);
+        assert!(first.width().max(first.height()) <= 640, "{:?}", layout);
+        assert_ne!(first, second, "{:?}", layout);
+    }
+}
+
+#[test]
+fn build_ups_end_on_the_finished_poster() {
+    let (_, bytes) = &corpus()[0];
+    let image = image::load_from_memory(bytes).unwrap().to_rgba8();
+    let args = args(&image, Pattern::Grid);
+    let poster = rasterize::animation_frame(args).unwrap();
+    let ink = |frame: &RgbaImage| {
+        frame
+            .pixels()
+            .filter(|pixel| **pixel != Rgba([255, 255, 255, 255]))
+            .count()
+    };
+
+    assert_eq!(rasterize::build_up(args, BuildUp::None, 8).count(), 0);
+
+    for build_up in [BuildUp::Grow, BuildUp::Sweep] {
+        let frames = rasterize::build_up(args, build_up, 8).collect::<Vec<_>>();
+        assert_eq!(frames.len(), 8, "{}", build_up);
+        assert_eq!(frames.last(), Some(&poster), "{}", build_up);
+        assert!(
+            frames
+                .iter()
+                .all(|frame| frame.dimensions() == poster.dimensions()),
+            "{}",
+            build_up
+        );
+        // more of the poster is inked in every frame
+        assert!(
+            frames.windows(2).all(|pair| ink(&pair[0]) <= ink(&pair[1])),
+            "{}",
+            build_up
+        );
+        assert!(ink(&frames[0]) < ink(&poster), "{}", build_up);
+    }
+}
+
+#[test]
+fn dots_too_small_to_print_are_left_out_and_counted() {
+    let (_, bytes) = &corpus()[0];
+    let image = image::load_from_memory(bytes).unwrap().to_rgba8();
+    let min_dot_size = MinDotSize {
+        enabled: true,
+        radius: 0.8,
+    };
+    let culled_args = RasterizeArgs {
+        min_dot_size,
+        ..args(&image, Pattern::Grid)
+    };
+    let circles = |args: RasterizeArgs| {
+        rasterize::rasterize_svg(args)
+            .map(|document| document.to_string().matches("<circle").count())
+            .sum::<usize>()
+    };
+
+    let culled = rasterize::culled_dot_count(culled_args);
+    assert!(culled > 0);
+    assert_eq!(
+        circles(args(&image, Pattern::Grid)) - circles(culled_args),
+        culled
+    );
+    assert_eq!(rasterize::culled_dot_count(args(&image, Pattern::Grid)), 0);
+
+    for csv in rasterize::rasterize_dot_map(culled_args) {
+        for line in csv.lines().skip(1) {
+            let radius: f32 = line.split(',').nth(2).unwrap().parse().unwrap();
+            assert!(radius >= min_dot_size.radius - 0.01, "{}", line);
+        }
+    }
+}
+
+#[test]
+fn optimized_squares_and_ellipses_are_copies_of_one_symbol() {
+    let (_, bytes) = &corpus()[0];
+    let image = image::load_from_memory(bytes).unwrap().to_rgba8();
+    let optimization = SvgOptimization {
+        enabled: true,
+        precision: 2,
+    };
+
+    for (dot_shape, element) in [(DotShape::Square, "<rect"), (DotShape::Ellipse, "<ellipse")] {
+        let args = RasterizeArgs {
+            dot_shape,
+            min_radius_percentage: 0.2,
+            ..args(&image, Pattern::Grid)
+        };
+        let plain = rasterize::rasterize_svg(args)
+            .map(|document| document.to_string())
+            .collect::<Vec<_>>();
+        let optimized = rasterize::rasterize_svg(RasterizeArgs {
+            svg_optimization: optimization,
+            ..args
+        })
+        .map(|document| document.to_string())
+        .collect::<Vec<_>>();
+
+        for (plain, optimized) in plain.iter().zip(&optimized) {
+            assert_eq!(optimized.matches("<symbol").count(), 1, "{:?}", dot_shape);
+            assert_eq!(
+                optimized.matches("<use").count(),
+                plain.matches(element).count(),
+                "{:?}",
+                dot_shape
+            );
+        }
+    }
+}
+
+#[test]
+fn svg_pages_are_sized_in_inches() {
+    let (_, bytes) = &corpus()[0];
+    let image = image::load_from_memory(bytes).unwrap().to_rgba8();
+    let args = args(&image, Pattern::Grid);
+
+    let pages = rasterize::rasterize_image(args).collect::<Vec<_>>();
+    let documents = rasterize::rasterize_svg(args).collect::<Vec<_>>();
+    assert_eq!(pages.len(), documents.len());
+
+    for (page, document) in pages.iter().zip(documents) {
+        let document = document.to_string();
+        let width = format!("width=\"{}in\"", page.width() as f32 / 72.0);
+        let height = format!("height=\"{}in\"", page.height() as f32 / 72.0);
+        assert!(document.contains(&width), "not {}", width);
+        assert!(document.contains(&height), "not {}", height);
+    }
+}
+
+#[test]
+fn print_html_has_every_svg_page_at_paper_size() {
+    for (name, bytes) in corpus() {
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        let args = args(&image, Pattern::Grid);
+
+        let documents = rasterize::rasterize_svg(args).collect::<Vec<_>>();
+        let html = rasterize::rasterize_print_html(args, "<corpus>");
+
+        assert_eq!(
+            html.matches("<div class=\"page\">").count(),
+            documents.len(),
+            "{}",
+            name
+        );
+        assert_eq!(html.matches("<svg").count(), documents.len(), "{}", name);
+        let size = format!(
+            "size: {}in {}in",
+            PAPER_WIDTH_PIXELS / 72.0,
+            PAPER_HEIGHT_PIXELS / 72.0
+        );
+        assert!(html.contains(&size), "{}: not {}", name, size);
+        assert!(html.contains("<title>&lt;corpus&gt;</title>"), "{}", name);
+    }
+}
+
+// paper sizes in points are rarely whole pixels. neighboring pages still
+// have to share no columns and skip none, however far along the row they are
+#[test]
+fn pages_tile_the_image_exactly() {
+    let wide = ImageBuffer::from_pixel(7000, 100, Rgba([0, 0, 0, 255]));
+    let paper_width_pixels = 595.28;
+    let args = RasterizeArgs {
+        paper_width_pixels,
+        pages_width: 7,
+        pages_height: 1,
+        ..args(&wide, Pattern::Grid)
+    };
+
+    let widths = rasterize::rasterize_image(args)
+        .map(|page| page.width())
+        .collect::<Vec<_>>();
+
+    assert_eq!(widths.len(), 7);
+    assert_eq!(
+        widths.iter().sum::<u32>(),
+        (7.0 * paper_width_pixels).ceil() as u32,
+        "{:?}",
+        widths
+    );
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn parallel_pages_match_serial_pages() {
+    for (name, bytes) in corpus() {
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+
+        for layout in [Layout::Fit, Layout::Matrix] {
+            let args = RasterizeArgs {
+                layout,
+                ..args(&image, Pattern::Grid)
+            };
+
+            assert!(
+                rasterize::rasterize_image_parallel(args)
+                    == rasterize::rasterize_image(args).collect::<Vec<_>>(),
+                "{} {:?}",
+                name,
+                layout
+            );
+        }
+    }
+}
+
+#[test]
+fn dark_images_draw_something() {
+    let black = ImageBuffer::from_pixel(16, 12, Rgba([0, 0, 0, 255]));
+
+    for pattern in Pattern::patterns() {
+        let pages = rasterize::rasterize_image(args(&black, *pattern)).collect::<Vec<_>>();
+
+        assert!(
+            pages
+                .iter()
+                .any(|page| page.pixels().any(|pixel| pixel[3] > 0)),
+            "{}: nothing drawn",
+            pattern
+        );
+    }
+}
+
+// the right half is fully transparent, with black left in its color channels
+#[test]
+fn transparent_squares_draw_nothing() {
+    let half = ImageBuffer::from_fn(16, 12, |x, _| Rgba([0, 0, 0, if x < 8 { 255 } else { 0 }]));
+
+    let args = RasterizeArgs {
+        color_depth: ColorDepth::Grayscale,
+        pages_width: 1,
+        pages_height: 1,
+        transparency: Transparency {
+            skip_transparent: true,
+            ..Transparency::default()
+        },
+        ..args(&half, Pattern::Grid)
+    };
+
+    for page in rasterize::rasterize_image(args) {
+        let (width, _) = page.dimensions();
+
+        assert!(page
+            .enumerate_pixels()
+            .any(|(x, _, pixel)| x < width / 2 && pixel[3] > 0));
+        assert!(page
+            .enumerate_pixels()
+            .all(|(x, _, pixel)| x < width * 3 / 4 || pixel[3] == 0));
+    }
+}
+
+#[test]
+fn svg_dots_are_labeled_with_their_page() {
+    let black = ImageBuffer::from_pixel(16, 12, Rgba([0, 0, 0, 255]));
+
+    for layout in [Layout::Fit, Layout::Matrix] {
+        let args = RasterizeArgs {
+            layout,
+            dot_metadata: true,
+            ..args(&black, Pattern::Grid)
+        };
+
+        for (i, document) in rasterize::rasterize_svg(args).enumerate() {
+            let document = document.to_string();
+            let circles = document.matches("<circle").count();
+
+            assert!(circles > 0, "{}: page {} has no dots", layout, i + 1);
+            assert_eq!(
+                document
+                    .matches(&format!("data-page=\"{}\"", i + 1))
+                    .count(),
+                circles,
+                "{}: page {}",
+                layout,
+                i + 1
+            );
+            assert_eq!(document.matches("data-cell=").count(), circles);
+            assert_eq!(document.matches("data-brightness=").count(), circles);
+        }
+    }
+}
+
+// a dark disc on white, so its contours turn all the way around
+#[test]
+fn dot_shapes_draw_in_every_backend() {
+    let disc = ImageBuffer::from_fn(24, 24, |x, y| {
+        let v = ((x as f32 - 12.0).hypot(y as f32 - 12.0) * 20.0).min(255.0) as u8;
+        Rgba([v, v, v, 255])
+    });
+
+    for dot_shape in DotShape::dot_shapes() {
+        for follow_contours in [false, true] {
+            let args = RasterizeArgs {
+                dot_shape: *dot_shape,
+                follow_contours,
+                pages_width: 1,
+                pages_height: 1,
+                // in black ink, so every dot's fill is the same and easy to find
+                color_depth: ColorDepth::Grayscale,
+                ..args(&disc, Pattern::Grid)
+            };
+
+            assert!(
+                rasterize::rasterize_image(args)
+                    .all(|page| page.pixels().any(|pixel| pixel[3] > 0)),
+                "{}: nothing drawn",
+                dot_shape
+            );
+            assert!(
+                rasterize::rasterize_svg(args)
+                    .all(|document| document.to_string().contains("fill=\"#000000\"")
+                        || document.to_string().contains("stroke=\"#000000\"")),
+                "{}: no dots",
+                dot_shape
+            );
+            assert_eq!(rasterize::rasterize_gcode(args).count(), 1, "{}", dot_shape);
+        }
+    }
+}
+
+// turned dots, so that their edges cut across pixels
+#[test]
+fn antialiased_dots_of_every_shape_have_soft_edges() {
+    let gray = ImageBuffer::from_pixel(24, 24, Rgba([100, 100, 100, 255]));
+
+    for dot_shape in DotShape::dot_shapes() {
+        let soft_pixels = |circle_algorithm| {
+            let args = RasterizeArgs {
+                dot_shape: *dot_shape,
+                circle_algorithm,
+                line_angle: 30.0,
+                pages_width: 1,
+                pages_height: 1,
+                ..args(&gray, Pattern::Grid)
+            };
+
+            rasterize::rasterize_image(args)
+                .map(|page| {
+                    page.pixels()
+                        .filter(|pixel| pixel[3] > 0 && pixel[3] < 255)
+                        .count()
+                })
+                .sum::<usize>()
+        };
+
+        assert_eq!(soft_pixels(CircleAlgorithm::Scanline), 0, "{}", dot_shape);
+        assert!(
+            soft_pixels(CircleAlgorithm::Antialiased) > 0,
+            "{}",
+            dot_shape
+        );
+    }
+}
+
+#[test]
+fn supersampled_pages_are_the_same_size_with_soft_edges() {
+    let gray = ImageBuffer::from_pixel(24, 24, Rgba([100, 100, 100, 255]));
+
+    for supersampling in [2, 4] {
+        let args = |supersampling| RasterizeArgs {
+            circle_algorithm: CircleAlgorithm::Scanline,
+            supersampling,
+            border: Border {
+                thickness: 3.0,
+                inset: 5.0,
+                ..Border::default()
+            },
+            ..args(&gray, Pattern::Grid)
+        };
+
+        let plain = rasterize::rasterize_image(args(1)).collect::<Vec<_>>();
+        let supersampled = rasterize::rasterize_image(args(supersampling)).collect::<Vec<_>>();
+        assert_eq!(plain.len(), supersampled.len());
+
+        for (plain, supersampled) in plain.iter().zip(&supersampled) {
+            assert_eq!(plain.dimensions(), supersampled.dimensions());
+            assert!(
+                supersampled
+                    .pixels()
+                    .any(|pixel| pixel[3] > 0 && pixel[3] < 255),
+                "{}x",
+                supersampling
+            );
+        }
+
+        // strips are supersampled the same way as whole pages
+        let pngs = rasterize::rasterize_png(args(supersampling));
+        for (page, png) in supersampled.iter().zip(pngs) {
+            let decoded = image::load_from_memory(&png).unwrap().to_rgba8();
+            assert!(decoded == *page, "{}x", supersampling);
+        }
+    }
+}
+
+// black on the left, white on the right
+#[test]
+fn edges_are_drawn_over_every_pattern() {
+    let halves = ImageBuffer::from_fn(16, 12, |x, _| {
+        let v = if x < 8 { 0 } else { 255 };
+        Rgba([v, v, v, 255])
+    });
+
+    for pattern in Pattern::patterns() {
+        let without = RasterizeArgs {
+            pages_width: 1,
+            pages_height: 1,
+            ..args(&halves, *pattern)
+        };
+        let with = RasterizeArgs {
+            edges: Edges {
+                enabled: true,
+                ..Edges::default()
+            },
+            ..without
+        };
+
+        let paths = |args| {
+            rasterize::rasterize_svg(args)
+                .map(|document| document.to_string().matches("<path").count())
+                .sum::<usize>()
+        };
+
+        assert_eq!(paths(with), paths(without) + 1, "{}", pattern);
+    }
+}
+
+#[test]
+fn framing_turns_before_cropping() {
+    let framing = Framing {
+        quarter_turns: 1,
+        crop_left: 0.5,
+        ..Framing::default()
+    };
+
+    assert_eq!(framing.dimensions(40, 20), (10, 40));
+    // crops that meet still leave a pixel
+    assert_eq!(
+        Framing {
+            crop_left: 1.0,
+            crop_right: 1.0,
+            ..framing
+        }
+        .dimensions(40, 20),
+        (1, 40)
+    );
+}
+
+#[test]
+fn regions_crop_within_the_current_crop() {
+    let mut framing = Framing::default();
+    framing.crop_to((0.75, 0.5), (0.25, 1.0));
+    assert_eq!(framing.dimensions(40, 20), (20, 10));
+
+    // a second region is picked out of what the first left
+    framing.crop_to((0.5, 0.0), (1.0, 1.0));
+    assert_eq!(framing.dimensions(40, 20), (10, 10));
+    assert_eq!(framing.crop_left, 0.5);
+}
+
+#[test]
+fn pngs_drawn_in_strips_match_whole_pages() {
+    let (_, bytes) = &corpus()[0];
+    let image = image::load_from_memory(bytes).unwrap().to_rgba8();
+
+    let shapes = Pattern::patterns()
+        .map(|pattern| (*pattern, DotShape::Circle))
+        .chain([DotShape::Ellipse, DotShape::Dash].map(|shape| (Pattern::Grid, shape)));
+
+    for (pattern, dot_shape) in shapes {
+        // tall enough pages to be drawn in a few strips
+        let args = RasterizeArgs {
+            output_pixels_per_inch: rasterize::PIXELS_PER_INCH * 5.0,
+            circle_algorithm: CircleAlgorithm::Antialiased,
+            max_radius_percentage: 1.5,
+            dot_shape,
+            border: Border {
+                thickness: 3.0,
+                inset: 5.0,
+                ..Border::default()
+            },
+            edges: Edges {
+                enabled: true,
+                ..Edges::default()
+            },
+            ..args(&image, pattern)
+        };
+
+        let pages = rasterize::rasterize_image(args).collect::<Vec<_>>();
+        let pngs = rasterize::rasterize_png(args).collect::<Vec<_>>();
+        assert_eq!(pages.len(), pngs.len(), "{}", pattern);
+
+        for (page, png) in pages.iter().zip(&pngs) {
+            let decoded = image::load_from_memory(png).unwrap().to_rgba8();
+            assert!(decoded.height() > 256, "{}", pattern);
+            assert!(decoded == *page, "{} {}", pattern, dot_shape);
+        }
+    }
+}
+
+#[test]
+fn pages_skipped_to_match_pages_drawn_in_turn() {
+    let (_, bytes) = &corpus()[0];
+    let image = image::load_from_memory(bytes).unwrap().to_rgba8();
+
+    for layout in [Layout::Fit, Layout::Matrix] {
+        let args = RasterizeArgs {
+            layout,
+            ..args(&image, Pattern::Grid)
+        };
+
+        let pngs = rasterize::rasterize_png(args).collect::<Vec<_>>();
+        let svgs = rasterize::rasterize_svg(args)
+            .map(|svg| svg.to_string())
+            .collect::<Vec<_>>();
+        assert!(pngs.len() > 1, "{:?}", layout);
+
+        for (i, (png, svg)) in pngs.iter().zip(&svgs).enumerate() {
+            assert!(rasterize::rasterize_png(args).nth(i).as_ref() == Some(png));
+            assert_eq!(
+                rasterize::rasterize_svg(args)
+                    .map_pages(|i, svg| (i, svg.to_string()))
+                    .nth(i),
+                Some((i, svg.clone())),
+                "{:?}",
+                layout
+            );
+        }
+
+        assert!(rasterize::rasterize_png(args).nth(pngs.len()).is_none());
+    }
+}
+
+#[test]
+fn resized_windows_match_whole_resizes() {
+    use image::imageops::FilterType;
+
+    for (name, bytes) in corpus() {
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+        let (width, height) = image.dimensions();
+
+        // scaled up and down
+        for (new_width, new_height) in [
+            (width * 3 + 1, height * 2 + 3),
+            (width / 3 + 1, height / 2 + 1),
+        ] {
+            for filter in [
+                FilterType::Nearest,
+                FilterType::Triangle,
+                FilterType::CatmullRom,
+                FilterType::Gaussian,
+                FilterType::Lanczos3,
+            ] {
+                let whole = image::imageops::resize(&image, new_width, new_height, filter);
+
+                let windows = [
+                    (0, 0, new_width, new_height),
+                    (0, 0, new_width / 2 + 1, new_height / 3 + 1),
+                    (
+                        new_width / 3,
+                        new_height / 2,
+                        new_width - new_width / 3,
+                        new_height - new_height / 2,
+                    ),
+                    (new_width - 1, new_height - 1, 1, 1),
+                ];
+
+                for (x, y, window_width, window_height) in windows {
+                    let window = rat::resample::resize_window(
+                        &image,
+                        (new_width, new_height),
+                        filter,
+                        (x, y, window_width, window_height),
+                    );
+                    let cropped =
+                        image::imageops::crop_imm(&whole, x, y, window_width, window_height)
+                            .to_image();
+
+                    assert!(window == cropped, "{} {:?} {} {}", name, filter, x, y);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn other_settings_do_not_panic() {
+    for (_, bytes) in corpus() {
+        let image = image::load_from_memory(&bytes).unwrap().to_rgba8();
+
+        for layout in [Layout::Fit, Layout::StepAndRepeat, Layout::Matrix] {
+            for color_depth in [
+                ColorDepth::Grayscale,
+                ColorDepth::TrueGrayscale,
+                ColorDepth::Gradient,
+                ColorDepth::Tritone,
+                ColorDepth::Palette,
+            ] {
+                let args = RasterizeArgs {
+                    layout,
+                    color_depth,
+                    dither: Dither::FloydSteinberg,
+                    draft: true,
+                    output_pixels_per_inch: rasterize::PIXELS_PER_INCH * 2.0,
+                    invert: ChannelInvert {
+                        red: true,
+                        green: false,
+                        blue: true,
+                    },
+                    tone: Tone {
+                        gamma: 2.2,
+                        brightness: -0.1,
+                        contrast: 0.5,
+                    },
+                    radius_curve: RadiusCurve {
+                        sizes: [1.0, 0.95, 0.5, 0.05, 0.0],
+                    },
+                    luma: Luma::Value,
+                    jitter: Jitter {
+                        radius: 0.5,
+                        position: 1.0,
+                        seed: 7,
+                    },
+                    edges: Edges {
+                        enabled: true,
+                        ..Edges::default()
+                    },
+                    framing: Framing {
+                        quarter_turns: 3,
+                        flip_horizontal: true,
+                        crop_left: 0.6,
+                        crop_right: 0.6,
+                        crop_top: 0.1,
+                        ..Framing::default()
+                    },
+                    mask: Some(&image),
+                    ..args(&image, Pattern::Grid)
+                };
+
+                rasterize::rasterize_image(args).for_each(drop);
+                rasterize::rasterize_svg(args).for_each(drop);
+                rasterize::rasterize_gcode(args).for_each(drop);
+            }
+        }
+    }
+}
+
+// each ink's layers go in a folder of the zip of their own, named after the ink
+#[test]
+fn plate_folders_are_named_after_their_inks() {
+    let image = RgbaImage::new(8, 8);
+    let args = RasterizeArgs {
+        color_depth: ColorDepth::Duotone,
+        ..args(&image, Pattern::Grid)
+    };
+
+    let folders = args.plate_folders();
+    assert_eq!(folders.len(), args.layer_inks().len());
+    for (ink, folder) in folders.iter().enumerate() {
+        let color = args.layer_inks()[ink].to_string();
+        assert_eq!(folder, &format!("plate{}_{}", ink + 1, &color[1..]));
+    }
+
+    let grayscale = RasterizeArgs {
+        color_depth: ColorDepth::Grayscale,
+        ..args
+    };
+    assert!(grayscale.plate_folders().is_empty());
+}
+
+#[test]
+fn srgb_tagged_pngs_still_decode() {
+    let png = ColorProfile::Srgb.tag_png(transparent_png());
+
+    assert!(png.windows(4).any(|chunk| chunk == b"sRGB"));
+    assert_eq!(
+        image::load_from_memory(&png).unwrap().to_rgba8(),
+        image::load_from_memory(&transparent_png())
+            .unwrap()
+            .to_rgba8()
+    );
+}
+
+#[test]
+fn empty_images_are_rejected() {
+    let empty = RgbaImage::new(0, 0);
+
+    assert!(rasterize::check_image(&empty).is_err());
+    assert!(rasterize::rasterize_image(args(&empty, Pattern::Grid))
+        .next()
+        .is_none());
+    assert!(rasterize::rasterize_svg(args(&empty, Pattern::Grid))
+        .next()
+        .is_none());
+}
+
+#[test]
+fn capabilities_cover_the_corpus() {
+    let capabilities = rat::capabilities::capabilities();
+
+    for format in ["png", "jpg", "gif"] {
+        assert!(capabilities.input_formats.contains(&format), "{}", format);
+    }
+    assert!(capabilities.features.contains(&"corpus-tests"));
+    assert_eq!(capabilities.patterns.len(), Pattern::patterns().len());
+}
+
+// four bands, getting darker to the right. each band should get more dots than
+// the one before it, rather than every band but the lightest filling right up
+#[test]
+fn stipple_density_follows_darkness() {
+    let (width, height, min_distance) = (400, 100, 4.0);
+    let points = stipple::poisson_disk_points(width, height, min_distance, 1, |x, _| {
+        (x / 100 + 1) as f32 / 4.0
+    });
+
+    let mut bands = [0; 4];
+    for (x, _) in points {
+        bands[(x as usize / 100).min(3)] += 1;
+    }
+
+    assert!(
+        bands.windows(2).all(|pair| pair[0] < pair[1]),
+        "{:?}",
+        bands
+    );
+    // a quarter of the darkness can't be a quarter of the dots, since a light area's
+    // dots are spaced out and collide less, but it should be well short of a full band
+    assert!(bands[3] * 2 > bands[0] * 3, "{:?}", bands);
+}
@@ -0,0 +1,391 @@
+// renders the bundled reference images in tests/golden/sources with fixed settings,
+// and compares every pattern's png and svg against the outputs checked in to
+// tests/golden/expected, so refactors of tiling and sampling can't quietly change
+// what gets drawn. pngs are compared by the brightness of small blocks of pixels,
+// and svgs element by element with a little slack on numbers, so that antialiasing
+// or float formatting settling slightly differently doesn't count as a change.
+// run with:
+//
+//     cargo test --features golden-tests --test golden
+//
+// when an output is meant to change, look over the new outputs written to the
+// temp directory by the failing run, and then write them over the expected ones with:
+//
+//     RAT_BLESS=1 cargo test --features golden-tests --test golden
+
+use image::{Rgba, RgbaImage};
+use rat::color::{Gradient, Inks, Palette};
+use rat::draw::CircleAlgorithm;
+use rat::rasterize::{
+    self, Border, ChannelInvert, ColorDepth, Dither, DotShape, Edges, Framing, Jitter, Layout,
+    Luma, MaskBlend, MinDotSize, ParameterMatrix, Pattern, RadiusCurve, RasterizeArgs, Resampling,
+    RowOrder, StepAndRepeat, SvgOptimization, Tone, Transparency,
+};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+const SOURCES: [&str; 2] = ["portrait", "ramp"];
+const PAPER_WIDTH_PIXELS: f32 = 96.0;
+const PAPER_HEIGHT_PIXELS: f32 = 72.0;
+// pngs are compared by the average brightness of blocks this many pixels on a side,
+// which may be this far apart, from 0 to 1
+const BLOCK_PIXELS: u32 = 4;
+const MAX_BLOCK_DIFFERENCE: f32 = 0.1;
+// numbers in svgs may be this far apart
+const MAX_NUMBER_DIFFERENCE: f64 = 0.01;
+
+fn golden_directory() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn args(image: &RgbaImage, pattern: Pattern) -> RasterizeArgs<'_> {
+    RasterizeArgs {
+        image,
+        mask: None,
+        paper_width_pixels: PAPER_WIDTH_PIXELS,
+        paper_height_pixels: PAPER_HEIGHT_PIXELS,
+        pages_width: 1,
+        pages_height: 1,
+        square_size: 6.0,
+        min_radius_percentage: 0.0,
+        max_radius_percentage: 1.0,
+        color_depth: ColorDepth::Rgb,
+        layout: Layout::Fit,
+        step_and_repeat: StepAndRepeat::default(),
+        border: Border::default(),
+        matrix: ParameterMatrix::default(),
+        gradient: Gradient::default(),
+        output_pixels_per_inch: rasterize::PIXELS_PER_INCH,
+        draft: false,
+        circle_algorithm: CircleAlgorithm::Scanline,
+        dither: Dither::None,
+        pattern,
+        mask_blend: MaskBlend::Multiply,
+        line_angle: 45.0,
+        invert: ChannelInvert::default(),
+        inks: Inks::default(),
+        ink_layer: None,
+        row_order: RowOrder::RowMajor,
+        palette: Palette::default(),
+        transparency: Transparency::default(),
+        tone: Tone::default(),
+        dot_metadata: false,
+        radius_curve: RadiusCurve::default(),
+        luma: Luma::Rec601,
+        jitter: Jitter::default(),
+        dot_shape: DotShape::Circle,
+        follow_contours: false,
+        edges: Edges::default(),
+        framing: Framing::default(),
+        resampling: Resampling::Triangle,
+        svg_optimization: SvgOptimization::default(),
+        min_dot_size: MinDotSize::default(),
+        supersampling: 1,
+    }
+}
+
+// checks `actual` against the expected output called `name`, or replaces it when blessing.
+// on a mismatch, `actual` is written out next to where it would be blessed from
+fn check(
+    name: &str,
+    actual: &[u8],
+    matches: impl Fn(&[u8], &[u8]) -> Result<(), String>,
+) -> Result<(), String> {
+    let expected_path = golden_directory().join("expected").join(name);
+
+    if std::env::var_os("RAT_BLESS").is_some() {
+        std::fs::write(&expected_path, actual).unwrap();
+        return Ok(());
+    }
+
+    let result = match std::fs::read(&expected_path) {
+        Ok(expected) => matches(&expected, actual),
+        Err(e) => Err(format!("no expected output ({}), bless it first", e)),
+    };
+
+    result.map_err(|e| {
+        let actual_path = std::env::temp_dir().join(format!("rat_golden_{}", name));
+        std::fs::write(&actual_path, actual).unwrap();
+        format!(
+            "{}: {}, the new output is {}",
+            name,
+            e,
+            actual_path.display()
+        )
+    })
+}
+
+// brightness as it would look printed on white paper
+fn brightness_on_white(pixel: &Rgba<u8>) -> f32 {
+    let alpha = pixel[3] as f32 / 255.0;
+    let luma =
+        (0.299 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.114 * pixel[2] as f32) / 255.0;
+    luma * alpha + (1.0 - alpha)
+}
+
+fn pngs_match(expected: &[u8], actual: &[u8]) -> Result<(), String> {
+    let expected = image::load_from_memory(expected)
+        .map_err(|e| e.to_string())?
+        .to_rgba8();
+    let actual = image::load_from_memory(actual)
+        .map_err(|e| e.to_string())?
+        .to_rgba8();
+
+    if expected.dimensions() != actual.dimensions() {
+        return Err(format!(
+            "{:?} instead of {:?}",
+            actual.dimensions(),
+            expected.dimensions()
+        ));
+    }
+
+    let (width, height) = expected.dimensions();
+    for block_y in (0..height).step_by(BLOCK_PIXELS as usize) {
+        for block_x in (0..width).step_by(BLOCK_PIXELS as usize) {
+            let block_brightness = |image: &RgbaImage| {
+                let mut total = 0.0;
+                let mut count = 0;
+                for y in block_y..(block_y + BLOCK_PIXELS).min(height) {
+                    for x in block_x..(block_x + BLOCK_PIXELS).min(width) {
+                        total += brightness_on_white(image.get_pixel(x, y));
+                        count += 1;
+                    }
+                }
+                total / count as f32
+            };
+
+            let difference = (block_brightness(&expected) - block_brightness(&actual)).abs();
+            if difference > MAX_BLOCK_DIFFERENCE {
+                return Err(format!(
+                    "the block at ({}, {}) is {:.3} brighter or darker",
+                    block_x, block_y, difference
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// each tag in order, as its name ("/g" for closing ones) and attributes, and each run
+// of text as "#text" with the text under "". attributes are kept sorted, since the
+// order they are written out in isn't fixed
+fn svg_elements(svg: &str) -> Vec<(String, BTreeMap<String, String>)> {
+    let mut elements = vec![];
+    let mut rest = svg;
+
+    while let Some(start) = rest.find('<') {
+        let text = rest[..start].trim();
+        if !text.is_empty() {
+            let mut attributes = BTreeMap::new();
+            attributes.insert(String::new(), text.to_string());
+            elements.push(("#text".to_string(), attributes));
+        }
+
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let tag = rest[start + 1..end].trim_end_matches('/').trim();
+        rest = &rest[end + 1..];
+
+        if tag.starts_with('?') || tag.starts_with('!') {
+            continue;
+        }
+
+        let (name, mut attributes_text) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+        let mut attributes = BTreeMap::new();
+        while let Some((key, value_and_rest)) = attributes_text.split_once("=\"") {
+            let (value, after) = value_and_rest
+                .split_once('"')
+                .unwrap_or((value_and_rest, ""));
+            attributes.insert(key.trim().to_string(), value.to_string());
+            attributes_text = after;
+        }
+
+        elements.push((name.to_string(), attributes));
+    }
+
+    elements
+}
+
+// `s` split into numbers and whatever is between them
+fn number_tokens(s: &str) -> Vec<Result<f64, String>> {
+    let mut tokens = vec![];
+    let mut other = String::new();
+    let chars = s.chars().collect::<Vec<_>>();
+    let mut i = 0;
+
+    while i < chars.len() {
+        // colors like #7e654b are words, not numbers
+        if chars[i] == '#' {
+            while i < chars.len() && (chars[i] == '#' || chars[i].is_ascii_alphanumeric()) {
+                other.push(chars[i]);
+                i += 1;
+            }
+            continue;
+        }
+
+        let starts_number = chars[i].is_ascii_digit()
+            || ((chars[i] == '-' || chars[i] == '.')
+                && matches!(chars.get(i + 1), Some(c) if c.is_ascii_digit() || *c == '.'));
+
+        if !starts_number {
+            other.push(chars[i]);
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        i += 1;
+        while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+            i += 1;
+        }
+        if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+            let exponent = i + 1 + usize::from(matches!(chars.get(i + 1), Some('-' | '+')));
+            if matches!(chars.get(exponent), Some(c) if c.is_ascii_digit()) {
+                i = exponent;
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+        }
+
+        let number = chars[start..i].iter().collect::<String>();
+        match number.parse::<f64>() {
+            Ok(number) => {
+                if !other.is_empty() {
+                    tokens.push(Err(std::mem::take(&mut other)));
+                }
+                tokens.push(Ok(number));
+            }
+            Err(_) => other.push_str(&number),
+        }
+    }
+
+    if !other.is_empty() {
+        tokens.push(Err(other));
+    }
+
+    tokens
+}
+
+fn values_match(expected: &str, actual: &str) -> bool {
+    let expected = number_tokens(expected);
+    let actual = number_tokens(actual);
+
+    expected.len() == actual.len()
+        && expected.iter().zip(actual.iter()).all(|pair| match pair {
+            (Ok(expected), Ok(actual)) => (expected - actual).abs() <= MAX_NUMBER_DIFFERENCE,
+            (Err(expected), Err(actual)) => expected.trim() == actual.trim(),
+            _ => false,
+        })
+}
+
+fn svgs_match(expected: &[u8], actual: &[u8]) -> Result<(), String> {
+    let expected = svg_elements(&String::from_utf8_lossy(expected));
+    let actual = svg_elements(&String::from_utf8_lossy(actual));
+
+    if expected.len() != actual.len() {
+        return Err(format!(
+            "{} elements instead of {}",
+            actual.len(),
+            expected.len()
+        ));
+    }
+
+    for (i, ((expected_name, expected_attributes), (actual_name, actual_attributes))) in
+        expected.iter().zip(actual.iter()).enumerate()
+    {
+        if expected_name != actual_name {
+            return Err(format!(
+                "element {} is {} instead of {}",
+                i, actual_name, expected_name
+            ));
+        }
+
+        if expected_attributes.keys().ne(actual_attributes.keys()) {
+            return Err(format!(
+                "element {} ({}) has attributes {:?} instead of {:?}",
+                i,
+                actual_name,
+                actual_attributes.keys().collect::<Vec<_>>(),
+                expected_attributes.keys().collect::<Vec<_>>()
+            ));
+        }
+
+        for (key, expected_value) in expected_attributes {
+            let actual_value = &actual_attributes[key];
+            if !values_match(expected_value, actual_value) {
+                return Err(format!(
+                    "element {} ({}) has {}=\"{}\" instead of \"{}\"",
+                    i, actual_name, key, actual_value, expected_value
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn encode_png(image: &RgbaImage) -> Vec<u8> {
+    let mut bytes = vec![];
+    image::codecs::png::PngEncoder::new(&mut bytes)
+        .encode(
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            image::ColorType::Rgba8,
+        )
+        .unwrap();
+    bytes
+}
+
+#[test]
+fn outputs_match_the_golden_outputs() {
+    let mut failures = vec![];
+
+    for source in SOURCES {
+        let path = golden_directory()
+            .join("sources")
+            .join(format!("{}.png", source));
+        let image = image::open(&path)
+            .unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+            .to_rgba8();
+
+        for pattern in Pattern::patterns() {
+            let name = format!("{}_{}", source, pattern.to_string().to_lowercase());
+            let args = args(&image, *pattern);
+
+            let page = rasterize::rasterize_image(args).next().unwrap();
+            if let Err(e) = check(&format!("{}.png", name), &encode_png(&page), pngs_match) {
+                failures.push(e);
+            }
+
+            let document = rasterize::rasterize_svg(args).next().unwrap();
+            if let Err(e) = check(
+                &format!("{}.svg", name),
+                document.to_string().as_bytes(),
+                svgs_match,
+            ) {
+                failures.push(e);
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn comparisons_allow_slack_but_not_changes() {
+    assert!(values_match("M1 2.004 L3 -4", "M1.002 2 L3 -4.005"));
+    assert!(!values_match("M1 2 L3 4", "M1 2 L3 4.5"));
+    assert!(!values_match("rotate(45)", "scale(45)"));
+    assert!(values_match("#7e654b", "#7e654b"));
+    assert!(!values_match("#7e654b", "#7e654c"));
+
+    let svg = r#"<svg a="1" b="2"><circle cx="3.001" r="1"/></svg>"#;
+    let reordered = r#"<svg b="2" a="1"><circle r="1" cx="3"/></svg>"#;
+    assert!(svgs_match(svg.as_bytes(), reordered.as_bytes()).is_ok());
+    assert!(svgs_match(svg.as_bytes(), r#"<svg a="1" b="2"></svg>"#.as_bytes()).is_err());
+}
@@ -0,0 +1,166 @@
+// checks of rasterizing that need no particular image format, so unlike the
+// corpus and golden tests they run with a plain:
+//
+//     cargo test
+
+use image::{GenericImageView, RgbaImage};
+use rat::rasterize::{
+    self, ColorDepth, ContactSheet, Framing, Layout, Pattern, RasterizeArgs, SvgOptimization,
+};
+
+mod common;
+use common::{args, gradient};
+
+// (name, image) of a few shapes of image, from a single pixel up
+fn images() -> Vec<(&'static str, RgbaImage)> {
+    vec![
+        ("gradient", gradient(48, 32)),
+        ("1x1", gradient(1, 1)),
+        ("very wide", gradient(2000, 1)),
+    ]
+}
+
+#[test]
+fn estimates_count_the_pages_that_are_made() {
+    for (name, image) in images() {
+        for layout in [Layout::Fit, Layout::StepAndRepeat, Layout::Matrix] {
+            for draft in [false, true] {
+                let args = RasterizeArgs {
+                    layout,
+                    draft,
+                    framing: Framing {
+                        quarter_turns: 1,
+                        ..Framing::default()
+                    },
+                    ..args(&image, Pattern::Grid)
+                };
+
+                let estimate = rasterize::estimate(args).unwrap();
+                let pages = rasterize::rasterize_image(args).collect::<Vec<_>>();
+                assert_eq!(estimate.pages, pages.len(), "{} {}", name, layout);
+
+                let pixels: u64 = pages
+                    .iter()
+                    .map(|page| page.width() as u64 * page.height() as u64)
+                    .sum();
+                assert!(estimate.peak_bytes >= pixels / pages.len() as u64 * 4);
+                assert!(estimate.dots > 0 && estimate.seconds > 0.0);
+                assert!(!estimate.is_too_big());
+            }
+        }
+    }
+
+    // hundreds of letter pages at print resolution are too much for a browser
+    let image = gradient(48, 32);
+    let estimate = rasterize::estimate(RasterizeArgs {
+        paper_width_pixels: 612.0,
+        paper_height_pixels: 792.0,
+        pages_width: 25,
+        pages_height: 25,
+        layout: Layout::StepAndRepeat,
+        output_pixels_per_inch: 300.0,
+        ..args(&image, Pattern::Grid)
+    })
+    .unwrap();
+    assert_eq!(estimate.pages, 625);
+    assert!(estimate.is_too_big());
+}
+
+#[test]
+fn contact_sheets_have_a_preview_for_every_combination() {
+    let image = gradient(48, 32);
+    let sheet = ContactSheet {
+        enabled: true,
+        steps: 3,
+        ..ContactSheet::default()
+    };
+    assert_eq!(sheet.square_sizes(), vec![8.0, 20.0, 32.0]);
+    assert_eq!(sheet.max_radius_percentages(), vec![50.0, 100.0, 150.0]);
+
+    for layout in [Layout::Fit, Layout::Matrix] {
+        let args = RasterizeArgs {
+            layout,
+            ..args(&image, Pattern::Grid)
+        };
+        let contact_sheet = rasterize::contact_sheet(args, sheet).unwrap();
+
+        // three previews and four gaps each way, the previews as tall as the poster is
+        // for its width, and no bigger than the cells
+        let poster = rasterize::rasterize_image(args.whole_poster())
+            .next()
+            .unwrap();
+        let (width, height) = contact_sheet.dimensions();
+        assert_eq!((width - 4 * 8) % 3, 0, "{:?}", layout);
+        assert_eq!((height - 4 * 8) % 3, 0, "{:?}", layout);
+        let (cell_width, cell_height) = ((width - 32) / 3, (height - 32) / 3);
+        assert!(cell_width.max(cell_height) <= 320, "{:?}", layout);
+        let aspect = poster.width() as f32 / poster.height() as f32;
+        assert!(
+            (cell_width as f32 / cell_height as f32 - aspect).abs() < 0.02,
+            "{:?}",
+            layout
+        );
+
+        // no two previews are drawn the same
+        let cells = (0..9)
+            .map(|i| {
+                let x = 8 + (i % 3) * (cell_width + 8);
+                let y = 8 + (i / 3) * (cell_height + 8);
+                contact_sheet
+                    .view(x, y, cell_width, cell_height)
+                    .to_image()
+                    .into_raw()
+            })
+            .collect::<Vec<_>>();
+        for (i, cell) in cells.iter().enumerate() {
+            assert!(
+                cells[i + 1..].iter().all(|other| other != cell),
+                "{:?}",
+                layout
+            );
+        }
+    }
+}
+
+#[test]
+fn optimized_svgs_are_smaller_and_draw_the_same_dots() {
+    let image = gradient(48, 32);
+    let optimization = SvgOptimization {
+        enabled: true,
+        precision: 1,
+    };
+
+    for pattern in Pattern::patterns() {
+        let plain = rasterize::rasterize_svg(args(&image, *pattern))
+            .map(|document| document.to_string())
+            .collect::<Vec<_>>();
+        let optimized = rasterize::rasterize_svg(RasterizeArgs {
+            svg_optimization: optimization,
+            ..args(&image, *pattern)
+        })
+        .map(|document| document.to_string())
+        .collect::<Vec<_>>();
+        assert_eq!(plain.len(), optimized.len(), "{}", pattern);
+
+        for (plain, optimized) in plain.iter().zip(&optimized) {
+            assert!(optimized.len() <= plain.len(), "{}", pattern);
+
+            // every dot left is big enough to see
+            let dots = optimized.matches("<circle").count();
+            assert!(dots <= plain.matches("<circle").count(), "{}", pattern);
+            assert!(!optimized.contains("r=\"0\""), "{}", pattern);
+        }
+    }
+
+    // with only a few colors, dots take them from their groups
+    let document = rasterize::rasterize_svg(RasterizeArgs {
+        color_depth: ColorDepth::Duotone,
+        svg_optimization: optimization,
+        ..args(&image, Pattern::Grid)
+    })
+    .next()
+    .unwrap()
+    .to_string();
+    assert!(document.contains("<g fill=\"#"));
+    assert!(document.matches("fill=").count() < document.matches("<circle").count());
+}
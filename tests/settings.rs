@@ -0,0 +1,95 @@
+// the settings kept outside of any run: their undo history, the numbers typed into
+// their controls, and the links they are shared by. these need no image, so unlike
+// the corpus and golden tests they run with a plain:
+//
+//     cargo test
+
+use rat::settings::{parse_number, Settings, SettingsHistory, HISTORY_LENGTH, HISTORY_MERGE_MS};
+
+// the default settings, told apart by their page width
+fn settings(pages_width: u32) -> Settings {
+    Settings {
+        pages_width,
+        ..Settings::default()
+    }
+}
+
+#[test]
+fn changes_close_together_are_undone_as_one() {
+    let mut history = SettingsHistory::default();
+    history.record(settings(1), 0.0);
+    history.record(settings(2), HISTORY_MERGE_MS / 2.0);
+    history.record(settings(3), HISTORY_MERGE_MS * 3.0);
+
+    assert_eq!(history.undo(settings(4)), Some(settings(3)));
+    assert_eq!(history.undo(settings(3)), Some(settings(1)));
+    assert_eq!(history.undo(settings(1)), None);
+}
+
+#[test]
+fn history_keeps_only_the_latest_changes() {
+    let mut history = SettingsHistory::default();
+    let changes = HISTORY_LENGTH as u32 + 10;
+    for i in 0..changes {
+        history.record(settings(i), i as f64 * HISTORY_MERGE_MS * 2.0);
+    }
+
+    let mut current = settings(changes);
+    let mut undone = 0;
+    while let Some(previous) = history.undo(current.clone()) {
+        current = previous;
+        undone += 1;
+    }
+
+    assert_eq!(undone, HISTORY_LENGTH);
+    assert_eq!(current, settings(10));
+}
+
+#[test]
+fn a_new_change_clears_redo() {
+    let mut history = SettingsHistory::default();
+    history.record(settings(1), 0.0);
+
+    assert_eq!(history.undo(settings(2)), Some(settings(1)));
+    assert!(history.can_redo());
+    assert_eq!(history.redo(settings(1)), Some(settings(2)));
+    assert_eq!(history.undo(settings(2)), Some(settings(1)));
+
+    history.record(settings(1), HISTORY_MERGE_MS * 2.0);
+    assert!(!history.can_redo());
+    assert_eq!(history.redo(settings(3)), None);
+}
+
+#[test]
+fn typed_numbers_allow_whitespace_and_decimal_commas() {
+    assert_eq!(parse_number::<f32>(" 18,5 "), Some(18.5));
+    assert_eq!(parse_number::<f32>("18.5"), Some(18.5));
+    assert_eq!(parse_number::<u32>("12\n"), Some(12));
+    assert_eq!(parse_number::<u32>("-1"), None);
+    assert_eq!(parse_number::<f32>(""), None);
+    assert_eq!(parse_number::<f32>("1,000.5"), None);
+}
+
+#[test]
+fn settings_round_trip_through_a_url_fragment() {
+    let changed = Settings {
+        pages_width: 3,
+        square_size: 7.5,
+        ..Settings::default()
+    };
+
+    for settings in [Settings::default(), changed] {
+        let fragment = settings.to_url_fragment();
+        assert_eq!(
+            Settings::from_url_fragment(&fragment),
+            Some(settings.clone())
+        );
+
+        // other parts of the fragment are passed over
+        let shared = format!("#view=1&{}", fragment.trim_start_matches('#'));
+        assert_eq!(Settings::from_url_fragment(&shared), Some(settings));
+    }
+
+    assert_eq!(Settings::from_url_fragment(""), None);
+    assert_eq!(Settings::from_url_fragment("#settings=not base64!"), None);
+}